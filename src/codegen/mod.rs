@@ -0,0 +1,401 @@
+//////////////////////// Entity code generation from header rows ////////////////////////
+
+use crate::mapper::sheet_row::SheetRow;
+use crate::spread_sheet_driver::{IntoStrVec, SpreadSheetDriver, SsdResult};
+use crate::types::{ColumnKind, Letters};
+use google_sheets4::chrono::NaiveDate;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// Rust type of the column inferred from the sample values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InferredType {
+    Bool,
+    I64,
+    F64,
+    /// ISO 8601 date strings ("2024-12-31")
+    Date,
+    String,
+}
+
+impl InferredType {
+    pub fn rust_type(&self) -> &'static str {
+        match self {
+            InferredType::Bool => "bool",
+            InferredType::I64 => "i64",
+            InferredType::F64 => "f64",
+            InferredType::Date => "NaiveDate",
+            InferredType::String => "String",
+        }
+    }
+
+    pub fn column_kind(&self) -> ColumnKind {
+        match self {
+            InferredType::Bool => ColumnKind::Boolean,
+            InferredType::I64 => ColumnKind::Integer,
+            InferredType::F64 => ColumnKind::Number,
+            InferredType::Date => ColumnKind::Date,
+            InferredType::String => ColumnKind::Text,
+        }
+    }
+
+    fn accepts(&self, value: &Value) -> bool {
+        let text = match value {
+            Value::String(s) => s.as_str(),
+            Value::Bool(_) => return *self == InferredType::Bool,
+            Value::Number(n) => {
+                return match self {
+                    InferredType::I64 => n.is_i64(),
+                    InferredType::F64 => true,
+                    _ => false,
+                };
+            }
+            _ => return false,
+        };
+
+        match self {
+            InferredType::Bool => bool::from_str(text).is_ok(),
+            InferredType::I64 => i64::from_str(text).is_ok(),
+            InferredType::F64 => f64::from_str(text).is_ok(),
+            InferredType::Date => NaiveDate::from_str(text).is_ok(),
+            InferredType::String => true,
+        }
+    }
+
+    /// Narrowest type accepting all values. Order matters: every i64 is also a valid f64
+    fn infer<'a>(values: impl Iterator<Item = &'a Value> + Clone) -> Self {
+        if values.clone().next().is_none() {
+            return InferredType::String;
+        }
+
+        [
+            InferredType::Bool,
+            InferredType::I64,
+            InferredType::F64,
+            InferredType::Date,
+        ]
+        .into_iter()
+        .find(|ty| values.clone().all(|v| ty.accepts(v)))
+        .unwrap_or(InferredType::String)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnSchema {
+    /// Header text as it is in the sheet
+    pub header: String,
+    /// Rust field name derived from the header
+    pub field: String,
+    pub ty: InferredType,
+    /// Some of the sample cells are empty, so the field will be `Option<_>`
+    pub optional: bool,
+}
+
+impl ColumnSchema {
+    pub fn field_type(&self) -> String {
+        match self.optional {
+            true => format!("Option<{}>", self.ty.rust_type()),
+            false => self.ty.rust_type().to_string(),
+        }
+    }
+}
+
+/// Entity layout inferred from the header row and a sample of data rows
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntitySchema {
+    pub name: String,
+    pub columns: Vec<ColumnSchema>,
+}
+
+impl EntitySchema {
+    /// `rows[0]` is the header row, the rest is the data sample.
+    /// Columns without data are inferred as optional strings
+    pub fn infer(name: &str, rows: &[SheetRow]) -> Self {
+        let Some((headers, sample)) = rows.split_first() else {
+            return Self {
+                name: name.to_string(),
+                columns: vec![],
+            };
+        };
+
+        let mut taken = HashSet::new();
+        let columns = headers
+            .iter()
+            .enumerate()
+            .map(|(col, header)| {
+                let header = match header {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                let cells = sample.iter().map(|row| row.get(col));
+                let values = cells.clone().flatten().filter(|v| !is_blank(v));
+
+                ColumnSchema {
+                    field: field_name(&header, col, &mut taken),
+                    ty: InferredType::infer(values),
+                    optional: sample.is_empty() || cells.clone().any(|c| c.is_none_or(is_blank)),
+                    header,
+                }
+            })
+            .collect();
+
+        Self {
+            name: name.to_string(),
+            columns,
+        }
+    }
+
+    /// Emits the struct with `SheetRowSerde` and `EntityEssentials` impls
+    pub fn to_rust(&self) -> String {
+        let mut code = String::new();
+        let uses_date = self.columns.iter().any(|c| c.ty == InferredType::Date);
+
+        code.push_str("use google_sheets_driver::mapper::sheet_row::{self, SheetRow, SheetRowExt, SheetRowSerde};\n");
+        code.push_str(
+            "use google_sheets_driver::types::{ColumnKind, ColumnMeta, EntityEssentials};\n",
+        );
+        if uses_date {
+            code.push_str("use google_sheets4::chrono::NaiveDate;\n");
+        }
+        code.push_str("use serde_json::Value;\n\n");
+
+        let _ = writeln!(code, "#[derive(Debug, Clone, PartialEq)]");
+        let _ = writeln!(code, "pub struct {} {{", self.name);
+        for (col, column) in self.columns.iter().enumerate() {
+            let letter = Letters::from_valid("A".to_string()) + col as u32;
+            let _ = writeln!(code, "    /// Column {}: {:?}", letter, column.header);
+            let _ = writeln!(code, "    pub {}: {},", column.field, column.field_type());
+        }
+        let _ = writeln!(code, "}}\n");
+
+        let _ = writeln!(code, "impl SheetRowSerde for {} {{", self.name);
+        let _ = writeln!(
+            code,
+            "    fn deserialize(row: SheetRow) -> sheet_row::Result<Self> {{"
+        );
+        let _ = writeln!(code, "        Ok(Self {{");
+        for (col, column) in self.columns.iter().enumerate() {
+            let parse = match column.optional {
+                true => "parse_optional_cell",
+                false => "parse_cell",
+            };
+            let _ = writeln!(
+                code,
+                "            {}: row.{}({}, {:?})?,",
+                column.field, parse, col, column.header
+            );
+        }
+        let _ = writeln!(code, "        }})");
+        let _ = writeln!(code, "    }}\n");
+        let _ = writeln!(
+            code,
+            "    fn serialize(&self) -> sheet_row::Result<SheetRow> {{"
+        );
+        let _ = writeln!(code, "        Ok(vec![");
+        for column in &self.columns {
+            let _ = writeln!(code, "            {},", serialize_expr(column));
+        }
+        let _ = writeln!(code, "        ])");
+        let _ = writeln!(code, "    }}");
+        let _ = writeln!(code, "}}\n");
+
+        let _ = writeln!(code, "impl EntityEssentials for {} {{", self.name);
+        let _ = writeln!(code, "    fn entity_width() -> u32 {{");
+        let _ = writeln!(code, "        {}", self.columns.len());
+        let _ = writeln!(code, "    }}\n");
+        let _ = writeln!(
+            code,
+            "    fn column_headers() -> &'static [&'static str] {{"
+        );
+        let headers: Vec<String> = self
+            .columns
+            .iter()
+            .map(|c| format!("{:?}", c.header))
+            .collect();
+        let _ = writeln!(code, "        &[{}]", headers.join(", "));
+        let _ = writeln!(code, "    }}\n");
+        let _ = writeln!(code, "    fn columns() -> Vec<ColumnMeta> {{");
+        let _ = writeln!(code, "        vec![");
+        for column in &self.columns {
+            let _ = writeln!(
+                code,
+                "            ColumnMeta::new({:?}).kind(ColumnKind::{:?}),",
+                column.header,
+                column.ty.column_kind()
+            );
+        }
+        let _ = writeln!(code, "        ]");
+        let _ = writeln!(code, "    }}");
+        let _ = writeln!(code, "}}");
+        code
+    }
+}
+
+impl SpreadSheetDriver {
+    /// Reads the header row and sample rows of `range` and infers entity schema from them.
+    /// Example: driver.infer_entity_schema("User", "users!A1:F50").await?.to_rust()
+    pub async fn infer_entity_schema<R>(&self, name: &str, range: R) -> SsdResult<EntitySchema>
+    where
+        R: ToString,
+    {
+        let rows = self.try_get_range(range).await?.into_vec();
+        Ok(EntitySchema::infer(name, &rows))
+    }
+}
+
+fn serialize_expr(column: &ColumnSchema) -> String {
+    let value = |access: &str, by_ref: bool| match column.ty {
+        InferredType::String => format!("Value::String({access}.clone())"),
+        InferredType::Date => format!("Value::String({access}.to_string())"),
+        _ if by_ref => format!("Value::from(*{access})"),
+        _ => format!("Value::from({access})"),
+    };
+
+    let access = format!("self.{}", column.field);
+    match column.optional {
+        true => format!(
+            "{access}.as_ref().map(|v| {}).unwrap_or_else(|| Value::String(String::new()))",
+            value("v", true)
+        ),
+        false => value(&access, false),
+    }
+}
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false",
+    "fn", "for", "gen", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where",
+    "while", "yield",
+];
+
+/// Keywords which can't be raw identifiers, suffixed instead. `Self` is lowercased by then
+const PATH_KEYWORDS: &[&str] = &["crate", "self", "super"];
+
+/// snake_case identifier from free-form header text, unique within the struct
+fn field_name(header: &str, col: usize, taken: &mut HashSet<String>) -> String {
+    let mut name = String::new();
+    for c in header.trim().chars() {
+        if c.is_ascii_alphanumeric() {
+            if c.is_ascii_uppercase() && name.chars().last().is_some_and(|p| p.is_ascii_lowercase())
+            {
+                name.push('_');
+            }
+            name.push(c.to_ascii_lowercase());
+        } else if !name.is_empty() && !name.ends_with('_') {
+            name.push('_');
+        }
+    }
+    let mut name = name.trim_end_matches('_').to_string();
+
+    if name.is_empty() {
+        name = format!("column_{col}");
+    } else if name.starts_with(|c: char| c.is_ascii_digit()) {
+        name = format!("field_{name}");
+    } else if PATH_KEYWORDS.contains(&name.as_str()) {
+        name = format!("{name}_");
+    } else if RUST_KEYWORDS.contains(&name.as_str()) {
+        name = format!("r#{name}");
+    }
+
+    if taken.contains(&name) {
+        name = format!("{name}_{col}");
+    }
+    taken.insert(name.clone());
+    name
+}
+
+fn is_blank(value: &Value) -> bool {
+    value.is_null() || value.as_str() == Some("")
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod codegen_tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rows(value: Value) -> Vec<SheetRow> {
+        serde_json::from_value(value).expect("Test: Expected rows")
+    }
+
+    #[test]
+    fn field_name__from_various_headers__ok() {
+        let mut taken = HashSet::new();
+        assert_eq!(field_name("User Id", 0, &mut taken), "user_id");
+        assert_eq!(field_name("createdAt", 1, &mut taken), "created_at");
+        assert_eq!(field_name("1st place", 2, &mut taken), "field_1st_place");
+        assert_eq!(field_name("type", 3, &mut taken), "r#type");
+        assert_eq!(field_name("  ", 4, &mut taken), "column_4");
+        assert_eq!(field_name("user-id", 5, &mut taken), "user_id_5");
+    }
+
+    #[test]
+    fn field_name__path_keywords__suffixed() {
+        for (header, expected) in [
+            ("crate", "crate_"),
+            ("super", "super_"),
+            ("self", "self_"),
+            ("Self", "self_"),
+        ] {
+            assert_eq!(field_name(header, 0, &mut HashSet::new()), expected);
+        }
+    }
+
+    #[test]
+    fn field_name__edition_2024_keyword__raw_identifier() {
+        let mut taken = HashSet::new();
+        assert_eq!(field_name("gen", 0, &mut taken), "r#gen");
+    }
+
+    #[test]
+    fn infer__column_types__ok() {
+        let schema = EntitySchema::infer(
+            "User",
+            &rows(json!([
+                ["id", "score", "active", "born", "name", "email", "notes"],
+                [1, 1.5, true, "2000-01-31", "Joe", "joe@example.com", ""],
+                ["2", "3", "false", "1999-12-01", "John"],
+            ])),
+        );
+
+        let types: Vec<(InferredType, bool)> =
+            schema.columns.iter().map(|c| (c.ty, c.optional)).collect();
+        assert_eq!(
+            types,
+            vec![
+                (InferredType::I64, false),
+                (InferredType::F64, false),
+                (InferredType::Bool, false),
+                (InferredType::Date, false),
+                (InferredType::String, false),
+                (InferredType::String, true),
+                (InferredType::String, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn to_rust__emits_struct_and_impls__ok() {
+        let schema = EntitySchema::infer(
+            "User",
+            &rows(json!([["User Id", "E-mail"], [1, "joe@example.com"], [2]])),
+        );
+        let code = schema.to_rust();
+
+        assert!(code.contains("pub struct User {"));
+        assert!(code.contains("    /// Column A: \"User Id\"\n    pub user_id: i64,"));
+        assert!(code.contains("    pub e_mail: Option<String>,"));
+        assert!(code.contains("user_id: row.parse_cell(0, \"User Id\")?,"));
+        assert!(code.contains("e_mail: row.parse_optional_cell(1, \"E-mail\")?,"));
+        assert!(code.contains("Value::from(self.user_id),"));
+        assert!(code.contains(
+            "self.e_mail.as_ref().map(|v| Value::String(v.clone())).unwrap_or_else(|| Value::String(String::new())),"
+        ));
+        assert!(code.contains("fn entity_width() -> u32 {\n        2\n    }"));
+        assert!(code.contains(r#"        &["User Id", "E-mail"]"#));
+        assert!(code.contains(r#"ColumnMeta::new("User Id").kind(ColumnKind::Integer),"#));
+        assert!(!code.contains("NaiveDate"));
+    }
+}
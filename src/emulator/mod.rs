@@ -0,0 +1,310 @@
+//////////////////////// In-process fake of the Sheets values API ////////////////////////
+// Implements just enough of `spreadsheets.values` (batchGetByDataFilter, update, append)
+// over HTTP to run the driver and the ORM end-to-end without credentials.
+// Storage and semantics are the ones of `MemoryBackend`.
+
+use crate::mapper::sheet_row::SheetRow;
+use crate::spread_sheet_driver::SpreadSheetDriver;
+use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+use crate::types::{SheetA1CellId, SheetA1Range};
+use error_stack::{Report, ResultExt};
+use google_sheets4::api::{BatchGetValuesByDataFilterRequest, ValueRange};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde::Serialize;
+use serde_json::json;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::oneshot;
+use tracing::debug;
+
+#[derive(Debug, thiserror::Error)]
+pub enum EmulatorError {
+    #[error("Can't start emulator server")]
+    StartupError,
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+}
+
+pub type EmulatorResult<T> = error_stack::Result<T, EmulatorError>;
+
+/// HTTP server which is stopped when dropped
+/// Example:
+/// let emulator = SheetsEmulator::start().await?;
+/// let driver = emulator.driver("document");
+pub struct SheetsEmulator {
+    addr: SocketAddr,
+    backend: Arc<MemoryBackend>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl SheetsEmulator {
+    /// Binds a random local port and serves requests on the current tokio runtime
+    pub async fn start() -> EmulatorResult<Self> {
+        let backend = Arc::new(MemoryBackend::new());
+
+        let state = backend.clone();
+        let make_service = make_service_fn(move |_| {
+            let state = state.clone();
+            async move { Ok::<_, Infallible>(service_fn(move |req| handle(state.clone(), req))) }
+        });
+
+        let server = Server::try_bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+            .map_err(Report::new)
+            .change_context(EmulatorError::StartupError)?
+            .serve(make_service);
+        let addr = server.local_addr();
+
+        let (shutdown, stopped) = oneshot::channel::<()>();
+        tokio::spawn(server.with_graceful_shutdown(async {
+            let _ = stopped.await;
+        }));
+        debug!("Sheets emulator is listening on {}", addr);
+
+        Ok(Self {
+            addr,
+            backend,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    /// Base URL to pass into [`SpreadSheetDriver::with_base_url`]
+    pub fn base_url(&self) -> String {
+        format!("http://{}/", self.addr)
+    }
+
+    /// Credential-less driver pointed at the emulator
+    pub fn driver<S>(&self, document_id: S) -> SpreadSheetDriver
+    where
+        S: Into<String>,
+    {
+        SpreadSheetDriver::unauthenticated(document_id.into()).with_base_url(self.base_url())
+    }
+
+    /// Pre-populates the sheet with rows starting at `start`
+    pub fn seed(&self, start: &SheetA1CellId, rows: Vec<SheetRow>) {
+        self.backend
+            .workbook()
+            .write(&start.sheet_name, &start.cell, &rows);
+    }
+
+    /// Current content of the sheet starting from A1
+    pub fn sheet(&self, sheet: &str) -> Vec<SheetRow> {
+        self.backend.workbook().sheet(sheet)
+    }
+}
+
+impl Drop for SheetsEmulator {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+//////////////////////// Request handling ////////////////////////
+
+async fn handle(
+    backend: Arc<MemoryBackend>,
+    req: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let method = req.method().clone();
+    let path = percent_decode(req.uri().path());
+    debug!("Emulator request: {} {}", method, path);
+
+    let body = hyper::body::to_bytes(req.into_body())
+        .await
+        .map(|b| b.to_vec())
+        .unwrap_or_default();
+
+    let response = route(&backend, &method, &path, &body).unwrap_or_else(|e| {
+        json_response(
+            StatusCode::BAD_REQUEST,
+            &json!({ "error": { "code": 400, "message": format!("{e:?}"), "status": "INVALID_ARGUMENT" } }),
+        )
+    });
+    Ok(response)
+}
+
+fn route(
+    backend: &MemoryBackend,
+    method: &Method,
+    path: &str,
+    body: &[u8],
+) -> EmulatorResult<Response<Body>> {
+    let Some((_document_id, op)) = path
+        .strip_prefix("/v4/spreadsheets/")
+        .and_then(|rest| rest.split_once('/'))
+    else {
+        return Ok(not_found(path));
+    };
+
+    if *method == Method::POST && op == "values:batchGetByDataFilter" {
+        let req: BatchGetValuesByDataFilterRequest = parse_body(body)?;
+        let ranges = req
+            .data_filters
+            .unwrap_or_default()
+            .into_iter()
+            .map(|filter| match filter.a1_range {
+                Some(a1_range) => parse_range(&a1_range),
+                None => Err(Report::new(EmulatorError::InvalidRequest(
+                    "Only A1 data filters are supported".to_string(),
+                ))),
+            })
+            .collect::<EmulatorResult<Vec<_>>>()?;
+        return Ok(json_response(StatusCode::OK, &backend.batch_get(&ranges)));
+    }
+
+    let Some(range) = op.strip_prefix("values/") else {
+        return Ok(not_found(path));
+    };
+
+    if *method == Method::POST
+        && let Some(range) = range.strip_suffix(":append")
+    {
+        let req: ValueRange = parse_body(body)?;
+        let rows = req.values.unwrap_or_default();
+        return Ok(json_response(
+            StatusCode::OK,
+            &backend.append(&parse_range(range)?, &rows),
+        ));
+    }
+
+    if *method == Method::PUT {
+        let req: ValueRange = parse_body(body)?;
+        let rows = req.values.unwrap_or_default();
+        return Ok(json_response(
+            StatusCode::OK,
+            &backend.update(&parse_range(range)?, &rows),
+        ));
+    }
+
+    Ok(not_found(path))
+}
+
+fn parse_range(range: &str) -> EmulatorResult<SheetA1Range> {
+    SheetA1Range::from_raw(range)
+        .change_context_lazy(|| EmulatorError::InvalidRequest(format!("Unsupported range {range}")))
+}
+
+fn parse_body<T>(body: &[u8]) -> EmulatorResult<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    serde_json::from_slice(body)
+        .map_err(Report::new)
+        .change_context(EmulatorError::InvalidRequest(
+            "Malformed JSON body".to_string(),
+        ))
+}
+
+fn json_response<T>(status: StatusCode, body: &T) -> Response<Body>
+where
+    T: Serialize,
+{
+    let mut response = Response::new(Body::from(
+        serde_json::to_vec(body).expect("Expected API types to be serializable"),
+    ));
+    *response.status_mut() = status;
+    response.headers_mut().insert(
+        hyper::header::CONTENT_TYPE,
+        hyper::header::HeaderValue::from_static("application/json"),
+    );
+    response
+}
+
+fn not_found(path: &str) -> Response<Body> {
+    json_response(
+        StatusCode::NOT_FOUND,
+        &json!({ "error": { "code": 404, "message": format!("Not emulated: {path}"), "status": "NOT_FOUND" } }),
+    )
+}
+
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                result.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                result.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&result).into_owned()
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod request_tests {
+    use super::*;
+
+    #[test]
+    fn percent_decode__on_encoded_range__ok() {
+        assert_eq!(percent_decode("users%21A1%3AB2"), "users!A1:B2");
+        assert_eq!(percent_decode("100%"), "100%");
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod end_to_end_tests {
+    use super::*;
+    use crate::orm::Repository;
+    use crate::testing::examples_support::User;
+    use crate::types::Entity;
+    use serde_json::Value;
+    use tokio::sync::Mutex as AsyncMutex;
+
+    #[tokio::test]
+    async fn repository__insert_update_find__round_trip__ok() {
+        let emulator = SheetsEmulator::start()
+            .await
+            .expect("Test: Expected emulator to start");
+        emulator.seed(
+            &SheetA1CellId::from_primitives("users", "A", 1),
+            vec![vec![Value::from("1"), Value::from("Joe")]],
+        );
+        let repository = Repository::new(Arc::new(AsyncMutex::new(emulator.driver("document"))));
+
+        let start = SheetA1CellId::from_primitives("users", "A", 1);
+        let mut inserted = repository
+            .insert(
+                start.clone(),
+                1,
+                User {
+                    id: 2,
+                    name: "John".to_string(),
+                },
+            )
+            .await
+            .expect("Test: Expected insert to succeed");
+        assert_eq!(
+            inserted.position(),
+            &SheetA1CellId::from_primitives("users", "A", 2)
+        );
+
+        inserted.name = "Johnny".to_string();
+        repository
+            .update(&inserted)
+            .await
+            .expect("Test: Expected update to succeed");
+
+        let found: Vec<Entity<User>> = repository
+            .find_in_range(&start, 2)
+            .await
+            .expect("Test: Expected find to succeed");
+        let names: Vec<&str> = found.iter().map(|u| u.name.as_str()).collect();
+        assert_eq!(names, vec!["Joe", "Johnny"]);
+    }
+}
@@ -1,4 +1,9 @@
+pub mod codegen;
+#[cfg(feature = "emulator")]
+pub mod emulator;
 pub mod mapper;
 pub mod orm;
 pub mod spread_sheet_driver;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 pub mod types;
@@ -1,8 +1,10 @@
-use crate::types::{Letters, SpreadSheetDateTime};
+use crate::types::{Json, Letters, LinkTemplate, LinkedId, SpreadSheetDateTime, hyperlink_label};
 use derive_more::Deref;
 use derive_more::with_trait::From;
 use error_stack::{Context, Report, ResultExt};
 use google_sheets4::chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
 use std::fmt;
 use std::ops::Deref;
 use std::str::FromStr;
@@ -19,6 +21,16 @@ impl fmt::Display for CellParsingError {
 
 pub type CellSerdeResult<T> = error_stack::Result<T, CellParsingError>;
 
+#[derive(Debug)]
+pub struct CellSerializationError;
+impl Context for CellSerializationError {}
+
+impl fmt::Display for CellSerializationError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str("Could not convert type to cell")
+    }
+}
+
 #[derive(Debug, Deref, From)]
 pub struct SheetRawCell(String);
 
@@ -26,6 +38,10 @@ pub trait SheetRawCellSerde {
     fn serialize(&self) -> SheetRawCell {
         panic!("Serialization is not supported by default. You need explicitly opt in for it")
     }
+    /// Fallible `serialize`, for types whose serialization can fail
+    fn try_serialize(&self) -> error_stack::Result<SheetRawCell, CellSerializationError> {
+        Ok(self.serialize())
+    }
     fn deserialize(cell: SheetRawCell) -> CellSerdeResult<Self>
     where
         Self: Sized;
@@ -67,6 +83,53 @@ impl SheetRawCellSerde for Letters {
     }
 }
 
+impl<T, L> SheetRawCellSerde for LinkedId<T, L>
+where
+    T: SheetRawCellSerde + fmt::Display,
+    L: LinkTemplate,
+{
+    fn serialize(&self) -> SheetRawCell {
+        SheetRawCell(self.to_formula())
+    }
+
+    fn deserialize(cell: SheetRawCell) -> CellSerdeResult<Self>
+    where
+        Self: Sized,
+    {
+        let id = T::deserialize(SheetRawCell(hyperlink_label(&cell)))?;
+        Ok(LinkedId::new(id))
+    }
+}
+
+impl<T> SheetRawCellSerde for Json<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    /// Panics if the payload has no JSON representation, e.g. a map with non-string keys.
+    /// Use [`SheetRawCellSerde::try_serialize`] to handle such payloads
+    fn serialize(&self) -> SheetRawCell {
+        self.try_serialize()
+            .unwrap_or_else(|e| panic!("Can't serialize JSON cell payload: {e:?}"))
+    }
+
+    fn try_serialize(&self) -> error_stack::Result<SheetRawCell, CellSerializationError> {
+        self.to_json()
+            .map(SheetRawCell)
+            .map_err(Report::new)
+            .change_context(CellSerializationError)
+    }
+
+    fn deserialize(cell: SheetRawCell) -> CellSerdeResult<Self>
+    where
+        Self: Sized,
+    {
+        Json::from_json(&cell)
+            .map_err(Report::new)
+            .change_context(CellParsingError)
+            .attach_printable_lazy(|| format!("Input: {:?}", cell))
+    }
+}
+
 /// Third party types
 impl SheetRawCellSerde for DateTime<Utc> {
     fn deserialize(cell: SheetRawCell) -> CellSerdeResult<Self> {
@@ -31,6 +31,13 @@ pub enum ParseError {
 
 pub type SheetRow = Vec<Value>;
 
+/// Integers from 2^53 up aren't exact as a sheet number (a double): 2^53 + 1 is stored as 2^53.
+/// Longer IDs (e.g. snowflake ones) survive round trips only as text, so serialize them as
+/// strings. `InputMode::UserEntered` writes of the driver keep such strings as text, as does
+/// writing their column with `InputMode::Raw`, see
+/// [`crate::orm::table_options::TableOptions::column_input_mode`]
+pub const MAX_EXACT_INTEGER: u64 = 1 << 53;
+
 pub trait SheetRowSerde {
     fn deserialize(row: SheetRow) -> Result<Self>
     where
@@ -46,6 +53,14 @@ pub trait SheetRowExt {
         cell_id: usize,
         column_name: &'static str,
     ) -> Result<T>;
+
+    /// Same as `parse_cell`, but missing and empty cells are parsed as None.
+    /// The API omits trailing empty cells, so optional columns may be absent from the row
+    fn parse_optional_cell<T: SheetRawCellSerde>(
+        &self,
+        cell_id: usize,
+        column_name: &'static str,
+    ) -> Result<Option<T>>;
 }
 impl SheetRowExt for SheetRow {
     fn parse_cell<T: SheetRawCellSerde>(
@@ -71,6 +86,18 @@ impl SheetRowExt for SheetRow {
             })
         })
     }
+
+    fn parse_optional_cell<T: SheetRawCellSerde>(
+        &self,
+        cell_id: usize,
+        column_name: &'static str,
+    ) -> Result<Option<T>> {
+        match self.get(cell_id) {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::String(s)) if s.is_empty() => Ok(None),
+            Some(_) => self.parse_cell(cell_id, column_name).map(Some),
+        }
+    }
 }
 
 fn try_unwrap_value<'a>(
@@ -88,6 +115,65 @@ fn stringify_json_value(value: &Value) -> String {
     match value {
         Value::String(s) => s.clone(),
         Value::Array(_) => panic!("Array is not supported by this crappy implementation"),
+        // Unformatted integers may come as floats, e.g. "3.0", which integer types can't parse.
+        // From 2^53 up the digits may be lost already, so integer types fail on the exponent
+        // form instead of getting a different number
+        Value::Number(n) => match n.as_f64() {
+            Some(f) if n.is_f64() && f.fract() == 0.0 && f.abs() < MAX_EXACT_INTEGER as f64 => {
+                (f as i64).to_string()
+            }
+            _ => n.to_string(),
+        },
         _ => value.to_string(),
     }
 }
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod sheet_row_tests {
+    use super::*;
+
+    #[test]
+    fn parse_optional_cell__missing_and_empty__none() {
+        let row: SheetRow = vec![Value::String(String::new())];
+        assert_eq!(row.parse_optional_cell::<i32>(0, "empty").unwrap(), None);
+        assert_eq!(row.parse_optional_cell::<i32>(1, "missing").unwrap(), None);
+    }
+
+    #[test]
+    fn parse_optional_cell__present__some() {
+        let row: SheetRow = vec![Value::String("42".to_string())];
+        assert_eq!(
+            row.parse_optional_cell::<i32>(0, "answer").unwrap(),
+            Some(42)
+        );
+    }
+
+    #[test]
+    fn parse_cell__integral_float__parsed_as_integer_and_float() {
+        let row: SheetRow = vec![serde_json::json!(3.0), serde_json::json!(0.15)];
+        assert_eq!(row.parse_cell::<i64>(0, "quantity").unwrap(), 3);
+        assert_eq!(row.parse_cell::<f64>(0, "quantity").unwrap(), 3.0);
+        assert_eq!(row.parse_cell::<f64>(1, "discount").unwrap(), 0.15);
+    }
+
+    #[test]
+    fn parse_cell__float_beyond_exact_integers__error_instead_of_other_number() {
+        let row: SheetRow = vec![
+            serde_json::json!(1.2345678901234568e18),
+            Value::String("1234567890123456789".to_string()),
+            serde_json::json!(9007199254740992.0),
+            serde_json::json!(9007199254740991.0),
+        ];
+        assert!(row.parse_cell::<i64>(0, "id").is_err());
+        assert_eq!(row.parse_cell::<i64>(1, "id").unwrap(), 1234567890123456789);
+        assert!(row.parse_cell::<i64>(2, "id").is_err());
+        assert_eq!(row.parse_cell::<i64>(3, "id").unwrap(), 9007199254740991);
+    }
+
+    #[test]
+    fn parse_optional_cell__invalid__err() {
+        let row: SheetRow = vec![Value::String("forty two".to_string())];
+        assert!(row.parse_optional_cell::<i32>(0, "answer").is_err());
+    }
+}
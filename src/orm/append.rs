@@ -0,0 +1,543 @@
+//////////////////////// Multi-row inserts ////////////////////////
+
+use crate::mapper::sheet_row;
+use crate::mapper::sheet_row::SheetRow;
+use crate::orm::audit::{AuditOperation, AuditRecord};
+use crate::orm::identity::{RowIdentity, tag_rows};
+use crate::orm::options::RepositoryOptions;
+use crate::orm::table::Table;
+use crate::orm::{Repository, RepositoryError, Result, convert_into_range};
+use crate::spread_sheet_driver::metadata::{
+    metadata_filter, tag_rows_request, unique_token, untag_request,
+};
+use crate::spread_sheet_driver::responses::AppendSummary;
+use crate::spread_sheet_driver::structure::{delete_rows_request, insert_rows_request};
+use crate::spread_sheet_driver::{IntoStrVec, SpreadSheetDriver};
+use crate::types::{
+    A1CellId, Entity, EntityEssentials, MajorDimension, SheetA1CellId, SheetA1Range,
+};
+use error_stack::{Report, ResultExt, bail};
+use google_sheets4::api::DataFilterValueRange;
+use huh::ErrorStackExt;
+use serde_json::Value;
+use tracing::{debug, warn};
+
+/// Developer metadata key of the temporary tag put on reserved rows
+const RESERVATION_KEY: &str = "google_sheets_driver.reservation";
+
+/// Capacity of the table assumed by [`Repository::insert_many`], the default of
+/// [`crate::types::EntityTable::rows`]
+const INSERT_MANY_ROWS: u32 = 1000;
+
+/// How a block of rows is placed after the last row of the table
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AppendStrategy {
+    /// Single `values.append` call, the API decides where the table ends
+    #[default]
+    Append,
+    /// Inserts blank rows tagged with developer metadata first and then writes into the rows
+    /// found by the tag. Concurrent writers may reorder blocks, but never interleave their rows.
+    /// Not supported by local backends
+    Reserve,
+}
+
+impl Repository {
+    /// Inserts all entities as one contiguous block after the last row of the table.
+    /// `start` and `rows` define the table, same as in [`Repository::insert`]
+    pub async fn insert_all<E>(
+        &self,
+        start: SheetA1CellId,
+        rows: u32,
+        entities: Vec<E>,
+        strategy: AppendStrategy,
+    ) -> Result<Vec<Entity<E>>>
+    where
+        E: EntityEssentials,
+    {
+        if entities.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let data = entities
+            .iter()
+            .map(|e| e.serialize())
+            .collect::<sheet_row::Result<Vec<SheetRow>>>()
+            .change_context(RepositoryError::DriverError)?;
+
+        let written = match self.is_audited() {
+            true => data.clone(),
+            false => vec![],
+        };
+        let range = convert_into_range(&start, rows, E::entity_width());
+        let driver = self.driver.lock().await;
+        let positions = match strategy {
+            AppendStrategy::Append => append_block(&driver, &range, data, &self.options).await?,
+            AppendStrategy::Reserve => {
+                let count = data.len() as u32;
+                let first_row = reserve_block(&driver, &range, data, &self.options).await?;
+                (first_row..first_row + count)
+                    .map(|row| {
+                        SheetA1CellId::new(
+                            &start.sheet_name,
+                            A1CellId::from_primitives(&start.cell.col, row),
+                        )
+                    })
+                    .collect()
+            }
+        };
+        let first_row = positions[0].cell.row.get();
+        debug!(
+            "Inserted {} entities from row {}",
+            entities.len(),
+            first_row
+        );
+
+        let row_tags = match self.identity {
+            RowIdentity::Position => vec![None; entities.len()],
+            RowIdentity::Metadata => {
+                tag_rows(&driver, &start.sheet_name, first_row, entities.len() as u32)
+                    .await?
+                    .into_iter()
+                    .map(Some)
+                    .collect()
+            }
+        };
+
+        drop(driver);
+
+        let inserted: Vec<Entity<E>> = entities
+            .into_iter()
+            .zip(row_tags)
+            .zip(positions)
+            .map(|((data, row_tag), position)| Entity {
+                position,
+                data,
+                row_tag,
+            })
+            .collect();
+        let records = inserted
+            .iter()
+            .zip(&written)
+            .map(|(entity, row)| AuditRecord::new(AuditOperation::Insert, entity, None, Some(row)))
+            .collect();
+        self.record_audit(records).await?;
+        Ok(inserted)
+    }
+}
+
+impl Repository {
+    /// Inserts the entities with a single `values.append` call, see [`Repository::insert_all`].
+    /// Positions of the returned entities are taken from the appended range. The table is
+    /// assumed to hold up to 1000 rows, [`Table::insert_many`] takes the capacity of the table
+    pub async fn insert_many<E>(
+        &self,
+        start: SheetA1CellId,
+        entities: Vec<E>,
+    ) -> Result<Vec<Entity<E>>>
+    where
+        E: EntityEssentials,
+    {
+        self.within_retry_budget(self.insert_all(
+            start,
+            INSERT_MANY_ROWS,
+            entities,
+            AppendStrategy::Append,
+        ))
+        .await
+    }
+}
+
+impl<E> Table<'_, E>
+where
+    E: EntityEssentials,
+{
+    /// Same as [`Repository::insert_many`] with the capacity and the options of the table
+    pub async fn insert_many(&self, entities: Vec<E>) -> Result<Vec<Entity<E>>> {
+        let repository = self.configured_repository();
+        repository
+            .within_retry_budget(repository.insert_all(
+                self.start().clone(),
+                self.rows(),
+                entities,
+                AppendStrategy::Append,
+            ))
+            .await
+    }
+}
+
+/// Returns positions of the written rows, verified against the size of `data`
+async fn append_block(
+    driver: &SpreadSheetDriver,
+    table: &SheetA1Range,
+    data: Vec<SheetRow>,
+    options: &RepositoryOptions,
+) -> Result<Vec<SheetA1CellId>> {
+    let (rows, width) = (
+        data.len(),
+        data.iter().map(Vec::len).max().unwrap_or_default(),
+    );
+    let avr = driver
+        .try_append_rows_as(table.to_string(), data, options.input_mode)
+        .await
+        .change_context(RepositoryError::DriverError)?;
+
+    let updated = match AppendSummary::from_response(&avr) {
+        Ok(appended) => appended.updates.range,
+        Err(e) => {
+            return Err(e.change_context(RepositoryError::UnexpectedResponse {
+                what: "AppendValuesResponse doesn't have a valid 'updates.updated_range'",
+                input: format!("Input range: {:?}", table),
+                response: Box::new(avr),
+            }));
+        }
+    };
+    let Some(positions) = row_positions(&updated, rows, width as u32) else {
+        bail!(RepositoryError::UnexpectedResponse {
+            what: "Updated range doesn't match the size of the written rows",
+            input: format!("{rows} rows of {width} cells into {table:?}"),
+            response: Box::new(avr)
+        });
+    };
+    Ok(positions)
+}
+
+/// Positions of `rows` rows written as one block into `updated`.
+/// `None` if the range is not exactly `rows` high and `width` wide
+pub(crate) fn row_positions(
+    updated: &SheetA1Range,
+    rows: usize,
+    width: u32,
+) -> Option<Vec<SheetA1CellId>> {
+    let start = &updated.range.start;
+    let end = &updated.range.end;
+    let height = end.row.get().checked_sub(start.row.get())? + 1;
+    let columns = end.column().get().checked_sub(start.column().get())? + 1;
+    if height as usize != rows || columns != width {
+        return None;
+    }
+
+    Some(
+        (start.row.get()..=end.row.get())
+            .map(|row| {
+                SheetA1CellId::new(&updated.sheet, A1CellId::from_primitives(&start.col, row))
+            })
+            .collect(),
+    )
+}
+
+/// Returns 1-based number of the first written row
+async fn reserve_block(
+    driver: &SpreadSheetDriver,
+    table: &SheetA1Range,
+    data: Vec<SheetRow>,
+    options: &RepositoryOptions,
+) -> Result<u32> {
+    let occupied = driver
+        .try_get_range_with(table, &options.read_options())
+        .await
+        .change_context(RepositoryError::DriverError)?
+        .into_vec()
+        .len() as u32;
+    let sheet_id = driver
+        .try_get_sheet_id(&table.sheet)
+        .await
+        .change_context(RepositoryError::DriverError)?;
+
+    // 0-based index of the first row after the table
+    let start_index = table.range.start.row.get() - 1 + occupied;
+    let count = data.len() as u32;
+    let token = unique_token();
+    driver
+        .try_batch_update(vec![
+            insert_rows_request(sheet_id, start_index, count),
+            tag_rows_request(sheet_id, start_index, count, RESERVATION_KEY, &token),
+        ])
+        .await
+        .change_context(RepositoryError::DriverError)?;
+
+    // The tag spans whole rows, so values are written from column A.
+    // Null cells are skipped by the API, which keeps columns before the table untouched
+    let padding = table.range.start.column().get() as usize - 1;
+    let written = driver
+        .try_write_by_data_filter(
+            vec![DataFilterValueRange {
+                data_filter: Some(metadata_filter(RESERVATION_KEY, &token)),
+                major_dimension: Some(MajorDimension::Rows.to_string()),
+                values: Some(data.into_iter().map(|row| pad_row(row, padding)).collect()),
+            }],
+            options.input_mode,
+        )
+        .await;
+    let response = match written {
+        Ok(response) => response,
+        Err(e) => {
+            // Blank rows would be a hole for the next append and a stale tag could be matched
+            // by a later reservation, so the sheet is put back the way it was
+            if let Err(release) = driver
+                .try_batch_update(vec![
+                    untag_request(metadata_filter(RESERVATION_KEY, &token)),
+                    delete_rows_request(sheet_id, start_index, count),
+                ])
+                .await
+            {
+                warn!(
+                    "Can't release reserved rows {}: {}",
+                    token,
+                    release.to_string_no_bt()
+                );
+            }
+            driver.invalidate_sheets_cache();
+            return Err(e.change_context(RepositoryError::DriverError));
+        }
+    };
+
+    if let Err(e) = driver
+        .try_batch_update(vec![untag_request(metadata_filter(
+            RESERVATION_KEY,
+            &token,
+        ))])
+        .await
+    {
+        warn!(
+            "Can't remove reservation tag {}: {}",
+            token,
+            e.to_string_no_bt()
+        );
+    }
+
+    let updated_range = response
+        .responses
+        .as_ref()
+        .and_then(|r| r.first())
+        .and_then(|r| r.updated_range.clone());
+    let Some(updated_range) = updated_range else {
+        return Err(Report::new(RepositoryError::DriverError)
+            .attach_printable(format!("Reserved rows were not written: {response:?}")));
+    };
+
+    let updated =
+        SheetA1Range::from_raw(updated_range).change_context(RepositoryError::ParsingError)?;
+    Ok(updated.range.start.row.get())
+}
+
+fn pad_row(row: SheetRow, padding: usize) -> SheetRow {
+    std::iter::repeat_n(Value::Null, padding)
+        .chain(row)
+        .collect()
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod append_tests {
+    use super::*;
+    use crate::spread_sheet_driver::backend::SheetsBackend;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use crate::spread_sheet_driver::{SpreadSheetDriverError, SsdResult};
+    use crate::testing::examples_support::User;
+    use crate::testing::fixtures::backend_repository;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[test]
+    fn pad_row__with_offset__prepends_nulls() {
+        let row = vec![Value::from("1")];
+        assert_eq!(pad_row(row.clone(), 0), row);
+        assert_eq!(
+            pad_row(row, 2),
+            vec![Value::Null, Value::Null, Value::from("1")]
+        );
+    }
+
+    #[test]
+    fn row_positions__matching_size__one_per_row() {
+        let updated = SheetA1Range::from_raw("users!B4:C6").expect("Test: Expected range");
+
+        let positions = row_positions(&updated, 3, 2).expect("Test: Expected positions");
+
+        let cells: Vec<String> = positions.iter().map(|p| p.cell.to_string()).collect();
+        assert_eq!(cells, vec!["B4", "B5", "B6"]);
+        assert_eq!(row_positions(&updated, 2, 2), None);
+        assert_eq!(row_positions(&updated, 3, 3), None);
+    }
+
+    #[tokio::test]
+    async fn insert_all__append_strategy__positions_follow_existing_rows() {
+        let backend = MemoryBackend::new();
+        backend
+            .workbook()
+            .set_sheet("users", vec![vec![Value::from("1"), Value::from("Joe")]]);
+        let repository = backend_repository(backend);
+
+        let users = vec![
+            User {
+                id: 2,
+                name: "John".to_string(),
+            },
+            User {
+                id: 3,
+                name: "Jane".to_string(),
+            },
+        ];
+        let start = SheetA1CellId::from_primitives("users", "A", 1);
+        let inserted = repository
+            .insert_all(start.clone(), 10, users.clone(), AppendStrategy::Append)
+            .await
+            .expect("Test: Expected insert to succeed");
+
+        let positions: Vec<String> = inserted
+            .iter()
+            .map(|e| e.position().cell.to_string())
+            .collect();
+        assert_eq!(positions, vec!["A2", "A3"]);
+
+        let found: Vec<Entity<User>> = repository
+            .find_in_range(&start, 3)
+            .await
+            .expect("Test: Expected find to succeed");
+        assert_eq!(found[1..], inserted[..]);
+    }
+
+    /// Memory backend which applies row insertions and deletions and keeps the developer
+    /// metadata tags, while every write by data filter fails
+    #[derive(Debug, Default)]
+    struct FailingWriteBackend {
+        memory: Arc<MemoryBackend>,
+        tags: Arc<std::sync::Mutex<Vec<Value>>>,
+    }
+
+    impl SheetsBackend for FailingWriteBackend {
+        fn handle(&self, operation: &str, request: &Value) -> SsdResult<Value> {
+            match operation {
+                "spreadsheets.get" => Ok(json!({
+                    "sheets": [{ "properties": { "sheetId": 0, "title": "users" } }]
+                })),
+                "spreadsheets.batchUpdate" => {
+                    let mut tags = self.tags.lock().expect("Test: Expected lock");
+                    let mut workbook = self.memory.workbook();
+                    let mut sheet = workbook.sheet("users");
+                    let rows = |dimension: &Value| {
+                        let index = |key: &str| dimension["range"][key].as_u64().unwrap_or(0);
+                        index("startIndex") as usize..index("endIndex") as usize
+                    };
+                    for request in request["requests"].as_array().into_iter().flatten() {
+                        if !request["insertDimension"].is_null() {
+                            let inserted = rows(&request["insertDimension"]);
+                            sheet.splice(inserted.start..inserted.start, inserted.map(|_| vec![]));
+                        }
+                        if !request["deleteDimension"].is_null() {
+                            sheet.drain(rows(&request["deleteDimension"]));
+                        }
+                        let created = &request["createDeveloperMetadata"]["developerMetadata"];
+                        if !created.is_null() {
+                            tags.push(created["metadataValue"].clone());
+                        }
+                        let deleted = &request["deleteDeveloperMetadata"]["dataFilter"];
+                        if !deleted.is_null() {
+                            let value = &deleted["developerMetadataLookup"]["metadataValue"];
+                            tags.retain(|tag| tag != value);
+                        }
+                    }
+                    workbook.set_sheet("users", sheet);
+                    Ok(json!({ "replies": [] }))
+                }
+                "values.batchUpdateByDataFilter" => bail!(SpreadSheetDriverError::ApiError(
+                    "Test: write failed".to_string()
+                )),
+                _ => self.memory.handle(operation, request),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn insert_all__reserve_write_fails__reserved_rows_and_tag_removed() {
+        let backend = FailingWriteBackend::default();
+        let original = vec![
+            vec![Value::from("1"), Value::from("Joe")],
+            vec![],
+            vec![Value::from("Notes below the table")],
+        ];
+        backend
+            .memory
+            .workbook()
+            .set_sheet("users", original.clone());
+        let (memory, tags) = (backend.memory.clone(), backend.tags.clone());
+        let repository = backend_repository(backend);
+
+        let error = repository
+            .insert_all(
+                SheetA1CellId::from_primitives("users", "A", 1),
+                1,
+                vec![User {
+                    id: 2,
+                    name: "John".to_string(),
+                }],
+                AppendStrategy::Reserve,
+            )
+            .await
+            .expect_err("Test: Expected the write to fail");
+
+        assert!(matches!(
+            error.current_context(),
+            RepositoryError::DriverError
+        ));
+        assert_eq!(memory.workbook().sheet("users"), original);
+        assert!(tags.lock().expect("Test: Expected lock").is_empty());
+    }
+
+    #[tokio::test]
+    async fn insert_many__table__positions_from_appended_range() {
+        let backend = MemoryBackend::new();
+        backend.workbook().set_sheet(
+            "users",
+            vec![
+                vec![Value::Null, Value::from("1"), Value::from("Joe")],
+                vec![Value::Null, Value::from("2"), Value::from("John")],
+            ],
+        );
+        let repository = backend_repository(backend);
+        let table = repository.table::<User>(SheetA1CellId::from_primitives("users", "B", 1), 10);
+
+        let inserted = table
+            .insert_many(vec![
+                User {
+                    id: 3,
+                    name: "Jane".to_string(),
+                },
+                User {
+                    id: 4,
+                    name: "Jim".to_string(),
+                },
+            ])
+            .await
+            .expect("Test: Expected insert to succeed");
+
+        let positions: Vec<String> = inserted.iter().map(|e| e.position().to_string()).collect();
+        assert_eq!(positions, vec!["users!B3", "users!B4"]);
+        let found = table
+            .find_all()
+            .await
+            .expect("Test: Expected find to succeed");
+        assert_eq!(found[2..], inserted[..]);
+    }
+
+    #[tokio::test]
+    async fn insert_many__repository__single_append_after_the_table() {
+        let backend = MemoryBackend::new();
+        backend
+            .workbook()
+            .set_sheet("users", vec![vec![Value::from("1"), Value::from("Joe")]]);
+        let repository = backend_repository(backend);
+
+        let inserted = repository
+            .insert_many(
+                SheetA1CellId::from_primitives("users", "A", 1),
+                vec![User::new(2, "John"), User::new(3, "Jane")],
+            )
+            .await
+            .expect("Test: Expected insert to succeed");
+
+        let positions: Vec<String> = inserted.iter().map(|e| e.position().to_string()).collect();
+        assert_eq!(positions, vec!["users!A2", "users!A3"]);
+        assert_eq!(inserted[1].data(), &User::new(3, "Jane"));
+    }
+}
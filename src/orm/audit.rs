@@ -0,0 +1,188 @@
+//////////////////////// Audit trail of ORM mutations ////////////////////////
+
+use crate::mapper::sheet_row::SheetRow;
+use crate::orm::{Repository, RepositoryError, Result, convert_into_range};
+use crate::types::{Entity, EntityEssentials, InputMode, SheetA1CellId};
+use derive_more::Display;
+use error_stack::ResultExt;
+use google_sheets4::chrono::{SecondsFormat, Utc};
+use serde_json::Value;
+
+/// Number of columns of an audit record: timestamp, operation, entity key, old and new values
+pub const AUDIT_WIDTH: u32 = 5;
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+pub enum AuditOperation {
+    #[display("insert")]
+    Insert,
+    #[display("update")]
+    Update,
+    #[display("delete")]
+    Delete,
+}
+
+/// Single row of the audit sheet
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditRecord {
+    /// RFC 3339 UTC time of the mutation
+    pub timestamp: String,
+    pub operation: AuditOperation,
+    /// Row id of the entity (see [`crate::orm::identity::RowIdentity::Metadata`]) or its A1 position
+    pub entity_key: String,
+    /// Cells as a JSON array, empty for inserts
+    pub old_values: String,
+    /// Cells as a JSON array, empty for deletes
+    pub new_values: String,
+}
+
+impl AuditRecord {
+    pub fn new<E>(
+        operation: AuditOperation,
+        entity: &Entity<E>,
+        old: Option<&SheetRow>,
+        new: Option<&SheetRow>,
+    ) -> Self
+    where
+        E: EntityEssentials,
+    {
+        let cells = |row: Option<&SheetRow>| {
+            row.map(|row| Value::Array(row.clone()).to_string())
+                .unwrap_or_default()
+        };
+        let position = entity.position();
+        Self {
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            operation,
+            entity_key: entity
+                .row_tag()
+                .map(str::to_string)
+                .unwrap_or_else(|| position.to_string()),
+            old_values: cells(old),
+            new_values: cells(new),
+        }
+    }
+
+    fn to_row(&self) -> SheetRow {
+        vec![
+            Value::String(self.timestamp.clone()),
+            Value::String(self.operation.to_string()),
+            Value::String(self.entity_key.clone()),
+            Value::String(self.old_values.clone()),
+            Value::String(self.new_values.clone()),
+        ]
+    }
+}
+
+/// Table the audit records are appended to, e.g. a dedicated "audit" sheet
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditLog {
+    pub start: SheetA1CellId,
+    pub rows: u32,
+}
+
+impl Repository {
+    /// Opts in for the audit trail: every insert, update, delete and applied sync plan
+    /// appends a record to `log` right after the mutation succeeds.
+    /// Updates read the row first to record the old values.
+    /// The API can't append and update in one request, so a failed audit append fails the
+    /// call while the mutation itself stays applied
+    pub fn with_audit(mut self, log: AuditLog) -> Self {
+        self.audit = Some(log);
+        self
+    }
+
+    pub fn audit_log(&self) -> Option<&AuditLog> {
+        self.audit.as_ref()
+    }
+
+    pub(crate) fn is_audited(&self) -> bool {
+        self.audit.is_some()
+    }
+
+    /// Appends the records as one block, no-op if audit is off
+    pub(crate) async fn record_audit(&self, records: Vec<AuditRecord>) -> Result<()> {
+        let Some(log) = &self.audit else {
+            return Ok(());
+        };
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        let range = convert_into_range(&log.start, log.rows, AUDIT_WIDTH);
+        self.driver
+            .lock()
+            .await
+            .try_append_rows_as(
+                range.to_string(),
+                records.iter().map(AuditRecord::to_row).collect(),
+                InputMode::Raw,
+            )
+            .await
+            .change_context(RepositoryError::DriverError)
+            .attach_printable("Can't append audit records")?;
+        Ok(())
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod audit_tests {
+    use super::*;
+    use crate::spread_sheet_driver::IntoStrVec;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use crate::testing::examples_support::User;
+    use crate::testing::fixtures::shared_driver;
+
+    #[tokio::test]
+    async fn insert_and_update__audited__records_appended() {
+        let driver = shared_driver(MemoryBackend::new());
+        let repository = Repository::new(driver.clone()).with_audit(AuditLog {
+            start: SheetA1CellId::from_primitives("audit", "A", 1),
+            rows: 100,
+        });
+        let start = SheetA1CellId::from_primitives("users", "A", 1);
+
+        let mut joe = repository
+            .insert(
+                start,
+                10,
+                User {
+                    id: 1,
+                    name: "Joe".to_string(),
+                },
+            )
+            .await
+            .expect("Test: Expected insert to succeed");
+        joe.name = "Joseph".to_string();
+        repository
+            .update(&joe)
+            .await
+            .expect("Test: Expected update to succeed");
+
+        let audit = driver
+            .lock()
+            .await
+            .try_get_range("audit!A1:E10")
+            .await
+            .expect("Test: Expected audit to be readable")
+            .into_vec();
+        let records: Vec<Vec<Value>> = audit.into_iter().map(|row| row[1..].to_vec()).collect();
+        assert_eq!(
+            records,
+            vec![
+                vec![
+                    Value::from("insert"),
+                    Value::from("users!A1"),
+                    Value::from(""),
+                    Value::from(r#"["1","Joe"]"#),
+                ],
+                vec![
+                    Value::from("update"),
+                    Value::from("users!A1"),
+                    Value::from(r#"["1","Joe"]"#),
+                    Value::from(r#"["1","Joseph"]"#),
+                ],
+            ]
+        );
+    }
+}
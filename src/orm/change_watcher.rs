@@ -0,0 +1,342 @@
+//////////////////////// Polling change subscriptions ////////////////////////
+
+use crate::orm::Result;
+use crate::orm::snapshot::{Snapshot, SnapshotDiff};
+use crate::orm::table::Table;
+use crate::types::{Entity, EntityEssentials, SheetA1CellId};
+use futures::{Stream, stream};
+use std::collections::VecDeque;
+use std::time::Duration;
+use tracing::debug;
+
+/// Poll interval unless another one is given to [`ChangeWatcher::new`]
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Inserted,
+    Updated,
+    Deleted,
+}
+
+/// Entity level change of a watched table. Rows are matched by position, see [`SnapshotDiff`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChangeEvent<E>
+where
+    E: EntityEssentials,
+{
+    Inserted(Entity<E>),
+    Updated {
+        position: SheetA1CellId,
+        before: E,
+        after: E,
+    },
+    Deleted(Entity<E>),
+}
+
+impl<E> ChangeEvent<E>
+where
+    E: EntityEssentials,
+{
+    pub fn kind(&self) -> ChangeKind {
+        match self {
+            ChangeEvent::Inserted(_) => ChangeKind::Inserted,
+            ChangeEvent::Updated { .. } => ChangeKind::Updated,
+            ChangeEvent::Deleted(_) => ChangeKind::Deleted,
+        }
+    }
+
+    pub fn position(&self) -> &SheetA1CellId {
+        match self {
+            ChangeEvent::Inserted(entity) | ChangeEvent::Deleted(entity) => entity.position(),
+            ChangeEvent::Updated { position, .. } => position,
+        }
+    }
+
+    /// Data after the change, `None` for deletions
+    pub fn after(&self) -> Option<&E> {
+        match self {
+            ChangeEvent::Inserted(entity) => Some(entity.data()),
+            ChangeEvent::Updated { after, .. } => Some(after),
+            ChangeEvent::Deleted(_) => None,
+        }
+    }
+
+    /// Data before the change, `None` for insertions
+    pub fn before(&self) -> Option<&E> {
+        match self {
+            ChangeEvent::Inserted(_) => None,
+            ChangeEvent::Updated { before, .. } => Some(before),
+            ChangeEvent::Deleted(entity) => Some(entity.data()),
+        }
+    }
+}
+
+/// Insertions, then updates and deletions, each in the order of the rows
+fn change_events<E>(diff: SnapshotDiff<E>) -> Vec<ChangeEvent<E>>
+where
+    E: EntityEssentials,
+{
+    let inserted = diff.added.into_iter().map(ChangeEvent::Inserted);
+    let updated = diff
+        .changed
+        .into_iter()
+        .map(|changed| ChangeEvent::Updated {
+            position: changed.position,
+            before: changed.before,
+            after: changed.after,
+        });
+    let deleted = diff.removed.into_iter().map(ChangeEvent::Deleted);
+    inserted.chain(updated).chain(deleted).collect()
+}
+
+/// Detects changes by reading the watched tables every poll interval and diffing their
+/// snapshots. Each table is read by its own [`Subscription`], so bots only pay for the tables
+/// they react to
+#[derive(Debug, Clone)]
+pub struct ChangeWatcher {
+    interval: Duration,
+}
+
+impl Default for ChangeWatcher {
+    fn default() -> Self {
+        Self::new(DEFAULT_POLL_INTERVAL)
+    }
+}
+
+impl ChangeWatcher {
+    pub fn new(interval: Duration) -> Self {
+        Self { interval }
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Changes of the table made after the first read of the subscription.
+    /// Rows present at that moment are not reported
+    pub fn subscribe<'r, E>(&self, table: Table<'r, E>) -> Subscription<'r, E>
+    where
+        E: EntityEssentials,
+    {
+        Subscription {
+            table,
+            interval: self.interval,
+            last: None,
+            pending: VecDeque::new(),
+            filters: vec![],
+        }
+    }
+}
+
+type EventFilter<'r, E> = Box<dyn Fn(&ChangeEvent<E>) -> bool + Send + Sync + 'r>;
+
+/// Async stream of the changes of one table, read with [`Subscription::next_event`]
+pub struct Subscription<'r, E>
+where
+    E: EntityEssentials,
+{
+    table: Table<'r, E>,
+    interval: Duration,
+    /// Snapshot of the previous poll, `None` before the first one
+    last: Option<Snapshot<E>>,
+    /// Events of the last poll not returned by `next_event` yet
+    pending: VecDeque<ChangeEvent<E>>,
+    filters: Vec<EventFilter<'r, E>>,
+}
+
+impl<'r, E> Subscription<'r, E>
+where
+    E: EntityEssentials,
+{
+    /// Starts from a snapshot taken earlier instead of the first read, so changes made in
+    /// between are reported too
+    pub fn with_baseline(mut self, baseline: Snapshot<E>) -> Self {
+        self.last = Some(baseline);
+        self
+    }
+
+    /// Keeps only events matching `filter`, all filters have to match
+    pub fn with_filter<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(&ChangeEvent<E>) -> bool + Send + Sync + 'r,
+    {
+        self.filters.push(Box::new(filter));
+        self
+    }
+
+    /// Keeps only events of the given kinds
+    pub fn only(self, kinds: &[ChangeKind]) -> Self {
+        let kinds = kinds.to_vec();
+        self.with_filter(move |event| kinds.contains(&event.kind()))
+    }
+
+    pub fn table(&self) -> &Table<'r, E> {
+        &self.table
+    }
+
+    /// Reads the table once and returns the matching changes since the previous poll.
+    /// The first poll only remembers the content and returns nothing
+    pub async fn poll(&mut self) -> Result<Vec<ChangeEvent<E>>> {
+        let current = self.table.snapshot().await?;
+        let events = match &self.last {
+            Some(last) => change_events(last.diff(&current)),
+            None => vec![],
+        };
+        self.last = Some(current);
+        debug!(
+            "Polled table at {}, {} changes",
+            self.table.start(),
+            events.len()
+        );
+        Ok(events
+            .into_iter()
+            .filter(|event| self.filters.iter().all(|filter| filter(event)))
+            .collect())
+    }
+
+    /// Next matching change, polling every interval until there's one
+    pub async fn next_event(&mut self) -> Result<ChangeEvent<E>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(event);
+            }
+            if self.last.is_some() {
+                tokio::time::sleep(self.interval).await;
+            }
+            let events = self.poll().await?;
+            self.pending.extend(events);
+        }
+    }
+
+    /// Endless stream of [`Subscription::next_event`], for `StreamExt` combinators and `select!`.
+    /// A failed poll is yielded as an error and the next item polls again
+    pub fn into_stream(self) -> impl Stream<Item = Result<ChangeEvent<E>>> {
+        stream::unfold(self, |mut subscription| async move {
+            let event = subscription.next_event().await;
+            Some((event, subscription))
+        })
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod change_watcher_tests {
+    use super::*;
+    use crate::orm::Repository;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use crate::testing::examples_support::User;
+    use crate::testing::fixtures::backend_repository;
+    use futures::StreamExt;
+    use serde_json::Value;
+
+    fn user(id: i32, name: &str) -> User {
+        User {
+            id,
+            name: name.to_string(),
+        }
+    }
+
+    fn repository() -> Repository {
+        let backend = MemoryBackend::new();
+        backend.workbook().set_sheet(
+            "users",
+            vec![
+                vec![Value::from("1"), Value::from("Joe")],
+                vec![Value::from("2"), Value::from("John")],
+            ],
+        );
+        backend_repository(backend)
+    }
+
+    #[tokio::test]
+    async fn poll__insert_update_delete__entity_events_after_baseline() {
+        let repository = repository();
+        let table = repository.table::<User>(SheetA1CellId::from_primitives("users", "A", 1), 10);
+        let mut subscription = ChangeWatcher::default().subscribe(table.clone());
+
+        let baseline = subscription.poll().await.expect("Test: Expected poll");
+        assert!(baseline.is_empty());
+
+        let mut entities = table.find_all().await.expect("Test: Expected entities");
+        let jane = table
+            .insert(user(3, "Jane"))
+            .await
+            .expect("Test: Expected insert");
+        entities[0].data.name = "Joey".to_string();
+        repository
+            .update(&entities[0])
+            .await
+            .expect("Test: Expected update");
+        let changed = subscription.poll().await.expect("Test: Expected poll");
+        repository
+            .delete(&jane)
+            .await
+            .expect("Test: Expected delete");
+        let deleted = subscription.poll().await.expect("Test: Expected poll");
+
+        let kinds: Vec<ChangeKind> = changed.iter().map(ChangeEvent::kind).collect();
+        assert_eq!(kinds, vec![ChangeKind::Inserted, ChangeKind::Updated]);
+        assert_eq!(changed[0].after(), Some(&user(3, "Jane")));
+        assert_eq!(changed[1].before(), Some(&user(1, "Joe")));
+        assert_eq!(changed[1].after(), Some(&user(1, "Joey")));
+        assert_eq!(deleted, vec![ChangeEvent::Deleted(jane)]);
+    }
+
+    #[tokio::test]
+    async fn next_event__filtered_subscription__only_matching_events() {
+        let repository = repository();
+        let table = repository.table::<User>(SheetA1CellId::from_primitives("users", "A", 1), 10);
+        let baseline = table.snapshot().await.expect("Test: Expected snapshot");
+        let mut subscription = ChangeWatcher::new(Duration::from_millis(1))
+            .subscribe(table.clone())
+            .with_baseline(baseline)
+            .only(&[ChangeKind::Inserted])
+            .with_filter(|event| event.after().is_some_and(|user| user.id > 3));
+
+        for (id, name) in [(3, "Jane"), (4, "Jill")] {
+            table
+                .insert(user(id, name))
+                .await
+                .expect("Test: Expected insert");
+        }
+        let event = subscription
+            .next_event()
+            .await
+            .expect("Test: Expected event");
+
+        assert_eq!(event.after(), Some(&user(4, "Jill")));
+        assert_eq!(
+            event.position(),
+            &SheetA1CellId::from_primitives("users", "A", 4)
+        );
+    }
+
+    #[tokio::test]
+    async fn into_stream__inserted_rows__events_taken_from_stream() {
+        let repository = repository();
+        let table = repository.table::<User>(SheetA1CellId::from_primitives("users", "A", 1), 10);
+        let baseline = table.snapshot().await.expect("Test: Expected snapshot");
+        let stream = ChangeWatcher::new(Duration::from_millis(1))
+            .subscribe(table.clone())
+            .with_baseline(baseline)
+            .into_stream();
+
+        for (id, name) in [(3, "Jane"), (4, "Jill")] {
+            table
+                .insert(user(id, name))
+                .await
+                .expect("Test: Expected insert");
+        }
+        let events: Vec<_> = stream.take(2).collect().await;
+
+        let inserted: Vec<Option<&User>> = events
+            .iter()
+            .map(|event| event.as_ref().expect("Test: Expected event").after())
+            .collect();
+        assert_eq!(
+            inserted,
+            vec![Some(&user(3, "Jane")), Some(&user(4, "Jill"))]
+        );
+    }
+}
@@ -0,0 +1,229 @@
+//////////////////////// Typed column handle ////////////////////////
+
+use crate::mapper::sheet_cell::SheetRawCellSerde;
+use crate::mapper::sheet_row;
+use crate::mapper::sheet_row::SheetRowExt;
+use crate::orm::table::Table;
+use crate::orm::{RepositoryError, Result};
+use crate::spread_sheet_driver::IntoStrVec;
+use crate::types::{A1CellId, A1Range, EntityEssentials, SheetA1CellId, SheetA1Range};
+use error_stack::{ResultExt, bail};
+use serde_json::Value;
+use std::fmt::Display;
+use std::marker::PhantomData;
+
+/// Single column of a table with values of type `T`. Reads and writes only that column,
+/// so e.g. collecting emails doesn't fetch the whole entities
+#[derive(Debug)]
+pub struct TableColumn<'r, E, T>
+where
+    E: EntityEssentials,
+{
+    table: Table<'r, E>,
+    offset: u32,
+    name: &'static str,
+    _value: PhantomData<T>,
+}
+
+impl<'r, E> Table<'r, E>
+where
+    E: EntityEssentials,
+{
+    /// Column under the `name` header of [`EntityEssentials::column_headers`]
+    pub fn column<T>(&self, name: &'static str) -> Result<TableColumn<'r, E, T>>
+    where
+        T: SheetRawCellSerde,
+    {
+        let Some(offset) = E::column_headers()
+            .iter()
+            .position(|&header| header == name)
+        else {
+            bail!(RepositoryError::InvalidArgument(format!(
+                "Entity of the table at {} has no column '{name}'",
+                self.start()
+            )));
+        };
+        Ok(TableColumn {
+            table: self.clone(),
+            offset: offset as u32,
+            name,
+            _value: PhantomData,
+        })
+    }
+}
+
+impl<E, T> TableColumn<'_, E, T>
+where
+    E: EntityEssentials,
+    T: SheetRawCellSerde,
+{
+    /// 0-based offset of the column from the table start column
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// Range of the column over `rows` rows from the table start
+    fn range(&self, rows: u32) -> SheetA1Range {
+        let start = self.table.start();
+        let first = A1CellId::new(start.cell.col.clone() + self.offset, start.cell.row);
+        let last = first.delta(0, rows as i32 - 1);
+        SheetA1Range::new(&start.sheet_name, A1Range::new(first, last))
+    }
+
+    /// One value per row down to the last non-empty one, `None` for empty cells
+    pub async fn read_all(&self) -> Result<Vec<Option<T>>> {
+        let repository = self.table.configured_repository();
+        let rows = repository
+            .driver
+            .lock()
+            .await
+            .try_get_range_with(
+                &self.range(self.table.rows()),
+                &repository.options().read_options(),
+            )
+            .await
+            .change_context(RepositoryError::DriverError)?
+            .into_vec();
+
+        rows.iter()
+            .map(|row| row.parse_optional_cell(0, self.name))
+            .collect::<sheet_row::Result<_>>()
+            .change_context(RepositoryError::ParsingError)
+    }
+
+    /// Positions of the entities (at the table start column) with `value` in the column
+    pub async fn find(&self, value: &T) -> Result<Vec<SheetA1CellId>>
+    where
+        T: PartialEq,
+    {
+        let start = self.table.start();
+        Ok(self
+            .read_all()
+            .await?
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| cell.as_ref() == Some(value))
+            .map(|(row, _)| SheetA1CellId::new(&start.sheet_name, start.cell.delta(0, row as i32)))
+            .collect())
+    }
+
+    /// Writes `values` down the column from the table start, leaving the rows below intact
+    pub async fn write_all(&self, values: Vec<T>) -> Result<()>
+    where
+        T: Display,
+    {
+        if values.is_empty() {
+            return Ok(());
+        }
+        if values.len() as u32 > self.table.rows() {
+            bail!(RepositoryError::InvalidArgument(format!(
+                "{} values don't fit into the {} rows of the table at {}",
+                values.len(),
+                self.table.rows(),
+                self.table.start()
+            )));
+        }
+
+        let repository = self.table.configured_repository();
+        let input_mode = self
+            .table
+            .options()
+            .column_input_modes
+            .get(&self.offset)
+            .copied()
+            .unwrap_or(repository.options().input_mode);
+        let range = self.range(values.len() as u32);
+        let rows = values
+            .iter()
+            .map(|value| vec![Value::String(value.to_string())])
+            .collect();
+        repository
+            .driver
+            .lock()
+            .await
+            .try_write_range_as(range.to_string().as_str(), rows, input_mode)
+            .await
+            .change_context(RepositoryError::DriverError)?;
+        Ok(())
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod column_tests {
+    use super::*;
+    use crate::orm::Repository;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use crate::testing::examples_support::User;
+    use crate::testing::fixtures::{backend_repository, memory_repository};
+
+    fn repository() -> Repository {
+        let backend = MemoryBackend::new();
+        backend.workbook().set_sheet(
+            "users",
+            vec![
+                vec![Value::from("id"), Value::from("name")],
+                vec![Value::from("1"), Value::from("Joe")],
+                vec![Value::from("2"), Value::from("")],
+                vec![Value::from("3"), Value::from("Jane")],
+            ],
+        );
+        backend_repository(backend)
+    }
+
+    #[tokio::test]
+    async fn read_all_and_find__name_column__values_and_positions() {
+        let repository = repository();
+        let table = repository.table::<User>(SheetA1CellId::from_primitives("users", "A", 2), 10);
+        let names = table
+            .column::<String>("name")
+            .expect("Test: Expected name column");
+
+        let values = names.read_all().await.expect("Test: Expected names");
+        assert_eq!(
+            values,
+            vec![Some("Joe".to_string()), None, Some("Jane".to_string())]
+        );
+        let found = names
+            .find(&"Jane".to_string())
+            .await
+            .expect("Test: Expected search");
+        assert_eq!(found, vec![SheetA1CellId::from_primitives("users", "A", 4)]);
+    }
+
+    #[tokio::test]
+    async fn write_all__ids__only_the_column_written() {
+        let repository = repository();
+        let table = repository.table::<User>(SheetA1CellId::from_primitives("users", "A", 2), 10);
+        let ids = table.column::<i32>("id").expect("Test: Expected id column");
+
+        ids.write_all(vec![10, 20])
+            .await
+            .expect("Test: Expected column write");
+
+        let written = ids.read_all().await.expect("Test: Expected ids");
+        assert_eq!(written, vec![Some(10), Some(20), Some(3)]);
+        let names = table
+            .column::<String>("name")
+            .expect("Test: Expected name column")
+            .read_all()
+            .await
+            .expect("Test: Expected names");
+        assert_eq!(names[0], Some("Joe".to_string()));
+    }
+
+    #[test]
+    fn column__unknown_header__invalid_argument() {
+        let repository = memory_repository();
+        let table = repository.table::<User>(SheetA1CellId::from_primitives("users", "A", 2), 10);
+
+        let report = table
+            .column::<String>("phone")
+            .expect_err("Test: Expected unknown column");
+
+        assert!(matches!(
+            report.current_context(),
+            RepositoryError::InvalidArgument(_)
+        ));
+    }
+}
@@ -0,0 +1,210 @@
+//////////////////////// Single column aggregates ////////////////////////
+
+use crate::orm::table::Table;
+use crate::orm::{RepositoryError, Result};
+use crate::spread_sheet_driver::IntoStrVec;
+use crate::types::{A1CellId, A1Range, Entity, EntityEssentials, SheetA1Range};
+use error_stack::{ResultExt, bail};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+
+/// Aggregates over the non-empty cells of a column.
+/// Numeric aggregates only take cells which parse as numbers into account
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnStats {
+    /// Non-empty cells
+    pub count: usize,
+    /// Distinct non-empty values
+    pub distinct: usize,
+    /// Cells holding numbers
+    pub numeric_count: usize,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub sum: f64,
+}
+
+impl ColumnStats {
+    pub fn from_cells<'a, I>(cells: I) -> Self
+    where
+        I: IntoIterator<Item = &'a Value>,
+    {
+        let mut stats = ColumnStats {
+            count: 0,
+            distinct: 0,
+            numeric_count: 0,
+            min: None,
+            max: None,
+            sum: 0.0,
+        };
+        let mut seen = HashSet::new();
+
+        for cell in cells {
+            let text = match cell {
+                Value::Null => continue,
+                Value::String(s) if s.is_empty() => continue,
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            stats.count += 1;
+
+            if let Ok(number) = text.trim().parse::<f64>() {
+                stats.numeric_count += 1;
+                stats.sum += number;
+                stats.min = Some(stats.min.map_or(number, |min| min.min(number)));
+                stats.max = Some(stats.max.map_or(number, |max| max.max(number)));
+            }
+            seen.insert(text);
+        }
+
+        stats.distinct = seen.len();
+        stats
+    }
+
+    /// Average of the numeric cells
+    pub fn mean(&self) -> Option<f64> {
+        (self.numeric_count > 0).then(|| self.sum / self.numeric_count as f64)
+    }
+}
+
+impl<E> Table<'_, E>
+where
+    E: EntityEssentials,
+{
+    /// Computes stats of the 0-based `column` of the table, reading only that column
+    pub async fn column_stats(&self, column: u32) -> Result<ColumnStats> {
+        if column >= self.width() {
+            bail!(RepositoryError::InvalidArgument(format!(
+                "Column {} is out of the table width {}",
+                column,
+                self.width()
+            )));
+        }
+
+        let start = self.start();
+        let first = A1CellId::new(start.cell.col.clone() + column, start.cell.row);
+        let last = first.delta(0, self.rows() as i32 - 1);
+        let range = SheetA1Range::new(&start.sheet_name, A1Range::new(first, last));
+
+        let rows = self
+            .repository()
+            .driver
+            .lock()
+            .await
+            .try_get_range_with(&range, &self.repository().options().read_options())
+            .await
+            .change_context(RepositoryError::DriverError)?
+            .into_vec();
+
+        Ok(ColumnStats::from_cells(
+            rows.iter().filter_map(|row| row.first()),
+        ))
+    }
+
+    /// Entities grouped by the key, ordered by it. Entities of a group keep the table order.
+    /// Sizes of the groups are the value frequencies, e.g. `table.group_by(|u| u.city.clone())`
+    pub async fn group_by<K, F>(&self, key: F) -> Result<BTreeMap<K, Vec<Entity<E>>>>
+    where
+        K: Ord,
+        F: Fn(&E) -> K,
+    {
+        let mut groups: BTreeMap<K, Vec<Entity<E>>> = BTreeMap::new();
+        for entity in self.find_all().await? {
+            groups.entry(key(entity.data())).or_default().push(entity);
+        }
+        Ok(groups)
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod column_stats_tests {
+    use super::*;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use crate::testing::examples_support::User;
+    use crate::testing::fixtures::backend_repository;
+    use crate::types::SheetA1CellId;
+    use serde_json::json;
+
+    #[test]
+    fn from_cells__mixed_values__ok() {
+        let cells = [
+            json!(3),
+            json!("1.5"),
+            json!(""),
+            json!("n/a"),
+            json!(3),
+            Value::Null,
+        ];
+
+        let stats = ColumnStats::from_cells(&cells);
+
+        assert_eq!(stats.count, 4);
+        assert_eq!(stats.distinct, 3);
+        assert_eq!(stats.numeric_count, 3);
+        assert_eq!(stats.min, Some(1.5));
+        assert_eq!(stats.max, Some(3.0));
+        assert_eq!(stats.sum, 7.5);
+        assert_eq!(stats.mean(), Some(2.5));
+    }
+
+    #[tokio::test]
+    async fn column_stats__table_column__reads_single_column() {
+        let backend = MemoryBackend::new();
+        backend.workbook().set_sheet(
+            "users",
+            vec![
+                vec![json!("1"), json!("Joe")],
+                vec![json!("2"), json!("John")],
+                vec![json!("3"), json!("Joe")],
+            ],
+        );
+        let repository = backend_repository(backend);
+        let table = repository.table::<User>(SheetA1CellId::from_primitives("users", "A", 1), 10);
+
+        let ids = table
+            .column_stats(0)
+            .await
+            .expect("Test: Expected id stats");
+        let names = table
+            .column_stats(1)
+            .await
+            .expect("Test: Expected name stats");
+
+        assert_eq!((ids.count, ids.sum, ids.max), (3, 6.0, Some(3.0)));
+        assert_eq!(
+            (names.count, names.distinct, names.numeric_count),
+            (3, 2, 0)
+        );
+        assert!(table.column_stats(2).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn group_by__name__ordered_groups_in_table_order() {
+        let backend = MemoryBackend::new();
+        backend.workbook().set_sheet(
+            "users",
+            vec![
+                vec![json!("1"), json!("Joe")],
+                vec![json!("2"), json!("John")],
+                vec![json!("3"), json!("Joe")],
+            ],
+        );
+        let repository = backend_repository(backend);
+        let table = repository.table::<User>(SheetA1CellId::from_primitives("users", "A", 1), 10);
+
+        let groups = table
+            .group_by(|user| user.name.clone())
+            .await
+            .expect("Test: Expected groups");
+
+        let ids: Vec<(&str, Vec<i32>)> = groups
+            .iter()
+            .map(|(name, users)| (name.as_str(), users.iter().map(|u| u.id).collect()))
+            .collect();
+        assert_eq!(ids, vec![("Joe", vec![1, 3]), ("John", vec![2])]);
+        assert_eq!(
+            groups["John"][0].position(),
+            &SheetA1CellId::from_primitives("users", "A", 2)
+        );
+    }
+}
@@ -0,0 +1,108 @@
+//////////////////////// Bounded concurrency of repository operations ////////////////////////
+
+use crate::orm::{Repository, RepositoryError, Result};
+use error_stack::bail;
+use futures::StreamExt;
+use futures::stream;
+use tracing::debug;
+
+impl Repository {
+    /// Runs `operation` for every item with at most `max_in_flight` of them at a time, instead
+    /// of a `join_all` which sends everything at once and runs into the API quota.
+    /// All the calls share one retry budget (see [`Repository::within_retry_budget`]), so a
+    /// failing API doesn't get `max_retries` per item, and the rate limit of the driver (see
+    /// [`crate::spread_sheet_driver::SpreadSheetDriver::with_rate_limit`]) if it has one.
+    /// The results are in the order of `items`, a failed operation doesn't stop the others.
+    ///
+    /// The operations run on the current task and the driver sends one call at a time behind
+    /// its lock: concurrency overlaps the work around the calls (serialization, parsing,
+    /// delays of the operations), not the calls themselves
+    pub async fn for_each_concurrent<T, R, F, Fut>(
+        &self,
+        items: impl IntoIterator<Item = T>,
+        max_in_flight: usize,
+        operation: F,
+    ) -> Result<Vec<Result<R>>>
+    where
+        F: Fn(T) -> Fut,
+        Fut: Future<Output = Result<R>>,
+    {
+        if max_in_flight == 0 {
+            bail!(RepositoryError::InvalidArgument(
+                "max_in_flight must be greater than 0".to_string()
+            ));
+        }
+
+        let budget = self.driver.lock().await.retry_budget();
+        let run = stream::iter(items)
+            .map(operation)
+            .buffered(max_in_flight)
+            .collect::<Vec<_>>();
+        let results = match budget {
+            Some(budget) => budget.scope(run).await,
+            None => run.await,
+        };
+
+        debug!("Ran {} operations concurrently", results.len());
+        Ok(results)
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod concurrent_tests {
+    use super::*;
+    use crate::testing::examples_support::User;
+    use crate::testing::fixtures::memory_repository;
+    use crate::types::SheetA1CellId;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn for_each_concurrent__many_inserts__bounded_and_in_order() {
+        let repository = memory_repository();
+        let start = SheetA1CellId::from_primitives("users", "A", 1);
+        let (running, most) = (AtomicUsize::new(0), AtomicUsize::new(0));
+
+        let results = repository
+            .for_each_concurrent(1..=5, 2, |id| {
+                let (repository, start, running, most) = (&repository, &start, &running, &most);
+                async move {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    most.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(1)).await;
+                    let user = User {
+                        id,
+                        name: format!("user {id}"),
+                    };
+                    let inserted = repository.insert(start.clone(), 100, user).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    inserted.map(|entity| entity.data.id)
+                }
+            })
+            .await
+            .expect("Test: Expected operations to run");
+
+        let ids: Vec<i32> = results
+            .into_iter()
+            .map(|result| result.expect("Test: Expected insert"))
+            .collect();
+        assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+        assert_eq!(most.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn for_each_concurrent__zero_in_flight__invalid_argument() {
+        let repository = memory_repository();
+
+        let err = repository
+            .for_each_concurrent(vec![1], 0, |id: i32| async move { Ok(id) })
+            .await
+            .expect_err("Test: Expected zero concurrency to be rejected");
+
+        assert!(matches!(
+            err.current_context(),
+            RepositoryError::InvalidArgument(_)
+        ));
+    }
+}
@@ -0,0 +1,213 @@
+//////////////////////// Duplicate rows detection and cleanup ////////////////////////
+
+use crate::orm::table::Table;
+use crate::orm::{RepositoryError, Result};
+use crate::spread_sheet_driver::structure::delete_rows_request;
+use crate::types::{Entity, EntityEssentials};
+use error_stack::ResultExt;
+use std::collections::HashMap;
+use std::hash::Hash;
+use tracing::info;
+
+/// Which entity of a duplicate group survives [`Table::dedupe`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DedupeStrategy {
+    /// The topmost row
+    #[default]
+    KeepFirst,
+    /// The bottommost row, e.g. when rows are appended as newer versions
+    KeepLast,
+}
+
+impl<E> Table<'_, E>
+where
+    E: EntityEssentials,
+{
+    /// Groups of entities with the same key, in the order of the first row of each group.
+    /// Entities without duplicates are not returned
+    pub async fn find_duplicates<K, F>(&self, key_fn: F) -> Result<Vec<Vec<Entity<E>>>>
+    where
+        K: Eq + Hash,
+        F: Fn(&E) -> K,
+    {
+        Ok(group_duplicates(self.find_all().await?, key_fn))
+    }
+
+    /// Deletes all but one entity of every duplicate group with a single batch update
+    /// and returns the deleted entities.
+    /// Whole sheet rows are deleted, so anything next to the table in those rows goes as well
+    pub async fn dedupe<K, F>(&self, key_fn: F, strategy: DedupeStrategy) -> Result<Vec<Entity<E>>>
+    where
+        K: Eq + Hash,
+        F: Fn(&E) -> K,
+    {
+        let mut removed: Vec<Entity<E>> = self
+            .find_duplicates(key_fn)
+            .await?
+            .into_iter()
+            .flat_map(|mut group| {
+                match strategy {
+                    DedupeStrategy::KeepFirst => group.remove(0),
+                    DedupeStrategy::KeepLast => group.pop().expect("Expected non-empty group"),
+                };
+                group
+            })
+            .collect();
+        if removed.is_empty() {
+            return Ok(removed);
+        }
+
+        // Bottom up, so deletions don't shift rows which are yet to be deleted
+        removed.sort_by_key(|e| std::cmp::Reverse(e.position.cell.row.get()));
+        let driver = self.repository().driver.lock().await;
+        let sheet_id = driver
+            .try_get_sheet_id(&self.start().sheet_name)
+            .await
+            .change_context(RepositoryError::DriverError)?;
+        let requests = removed
+            .iter()
+            .map(|e| delete_rows_request(sheet_id, e.position.cell.row.get() - 1, 1))
+            .collect();
+        driver
+            .try_batch_update(requests)
+            .await
+            .change_context(RepositoryError::DriverError)?;
+
+        info!(
+            "Removed {} duplicates from {:?}",
+            removed.len(),
+            self.start()
+        );
+        removed.reverse();
+        Ok(removed)
+    }
+}
+
+fn group_duplicates<E, K, F>(entities: Vec<Entity<E>>, key_fn: F) -> Vec<Vec<Entity<E>>>
+where
+    E: EntityEssentials,
+    K: Eq + Hash,
+    F: Fn(&E) -> K,
+{
+    let mut groups: Vec<Vec<Entity<E>>> = vec![];
+    let mut index: HashMap<K, usize> = HashMap::new();
+    for entity in entities {
+        let key = key_fn(&entity.data);
+        match index.get(&key) {
+            Some(&i) => groups[i].push(entity),
+            None => {
+                index.insert(key, groups.len());
+                groups.push(vec![entity]);
+            }
+        }
+    }
+    groups.retain(|group| group.len() > 1);
+    groups
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod dedupe_tests {
+    use super::*;
+    use crate::spread_sheet_driver::cassette::Interaction;
+    use crate::testing::examples_support::User;
+    use crate::testing::fixtures::{MatchedValueRangeBuilder, replay_repository};
+    use crate::types::{SheetA1CellId, SheetGid};
+    use google_sheets4::api::{
+        BatchGetValuesByDataFilterResponse, BatchUpdateSpreadsheetResponse, Sheet, SheetProperties,
+        Spreadsheet,
+    };
+    use serde::Serialize;
+    use serde_json::{Value, json};
+
+    fn interaction<T>(operation: &str, request: Value, response: T) -> Interaction
+    where
+        T: Serialize,
+    {
+        Interaction {
+            operation: operation.to_string(),
+            request,
+            response: serde_json::to_value(response).expect("Test: Expected to serialize"),
+        }
+    }
+
+    fn read_interaction() -> Interaction {
+        let values = BatchGetValuesByDataFilterResponse {
+            value_ranges: Some(vec![
+                MatchedValueRangeBuilder::new("users!A1:B11")
+                    .row(["1", "Joe"])
+                    .row(["2", "John"])
+                    .row(["1", "Joe"])
+                    .row(["2", "Johnny"])
+                    .row(["1", "Joseph"])
+                    .build(),
+            ]),
+            ..Default::default()
+        };
+        interaction(
+            "values.batchGetByDataFilter",
+            json!({ "range": "users!A1:B11" }),
+            values,
+        )
+    }
+
+    fn rows(entities: &[Entity<User>]) -> Vec<u32> {
+        entities.iter().map(|e| e.position.cell.row.get()).collect()
+    }
+
+    #[tokio::test]
+    async fn find_duplicates__by_id__groups_in_row_order() {
+        let repository = replay_repository(vec![read_interaction()]);
+        let table = repository.table::<User>(SheetA1CellId::from_primitives("users", "A", 1), 10);
+
+        let groups = table
+            .find_duplicates(|user| user.id)
+            .await
+            .expect("Test: Expected duplicates");
+
+        let groups: Vec<Vec<u32>> = groups.iter().map(|g| rows(g)).collect();
+        assert_eq!(groups, vec![vec![1, 3, 5], vec![2, 4]]);
+    }
+
+    #[tokio::test]
+    async fn dedupe__keep_last__deletes_rows_bottom_up() {
+        let spreadsheet = Spreadsheet {
+            sheets: Some(vec![Sheet {
+                properties: Some(SheetProperties {
+                    sheet_id: Some(7),
+                    title: Some("users".to_string()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+        let repository = replay_repository(vec![
+            read_interaction(),
+            interaction(
+                "spreadsheets.get",
+                json!({ "fields": "sheets.properties" }),
+                spreadsheet,
+            ),
+            interaction(
+                "spreadsheets.batchUpdate",
+                json!({
+                    "requests": [
+                        delete_rows_request(SheetGid(7), 2, 1),
+                        delete_rows_request(SheetGid(7), 1, 1),
+                        delete_rows_request(SheetGid(7), 0, 1),
+                    ]
+                }),
+                BatchUpdateSpreadsheetResponse::default(),
+            ),
+        ]);
+        let table = repository.table::<User>(SheetA1CellId::from_primitives("users", "A", 1), 10);
+
+        let removed = table
+            .dedupe(|user| user.id, DedupeStrategy::KeepLast)
+            .await
+            .expect("Test: Expected dedupe to succeed");
+
+        assert_eq!(rows(&removed), vec![1, 2, 3]);
+    }
+}
@@ -0,0 +1,300 @@
+//////////////////////// Discovery of tables on a sheet ////////////////////////
+
+use crate::mapper::sheet_row::SheetRow;
+use crate::orm::migration::cell_text;
+use crate::orm::{Repository, RepositoryError, Result};
+use crate::types::{A1CellId, A1Range, NumCellId, SheetA1CellId, SheetA1Range};
+use error_stack::{ResultExt, bail};
+use std::ops::RangeInclusive;
+use tracing::debug;
+
+/// Block of data found on a sheet: a header row with the rows below it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredTable {
+    /// Header row and the data rows
+    pub range: SheetA1Range,
+    pub headers: Vec<String>,
+    /// Data rows, without the header
+    pub rows: u32,
+}
+
+impl DiscoveredTable {
+    /// First cell below the header, the start [`Repository::table`] takes
+    pub fn start(&self) -> SheetA1CellId {
+        SheetA1CellId::new(&self.range.sheet, self.range.range.start.delta(0, 1))
+    }
+
+    pub fn width(&self) -> u32 {
+        self.headers.len() as u32
+    }
+}
+
+impl Repository {
+    /// Scans the whole grid of `sheet` for rectangular blocks of cells, separated from each
+    /// other by empty rows and columns, whose first row has a header in every column.
+    /// Blocks with an incomplete first row are not taken as tables and skipped.
+    /// Lets generic tools work on sheets nobody described, tables are top to bottom
+    pub async fn discover_tables(&self, sheet: &str) -> Result<Vec<DiscoveredTable>> {
+        let driver = self.driver.lock().await;
+        let properties = driver
+            .try_get_sheets_properties()
+            .await
+            .change_context(RepositoryError::DriverError)?;
+        let Some(properties) = properties
+            .iter()
+            .find(|p| p.title.as_deref() == Some(sheet))
+        else {
+            bail!(RepositoryError::InvalidArgument(format!(
+                "Sheet '{sheet}' is not found"
+            )));
+        };
+        let grid = properties.grid_properties.clone().unwrap_or_default();
+        let rows = grid.row_count.unwrap_or_default().max(0) as u32;
+        let columns = grid.column_count.unwrap_or_default().max(0) as u32;
+        if rows == 0 || columns == 0 {
+            return Ok(vec![]);
+        }
+
+        let last = A1CellId::from(NumCellId::from_primitives(columns - 1, rows - 1));
+        let range = SheetA1Range::new(sheet, A1Range::new(A1CellId::from_primitives("A", 1), last));
+        let values = driver
+            .try_get_values(&range)
+            .await
+            .change_context(RepositoryError::DriverError)?
+            .values
+            .unwrap_or_default();
+        drop(driver);
+
+        let tables: Vec<DiscoveredTable> = find_blocks(&values)
+            .into_iter()
+            .filter_map(|block| block.into_table(sheet, &values))
+            .collect();
+        debug!("Found {} tables on sheet {}", tables.len(), sheet);
+        Ok(tables)
+    }
+}
+
+/// Rectangle of the grid, 0-based and inclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Block {
+    top: usize,
+    left: usize,
+    bottom: usize,
+    right: usize,
+}
+
+impl Block {
+    /// `None` if a header is missing
+    fn into_table(self, sheet: &str, grid: &[SheetRow]) -> Option<DiscoveredTable> {
+        let headers: Vec<String> = (self.left..=self.right)
+            .map(|col| cell(grid, self.top, col))
+            .collect();
+        if headers.iter().any(String::is_empty) {
+            return None;
+        }
+        let corner = |row: usize, col: usize| {
+            A1CellId::from(NumCellId::from_primitives(col as u32, row as u32))
+        };
+        Some(DiscoveredTable {
+            range: SheetA1Range::new(
+                sheet,
+                A1Range::new(corner(self.top, self.left), corner(self.bottom, self.right)),
+            ),
+            headers,
+            rows: (self.bottom - self.top) as u32,
+        })
+    }
+}
+
+fn cell(grid: &[SheetRow], row: usize, col: usize) -> String {
+    grid.get(row)
+        .and_then(|r| r.get(col))
+        .map(cell_text)
+        .unwrap_or_default()
+}
+
+fn is_filled(grid: &[SheetRow], row: usize, col: usize) -> bool {
+    !cell(grid, row, col).is_empty()
+}
+
+/// Blocks of filled cells separated by empty rows and columns, top to bottom
+fn find_blocks(grid: &[SheetRow]) -> Vec<Block> {
+    let width = grid.iter().map(Vec::len).max().unwrap_or_default();
+    if grid.is_empty() || width == 0 {
+        return vec![];
+    }
+    let mut blocks = vec![];
+    split(
+        grid,
+        Block {
+            top: 0,
+            left: 0,
+            bottom: grid.len() - 1,
+            right: width - 1,
+        },
+        &mut blocks,
+    );
+    blocks.sort_by_key(|block| (block.top, block.left));
+    blocks
+}
+
+/// Splits by empty rows, then the parts by empty columns, until the parts don't split anymore.
+/// Columns of one table may hold empty rows which are filled in the neighbour ones
+fn split(grid: &[SheetRow], block: Block, blocks: &mut Vec<Block>) {
+    let filled_row = |row| (block.left..=block.right).any(|col| is_filled(grid, row, col));
+    for (top, bottom) in runs(block.top..=block.bottom, filled_row) {
+        let filled_col = |col| (top..=bottom).any(|row| is_filled(grid, row, col));
+        for (left, right) in runs(block.left..=block.right, filled_col) {
+            let part = Block {
+                top,
+                left,
+                bottom,
+                right,
+            };
+            match part == block {
+                true => blocks.push(part),
+                false => split(grid, part, blocks),
+            }
+        }
+    }
+}
+
+/// Inclusive runs of the consecutive indices which `keep`
+fn runs<F>(indices: RangeInclusive<usize>, keep: F) -> Vec<(usize, usize)>
+where
+    F: Fn(usize) -> bool,
+{
+    let mut runs = vec![];
+    let mut start = None;
+    let end = *indices.end();
+    for index in indices {
+        match (keep(index), start) {
+            (true, None) => start = Some(index),
+            (false, Some(first)) => {
+                runs.push((first, index - 1));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(first) = start {
+        runs.push((first, end));
+    }
+    runs
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod discovery_tests {
+    use super::*;
+    use crate::spread_sheet_driver::SsdResult;
+    use crate::spread_sheet_driver::backend::SheetsBackend;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use crate::testing::fixtures::backend_repository;
+    use serde_json::{Value, json};
+
+    /// Sheet "data" of 20x10 cells, values from the memory
+    #[derive(Debug, Default)]
+    struct GridBackend {
+        memory: MemoryBackend,
+    }
+
+    impl SheetsBackend for GridBackend {
+        fn handle(&self, operation: &str, request: &Value) -> SsdResult<Value> {
+            match operation {
+                "spreadsheets.get" => Ok(json!({
+                    "sheets": [{ "properties": {
+                        "sheetId": 1,
+                        "title": "data",
+                        "gridProperties": { "rowCount": 20, "columnCount": 10 }
+                    }}]
+                })),
+                _ => self.memory.handle(operation, request),
+            }
+        }
+    }
+
+    fn row(cells: &[&str]) -> SheetRow {
+        cells.iter().map(|&cell| Value::from(cell)).collect()
+    }
+
+    #[test]
+    fn find_blocks__side_by_side_and_stacked__separate_blocks() {
+        let grid = vec![
+            row(&["id", "name", "", "sku"]),
+            row(&["1", "Joe", "", "X1"]),
+            row(&["2", "", "", ""]),
+            row(&[]),
+            row(&["", "", "", "total"]),
+        ];
+
+        let blocks = find_blocks(&grid);
+
+        assert_eq!(
+            blocks,
+            vec![
+                Block {
+                    top: 0,
+                    left: 0,
+                    bottom: 2,
+                    right: 1
+                },
+                Block {
+                    top: 0,
+                    left: 3,
+                    bottom: 1,
+                    right: 3
+                },
+                Block {
+                    top: 4,
+                    left: 3,
+                    bottom: 4,
+                    right: 3
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn discover_tables__two_tables__ranges_and_headers() {
+        let backend = GridBackend::default();
+        backend.memory.workbook().set_sheet(
+            "data",
+            vec![
+                row(&[]),
+                row(&["", "id", "name"]),
+                row(&["", "1", "Joe"]),
+                row(&["", "2", "Jane"]),
+                row(&[]),
+                row(&["note", ""]),
+                row(&["", "", "", "", "sku", "price"]),
+                row(&["", "", "", "", "X1", "10"]),
+            ],
+        );
+        let repository = backend_repository(backend);
+
+        let tables = repository
+            .discover_tables("data")
+            .await
+            .expect("Test: Expected discovery");
+
+        assert_eq!(tables.len(), 3);
+        assert_eq!(
+            tables[0].range,
+            SheetA1Range::from_raw("data!B2:C4").unwrap()
+        );
+        assert_eq!(tables[0].headers, vec!["id", "name"]);
+        assert_eq!(tables[0].rows, 2);
+        assert_eq!(
+            tables[0].start(),
+            SheetA1CellId::from_primitives("data", "B", 3)
+        );
+        assert_eq!(tables[1].headers, vec!["note"]);
+        assert_eq!(tables[1].rows, 0);
+        assert_eq!(
+            tables[2].range,
+            SheetA1Range::from_raw("data!E7:F8").unwrap()
+        );
+        assert_eq!(tables[2].headers, vec!["sku", "price"]);
+    }
+}
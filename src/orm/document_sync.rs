@@ -0,0 +1,204 @@
+//////////////////////// Sync of a table into another spreadsheet ////////////////////////
+
+use crate::orm::Result;
+use crate::orm::snapshot::Snapshot;
+use crate::orm::sync::PlannedWrite;
+use crate::orm::table::Table;
+use crate::types::{EntityEssentials, SheetA1CellId};
+use tracing::{info, warn};
+
+/// Target row edited since the last sync, which the sync would have overwritten
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncConflict<E> {
+    pub position: SheetA1CellId,
+    /// Content the last sync left in the row
+    pub synced: Option<E>,
+    /// Content of the row in the target now
+    pub target: Option<E>,
+    /// Content the source has for the row, `None` if the row is to be cleared
+    pub source: Option<E>,
+}
+
+/// Outcome of [`Table::sync_from`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentSyncReport<E>
+where
+    E: EntityEssentials,
+{
+    /// Writes made to the target
+    pub applied: Vec<PlannedWrite<E>>,
+    /// Rows left untouched in the target
+    pub conflicts: Vec<SyncConflict<E>>,
+    /// Target content after the sync, the `last_synced` of the next run
+    pub synced: Snapshot<E>,
+}
+
+impl<E> DocumentSyncReport<E>
+where
+    E: EntityEssentials,
+{
+    pub fn has_conflicts(&self) -> bool {
+        !self.conflicts.is_empty()
+    }
+}
+
+impl<E> Table<'_, E>
+where
+    E: EntityEssentials,
+{
+    /// Makes this table contain the rows of `source`, writing only the rows which differ.
+    /// The tables may be in different spreadsheets behind different drivers (and
+    /// credentials), e.g. per-team sheets aggregated into a master one.
+    /// With `last_synced` (the `synced` snapshot of the previous report) rows edited in this
+    /// table since then are not overwritten but reported as conflicts. Without it the source
+    /// wins everywhere
+    pub async fn sync_from(
+        &self,
+        source: &Table<'_, E>,
+        last_synced: Option<&Snapshot<E>>,
+    ) -> Result<DocumentSyncReport<E>> {
+        let rows: Vec<E> = source
+            .find_all()
+            .await?
+            .into_iter()
+            .map(|entity| entity.data)
+            .collect();
+        let repository = self.repository();
+        let mut plan = repository.plan_sync(self, rows).await?;
+
+        let mut conflicts = vec![];
+        if let Some(last_synced) = last_synced {
+            let current = plan.base().clone();
+            plan.retain_writes(|write| {
+                let position = write.position();
+                let synced = data_at(last_synced, position);
+                let target = data_at(&current, position);
+                if synced == target {
+                    return true;
+                }
+                let source = match write {
+                    PlannedWrite::Insert { data, .. }
+                    | PlannedWrite::Update { after: data, .. } => Some(data.clone()),
+                    PlannedWrite::Clear { .. } => None,
+                };
+                conflicts.push(SyncConflict {
+                    position: position.clone(),
+                    synced,
+                    target,
+                    source,
+                });
+                false
+            });
+        }
+
+        let applied = plan.writes().to_vec();
+        if !plan.is_empty() {
+            repository.apply(plan).await?;
+        }
+        if !conflicts.is_empty() {
+            warn!(
+                "{} rows of {} are edited since the last sync and kept",
+                conflicts.len(),
+                self.start()
+            );
+        }
+        info!(
+            "Synced {} into {}: {} writes",
+            source.start(),
+            self.start(),
+            applied.len()
+        );
+
+        Ok(DocumentSyncReport {
+            applied,
+            conflicts,
+            synced: self.snapshot().await?,
+        })
+    }
+}
+
+fn data_at<E>(snapshot: &Snapshot<E>, position: &SheetA1CellId) -> Option<E>
+where
+    E: EntityEssentials,
+{
+    snapshot
+        .entities()
+        .iter()
+        .find(|entity| entity.position() == position)
+        .map(|entity| entity.data().clone())
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod document_sync_tests {
+    use super::*;
+    use crate::orm::Repository;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use crate::testing::examples_support::User;
+    use crate::testing::fixtures::backend_repository;
+    use crate::types::Entity;
+    use serde_json::Value;
+
+    fn user(id: i32, name: &str) -> User {
+        User {
+            id,
+            name: name.to_string(),
+        }
+    }
+
+    fn repository(sheet: &str, rows: &[(i32, &str)]) -> Repository {
+        let backend = MemoryBackend::new();
+        backend.workbook().set_sheet(
+            sheet,
+            rows.iter()
+                .map(|(id, name)| vec![Value::from(id.to_string()), Value::from(*name)])
+                .collect(),
+        );
+        backend_repository(backend)
+    }
+
+    #[tokio::test]
+    async fn sync_from__target_row_edited_since_last_sync__kept_and_reported() {
+        let team = repository("team", &[(1, "Joe"), (2, "John"), (3, "Jane")]);
+        let master = repository("master", &[(1, "Joe"), (2, "Johnny")]);
+        let source = team.table::<User>(SheetA1CellId::from_primitives("team", "A", 1), 10);
+        let target = master.table::<User>(SheetA1CellId::from_primitives("master", "A", 1), 10);
+        let position = |row| SheetA1CellId::from_primitives("master", "A", row);
+        let last_synced = Snapshot::new(
+            position(1),
+            vec![
+                Entity::new(position(1), user(1, "Joe")),
+                Entity::new(position(2), user(2, "John")),
+            ],
+        );
+
+        let report = target
+            .sync_from(&source, Some(&last_synced))
+            .await
+            .expect("Test: Expected sync");
+
+        assert_eq!(
+            report.applied,
+            vec![PlannedWrite::Insert {
+                position: position(3),
+                data: user(3, "Jane"),
+            }]
+        );
+        assert_eq!(
+            report.conflicts,
+            vec![SyncConflict {
+                position: position(2),
+                synced: Some(user(2, "John")),
+                target: Some(user(2, "Johnny")),
+                source: Some(user(2, "John")),
+            }]
+        );
+        let names: Vec<String> = report
+            .synced
+            .entities()
+            .iter()
+            .map(|entity| entity.data().name.clone())
+            .collect();
+        assert_eq!(names, vec!["Joe", "Johnny", "Jane"]);
+    }
+}
@@ -0,0 +1,263 @@
+//////////////////////// Append-only event log ////////////////////////
+
+use crate::mapper::sheet_row;
+use crate::mapper::sheet_row::{SheetRow, SheetRowExt, SheetRowSerde};
+use crate::orm::append::AppendStrategy;
+use crate::orm::{Repository, Result};
+use crate::types::{Entity, EntityEssentials, SheetA1CellId};
+use serde_json::Value;
+use tracing::debug;
+
+/// Buffered events written by one append unless the threshold is changed
+pub const DEFAULT_FLUSH_THRESHOLD: usize = 100;
+
+/// Row of the log: sequence number in the first column, the event in the following ones
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoggedEvent<E> {
+    pub sequence: u64,
+    pub event: E,
+}
+
+impl<E> SheetRowSerde for LoggedEvent<E>
+where
+    E: EntityEssentials,
+{
+    fn deserialize(row: SheetRow) -> sheet_row::Result<Self> {
+        Ok(Self {
+            sequence: row.parse_cell(0, "sequence")?,
+            event: E::deserialize(row.into_iter().skip(1).collect())?,
+        })
+    }
+
+    fn serialize(&self) -> sheet_row::Result<SheetRow> {
+        let mut row = vec![Value::String(self.sequence.to_string())];
+        row.extend(self.event.serialize()?);
+        Ok(row)
+    }
+}
+
+impl<E> EntityEssentials for LoggedEvent<E>
+where
+    E: EntityEssentials,
+{
+    fn entity_width() -> u32 {
+        E::entity_width() + 1
+    }
+}
+
+/// Table used as an append-only stream of events, e.g. a lightweight audit trail.
+/// Events are buffered and appended in blocks, each one numbered with the next sequence
+/// number, so readers can resume with [`EventLog::since`].
+/// Sequence numbers are continued from the last row of the sheet, which makes them monotonic
+/// as long as the log has a single writer. Buffered events are lost unless flushed
+pub struct EventLog<'r, E>
+where
+    E: EntityEssentials,
+{
+    repository: &'r Repository,
+    start: SheetA1CellId,
+    rows: u32,
+    flush_threshold: usize,
+    buffer: Vec<E>,
+    /// Sequence number of the next event, read from the sheet on the first flush
+    next_sequence: Option<u64>,
+}
+
+impl Repository {
+    /// Event log in `rows` rows from `start`, same as [`Repository::table`]
+    pub fn event_log<E>(&self, start: SheetA1CellId, rows: u32) -> EventLog<'_, E>
+    where
+        E: EntityEssentials,
+    {
+        EventLog {
+            repository: self,
+            start,
+            rows,
+            flush_threshold: DEFAULT_FLUSH_THRESHOLD,
+            buffer: vec![],
+            next_sequence: None,
+        }
+    }
+}
+
+impl<E> EventLog<'_, E>
+where
+    E: EntityEssentials,
+{
+    /// Events kept in memory before [`EventLog::push`] appends them, 1 appends every event
+    pub fn with_flush_threshold(mut self, flush_threshold: usize) -> Self {
+        self.flush_threshold = flush_threshold.max(1);
+        self
+    }
+
+    pub fn start(&self) -> &SheetA1CellId {
+        &self.start
+    }
+
+    /// Events pushed but not appended yet
+    pub fn buffered(&self) -> &[E] {
+        &self.buffer
+    }
+
+    /// Buffers the event, appending the buffer once it reaches the flush threshold
+    pub async fn push(&mut self, event: E) -> Result<Vec<Entity<LoggedEvent<E>>>> {
+        self.buffer.push(event);
+        if self.buffer.len() < self.flush_threshold {
+            return Ok(vec![]);
+        }
+        self.flush().await
+    }
+
+    /// Appends the buffered events as one block below the last row of the log
+    pub async fn flush(&mut self) -> Result<Vec<Entity<LoggedEvent<E>>>> {
+        if self.buffer.is_empty() {
+            return Ok(vec![]);
+        }
+        let first = match self.next_sequence {
+            Some(sequence) => sequence,
+            None => self
+                .read_all()
+                .await?
+                .last()
+                .map_or(1, |last| last.data().sequence + 1),
+        };
+        let events: Vec<LoggedEvent<E>> = self
+            .buffer
+            .iter()
+            .cloned()
+            .zip(first..)
+            .map(|(event, sequence)| LoggedEvent { sequence, event })
+            .collect();
+        let count = events.len() as u64;
+        debug!(
+            "Appending events {}..{} to the log at {}",
+            first,
+            first + count,
+            self.start
+        );
+
+        let appended = self
+            .repository
+            .insert_all(
+                self.start.clone(),
+                self.rows,
+                events,
+                AppendStrategy::Append,
+            )
+            .await?;
+        self.buffer.clear();
+        self.next_sequence = Some(first + count);
+        Ok(appended)
+    }
+
+    /// Last `n` appended events, oldest first
+    pub async fn tail(&self, n: usize) -> Result<Vec<Entity<LoggedEvent<E>>>> {
+        let mut events = self.read_all().await?;
+        let skipped = events.len().saturating_sub(n);
+        Ok(events.split_off(skipped))
+    }
+
+    /// Appended events with a sequence number greater than `sequence`, oldest first.
+    /// Pass the last sequence number seen to get the new events
+    pub async fn since(&self, sequence: u64) -> Result<Vec<Entity<LoggedEvent<E>>>> {
+        let events = self.read_all().await?;
+        Ok(events
+            .into_iter()
+            .filter(|event| event.data().sequence > sequence)
+            .collect())
+    }
+
+    async fn read_all(&self) -> Result<Vec<Entity<LoggedEvent<E>>>> {
+        self.repository
+            .find_in_range::<LoggedEvent<E>>(&self.start, self.rows)
+            .await
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod event_log_tests {
+    use super::*;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use crate::testing::fixtures::backend_repository;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Login {
+        user: String,
+    }
+
+    impl SheetRowSerde for Login {
+        fn deserialize(row: SheetRow) -> sheet_row::Result<Self> {
+            Ok(Self {
+                user: row.parse_cell(0, "user")?,
+            })
+        }
+
+        fn serialize(&self) -> sheet_row::Result<SheetRow> {
+            Ok(vec![Value::String(self.user.clone())])
+        }
+    }
+
+    impl EntityEssentials for Login {
+        fn entity_width() -> u32 {
+            1
+        }
+    }
+
+    fn login(user: &str) -> Login {
+        Login {
+            user: user.to_string(),
+        }
+    }
+
+    fn repository(rows: Vec<SheetRow>) -> Repository {
+        let backend = MemoryBackend::new();
+        backend.workbook().set_sheet("events", rows);
+        backend_repository(backend)
+    }
+
+    #[tokio::test]
+    async fn push__threshold_reached__appended_with_sequence_continued() {
+        let repository = repository(vec![vec![Value::from("7"), Value::from("Joe")]]);
+        let mut log = repository
+            .event_log::<Login>(SheetA1CellId::from_primitives("events", "A", 1), 100)
+            .with_flush_threshold(2);
+
+        let first = log.push(login("John")).await.expect("Test: Expected push");
+        assert!(first.is_empty());
+        assert_eq!(log.buffered(), &[login("John")]);
+        let appended = log.push(login("Jane")).await.expect("Test: Expected push");
+
+        assert!(log.buffered().is_empty());
+        let sequences: Vec<u64> = appended.iter().map(|e| e.data().sequence).collect();
+        assert_eq!(sequences, vec![8, 9]);
+        assert_eq!(
+            appended[1].position(),
+            &SheetA1CellId::from_primitives("events", "A", 3)
+        );
+    }
+
+    #[tokio::test]
+    async fn tail_and_since__appended_events__latest_oldest_first() {
+        let repository = repository(vec![]);
+        let mut log =
+            repository.event_log::<Login>(SheetA1CellId::from_primitives("events", "A", 1), 100);
+        for user in ["Joe", "John", "Jane"] {
+            log.push(login(user)).await.expect("Test: Expected push");
+        }
+        log.flush().await.expect("Test: Expected flush");
+
+        let tail = log.tail(2).await.expect("Test: Expected tail");
+        let since = log.since(1).await.expect("Test: Expected since");
+
+        let users = |events: Vec<Entity<LoggedEvent<Login>>>| -> Vec<String> {
+            events
+                .into_iter()
+                .map(|e| e.data().event.user.clone())
+                .collect()
+        };
+        assert_eq!(users(tail), vec!["John", "Jane"]);
+        assert_eq!(users(since), vec!["John", "Jane"]);
+        assert!(log.since(3).await.expect("Test: Expected since").is_empty());
+    }
+}
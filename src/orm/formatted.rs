@@ -0,0 +1,398 @@
+//////////////////////// Writes styled in the same request ////////////////////////
+
+use crate::mapper::sheet_row;
+use crate::mapper::sheet_row::{SheetRow, SheetRowSerde};
+use crate::orm::audit::{AuditOperation, AuditRecord};
+use crate::orm::identity::{RowIdentity, tag_rows};
+use crate::orm::table::Table;
+use crate::orm::{RepositoryError, Result, convert_into_range, writable_runs};
+use crate::spread_sheet_driver::IntoStrVec;
+use crate::spread_sheet_driver::format::{repeat_format_request, write_cells_request};
+use crate::types::{Entity, EntityEssentials, InputMode, SheetA1CellId, SheetGid};
+use error_stack::{ResultExt, bail};
+use google_sheets4::api::{CellFormat, Request};
+use std::collections::BTreeMap;
+use std::ops::Range;
+use tracing::debug;
+
+/// Formats sent in the same batchUpdate as the values of the rows
+#[derive(Debug, Clone, Default)]
+pub struct RowFormat {
+    /// Applied to every column of the row, e.g. a highlight of the inserted rows
+    pub row: Option<CellFormat>,
+    /// Applied to single columns by their offset from the table start column, on top of the
+    /// row format, e.g. a date number format
+    pub columns: BTreeMap<u32, CellFormat>,
+}
+
+impl RowFormat {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn row(mut self, format: CellFormat) -> Self {
+        self.row = Some(format);
+        self
+    }
+
+    pub fn column(mut self, column: u32, format: CellFormat) -> Self {
+        self.columns.insert(column, format);
+        self
+    }
+
+    /// Requests formatting the 0-based `rows` of a table `width` columns wide
+    fn requests(
+        &self,
+        sheet_id: SheetGid,
+        rows: Range<u32>,
+        first_column: u32,
+        width: u32,
+    ) -> Vec<Request> {
+        let row = self.row.iter().map(|format| {
+            repeat_format_request(
+                sheet_id,
+                rows.clone(),
+                first_column..first_column + width,
+                format,
+            )
+        });
+        let columns = self
+            .columns
+            .iter()
+            .filter(|(column, _)| **column < width)
+            .map(|(column, format)| {
+                let column = first_column + column;
+                repeat_format_request(sheet_id, rows.clone(), column..column + 1, format)
+            });
+        row.chain(columns).collect()
+    }
+}
+
+impl<E> Table<'_, E>
+where
+    E: EntityEssentials,
+{
+    /// Same as [`Table::update`], but the row is written through `updateCells` together with
+    /// `format`, so values and styles are applied in one round trip and atomically.
+    /// See [`write_cells_request`] on how values are interpreted
+    pub async fn update_formatted(&self, entity: &Entity<E>, format: &RowFormat) -> Result<()> {
+        let repository = self.configured_repository();
+        let position = repository.current_position(entity).await?;
+        let row = entity
+            .data()
+            .serialize()
+            .change_context(RepositoryError::DriverError)?;
+        let old = match repository.is_audited() {
+            true => repository
+                .find_by_position::<E>(position.clone())
+                .await?
+                .map(|e| e.data.serialize())
+                .transpose()
+                .change_context(RepositoryError::DriverError)?,
+            false => None,
+        };
+
+        let driver = repository.driver.lock().await;
+        let sheet_id = driver
+            .try_get_sheet_id(&position.sheet_name)
+            .await
+            .change_context(RepositoryError::DriverError)?;
+        let (row_index, column) = (
+            position.cell.row.get() - 1,
+            position.cell.col.column_number() - 1,
+        );
+        let mut requests = self.write_requests(sheet_id, row_index, column, &[row.clone()]);
+        requests.extend(format.requests(sheet_id, row_index..row_index + 1, column, self.width()));
+        debug!(
+            "Updating entity at {} with {} requests",
+            position,
+            requests.len()
+        );
+        driver
+            .try_batch_update(requests)
+            .await
+            .change_context(RepositoryError::DriverError)?;
+        drop(driver);
+
+        repository
+            .record_audit(vec![AuditRecord::new(
+                AuditOperation::Update,
+                entity,
+                old.as_ref(),
+                Some(&row),
+            )])
+            .await
+    }
+
+    /// Writes the entities right after the last occupied row of the table with `format`
+    /// in a single batchUpdate, e.g. a report with its new rows highlighted.
+    /// Serialized with the other read-then-write operations of the repository
+    pub async fn insert_all_formatted(
+        &self,
+        entities: Vec<E>,
+        format: &RowFormat,
+    ) -> Result<Vec<Entity<E>>> {
+        if entities.is_empty() {
+            return Ok(vec![]);
+        }
+        let data = entities
+            .iter()
+            .map(|entity| entity.serialize())
+            .collect::<sheet_row::Result<Vec<SheetRow>>>()
+            .change_context(RepositoryError::DriverError)?;
+
+        let repository = self.configured_repository();
+        let _guard = repository.upserts.lock().await;
+        let start = self.start();
+        let driver = repository.driver.lock().await;
+        let occupied = driver
+            .try_get_range_with(
+                &convert_into_range(start, self.rows(), self.width()),
+                &repository.options().read_options(),
+            )
+            .await
+            .change_context(RepositoryError::DriverError)?
+            .into_vec()
+            .len() as u32;
+        let count = data.len() as u32;
+        if occupied + count > self.rows() {
+            bail!(RepositoryError::InvalidArgument(format!(
+                "{count} rows don't fit into the table at {start}, {occupied} of {} rows are taken",
+                self.rows()
+            )));
+        }
+
+        let sheet_id = driver
+            .try_get_sheet_id(&start.sheet_name)
+            .await
+            .change_context(RepositoryError::DriverError)?;
+        let (row_index, column) = (
+            start.cell.row.get() - 1 + occupied,
+            start.cell.col.column_number() - 1,
+        );
+        let mut requests = self.write_requests(sheet_id, row_index, column, &data);
+        requests.extend(format.requests(
+            sheet_id,
+            row_index..row_index + count,
+            column,
+            self.width(),
+        ));
+        driver
+            .try_batch_update(requests)
+            .await
+            .change_context(RepositoryError::DriverError)?;
+
+        let row_tags = match repository.identity {
+            RowIdentity::Position => vec![None; entities.len()],
+            RowIdentity::Metadata => tag_rows(&driver, &start.sheet_name, row_index + 1, count)
+                .await?
+                .into_iter()
+                .map(Some)
+                .collect(),
+        };
+        drop(driver);
+
+        let inserted: Vec<Entity<E>> = entities
+            .into_iter()
+            .zip(row_tags)
+            .enumerate()
+            .map(|(offset, (data, row_tag))| Entity {
+                position: SheetA1CellId::new(
+                    &start.sheet_name,
+                    start.cell.delta(0, (occupied as usize + offset) as i32),
+                ),
+                data,
+                row_tag,
+            })
+            .collect();
+        let records = inserted
+            .iter()
+            .zip(&data)
+            .map(|(entity, row)| AuditRecord::new(AuditOperation::Insert, entity, None, Some(row)))
+            .collect();
+        repository.record_audit(records).await?;
+        Ok(inserted)
+    }
+
+    /// One `updateCells` per run of writable columns with the same input mode
+    fn write_requests(
+        &self,
+        sheet_id: SheetGid,
+        row_index: u32,
+        column: u32,
+        rows: &[SheetRow],
+    ) -> Vec<Request> {
+        let width = rows.iter().map(Vec::len).max().unwrap_or_default() as u32;
+        self.value_runs(width)
+            .into_iter()
+            .map(|(run, mode)| {
+                let values: Vec<SheetRow> = rows
+                    .iter()
+                    .map(|row| {
+                        let end = (run.end as usize).min(row.len());
+                        row.get(run.start as usize..end)
+                            .unwrap_or_default()
+                            .to_vec()
+                    })
+                    .collect();
+                write_cells_request(sheet_id, row_index, column + run.start, &values, mode)
+            })
+            .collect()
+    }
+
+    /// Writable columns (see [`EntityEssentials::read_only_columns`]) split by input mode
+    fn value_runs(&self, width: u32) -> Vec<(Range<u32>, InputMode)> {
+        let table_mode = self.configured_repository().options().input_mode;
+        let mode_of = |column: u32| {
+            self.options()
+                .column_input_modes
+                .get(&column)
+                .copied()
+                .unwrap_or(table_mode)
+        };
+
+        let mut runs: Vec<(Range<u32>, InputMode)> = vec![];
+        for run in writable_runs(width, E::read_only_columns()) {
+            for column in run {
+                let mode = mode_of(column);
+                match runs.last_mut() {
+                    Some((last, last_mode)) if last.end == column && *last_mode == mode => {
+                        last.end += 1
+                    }
+                    _ => runs.push((column..column + 1, mode)),
+                }
+            }
+        }
+        runs
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod formatted_tests {
+    use super::*;
+    use crate::orm::table_options::TableOptions;
+    use crate::spread_sheet_driver::SsdResult;
+    use crate::spread_sheet_driver::backend::SheetsBackend;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use crate::testing::examples_support::User;
+    use crate::testing::fixtures::backend_repository;
+    use google_sheets4::api::Color;
+    use serde_json::{Value, json};
+    use std::sync::Arc;
+
+    /// Memory backend which keeps the batchUpdate requests instead of applying them
+    #[derive(Debug, Default)]
+    struct RecordingBackend {
+        memory: MemoryBackend,
+        batches: Arc<std::sync::Mutex<Vec<Value>>>,
+    }
+
+    impl SheetsBackend for RecordingBackend {
+        fn handle(&self, operation: &str, request: &Value) -> SsdResult<Value> {
+            match operation {
+                "spreadsheets.get" => Ok(json!({
+                    "sheets": [{ "properties": { "sheetId": 5, "title": "users" } }]
+                })),
+                "spreadsheets.batchUpdate" => {
+                    let mut batches = self.batches.lock().expect("Test: Expected lock");
+                    batches.push(request["requests"].clone());
+                    Ok(json!({ "replies": [] }))
+                }
+                _ => self.memory.handle(operation, request),
+            }
+        }
+    }
+
+    fn highlight() -> CellFormat {
+        CellFormat {
+            background_color: Some(Color {
+                green: Some(1.0),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn insert_all_formatted__after_existing_rows__values_and_format_in_one_batch() {
+        let backend = RecordingBackend::default();
+        let batches = backend.batches.clone();
+        backend.memory.workbook().set_sheet(
+            "users",
+            vec![
+                vec![Value::from("id"), Value::from("name")],
+                vec![Value::from("1"), Value::from("Joe")],
+            ],
+        );
+        let repository = backend_repository(backend);
+        let table = repository.table::<User>(SheetA1CellId::from_primitives("users", "A", 2), 10);
+
+        let inserted = table
+            .insert_all_formatted(
+                vec![
+                    User {
+                        id: 2,
+                        name: "John".to_string(),
+                    },
+                    User {
+                        id: 3,
+                        name: "Jane".to_string(),
+                    },
+                ],
+                &RowFormat::new().row(highlight()),
+            )
+            .await
+            .expect("Test: Expected formatted insert");
+
+        assert_eq!(
+            inserted[1].position(),
+            &SheetA1CellId::from_primitives("users", "A", 4)
+        );
+        let batches = batches.lock().expect("Test: Expected lock");
+        assert_eq!(batches.len(), 1);
+        let update = &batches[0][0]["updateCells"];
+        assert_eq!(update["start"]["sheetId"], 5);
+        assert_eq!(update["start"]["rowIndex"], 2);
+        assert_eq!(
+            update["rows"][1]["values"][0]["userEnteredValue"]["numberValue"],
+            3.0
+        );
+        let repeat = &batches[0][1]["repeatCell"];
+        assert_eq!(repeat["range"]["startRowIndex"], 2);
+        assert_eq!(repeat["range"]["endRowIndex"], 4);
+        assert_eq!(repeat["fields"], "userEnteredFormat.backgroundColor");
+    }
+
+    #[tokio::test]
+    async fn update_formatted__raw_id_column__split_by_input_mode() {
+        let backend = RecordingBackend::default();
+        let batches = backend.batches.clone();
+        let repository = backend_repository(backend);
+        let table = repository
+            .table::<User>(SheetA1CellId::from_primitives("users", "A", 2), 10)
+            .with_options(TableOptions::default().column_input_mode(0, InputMode::Raw));
+        let entity = Entity::new(
+            SheetA1CellId::from_primitives("users", "A", 3),
+            User {
+                id: 7,
+                name: "Joe".to_string(),
+            },
+        );
+
+        table
+            .update_formatted(&entity, &RowFormat::new().column(1, highlight()))
+            .await
+            .expect("Test: Expected formatted update");
+
+        let batches = batches.lock().expect("Test: Expected lock");
+        let requests = &batches[0];
+        assert_eq!(
+            requests[0]["updateCells"]["rows"][0]["values"][0]["userEnteredValue"]["stringValue"],
+            "7"
+        );
+        assert_eq!(requests[1]["updateCells"]["start"]["columnIndex"], 1);
+        assert_eq!(requests[2]["repeatCell"]["range"]["startColumnIndex"], 1);
+        assert_eq!(requests[2]["repeatCell"]["range"]["endColumnIndex"], 2);
+    }
+}
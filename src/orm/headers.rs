@@ -0,0 +1,154 @@
+//////////////////////// Header row of the table ////////////////////////
+
+use crate::orm::migration::cell_text;
+use crate::orm::table::Table;
+use crate::orm::{Repository, RepositoryError, Result};
+use crate::spread_sheet_driver::IntoStrVec;
+use crate::types::{A1CellId, A1Range, EntityEssentials, InputMode, SheetA1CellId, SheetA1Range};
+use error_stack::{ResultExt, bail};
+use serde_json::Value;
+use std::num::NonZero;
+use tracing::info;
+
+impl Repository {
+    /// Same as [`Repository::table`], but the header row is checked, see [`Table::ensure_headers`]
+    pub async fn checked_table<E>(&self, start: SheetA1CellId, rows: u32) -> Result<Table<'_, E>>
+    where
+        E: EntityEssentials,
+    {
+        let table = self.table(start, rows);
+        table.ensure_headers().await?;
+        Ok(table)
+    }
+}
+
+impl<E> Table<'_, E>
+where
+    E: EntityEssentials,
+{
+    /// Compares the row right above the table start with [`EntityEssentials::column_headers`].
+    /// An empty header row gets the headers written, a different one fails with
+    /// [`RepositoryError::HeaderMismatch`], so the code and the sheet don't drift apart
+    pub async fn ensure_headers(&self) -> Result<()> {
+        let expected = E::column_headers();
+        let start = self.start();
+        if expected.is_empty() {
+            bail!(RepositoryError::InvalidArgument(format!(
+                "Entity of the table at {start} declares no column headers"
+            )));
+        }
+        let Some(header_row) = NonZero::new(start.cell.row.get() - 1) else {
+            bail!(RepositoryError::InvalidArgument(format!(
+                "Table at {start} has no header row above it"
+            )));
+        };
+
+        let first = A1CellId::new(start.cell.col.clone(), header_row);
+        let range = SheetA1Range::new(
+            &start.sheet_name,
+            A1Range::new(first.clone(), first.delta(expected.len() as i32 - 1, 0)),
+        );
+        let driver = self.repository().driver.lock().await;
+        let found: Vec<String> = driver
+            .try_get_range(&range)
+            .await
+            .change_context(RepositoryError::DriverError)?
+            .into_vec()
+            .into_iter()
+            .next()
+            .unwrap_or_default()
+            .iter()
+            .map(cell_text)
+            .collect();
+
+        if found.iter().all(String::is_empty) {
+            let headers = expected.iter().map(|&header| Value::from(header)).collect();
+            driver
+                .try_write_range_as(range.to_string().as_str(), vec![headers], InputMode::Raw)
+                .await
+                .change_context(RepositoryError::DriverError)?;
+            info!("Wrote headers of the table at {} to {}", start, range);
+            return Ok(());
+        }
+
+        let matches = found.len() == expected.len()
+            && found
+                .iter()
+                .zip(expected)
+                .all(|(found, &expected)| found == expected);
+        if !matches {
+            bail!(RepositoryError::HeaderMismatch {
+                table: start.to_string(),
+                expected: expected.iter().map(|header| header.to_string()).collect(),
+                found,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod headers_tests {
+    use super::*;
+    use crate::mapper::sheet_row::SheetRow;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use crate::testing::examples_support::User;
+    use crate::testing::fixtures::backend_repository;
+
+    fn repository(rows: Vec<SheetRow>) -> Repository {
+        let backend = MemoryBackend::new();
+        backend.workbook().set_sheet("users", rows);
+        backend_repository(backend)
+    }
+
+    #[tokio::test]
+    async fn checked_table__empty_sheet__headers_written() {
+        let repository = repository(vec![]);
+
+        repository
+            .checked_table::<User>(SheetA1CellId::from_primitives("users", "A", 2), 10)
+            .await
+            .expect("Test: Expected headers to be written");
+
+        let header = repository
+            .driver
+            .lock()
+            .await
+            .try_get_range("users!A1:B1")
+            .await
+            .expect("Test: Expected header row")
+            .into_vec();
+        assert_eq!(header, vec![vec![Value::from("id"), Value::from("name")]]);
+    }
+
+    #[tokio::test]
+    async fn checked_table__matching_headers__ok() {
+        let repository = repository(vec![vec![Value::from("id"), Value::from("name")]]);
+
+        repository
+            .checked_table::<User>(SheetA1CellId::from_primitives("users", "A", 2), 10)
+            .await
+            .expect("Test: Expected matching headers");
+    }
+
+    #[tokio::test]
+    async fn checked_table__renamed_column__header_mismatch() {
+        let repository = repository(vec![vec![Value::from("id"), Value::from("full name")]]);
+
+        let report = repository
+            .checked_table::<User>(SheetA1CellId::from_primitives("users", "A", 2), 10)
+            .await
+            .expect_err("Test: Expected header mismatch");
+
+        match report.current_context() {
+            RepositoryError::HeaderMismatch {
+                expected, found, ..
+            } => {
+                assert_eq!(expected, &vec!["id".to_string(), "name".to_string()]);
+                assert_eq!(found, &vec!["id".to_string(), "full name".to_string()]);
+            }
+            other => panic!("Test: Unexpected error {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//////////////////////// Idempotent inserts ////////////////////////
+
+use crate::orm::identity::{search_tag, tag_row};
+use crate::orm::{Repository, Result};
+use crate::types::{A1CellId, Entity, EntityEssentials, SheetA1CellId};
+use std::num::NonZero;
+use tracing::info;
+
+/// Developer metadata key of the tag holding client-provided idempotency keys
+pub const IDEMPOTENCY_KEY: &str = "google_sheets_driver.idempotency_key";
+
+impl Repository {
+    /// Same as [`Repository::insert`], but does nothing if an entity was already inserted with the same
+    /// `key` (e.g. by a previous attempt of a retried job) and returns that entity instead.
+    /// The key is kept in developer metadata of the inserted row, so it survives sorting of the sheet.
+    /// If the process dies between the append and the tagging, a retry will insert the row again
+    pub async fn insert_idempotent<E>(
+        &self,
+        start: SheetA1CellId,
+        rows: u32,
+        entity_data: E,
+        key: &str,
+    ) -> Result<Entity<E>>
+    where
+        E: EntityEssentials,
+    {
+        let tagged = search_tag(&*self.driver.lock().await, IDEMPOTENCY_KEY, key).await?;
+        if let Some((_, row_index)) = tagged {
+            let row = NonZero::new(row_index + 1).expect("Expected 1-based row to be non-zero");
+            let position = SheetA1CellId::new(
+                &start.sheet_name,
+                A1CellId::new(start.cell.col.clone(), row),
+            );
+
+            if let Some(existing) = self.find_by_position(position).await? {
+                info!("Entity with idempotency key {} is already inserted", key);
+                return Ok(existing);
+            }
+        }
+
+        let entity = self.insert(start, rows, entity_data).await?;
+        tag_row(
+            &*self.driver.lock().await,
+            &entity.position.sheet_name,
+            entity.position.cell.row.get(),
+            IDEMPOTENCY_KEY,
+            key,
+        )
+        .await?;
+        Ok(entity)
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod idempotency_tests {
+    use super::*;
+    use crate::spread_sheet_driver::cassette::Interaction;
+    use crate::spread_sheet_driver::metadata::{metadata_filter, tag_rows_request};
+    use crate::spread_sheet_driver::structure::rows_range;
+    use crate::testing::examples_support::User;
+    use crate::testing::fixtures::{
+        AppendValuesResponseBuilder, MatchedValueRangeBuilder, replay_repository,
+    };
+    use crate::types::SheetGid;
+    use google_sheets4::api::{
+        BatchGetValuesByDataFilterResponse, BatchUpdateSpreadsheetResponse, DeveloperMetadata,
+        DeveloperMetadataLocation, MatchedDeveloperMetadata, SearchDeveloperMetadataResponse,
+        Sheet, SheetProperties, Spreadsheet,
+    };
+    use serde::Serialize;
+    use serde_json::{Value, json};
+
+    fn interaction<T>(operation: &str, request: Value, response: T) -> Interaction
+    where
+        T: Serialize,
+    {
+        Interaction {
+            operation: operation.to_string(),
+            request,
+            response: serde_json::to_value(response).expect("Test: Expected to serialize"),
+        }
+    }
+
+    fn search_interaction(row_index: Option<u32>) -> Interaction {
+        let matched = row_index.map(|index| MatchedDeveloperMetadata {
+            developer_metadata: Some(DeveloperMetadata {
+                location: Some(DeveloperMetadataLocation {
+                    dimension_range: Some(rows_range(SheetGid(0), index, 1)),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+
+        interaction(
+            "developerMetadata.search",
+            json!({ "dataFilters": [metadata_filter(IDEMPOTENCY_KEY, "job-1")] }),
+            SearchDeveloperMetadataResponse {
+                matched_developer_metadata: Some(matched.into_iter().collect()),
+            },
+        )
+    }
+
+    fn john() -> User {
+        User {
+            id: 2,
+            name: "John".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn insert_idempotent__on_new_key__appends_and_tags_row() {
+        let spreadsheet = Spreadsheet {
+            sheets: Some(vec![Sheet {
+                properties: Some(SheetProperties {
+                    sheet_id: Some(0),
+                    title: Some("users".to_string()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+        let repository = replay_repository(vec![
+            search_interaction(None),
+            interaction(
+                "values.append",
+                json!({ "range": "users!A1:B11", "values": [["2", "John"]] }),
+                AppendValuesResponseBuilder::new("users!A2:B2")
+                    .row(["2", "John"])
+                    .build(),
+            ),
+            interaction(
+                "spreadsheets.get",
+                json!({ "fields": "sheets.properties" }),
+                spreadsheet,
+            ),
+            interaction(
+                "spreadsheets.batchUpdate",
+                json!({ "requests": [tag_rows_request(SheetGid(0), 1, 1, IDEMPOTENCY_KEY, "job-1")] }),
+                BatchUpdateSpreadsheetResponse::default(),
+            ),
+        ]);
+
+        let start = SheetA1CellId::from_primitives("users", "A", 1);
+        let inserted = repository
+            .insert_idempotent(start, 10, john(), "job-1")
+            .await
+            .expect("Test: Expected insert to succeed");
+
+        assert_eq!(
+            inserted.position(),
+            &SheetA1CellId::from_primitives("users", "A", 2)
+        );
+    }
+
+    #[tokio::test]
+    async fn insert_idempotent__on_known_key__returns_existing_entity() {
+        let values = BatchGetValuesByDataFilterResponse {
+            value_ranges: Some(vec![
+                MatchedValueRangeBuilder::new("users!A2:B3")
+                    .row(["2", "John"])
+                    .build(),
+            ]),
+            ..Default::default()
+        };
+        // No append is recorded, so an attempt to insert again fails with replay miss
+        let repository = replay_repository(vec![
+            search_interaction(Some(1)),
+            interaction(
+                "values.batchGetByDataFilter",
+                json!({ "range": "users!A2:B3" }),
+                values,
+            ),
+        ]);
+
+        let start = SheetA1CellId::from_primitives("users", "A", 1);
+        let existing = repository
+            .insert_idempotent(start, 10, john(), "job-1")
+            .await
+            .expect("Test: Expected existing entity");
+
+        assert_eq!(
+            existing.position(),
+            &SheetA1CellId::from_primitives("users", "A", 2)
+        );
+        assert_eq!(existing.data(), &john());
+    }
+}
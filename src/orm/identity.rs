@@ -0,0 +1,326 @@
+//////////////////////// Row identity via developer metadata ////////////////////////
+
+use crate::orm::{Repository, RepositoryError, Result};
+use crate::spread_sheet_driver::SpreadSheetDriver;
+use crate::spread_sheet_driver::metadata::{
+    metadata_filter, tag_rows_request, tagged_row, unique_token,
+};
+use crate::spread_sheet_driver::structure::delete_rows_request;
+use crate::types::{A1CellId, Entity, EntityEssentials, SheetA1CellId, SheetGid};
+use error_stack::{ResultExt, bail};
+use std::num::NonZero;
+use tracing::debug;
+
+/// Developer metadata key of the row id tag
+pub const ROW_ID_KEY: &str = "google_sheets_driver.row_id";
+
+/// How the repository finds the row of an entity it has seen before
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RowIdentity {
+    /// By the A1 position remembered on read/insert
+    #[default]
+    Position,
+    /// Inserted rows are tagged with a stable id kept in developer metadata.
+    /// The tag follows the row when the sheet is sorted or rows are inserted above,
+    /// so `update`, `delete` and `find_by_id` keep working after manual edits
+    Metadata,
+}
+
+impl Repository {
+    /// Opts in for metadata based row identity (see [`RowIdentity::Metadata`])
+    pub fn with_row_identity(mut self, identity: RowIdentity) -> Self {
+        self.identity = identity;
+        self
+    }
+
+    pub fn row_identity(&self) -> RowIdentity {
+        self.identity
+    }
+
+    /// Finds the entity by the id assigned on insert.
+    /// `start` defines the sheet and the first column of the table
+    pub async fn find_by_id<E>(&self, start: &SheetA1CellId, id: &str) -> Result<Option<Entity<E>>>
+    where
+        E: EntityEssentials,
+    {
+        let Some(position) = locate(&*self.driver.lock().await, start, id).await? else {
+            return Ok(None);
+        };
+
+        let entity = self.find_by_position(position).await?;
+        Ok(entity.map(|entity| Entity {
+            row_tag: Some(id.to_string()),
+            ..entity
+        }))
+    }
+
+    /// Current position of the row tagged with the entity id, or the remembered one
+    pub(crate) async fn current_position<E>(&self, entity: &Entity<E>) -> Result<SheetA1CellId>
+    where
+        E: EntityEssentials,
+    {
+        let (RowIdentity::Metadata, Some(id)) = (self.identity, &entity.row_tag) else {
+            return Ok(entity.position.clone());
+        };
+
+        let Some(position) = locate(&*self.driver.lock().await, &entity.position, id).await? else {
+            bail!(RepositoryError::InvalidArgument(format!(
+                "Row with id '{id}' is not found"
+            )));
+        };
+        debug!("Entity {} is located at {:?}", id, position);
+        Ok(position)
+    }
+
+    /// Physically removes the row tagged with the entity id (metadata is removed with it)
+    pub(crate) async fn delete_tagged_row(&self, id: &str) -> Result<()> {
+        let driver = self.driver.lock().await;
+        let Some((sheet_id, row_index)) = search_tag(&driver, ROW_ID_KEY, id).await? else {
+            bail!(RepositoryError::InvalidArgument(format!(
+                "Row with id '{id}' is not found"
+            )));
+        };
+
+        driver
+            .try_batch_update(vec![delete_rows_request(sheet_id, row_index, 1)])
+            .await
+            .change_context(RepositoryError::DriverError)?;
+        // The grid is a row shorter now
+        driver.invalidate_sheets_cache();
+        Ok(())
+    }
+}
+
+/// Tags `count` rows starting from the 1-based `first_row` with new ids, one id per row
+pub(crate) async fn tag_rows(
+    driver: &SpreadSheetDriver,
+    sheet: &str,
+    first_row: u32,
+    count: u32,
+) -> Result<Vec<String>> {
+    let sheet_id = driver
+        .try_get_sheet_id(sheet)
+        .await
+        .change_context(RepositoryError::DriverError)?;
+
+    let ids: Vec<String> = (0..count).map(|_| unique_token()).collect();
+    let requests = ids
+        .iter()
+        .zip(first_row - 1..)
+        .map(|(id, index)| tag_rows_request(sheet_id, index, 1, ROW_ID_KEY, id))
+        .collect();
+
+    driver
+        .try_batch_update(requests)
+        .await
+        .change_context(RepositoryError::DriverError)?;
+    Ok(ids)
+}
+
+/// Tags the 1-based `row` with `key=value`
+pub(crate) async fn tag_row(
+    driver: &SpreadSheetDriver,
+    sheet: &str,
+    row: u32,
+    key: &str,
+    value: &str,
+) -> Result<()> {
+    let sheet_id = driver
+        .try_get_sheet_id(sheet)
+        .await
+        .change_context(RepositoryError::DriverError)?;
+
+    driver
+        .try_batch_update(vec![tag_rows_request(sheet_id, row - 1, 1, key, value)])
+        .await
+        .change_context(RepositoryError::DriverError)?;
+    Ok(())
+}
+
+/// Sheet id and 0-based index of the row tagged with `key=value`. Tags are expected to be unique
+pub(crate) async fn search_tag(
+    driver: &SpreadSheetDriver,
+    key: &str,
+    value: &str,
+) -> Result<Option<(SheetGid, u32)>> {
+    let found = driver
+        .try_search_metadata(vec![metadata_filter(key, value)])
+        .await
+        .change_context(RepositoryError::DriverError)?;
+
+    match found.as_slice() {
+        [] => Ok(None),
+        [metadata] => Ok(tagged_row(metadata)),
+        _ => bail!(RepositoryError::InvalidArgument(format!(
+            "Tag {key}={value} is not unique"
+        ))),
+    }
+}
+
+/// Position of the tagged row within the column of `start`, on the sheet the tag is found on
+async fn locate(
+    driver: &SpreadSheetDriver,
+    start: &SheetA1CellId,
+    id: &str,
+) -> Result<Option<SheetA1CellId>> {
+    let Some((sheet_id, row_index)) = search_tag(driver, ROW_ID_KEY, id).await? else {
+        return Ok(None);
+    };
+
+    let sheet_name = driver
+        .try_get_sheet_title(sheet_id)
+        .await
+        .change_context(RepositoryError::DriverError)?;
+    let row = NonZero::new(row_index + 1).expect("Expected 1-based row to be non-zero");
+    Ok(Some(SheetA1CellId::new(
+        &sheet_name,
+        A1CellId::new(start.cell.col.clone(), row),
+    )))
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod identity_tests {
+    use super::*;
+    use crate::spread_sheet_driver::cassette::Interaction;
+    use crate::spread_sheet_driver::structure::rows_range;
+    use crate::testing::examples_support::User;
+    use crate::testing::fixtures::{MatchedValueRangeBuilder, replay_repository};
+    use google_sheets4::api::{
+        BatchGetValuesByDataFilterResponse, DeveloperMetadata, DeveloperMetadataLocation,
+        MatchedDeveloperMetadata, SearchDeveloperMetadataResponse, Sheet, SheetProperties,
+        Spreadsheet,
+    };
+    use serde_json::json;
+
+    fn search_interaction(id: &str, row: Option<(SheetGid, u32)>) -> Interaction {
+        let matched = row.map(|(sheet_id, index)| MatchedDeveloperMetadata {
+            developer_metadata: Some(DeveloperMetadata {
+                metadata_key: Some(ROW_ID_KEY.to_string()),
+                metadata_value: Some(id.to_string()),
+                location: Some(DeveloperMetadataLocation {
+                    dimension_range: Some(rows_range(sheet_id, index, 1)),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        let response = SearchDeveloperMetadataResponse {
+            matched_developer_metadata: Some(matched.into_iter().collect()),
+        };
+
+        Interaction {
+            operation: "developerMetadata.search".to_string(),
+            request: json!({ "dataFilters": [metadata_filter(ROW_ID_KEY, id)] }),
+            response: serde_json::to_value(response).expect("Test: Expected to serialize"),
+        }
+    }
+
+    fn sheets_interaction() -> Interaction {
+        let sheet = |sheet_id, title: &str| Sheet {
+            properties: Some(SheetProperties {
+                sheet_id: Some(sheet_id),
+                title: Some(title.to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let spreadsheet = Spreadsheet {
+            sheets: Some(vec![sheet(0, "users"), sheet(7, "archive")]),
+            ..Default::default()
+        };
+
+        Interaction {
+            operation: "spreadsheets.get".to_string(),
+            request: json!({ "fields": "sheets.properties" }),
+            response: serde_json::to_value(spreadsheet).expect("Test: Expected to serialize"),
+        }
+    }
+
+    fn tagged_repository(interactions: Vec<Interaction>) -> Repository {
+        replay_repository(interactions).with_row_identity(RowIdentity::Metadata)
+    }
+
+    #[tokio::test]
+    async fn find_by_id__on_moved_row__found_at_current_position() {
+        let values = BatchGetValuesByDataFilterResponse {
+            value_ranges: Some(vec![
+                MatchedValueRangeBuilder::new("users!A5:B6")
+                    .row(["3", "Jane"])
+                    .build(),
+            ]),
+            ..Default::default()
+        };
+        let repository = tagged_repository(vec![
+            search_interaction("abc", Some((SheetGid(0), 4))),
+            sheets_interaction(),
+            Interaction {
+                operation: "values.batchGetByDataFilter".to_string(),
+                request: json!({ "range": "users!A5:B6" }),
+                response: serde_json::to_value(values).expect("Test: Expected to serialize"),
+            },
+        ]);
+
+        let start = SheetA1CellId::from_primitives("users", "A", 1);
+        let found: Entity<User> = repository
+            .find_by_id(&start, "abc")
+            .await
+            .expect("Test: Expected lookup to succeed")
+            .expect("Test: Expected entity to be found");
+
+        assert_eq!(found.row_tag(), Some("abc"));
+        assert_eq!(
+            found.position(),
+            &SheetA1CellId::from_primitives("users", "A", 5)
+        );
+        assert_eq!(found.name, "Jane");
+    }
+
+    #[tokio::test]
+    async fn find_by_id__on_row_moved_to_other_sheet__found_on_that_sheet() {
+        let values = BatchGetValuesByDataFilterResponse {
+            value_ranges: Some(vec![
+                MatchedValueRangeBuilder::new("archive!A2:B3")
+                    .row(["3", "Jane"])
+                    .build(),
+            ]),
+            ..Default::default()
+        };
+        let repository = tagged_repository(vec![
+            search_interaction("abc", Some((SheetGid(7), 1))),
+            sheets_interaction(),
+            Interaction {
+                operation: "values.batchGetByDataFilter".to_string(),
+                request: json!({ "range": "archive!A2:B3" }),
+                response: serde_json::to_value(values).expect("Test: Expected to serialize"),
+            },
+        ]);
+
+        let start = SheetA1CellId::from_primitives("users", "A", 1);
+        let found: Entity<User> = repository
+            .find_by_id(&start, "abc")
+            .await
+            .expect("Test: Expected lookup to succeed")
+            .expect("Test: Expected entity to be found");
+
+        assert_eq!(
+            found.position(),
+            &SheetA1CellId::from_primitives("archive", "A", 2)
+        );
+        assert_eq!(found.name, "Jane");
+    }
+
+    #[tokio::test]
+    async fn find_by_id__on_unknown_id__none() {
+        let repository = tagged_repository(vec![search_interaction("abc", None)]);
+
+        let start = SheetA1CellId::from_primitives("users", "A", 1);
+        let found: Option<Entity<User>> = repository
+            .find_by_id(&start, "abc")
+            .await
+            .expect("Test: Expected lookup to succeed");
+
+        assert!(found.is_none());
+    }
+}
@@ -0,0 +1,283 @@
+//////////////////////// Bulk import with deadletters ////////////////////////
+
+use crate::orm::append::AppendStrategy;
+use crate::orm::{Repository, RepositoryError, Result};
+use crate::spread_sheet_driver::SpreadSheetDriverError;
+use crate::types::{
+    A1CellId, A1Range, Entity, EntityEssentials, InputMode, SheetA1CellId, SheetA1Range,
+};
+use error_stack::{Report, ResultExt, bail};
+use serde_json::Value;
+use tracing::{info, warn};
+
+/// What happens to the entities which fail to serialize or to be written
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Deadletter {
+    /// The import stops at the first failure, same as [`Repository::insert_all`]
+    #[default]
+    Fail,
+    /// Failed entities are given back in the [`ImportReport`], the rest is imported
+    Collect,
+    /// Same as `Collect`, and the failures are also appended to the named sheet as
+    /// (index, cause, entity) rows, so they can be fixed and imported again
+    Sheet(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportOptions {
+    /// Entities inserted per block. A failed write fails the whole block
+    pub chunk_rows: usize,
+    pub deadletter: Deadletter,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            chunk_rows: 500,
+            deadletter: Deadletter::default(),
+        }
+    }
+}
+
+/// Entity left out of the import
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeadletterRow<E> {
+    /// 0-based index among the imported entities
+    pub index: usize,
+    pub entity: E,
+    pub cause: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportReport<E> {
+    pub inserted: Vec<Entity<E>>,
+    pub deadletters: Vec<DeadletterRow<E>>,
+}
+
+impl Repository {
+    /// Inserts `entities` block by block after the last row of the table (see
+    /// [`Repository::insert_all`]). Entities which fail to serialize, or whose block fails to
+    /// be written, are handled as set by `options.deadletter` instead of being dropped
+    pub async fn import<E>(
+        &self,
+        start: SheetA1CellId,
+        rows: u32,
+        entities: Vec<E>,
+        options: &ImportOptions,
+    ) -> Result<ImportReport<E>>
+    where
+        E: EntityEssentials,
+    {
+        if options.chunk_rows == 0 {
+            bail!(RepositoryError::InvalidArgument(
+                "chunk_rows must be greater than 0".to_string()
+            ));
+        }
+
+        let mut report = ImportReport {
+            inserted: vec![],
+            deadletters: vec![],
+        };
+        let indexed: Vec<(usize, E)> = entities.into_iter().enumerate().collect();
+        for chunk in indexed.chunks(options.chunk_rows) {
+            let mut valid = vec![];
+            for (index, entity) in chunk {
+                match entity.serialize() {
+                    Ok(_) => valid.push((*index, entity.clone())),
+                    Err(error) if options.deadletter == Deadletter::Fail => {
+                        return Err(error).change_context(RepositoryError::DriverError);
+                    }
+                    Err(error) => report.deadletters.push(DeadletterRow {
+                        index: *index,
+                        entity: entity.clone(),
+                        cause: error.current_context().to_string(),
+                    }),
+                }
+            }
+            if valid.is_empty() {
+                continue;
+            }
+
+            let block = valid.iter().map(|(_, entity)| entity.clone()).collect();
+            match self
+                .insert_all(start.clone(), rows, block, AppendStrategy::Append)
+                .await
+            {
+                Ok(inserted) => report.inserted.extend(inserted),
+                Err(error) if options.deadletter == Deadletter::Fail => return Err(error),
+                Err(error) => {
+                    let cause = failure_cause(&error);
+                    warn!("Block of {} entities failed: {}", valid.len(), cause);
+                    report
+                        .deadletters
+                        .extend(valid.into_iter().map(|(index, entity)| DeadletterRow {
+                            index,
+                            entity,
+                            cause: cause.clone(),
+                        }));
+                }
+            }
+        }
+
+        if let Deadletter::Sheet(sheet) = &options.deadletter
+            && !report.deadletters.is_empty()
+        {
+            self.write_deadletters(sheet, &report.deadletters).await?;
+        }
+        info!(
+            "Imported {} entities, {} deadlettered",
+            report.inserted.len(),
+            report.deadletters.len()
+        );
+        Ok(report)
+    }
+
+    async fn write_deadletters<E>(&self, sheet: &str, rows: &[DeadletterRow<E>]) -> Result<()>
+    where
+        E: EntityEssentials,
+    {
+        let first = A1CellId::from_primitives("A", 1);
+        let range = SheetA1Range::new(sheet, A1Range::new(first.clone(), first.delta(2, 0)));
+        let rows = rows
+            .iter()
+            .map(|row| {
+                vec![
+                    Value::from(row.index),
+                    Value::from(row.cause.as_str()),
+                    Value::from(format!("{:?}", row.entity)),
+                ]
+            })
+            .collect();
+        self.driver
+            .lock()
+            .await
+            .try_append_rows_as(range.to_string(), rows, InputMode::Raw)
+            .await
+            .change_context(RepositoryError::DriverError)?;
+        Ok(())
+    }
+}
+
+/// Cause reported by the driver if any, the repository error otherwise
+fn failure_cause(error: &Report<RepositoryError>) -> String {
+    match error.downcast_ref::<SpreadSheetDriverError>() {
+        Some(cause) => cause.to_string(),
+        None => error.current_context().to_string(),
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod import_tests {
+    use super::*;
+    use crate::mapper::sheet_row;
+    use crate::mapper::sheet_row::{ParseError, SheetRow, SheetRowExt, SheetRowSerde};
+    use crate::testing::fixtures::memory_repository;
+
+    /// Fails to serialize without a name, so some rows of the import are rejected
+    #[derive(Debug, Clone, PartialEq)]
+    struct StrictUser {
+        id: i32,
+        name: String,
+    }
+
+    impl SheetRowSerde for StrictUser {
+        fn deserialize(row: SheetRow) -> sheet_row::Result<Self> {
+            Ok(Self {
+                id: row.parse_cell(0, "id")?,
+                name: row.parse_cell(1, "name")?,
+            })
+        }
+
+        fn serialize(&self) -> sheet_row::Result<SheetRow> {
+            if self.name.is_empty() {
+                bail!(ParseError::FieldIsMissing("name"));
+            }
+            Ok(vec![
+                Value::String(self.id.to_string()),
+                Value::String(self.name.clone()),
+            ])
+        }
+    }
+
+    impl EntityEssentials for StrictUser {
+        fn entity_width() -> u32 {
+            2
+        }
+    }
+
+    fn user(id: i32, name: &str) -> StrictUser {
+        StrictUser {
+            id,
+            name: name.to_string(),
+        }
+    }
+
+    fn repository() -> Repository {
+        memory_repository()
+    }
+
+    #[tokio::test]
+    async fn import__unserializable_entity__deadlettered_to_sheet() {
+        let repository = repository();
+        let options = ImportOptions {
+            chunk_rows: 2,
+            deadletter: Deadletter::Sheet("errors".to_string()),
+        };
+
+        let report = repository
+            .import(
+                SheetA1CellId::from_primitives("users", "A", 1),
+                100,
+                vec![user(1, "Joe"), user(2, ""), user(3, "Jane")],
+                &options,
+            )
+            .await
+            .expect("Test: Expected import");
+
+        let inserted: Vec<StrictUser> = report.inserted.into_iter().map(|e| e.data).collect();
+        assert_eq!(inserted, vec![user(1, "Joe"), user(3, "Jane")]);
+        assert_eq!(report.deadletters.len(), 1);
+        assert_eq!(report.deadletters[0].index, 1);
+        assert_eq!(
+            report.deadletters[0].cause,
+            "Field name is not found in row"
+        );
+        let errors = repository
+            .driver
+            .lock()
+            .await
+            .try_get_values("errors!A1:C1")
+            .await
+            .expect("Test: Expected error sheet")
+            .values
+            .unwrap_or_default();
+        assert_eq!(errors[0][0], 1);
+        assert_eq!(errors[0][1], "Field name is not found in row");
+    }
+
+    #[tokio::test]
+    async fn import__fail_mode__stops_at_first_failure() {
+        let repository = repository();
+
+        let report = repository
+            .import(
+                SheetA1CellId::from_primitives("users", "A", 1),
+                100,
+                vec![user(1, "Joe"), user(2, "")],
+                &ImportOptions::default(),
+            )
+            .await
+            .expect_err("Test: Expected the import to fail");
+
+        assert!(matches!(
+            report.current_context(),
+            RepositoryError::DriverError
+        ));
+        let found = repository
+            .find_in_range::<StrictUser>(&SheetA1CellId::from_primitives("users", "A", 1), 100)
+            .await
+            .expect("Test: Expected read");
+        assert!(found.is_empty());
+    }
+}
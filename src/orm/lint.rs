@@ -0,0 +1,398 @@
+//////////////////////// Structural checks of tables ////////////////////////
+
+use crate::mapper::sheet_row::SheetRow;
+use crate::orm::migration::cell_text;
+use crate::orm::table::Table;
+use crate::orm::{RepositoryError, Result};
+use crate::types::{
+    A1CellId, A1Range, ColumnKind, EntityEssentials, NumCellId, SheetA1CellId, SheetA1Range,
+    SpreadSheetDateTime,
+};
+use error_stack::ResultExt;
+use google_sheets4::api::GridRange;
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+use std::num::NonZero;
+use std::ops::Range;
+use tracing::info;
+
+/// Human edit of the sheet which breaks the declared table, found by [`Table::lint`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum LintIssue {
+    /// Header cell differs from [`EntityEssentials::column_headers`]
+    HeaderMismatch {
+        cell: String,
+        expected: &'static str,
+        found: String,
+    },
+    /// Merge which overlaps the table rows
+    MergedCells { range: String },
+    /// First non-empty cell of a row below the table, which reads of the table don't see
+    DataBelowTable { cell: String },
+    /// Cell which doesn't hold the kind declared by [`EntityEssentials::columns`]
+    UnexpectedKind {
+        cell: String,
+        expected: ColumnKind,
+        found: Value,
+    },
+    /// 0-based column without a declared kind which holds e.g. both numbers and texts
+    MixedKinds { column: u32 },
+}
+
+impl Display for LintIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LintIssue::HeaderMismatch {
+                cell,
+                expected,
+                found,
+            } => write!(f, "{cell}: header {found:?}, expected {expected:?}"),
+            LintIssue::MergedCells { range } => write!(f, "{range}: merged cells"),
+            LintIssue::DataBelowTable { cell } => write!(f, "{cell}: data below the table"),
+            LintIssue::UnexpectedKind {
+                cell,
+                expected,
+                found,
+            } => write!(f, "{cell}: {found} is not {expected:?}"),
+            LintIssue::MixedKinds { column } => {
+                write!(f, "Column {column}: values of different kinds")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintReport {
+    /// Start of the table data
+    pub table: SheetA1CellId,
+    pub issues: Vec<LintIssue>,
+}
+
+impl LintReport {
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl Display for LintReport {
+    /// One issue per line
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Table at {}: {} issues", self.table, self.issues.len())?;
+        for issue in &self.issues {
+            write!(f, "\n  {issue}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<E> Table<'_, E>
+where
+    E: EntityEssentials,
+{
+    /// Checks the table against the declaration of `E`: header names and order, merges
+    /// within the table rows, data below the table and kinds of the column values.
+    /// Issues are reported, not fixed, e.g. to stop a sync before parsing breaks on them
+    pub async fn lint(&self) -> Result<LintReport> {
+        let start = self.start().clone();
+        let driver = self.repository().driver.lock().await;
+        let layout = driver
+            .try_get_sheet_layout(&start.sheet_name)
+            .await
+            .change_context(RepositoryError::DriverError)?;
+        let grid = layout
+            .properties
+            .and_then(|p| p.grid_properties)
+            .unwrap_or_default();
+        let grid_rows = grid.row_count.unwrap_or_default().max(0) as u32;
+
+        // Header row, the table rows and everything below them down to the end of the grid
+        let first_row = start.cell.row.get() - 1;
+        let header_row = NonZero::new(first_row).filter(|_| !E::column_headers().is_empty());
+        let top = header_row.unwrap_or(start.cell.row);
+        // Rows of the table capacity past the grid don't exist, there's nothing to read there
+        let bottom = match grid_rows {
+            0 => first_row + self.rows(),
+            rows => rows,
+        }
+        .max(top.get());
+        let range = SheetA1Range::new(
+            &start.sheet_name,
+            A1Range::new(
+                A1CellId::new(start.cell.col.clone(), top),
+                A1CellId::new(
+                    start.cell.col.clone() + (self.width() - 1),
+                    NonZero::new(bottom).expect("Expected non-zero row"),
+                ),
+            ),
+        );
+        let mut values = driver
+            .try_get_values(&range)
+            .await
+            .change_context(RepositoryError::DriverError)?
+            .values
+            .unwrap_or_default()
+            .into_iter();
+        drop(driver);
+
+        let mut issues = vec![];
+        if header_row.is_some() {
+            let header = values.next().unwrap_or_default();
+            issues.extend(self.header_issues(&header));
+        }
+        let rows: Vec<SheetRow> = values.by_ref().take(self.rows() as usize).collect();
+        let below: Vec<SheetRow> = values.collect();
+
+        let (table_rows, table_columns) = self.grid_span();
+        issues.extend(
+            layout
+                .merges
+                .unwrap_or_default()
+                .iter()
+                .filter(|merge| overlaps(merge, &table_rows, &table_columns))
+                .map(|merge| LintIssue::MergedCells {
+                    range: merge_a1(&start.sheet_name, merge, &table_rows, &table_columns),
+                }),
+        );
+        issues.extend(self.kind_issues(&rows));
+        issues.extend(below.iter().enumerate().filter_map(|(index, row)| {
+            let column = row.iter().position(|cell| !cell_text(cell).is_empty())?;
+            Some(LintIssue::DataBelowTable {
+                cell: self.cell_a1(column as u32, (self.rows() as usize + index) as i32),
+            })
+        }));
+
+        let report = LintReport {
+            table: start,
+            issues,
+        };
+        info!(
+            "Linted the table at {}: {} issues",
+            report.table,
+            report.issues.len()
+        );
+        Ok(report)
+    }
+
+    fn header_issues(&self, header: &[Value]) -> Vec<LintIssue> {
+        E::column_headers()
+            .iter()
+            .enumerate()
+            .filter_map(|(column, &expected)| {
+                let found = header.get(column).map(cell_text).unwrap_or_default();
+                (found != expected).then(|| LintIssue::HeaderMismatch {
+                    cell: self.cell_a1(column as u32, -1),
+                    expected,
+                    found,
+                })
+            })
+            .collect()
+    }
+
+    fn kind_issues(&self, rows: &[SheetRow]) -> Vec<LintIssue> {
+        let columns = E::columns();
+        let mut issues = vec![];
+        for column in 0..self.width() as usize {
+            let kind = columns
+                .get(column)
+                .map(|meta| meta.kind)
+                .unwrap_or_default();
+            let cells = rows
+                .iter()
+                .enumerate()
+                .filter_map(|(index, row)| Some((index, row.get(column)?)))
+                .filter(|(_, cell)| !cell_text(cell).is_empty());
+            if kind == ColumnKind::Any {
+                let mut kinds = cells.map(|(_, cell)| std::mem::discriminant(cell));
+                let first = kinds.next();
+                if kinds.any(|kind| Some(kind) != first) {
+                    issues.push(LintIssue::MixedKinds {
+                        column: column as u32,
+                    });
+                }
+                continue;
+            }
+            issues.extend(
+                cells
+                    .filter(|(_, cell)| !fits(kind, cell))
+                    .map(|(index, cell)| LintIssue::UnexpectedKind {
+                        cell: self.cell_a1(column as u32, index as i32),
+                        expected: kind,
+                        found: cell.clone(),
+                    }),
+            );
+        }
+        issues
+    }
+
+    /// A1 of the cell `column` columns right of the start and `row` rows below it
+    fn cell_a1(&self, column: u32, row: i32) -> String {
+        let start = self.start();
+        SheetA1CellId::new(&start.sheet_name, start.cell.delta(column as i32, row)).to_string()
+    }
+
+    /// 0-based, end exclusive rows and columns of the table data
+    fn grid_span(&self) -> (Range<u32>, Range<u32>) {
+        let start = NumCellId::from(self.start().cell.clone());
+        (
+            start.row..start.row + self.rows(),
+            start.col..start.col + self.width(),
+        )
+    }
+}
+
+/// Values read unformatted: numbers and booleans are JSON ones, dates are serial numbers
+fn fits(kind: ColumnKind, cell: &Value) -> bool {
+    match (kind, cell) {
+        (ColumnKind::Any, _) => true,
+        (ColumnKind::Text, cell) => cell.is_string(),
+        (ColumnKind::Integer, Value::Number(number)) => {
+            number.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        (ColumnKind::Number, Value::Number(_)) => true,
+        (ColumnKind::Boolean, Value::Bool(_)) => true,
+        (ColumnKind::Date, Value::Number(number)) => number
+            .as_f64()
+            .and_then(SpreadSheetDateTime::from_raw)
+            .is_some(),
+        // Strings the sheet didn't parse, e.g. a number written as text
+        (_, Value::String(text)) => match kind {
+            ColumnKind::Integer => text.trim().parse::<i64>().is_ok(),
+            ColumnKind::Number => text.trim().parse::<f64>().is_ok(),
+            ColumnKind::Boolean => ["TRUE", "FALSE"].contains(&text.to_uppercase().as_str()),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Merge bounds are optional: a missing start is the first row or column,
+/// a missing end is the end of the grid
+fn overlaps(merge: &GridRange, rows: &Range<u32>, columns: &Range<u32>) -> bool {
+    let (row_start, row_end) = bounds(merge.start_row_index, merge.end_row_index);
+    let (col_start, col_end) = bounds(merge.start_column_index, merge.end_column_index);
+    row_start < rows.end
+        && rows.start < row_end
+        && col_start < columns.end
+        && columns.start < col_end
+}
+
+fn bounds(start: Option<i32>, end: Option<i32>) -> (u32, u32) {
+    (
+        start.unwrap_or_default().max(0) as u32,
+        end.map_or(u32::MAX, |end| end.max(0) as u32),
+    )
+}
+
+/// Unbounded ends are clamped to the table
+fn merge_a1(sheet: &str, merge: &GridRange, rows: &Range<u32>, columns: &Range<u32>) -> String {
+    let (row_start, _) = bounds(merge.start_row_index, merge.end_row_index);
+    let (col_start, _) = bounds(merge.start_column_index, merge.end_column_index);
+    let row_end = merge
+        .end_row_index
+        .map_or(rows.end, |end| end.max(1) as u32);
+    let col_end = merge
+        .end_column_index
+        .map_or(columns.end, |end| end.max(1) as u32);
+    let corner = |col: u32, row: u32| A1CellId::from(NumCellId::from_primitives(col, row));
+    SheetA1Range::new(
+        sheet,
+        A1Range::new(
+            corner(col_start, row_start),
+            corner(col_end - 1, row_end - 1),
+        ),
+    )
+    .to_string()
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod lint_tests {
+    use super::*;
+    use crate::spread_sheet_driver::SsdResult;
+    use crate::spread_sheet_driver::backend::SheetsBackend;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use crate::testing::examples_support::User;
+    use crate::testing::fixtures::backend_repository;
+    use serde_json::json;
+
+    /// Sheet "users" of 10x3 cells with A3:B3 merged, values from the memory
+    #[derive(Debug, Default)]
+    struct LayoutBackend {
+        memory: MemoryBackend,
+    }
+
+    impl SheetsBackend for LayoutBackend {
+        fn handle(&self, operation: &str, request: &Value) -> SsdResult<Value> {
+            match operation {
+                "spreadsheets.get" => Ok(json!({
+                    "sheets": [{
+                        "properties": {
+                            "sheetId": 1,
+                            "title": "users",
+                            "gridProperties": { "rowCount": 10, "columnCount": 3 }
+                        },
+                        "merges": [{
+                            "sheetId": 1,
+                            "startRowIndex": 2,
+                            "endRowIndex": 3,
+                            "startColumnIndex": 0,
+                            "endColumnIndex": 2
+                        }]
+                    }]
+                })),
+                _ => self.memory.handle(operation, request),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn lint__human_edits__every_issue_reported() {
+        let backend = LayoutBackend::default();
+        backend.memory.workbook().set_sheet(
+            "users",
+            vec![
+                vec![json!("id"), json!("Name")],
+                vec![json!("1"), json!("Joe")],
+                vec![json!(2), json!("Jane")],
+                vec![json!("x"), json!(5)],
+                vec![],
+                vec![json!(""), json!("stray")],
+            ],
+        );
+        let repository = backend_repository(backend);
+
+        let report = repository
+            .table::<User>(SheetA1CellId::from_primitives("users", "A", 2), 3)
+            .lint()
+            .await
+            .expect("Test: Expected lint report");
+
+        assert_eq!(
+            report.issues,
+            vec![
+                LintIssue::HeaderMismatch {
+                    cell: "users!B1".to_string(),
+                    expected: "name",
+                    found: "Name".to_string(),
+                },
+                LintIssue::MergedCells {
+                    range: "users!A3:B3".to_string()
+                },
+                LintIssue::UnexpectedKind {
+                    cell: "users!A4".to_string(),
+                    expected: ColumnKind::Integer,
+                    found: json!("x"),
+                },
+                LintIssue::UnexpectedKind {
+                    cell: "users!B4".to_string(),
+                    expected: ColumnKind::Text,
+                    found: json!(5),
+                },
+                LintIssue::DataBelowTable {
+                    cell: "users!B6".to_string()
+                },
+            ]
+        );
+        assert!(!report.is_clean());
+    }
+}
@@ -0,0 +1,384 @@
+//////////////////////// Table schema migrations ////////////////////////
+
+use crate::mapper::sheet_row::SheetRow;
+use crate::orm::table::Table;
+use crate::orm::{RepositoryError, Result};
+use crate::spread_sheet_driver::structure::cut_paste_request;
+use crate::spread_sheet_driver::{IntoStrVec, SpreadSheetDriver};
+use crate::types::{A1CellId, A1Range, EntityEssentials, InputMode, SheetA1Range, SheetGid};
+use error_stack::{ResultExt, bail};
+use google_sheets4::api::Request;
+use serde_json::Value;
+use std::num::NonZero;
+use std::ops::Range;
+use tracing::info;
+
+/// Single step of [`Table::migrate`]. Columns are addressed by their header
+#[derive(Debug, Clone, PartialEq)]
+pub enum SchemaChange {
+    /// Inserts a column before the 0-based `index` (the table width appends it),
+    /// writes the header and fills `default` into every non-empty row
+    AddColumn {
+        index: u32,
+        header: String,
+        default: Value,
+    },
+    RenameColumn {
+        from: String,
+        to: String,
+    },
+    /// Moves the column to the 0-based `to` index, shifting the columns in between
+    MoveColumn {
+        header: String,
+        to: u32,
+    },
+}
+
+impl<E> Table<'_, E>
+where
+    E: EntityEssentials,
+{
+    /// Applies the changes in order and returns the resulting headers.
+    /// Headers are expected in the row right above the table start.
+    /// Columns are shifted with cut/paste inside the table bounds only, so tables next to this
+    /// one are left intact, but the column right of the table is used as scratch space and
+    /// must be empty
+    pub async fn migrate(&self, changes: &[SchemaChange]) -> Result<Vec<String>> {
+        let start = self.start();
+        let Some(header_row) = NonZero::new(start.cell.row.get() - 1) else {
+            bail!(RepositoryError::InvalidArgument(format!(
+                "Table at {start:?} has no header row above it"
+            )));
+        };
+        let driver = self.repository().driver.lock().await;
+
+        // Headers, data and the columns to the right, which the table may grow into
+        let read_width = self.width() + changes.len() as u32 + 1;
+        let range = SheetA1Range::new(
+            &start.sheet_name,
+            A1Range::new(
+                A1CellId::new(start.cell.col.clone(), header_row),
+                A1CellId::new(
+                    start.cell.col.clone() + (read_width - 1),
+                    header_row.saturating_add(self.rows()),
+                ),
+            ),
+        );
+        let mut rows = driver
+            .try_get_range(&range)
+            .await
+            .change_context(RepositoryError::DriverError)?
+            .into_vec()
+            .into_iter();
+
+        let mut headers: Vec<String> = rows
+            .next()
+            .unwrap_or_default()
+            .iter()
+            .map(cell_text)
+            .collect();
+        while headers.last().is_some_and(String::is_empty) {
+            headers.pop();
+        }
+        let data: Vec<SheetRow> = rows.collect();
+        if data
+            .iter()
+            .any(|row| !row.iter().skip(headers.len()).all(is_blank))
+        {
+            bail!(RepositoryError::InvalidArgument(format!(
+                "Columns right of the table at {start:?} must be empty"
+            )));
+        }
+        let populated: Vec<bool> = data.iter().map(|row| !row.iter().all(is_blank)).collect();
+
+        let mut migration = Migration {
+            driver: &driver,
+            table: range,
+            sheet_id: None,
+            headers,
+            populated,
+        };
+        for change in changes {
+            migration.apply(change).await?;
+        }
+
+        info!("Migrated table at {start:?}: {:?}", migration.headers);
+        Ok(migration.headers)
+    }
+}
+
+struct Migration<'d> {
+    driver: &'d SpreadSheetDriver,
+    /// Header row and the data rows
+    table: SheetA1Range,
+    sheet_id: Option<SheetGid>,
+    headers: Vec<String>,
+    populated: Vec<bool>,
+}
+
+impl Migration<'_> {
+    async fn apply(&mut self, change: &SchemaChange) -> Result<()> {
+        let width = self.headers.len() as u32;
+        match change {
+            SchemaChange::AddColumn {
+                index,
+                header,
+                default,
+            } => {
+                if *index > width {
+                    bail!(RepositoryError::InvalidArgument(format!(
+                        "Can't add column at {index}, the table has {width} columns"
+                    )));
+                }
+                if *index < width {
+                    let sheet_id = self.sheet_id().await?;
+                    let request = self.cut_paste(sheet_id, *index..width, *index + 1);
+                    self.batch_update(vec![request]).await?;
+                }
+
+                let mut column = vec![vec![Value::String(header.clone())]];
+                let last = self.populated.iter().rposition(|p| *p).map_or(0, |i| i + 1);
+                column.extend(
+                    self.populated[..last]
+                        .iter()
+                        .map(|populated| match populated {
+                            true => vec![default.clone()],
+                            false => vec![Value::String(String::new())],
+                        }),
+                );
+                self.write_column(*index, column).await?;
+                self.headers.insert(*index as usize, header.clone());
+            }
+            SchemaChange::RenameColumn { from, to } => {
+                let index = self.index_of(from)?;
+                self.write_column(index, vec![vec![Value::String(to.clone())]])
+                    .await?;
+                self.headers[index as usize] = to.clone();
+            }
+            SchemaChange::MoveColumn { header, to } => {
+                let from = self.index_of(header)?;
+                if *to >= width {
+                    bail!(RepositoryError::InvalidArgument(format!(
+                        "Can't move column '{header}' to {to}, the table has {width} columns"
+                    )));
+                }
+                if from == *to {
+                    return Ok(());
+                }
+
+                let sheet_id = self.sheet_id().await?;
+                let shift = match from < *to {
+                    true => self.cut_paste(sheet_id, from + 1..*to + 1, from),
+                    false => self.cut_paste(sheet_id, *to..from, *to + 1),
+                };
+                // Through the scratch column, as the moved column is overwritten by the shift
+                let requests = vec![
+                    self.cut_paste(sheet_id, from..from + 1, width),
+                    shift,
+                    self.cut_paste(sheet_id, width..width + 1, *to),
+                ];
+                self.batch_update(requests).await?;
+                let moved = self.headers.remove(from as usize);
+                self.headers.insert(*to as usize, moved);
+            }
+        }
+        Ok(())
+    }
+
+    fn index_of(&self, header: &str) -> Result<u32> {
+        match self.headers.iter().position(|h| h == header) {
+            Some(index) => Ok(index as u32),
+            None => bail!(RepositoryError::InvalidArgument(format!(
+                "No column with header '{header}', headers: {:?}",
+                self.headers
+            ))),
+        }
+    }
+
+    /// Moves the table columns (0-based, relative to the table) to the `to` column, all rows
+    fn cut_paste(&self, sheet_id: SheetGid, columns: Range<u32>, to: u32) -> Request {
+        let first_row = self.table.range.start.row.get() - 1;
+        let last_row = self.table.range.end.row.get();
+        let first_column = self.table.range.start.column().get() - 1;
+        cut_paste_request(
+            sheet_id,
+            first_row..last_row,
+            first_column + columns.start..first_column + columns.end,
+            first_row,
+            first_column + to,
+        )
+    }
+
+    async fn sheet_id(&mut self) -> Result<SheetGid> {
+        if let Some(sheet_id) = self.sheet_id {
+            return Ok(sheet_id);
+        }
+        let sheet_id = self
+            .driver
+            .try_get_sheet_id(&self.table.sheet)
+            .await
+            .change_context(RepositoryError::DriverError)?;
+        self.sheet_id = Some(sheet_id);
+        Ok(sheet_id)
+    }
+
+    async fn batch_update(&self, requests: Vec<Request>) -> Result<()> {
+        self.driver
+            .try_batch_update(requests)
+            .await
+            .change_context(RepositoryError::DriverError)?;
+        Ok(())
+    }
+
+    async fn write_column(&self, index: u32, values: Vec<SheetRow>) -> Result<()> {
+        let start = self.table.range.start.delta(index as i32, 0);
+        let end = start.delta(0, values.len() as i32 - 1);
+        let range = SheetA1Range::new(&self.table.sheet, A1Range::new(start, end));
+        self.driver
+            .try_write_range_as(&range.to_string(), values, InputMode::UserEntered)
+            .await
+            .change_context(RepositoryError::DriverError)
+    }
+}
+
+pub(crate) fn cell_text(cell: &Value) -> String {
+    match cell {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn is_blank(cell: &Value) -> bool {
+    cell_text(cell).is_empty()
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod migration_tests {
+    use super::*;
+    use crate::spread_sheet_driver::cassette::Interaction;
+    use crate::testing::examples_support::User;
+    use crate::testing::fixtures::{MatchedValueRangeBuilder, replay_repository};
+    use crate::types::SheetA1CellId;
+    use google_sheets4::api::{
+        BatchGetValuesByDataFilterResponse, BatchUpdateSpreadsheetResponse, Sheet, SheetProperties,
+        Spreadsheet, UpdateValuesResponse,
+    };
+    use serde::Serialize;
+    use serde_json::json;
+
+    fn interaction<T>(operation: &str, request: Value, response: T) -> Interaction
+    where
+        T: Serialize,
+    {
+        Interaction {
+            operation: operation.to_string(),
+            request,
+            response: serde_json::to_value(response).expect("Test: Expected to serialize"),
+        }
+    }
+
+    fn read_interaction(range: &str) -> Interaction {
+        let values = BatchGetValuesByDataFilterResponse {
+            value_ranges: Some(vec![
+                MatchedValueRangeBuilder::new(range)
+                    .row(["id", "name"])
+                    .row(["1", "Joe"])
+                    .build(),
+            ]),
+            ..Default::default()
+        };
+        interaction(
+            "values.batchGetByDataFilter",
+            json!({ "range": range }),
+            values,
+        )
+    }
+
+    #[tokio::test]
+    async fn migrate__move_and_rename__cut_paste_through_scratch_column() {
+        let spreadsheet = Spreadsheet {
+            sheets: Some(vec![Sheet {
+                properties: Some(SheetProperties {
+                    sheet_id: Some(7),
+                    title: Some("users".to_string()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+        // Table rows 2..=11 plus the header, columns A:B plus the scratch column C
+        let requests = vec![
+            cut_paste_request(SheetGid(7), 0..11, 1..2, 0, 2),
+            cut_paste_request(SheetGid(7), 0..11, 0..1, 0, 1),
+            cut_paste_request(SheetGid(7), 0..11, 2..3, 0, 0),
+        ];
+        let repository = replay_repository(vec![
+            read_interaction("users!A1:E11"),
+            interaction(
+                "spreadsheets.get",
+                json!({ "fields": "sheets.properties" }),
+                spreadsheet,
+            ),
+            interaction(
+                "spreadsheets.batchUpdate",
+                json!({ "requests": requests }),
+                BatchUpdateSpreadsheetResponse::default(),
+            ),
+            interaction(
+                "values.update",
+                json!({
+                    "range": "users!A1:A1",
+                    "values": [["full_name"]]
+                }),
+                UpdateValuesResponse::default(),
+            ),
+        ]);
+        let table = repository.table::<User>(SheetA1CellId::from_primitives("users", "A", 2), 10);
+
+        let headers = table
+            .migrate(&[
+                SchemaChange::MoveColumn {
+                    header: "name".to_string(),
+                    to: 0,
+                },
+                SchemaChange::RenameColumn {
+                    from: "name".to_string(),
+                    to: "full_name".to_string(),
+                },
+            ])
+            .await
+            .expect("Test: Expected migration to succeed");
+
+        assert_eq!(headers, vec!["full_name".to_string(), "id".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn migrate__add_column_at_end__header_and_defaults_written() {
+        let repository = replay_repository(vec![
+            read_interaction("users!A1:D11"),
+            interaction(
+                "values.update",
+                json!({
+                    "range": "users!C1:C2",
+                    "values": [["active"], [true]]
+                }),
+                UpdateValuesResponse::default(),
+            ),
+        ]);
+        let table = repository.table::<User>(SheetA1CellId::from_primitives("users", "A", 2), 10);
+
+        let headers = table
+            .migrate(&[SchemaChange::AddColumn {
+                index: 2,
+                header: "active".to_string(),
+                default: Value::Bool(true),
+            }])
+            .await
+            .expect("Test: Expected migration to succeed");
+
+        assert_eq!(headers, vec!["id", "name", "active"]);
+    }
+}
@@ -1,8 +1,52 @@
-use crate::spread_sheet_driver::SharedSpreadSheetDriver;
-use crate::types::{A1CellId, A1Range, Entity, EntityEssentials, SheetA1CellId, SheetA1Range};
+pub mod append;
+pub mod audit;
+pub mod change_watcher;
+pub mod column;
+pub mod column_stats;
+pub mod concurrent;
+pub mod dedupe;
+pub mod discovery;
+pub mod document_sync;
+pub mod event_log;
+pub mod formatted;
+pub mod headers;
+pub mod idempotency;
+pub mod identity;
+pub mod import;
+pub mod lint;
+pub mod migration;
+pub mod multi_read;
+pub mod options;
+mod range_data;
+pub mod rollover;
+pub mod row_colors;
+pub mod snapshot;
+pub mod sorted;
+pub mod sync;
+pub mod table;
+pub mod table_diff;
+pub mod table_options;
+pub mod upsert;
+pub mod validation;
+
+use crate::orm::append::row_positions;
+use crate::orm::audit::{AuditLog, AuditOperation, AuditRecord};
+use crate::orm::identity::{RowIdentity, tag_rows};
+use crate::orm::migration::cell_text;
+use crate::orm::options::RepositoryOptions;
+use crate::orm::range_data::RangeData;
+use crate::spread_sheet_driver::responses::AppendSummary;
+use crate::spread_sheet_driver::structure::delete_rows_request;
+use crate::spread_sheet_driver::{SharedSpreadSheetDriver, matched_range};
+use crate::types::{
+    A1CellId, A1Range, Entity, EntityEssentials, SheetA1CellId, SheetA1Range, UserRange,
+    UserRangeBounds,
+};
 use error_stack::{ResultExt, bail};
 use google_sheets4::api::{AppendValuesResponse, MatchedValueRange};
+use serde_json::Value;
 use std::num::NonZero;
+use std::ops::Range;
 use std::sync::Arc;
 use tracing::{debug, info};
 
@@ -20,35 +64,121 @@ pub enum RepositoryError {
         input: String,
         response: Box<AppendValuesResponse>,
     },
+    #[error["Table has changed since the plan was made"]]
+    StalePlan,
+    #[error["Header row of the table at {table} is {found:?}, expected {expected:?}"]]
+    HeaderMismatch {
+        table: String,
+        expected: Vec<String>,
+        found: Vec<String>,
+    },
 }
 
 pub type Result<T> = error_stack::Result<T, RepositoryError>;
 
+/// How [`Repository::delete_with`] removes an entity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DeleteMode {
+    /// Empties the cells of the entity, the rows below stay where they are
+    #[default]
+    Clear,
+    /// Deletes the whole sheet row, the rows below shift up. Cells of other tables placed
+    /// side by side in the same row are deleted too
+    RemoveRow,
+}
+
 pub type SharedRepository = Arc<Repository>;
 pub struct Repository {
     pub driver: SharedSpreadSheetDriver,
+    identity: RowIdentity,
+    options: RepositoryOptions,
+    audit: Option<AuditLog>,
+    /// Serializes read-then-write operations, shared with the overriding repositories
+    upserts: Arc<tokio::sync::Mutex<()>>,
 }
 
 impl Repository {
     pub fn new(driver: SharedSpreadSheetDriver) -> Self {
-        Self { driver }
+        Self {
+            driver,
+            identity: RowIdentity::default(),
+            options: RepositoryOptions::default(),
+            audit: None,
+            upserts: Arc::default(),
+        }
     }
     pub async fn find_in_range<E>(&self, start: &SheetA1CellId, rows: u32) -> Result<Vec<Entity<E>>>
     where
         E: EntityEssentials,
     {
-        let range = convert_into_range(start, rows, E::entity_width());
+        self.find_in_range_of_width(start, rows, E::entity_width())
+            .await
+    }
+
+    /// Same as [`Repository::find_in_range`] for tables `width` columns wide
+    pub(crate) async fn find_in_range_of_width<E>(
+        &self,
+        start: &SheetA1CellId,
+        rows: u32,
+        width: u32,
+    ) -> Result<Vec<Entity<E>>>
+    where
+        E: EntityEssentials,
+    {
+        let range = convert_into_range(start, rows, width);
         let matched_value_range = self
             .driver
             .lock()
             .await
-            .try_get_range(&range)
+            .try_get_range_with(&range, &self.options.read_options())
             .await
             .change_context(RepositoryError::DriverError)?;
 
         matched_value_range.parse_positionally()
     }
 
+    /// Every entity of the table at `table_start`, without knowing its size up front.
+    /// Reads the entity columns down to the end of the data (e.g. `users!A2:B`) and stops at
+    /// the first fully empty row
+    pub async fn find_all<E>(&self, table_start: SheetA1CellId) -> Result<Vec<Entity<E>>>
+    where
+        E: EntityEssentials,
+    {
+        let last = table_start
+            .cell
+            .delta(E::entity_width().saturating_sub(1) as i32, 0);
+        let range = UserRange {
+            sheet: Some(table_start.sheet_name.clone()),
+            bounds: UserRangeBounds::Columns {
+                first: table_start.cell.col.clone(),
+                last: last.col,
+                from_row: table_start.cell.row,
+            },
+        };
+        let value_range = self
+            .driver
+            .lock()
+            .await
+            .try_get_values_with(&range, &self.options.read_options())
+            .await
+            .change_context(RepositoryError::DriverError)?;
+
+        let mut data = RangeData::from_value_range(value_range)?;
+        let filled = data
+            .rows
+            .iter()
+            .position(|row| row.iter().all(|cell| cell_text(cell).is_empty()))
+            .unwrap_or(data.rows.len());
+        debug!(
+            "Table at {} has {} rows of {} read",
+            table_start,
+            filled,
+            data.rows.len()
+        );
+        data.rows.truncate(filled);
+        data.parse_positionally()
+    }
+
     pub async fn find_by_position<E>(&self, start: SheetA1CellId) -> Result<Option<Entity<E>>>
     where
         E: EntityEssentials,
@@ -57,14 +187,33 @@ impl Repository {
         Ok(vec.first().cloned())
     }
 
+    /// Runs the calls of `operation` on a single retry budget of the driver, see
+    /// [`crate::spread_sheet_driver::SpreadSheetDriver::retry_budget`]. Keeps the latency
+    /// of a read, write and verify bounded instead of each call retrying on its own.
+    /// Operations running meanwhile on the same driver keep their own budgets
+    pub async fn within_retry_budget<T, F>(&self, operation: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        let budget = self.driver.lock().await.retry_budget();
+        match budget {
+            Some(budget) => budget.scope(operation).await,
+            None => operation.await,
+        }
+    }
+
     pub async fn update<E>(&self, entity: &Entity<E>) -> Result<()>
     where
         E: EntityEssentials,
     {
-        let new_row = entity.position.cell.row.get() + 1;
-        let end_col = entity.position.cell.col.clone() + E::entity_width();
-        let range = entity.position.clone().into_range(end_col, new_row);
+        self.within_retry_budget(self.apply_update(entity)).await
+    }
 
+    async fn apply_update<E>(&self, entity: &Entity<E>) -> Result<()>
+    where
+        E: EntityEssentials,
+    {
+        let position = self.current_position(entity).await?;
         let data = vec![
             entity
                 .data
@@ -72,16 +221,65 @@ impl Repository {
                 .serialize()
                 .change_context(RepositoryError::DriverError)?,
         ];
+        // Rows of dynamic width entities may be wider than the type declares
+        let width = E::entity_width().max(data[0].len() as u32);
+        let new_row = position.cell.row.get() + 1;
+        let end_col = position.cell.col.clone() + width;
+        let range = position.clone().into_range(end_col, new_row);
+
+        let old = match self.is_audited() {
+            true => self
+                .find_by_position::<E>(position)
+                .await?
+                .map(|e| e.data.serialize())
+                .transpose()
+                .change_context(RepositoryError::DriverError)?,
+            false => None,
+        };
 
         debug!("Updating entity\n{:#?}\nas raw data:{:#?}", entity, data);
 
-        self.driver
-            .lock()
-            .await
-            .try_write_range(range.to_string().as_str(), data)
-            .await
-            .change_context(RepositoryError::DriverError)?;
-        Ok(())
+        let read_only = E::read_only_columns();
+        let driver = self.driver.lock().await;
+        if read_only.is_empty() {
+            driver
+                .try_write_range_as(
+                    range.to_string().as_str(),
+                    data.clone(),
+                    self.options.input_mode,
+                )
+                .await
+                .change_context(RepositoryError::DriverError)?;
+        } else {
+            // One write per run of writable columns, leaving the sheet-side formulas intact
+            let row = &data[0];
+            for run in writable_runs(row.len() as u32, read_only) {
+                let range = SheetA1Range::new(
+                    &position.sheet_name,
+                    A1Range::new(
+                        position.cell.delta(run.start as i32, 0),
+                        position.cell.delta(run.end as i32 - 1, 0),
+                    ),
+                );
+                driver
+                    .try_write_range_as(
+                        range.to_string().as_str(),
+                        vec![row[run.start as usize..run.end as usize].to_vec()],
+                        self.options.input_mode,
+                    )
+                    .await
+                    .change_context(RepositoryError::DriverError)?;
+            }
+        }
+        drop(driver);
+
+        self.record_audit(vec![AuditRecord::new(
+            AuditOperation::Update,
+            entity,
+            old.as_ref(),
+            data.first(),
+        )])
+        .await
     }
 
     /// Inserts entity into specified table by appending it to the end of the range.
@@ -94,18 +292,49 @@ impl Repository {
     where
         E: EntityEssentials,
     {
-        let range = convert_into_range(&start, rows, E::entity_width());
+        self.insert_of_width(start, rows, E::entity_width(), entity_data)
+            .await
+    }
+
+    /// Same as [`Repository::insert`] for tables `width` columns wide
+    pub(crate) async fn insert_of_width<E>(
+        &self,
+        start: SheetA1CellId,
+        rows: u32,
+        table_width: u32,
+        entity_data: E,
+    ) -> Result<Entity<E>>
+    where
+        E: EntityEssentials,
+    {
+        self.within_retry_budget(self.append_entity(start, rows, table_width, entity_data))
+            .await
+    }
+
+    async fn append_entity<E>(
+        &self,
+        start: SheetA1CellId,
+        rows: u32,
+        table_width: u32,
+        entity_data: E,
+    ) -> Result<Entity<E>>
+    where
+        E: EntityEssentials,
+    {
+        let range = convert_into_range(&start, rows, table_width);
 
         let data = entity_data
             .clone()
             .serialize()
             .change_context(RepositoryError::DriverError)?;
+        let width = data.len() as u32;
+        let written = data.clone();
 
         let avr = self
             .driver
             .lock()
             .await
-            .try_append_row(range.to_string().as_str(), data)
+            .try_append_rows_as(range.to_string(), vec![data], self.options.input_mode)
             .await
             .change_context(RepositoryError::DriverError)?;
 
@@ -114,35 +343,145 @@ impl Repository {
             range, entity_data, avr
         );
 
-        let Some(updates) = &avr.updates else {
+        let appended = match AppendSummary::from_response(&avr) {
+            Ok(appended) => appended,
+            Err(e) => {
+                return Err(e.change_context(RepositoryError::UnexpectedResponse {
+                    what: "AppendValuesResponse doesn't have a valid 'updates.updated_range'",
+                    input: format!("Input range: {:?}, data: {:?}", range, entity_data),
+                    response: Box::new(avr),
+                }));
+            }
+        };
+        let updated = appended.updates.range;
+        let Some(position) = row_positions(&updated, 1, width).and_then(|p| p.into_iter().next())
+        else {
             bail!(RepositoryError::UnexpectedResponse {
-                what: "AppendValuesResponse doesn't have 'updates'",
+                what: "Updated range doesn't match the size of the written row",
                 input: format!("Input range: {:?}, data: {:?}", range, entity_data),
                 response: Box::new(avr)
             });
         };
 
-        let Some(updated_range) = &updates.updated_range else {
-            bail!(RepositoryError::UnexpectedResponse {
-                what: "UpdateValuesResponse doesn't have 'updated_range'",
-                input: format!("Input range: {:?}, data: {:?}", range, updates),
-                response: Box::new(avr)
-            });
+        let row_tag = match self.identity {
+            RowIdentity::Position => None,
+            RowIdentity::Metadata => {
+                let driver = self.driver.lock().await;
+                let ids =
+                    tag_rows(&driver, &position.sheet_name, position.cell.row.get(), 1).await?;
+                ids.into_iter().next()
+            }
         };
 
-        let position =
-            SheetA1Range::from_raw(updated_range).change_context(RepositoryError::ParsingError)?;
-        Ok(Entity {
-            position: position.start(),
+        let entity = Entity {
+            position,
             data: entity_data,
-        })
+            row_tag,
+        };
+        self.record_audit(vec![AuditRecord::new(
+            AuditOperation::Insert,
+            &entity,
+            None,
+            Some(&written),
+        )])
+        .await?;
+        Ok(entity)
     }
 
-    pub async fn delete<E>(&self, _entity: &Entity<E>) -> Result<()>
+    /// Removes the row of an entity tagged with an id (see [`RowIdentity::Metadata`]), so the
+    /// tag goes with it, and clears the cells of the others. See [`Repository::delete_with`]
+    pub async fn delete<E>(&self, entity: &Entity<E>) -> Result<()>
     where
         E: EntityEssentials,
     {
-        todo!("Brainstorm on how to delete entities properly")
+        let mode = match (self.identity, &entity.row_tag) {
+            (RowIdentity::Metadata, Some(_)) => DeleteMode::RemoveRow,
+            _ => DeleteMode::Clear,
+        };
+        self.delete_with(entity, mode).await
+    }
+
+    /// Deletes the entity where it's now: at its id tag for tagged rows, at the remembered
+    /// position for the others
+    pub async fn delete_with<E>(&self, entity: &Entity<E>, mode: DeleteMode) -> Result<()>
+    where
+        E: EntityEssentials,
+    {
+        let old = entity
+            .data
+            .serialize()
+            .change_context(RepositoryError::DriverError)?;
+        match (mode, self.identity, &entity.row_tag) {
+            (DeleteMode::RemoveRow, RowIdentity::Metadata, Some(id)) => {
+                self.delete_tagged_row(id).await?
+            }
+            (DeleteMode::RemoveRow, ..) => {
+                let position = self.current_position(entity).await?;
+                self.remove_row(&position).await?
+            }
+            (DeleteMode::Clear, ..) => {
+                let position = self.current_position(entity).await?;
+                let width = E::entity_width().max(old.len() as u32);
+                self.clear_row(&position, width, E::read_only_columns())
+                    .await?
+            }
+        }
+        debug!("Deleted entity at {} ({:?})", entity.position, mode);
+
+        self.record_audit(vec![AuditRecord::new(
+            AuditOperation::Delete,
+            entity,
+            Some(&old),
+            None,
+        )])
+        .await
+    }
+
+    async fn remove_row(&self, position: &SheetA1CellId) -> Result<()> {
+        let driver = self.driver.lock().await;
+        let sheet_id = driver
+            .try_get_sheet_id(&position.sheet_name)
+            .await
+            .change_context(RepositoryError::DriverError)?;
+        driver
+            .try_batch_update(vec![delete_rows_request(
+                sheet_id,
+                position.cell.row.get() - 1,
+                1,
+            )])
+            .await
+            .change_context(RepositoryError::DriverError)?;
+        // The grid is a row shorter now
+        driver.invalidate_sheets_cache();
+        Ok(())
+    }
+
+    /// Writes empty cells over the row, skipping the `read_only` columns like updates do
+    async fn clear_row(
+        &self,
+        position: &SheetA1CellId,
+        width: u32,
+        read_only: &[u32],
+    ) -> Result<()> {
+        let driver = self.driver.lock().await;
+        for run in writable_runs(width, read_only) {
+            let range = SheetA1Range::new(
+                &position.sheet_name,
+                A1Range::new(
+                    position.cell.delta(run.start as i32, 0),
+                    position.cell.delta(run.end as i32 - 1, 0),
+                ),
+            );
+            driver
+                .try_write_range_as(
+                    range.to_string().as_str(),
+                    vec![vec![Value::String(String::new()); run.len()]],
+                    self.options.input_mode,
+                )
+                .await
+                .change_context(RepositoryError::DriverError)?;
+        }
+        Ok(())
     }
 }
 
@@ -157,65 +496,28 @@ impl PositionalParsing for MatchedValueRange {
     where
         E: EntityEssentials,
     {
-        let sr = self.extract_range_from_filters()?;
-        let start = sr.range.start;
-
-        let data = self
-            .value_range
-            .expect("Expected to get range")
-            .values
-            .unwrap_or_default();
-
-        let data: Result<Vec<Entity<E>>> = data
-            .into_iter()
-            .enumerate()
-            .map(|(i, value)| {
-                let result: Result<Entity<E>> = E::deserialize(value)
-                    .map(|data| Entity {
-                        position: SheetA1CellId::from_primitives(
-                            &sr.sheet,
-                            &start.col,
-                            start.row.get() + i as u32,
-                        ),
-                        data: data,
-                    })
-                    .change_context(RepositoryError::ParsingError);
-                result
-            })
-            .collect();
-        data
+        RangeData::from_matched(self)?.parse_positionally()
     }
 
     fn extract_range_from_filters(&self) -> Result<SheetA1Range> {
-        let Some(filters) = self.data_filters.as_ref() else {
-            bail!(RepositoryError::InvalidArgument(
-                "MatchedValueRange doesn't have data filters".to_string()
-            ));
-        };
-
-        if filters.len() != 1 {
-            bail!(RepositoryError::InvalidArgument(
-                "MatchedValueRange doesn't have exactly one filter".to_string()
-            ));
-        };
-
-        let filter = filters
-            .first()
-            .expect("Expected to have exactly one filter");
-
-        let Some(range) = filter.a1_range.as_ref() else {
-            bail!(RepositoryError::InvalidArgument(
-                "Data filter doesn't have A1 range".to_string()
-            ));
-        };
-
-        let sr = SheetA1Range::from_raw(range.as_str())
-            .map_err(|e| RepositoryError::InvalidArgument(format!("{e}")))?;
-
-        Ok(sr)
+        matched_range(self).change_context_lazy(|| {
+            RepositoryError::InvalidArgument("Can't locate MatchedValueRange".to_string())
+        })
     }
 }
 
+/// Parses every range of a multi-filter response (see
+/// [`crate::spread_sheet_driver::SpreadSheetDriver::try_query`]), one `Vec` per matched range
+pub fn parse_matched_ranges<E>(ranges: Vec<MatchedValueRange>) -> Result<Vec<Vec<Entity<E>>>>
+where
+    E: EntityEssentials,
+{
+    ranges
+        .into_iter()
+        .map(PositionalParsing::parse_positionally)
+        .collect()
+}
+
 #[allow(non_snake_case)]
 #[cfg(test)]
 mod orm_tests {
@@ -223,68 +525,20 @@ mod orm_tests {
 
     use crate::mapper::sheet_row;
     use crate::mapper::sheet_row::{SheetRow, SheetRowExt, SheetRowSerde};
-    use google_sheets4::api::{DataFilter, ValueRange};
+    use crate::testing::examples_support::User;
+    use crate::testing::fixtures::MatchedValueRangeBuilder;
     use serde_json::Value;
     use std::fmt::Debug;
 
-    #[derive(Debug, Clone, PartialEq)]
-    struct User {
-        id: i32,
-        name: String,
-    }
-
-    impl SheetRowSerde for User {
-        fn deserialize(row: SheetRow) -> sheet_row::Result<Self>
-        where
-            Self: Sized,
-        {
-            Ok(Self {
-                id: row.parse_cell(0, "id")?,
-                name: row.parse_cell(1, "name")?,
-            })
-        }
-        fn serialize(&self) -> sheet_row::Result<SheetRow> {
-            Ok(vec![
-                Value::String(self.name.clone()),
-                Value::String(self.id.to_string()),
-            ])
-        }
-    }
-
-    impl EntityEssentials for User {
-        fn entity_width() -> u32 {
-            2
-        }
-    }
-
     #[cfg(test)]
     mod positional_parsing_tests {
         use super::*;
+        use google_sheets4::api::DataFilter;
 
-        fn get_mocked_query_response() -> MatchedValueRange {
-            MatchedValueRange {
-                data_filters: Some(vec![DataFilter {
-                    a1_range: Some("users!A1:B3".to_string()),
-                    ..Default::default()
-                }]),
-                value_range: Some(ValueRange {
-                    values: Some(vec![
-                        vec![
-                            Value::String("1".to_string()),
-                            Value::String("Joe".to_string()),
-                        ],
-                        vec![
-                            Value::String("2".to_string()),
-                            Value::String("John".to_string()),
-                        ],
-                        vec![
-                            Value::String("3".to_string()),
-                            Value::String("Jane".to_string()),
-                        ],
-                    ]),
-                    ..Default::default()
-                }),
-            }
+        pub(super) fn get_mocked_query_response() -> MatchedValueRange {
+            MatchedValueRangeBuilder::new("users!A1:B3")
+                .rows([["1", "Joe"], ["2", "John"], ["3", "Jane"]])
+                .build()
         }
 
         #[test]
@@ -305,6 +559,7 @@ mod orm_tests {
                         id: 1,
                         name: "Joe".to_string(),
                     },
+                    row_tag: None,
                 },
                 Entity {
                     position: SheetA1CellId::from_primitives("users", "A", 2),
@@ -312,6 +567,7 @@ mod orm_tests {
                         id: 2,
                         name: "John".to_string(),
                     },
+                    row_tag: None,
                 },
                 Entity {
                     position: SheetA1CellId::from_primitives("users", "A", 3),
@@ -319,13 +575,400 @@ mod orm_tests {
                         id: 3,
                         name: "Jane".to_string(),
                     },
+                    row_tag: None,
                 },
             ];
 
             println!("{:#?}", expected);
             assert_eq!(actual, expected)
         }
+
+        #[test]
+        fn parse_matched_ranges__metadata_and_a1_filters__positions_of_each_range() {
+            let mut by_metadata = MatchedValueRangeBuilder::new("orders!C5:D5")
+                .row(["4", "Jack"])
+                .build();
+            // Metadata lookups don't carry A1 range, position comes from the values range
+            by_metadata.data_filters = Some(vec![DataFilter::default()]);
+
+            let parsed: Vec<Vec<Entity<User>>> =
+                parse_matched_ranges(vec![get_mocked_query_response(), by_metadata])
+                    .expect("Test: Expected to parse both ranges");
+
+            assert_eq!(parsed[0].len(), 3);
+            assert_eq!(
+                parsed[1][0].position,
+                SheetA1CellId::from_primitives("orders", "C", 5)
+            );
+        }
+    }
+
+    #[cfg(test)]
+    mod replay_tests {
+        use super::*;
+        use crate::spread_sheet_driver::cassette::Interaction;
+        use crate::testing::fixtures::replay_repository;
+        use google_sheets4::api::BatchGetValuesByDataFilterResponse;
+        use serde_json::json;
+
+        #[tokio::test]
+        async fn given_recorded_range__when_find_in_range__then_entities_parsed() {
+            let response = BatchGetValuesByDataFilterResponse {
+                value_ranges: Some(vec![positional_parsing_tests::get_mocked_query_response()]),
+                ..Default::default()
+            };
+            let repository = replay_repository(vec![Interaction {
+                operation: "values.batchGetByDataFilter".to_string(),
+                request: json!({ "range": "users!A1:B4" }),
+                response: serde_json::to_value(response).expect("Test: Expected to serialize"),
+            }]);
+
+            let start = SheetA1CellId::from_primitives("users", "A", 1);
+            let actual: Vec<Entity<User>> = repository
+                .find_in_range(&start, 3)
+                .await
+                .expect("Test: Expected to replay range");
+
+            assert_eq!(actual.len(), 3);
+            assert_eq!(
+                actual[1].position,
+                SheetA1CellId::from_primitives("users", "A", 2)
+            );
+            assert_eq!(actual[1].name, "John");
+        }
+    }
+
+    #[cfg(test)]
+    mod find_all_tests {
+        use super::*;
+        use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+        use crate::testing::fixtures::backend_repository;
+
+        #[tokio::test]
+        async fn find_all__rows_after_blank_row__stops_at_blank_row() {
+            let backend = MemoryBackend::new();
+            let row =
+                |cells: [&str; 3]| -> SheetRow { cells.into_iter().map(Value::from).collect() };
+            backend.workbook().set_sheet(
+                "users",
+                vec![
+                    row(["", "id", "name"]),
+                    row(["", "1", "Joe"]),
+                    row(["", "2", "John"]),
+                    row(["", "", ""]),
+                    row(["", "4", "Notes below the table"]),
+                ],
+            );
+            let repository = backend_repository(backend);
+
+            let users: Vec<Entity<User>> = repository
+                .find_all(SheetA1CellId::from_primitives("users", "B", 2))
+                .await
+                .expect("Test: Expected entities");
+
+            let names: Vec<&str> = users.iter().map(|user| user.name.as_str()).collect();
+            assert_eq!(names, vec!["Joe", "John"]);
+            assert_eq!(
+                users[1].position,
+                SheetA1CellId::from_primitives("users", "B", 3)
+            );
+        }
+    }
+
+    #[cfg(test)]
+    mod large_integer_tests {
+        use super::*;
+        use crate::testing::fixtures::memory_repository;
+
+        #[derive(Debug, Clone, PartialEq)]
+        struct Message {
+            snowflake: u64,
+            text: String,
+        }
+
+        impl SheetRowSerde for Message {
+            fn deserialize(row: SheetRow) -> sheet_row::Result<Self> {
+                Ok(Self {
+                    snowflake: row.parse_cell(0, "snowflake")?,
+                    text: row.parse_cell(1, "text")?,
+                })
+            }
+
+            fn serialize(&self) -> sheet_row::Result<SheetRow> {
+                Ok(vec![
+                    Value::String(self.snowflake.to_string()),
+                    Value::String(self.text.clone()),
+                ])
+            }
+        }
+
+        impl EntityEssentials for Message {
+            fn entity_width() -> u32 {
+                2
+            }
+        }
+
+        fn message(snowflake: u64, text: &str) -> Message {
+            Message {
+                snowflake,
+                text: text.to_string(),
+            }
+        }
+
+        #[tokio::test]
+        async fn insert_and_update__integer_beyond_exact__sent_as_text() {
+            let repository = memory_repository();
+            let start = SheetA1CellId::from_primitives("messages", "A", 1);
+
+            repository
+                .insert(start.clone(), 10, message(1234567890123456789, "hi"))
+                .await
+                .expect("Test: Expected insert");
+            let mut small = repository
+                .insert(start, 10, message(42, "short"))
+                .await
+                .expect("Test: Expected insert");
+            small.data.snowflake = 9007199254740993;
+            repository
+                .update(&small)
+                .await
+                .expect("Test: Expected update");
+
+            let stored = repository
+                .driver
+                .lock()
+                .await
+                .try_get_range("messages!A1:A2")
+                .await
+                .expect("Test: Expected range")
+                .into_vec();
+            // The API stores the digits as text without the quote, the memory backend keeps it
+            assert_eq!(
+                stored,
+                vec![
+                    vec![Value::from("'1234567890123456789")],
+                    vec![Value::from("'9007199254740993")]
+                ]
+            );
+        }
+    }
+
+    #[cfg(test)]
+    mod write_mask_tests {
+        use super::*;
+        use crate::spread_sheet_driver::IntoStrVec;
+        use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+        use crate::testing::fixtures::backend_repository;
+
+        /// Order line with the total computed by the sheet
+        #[derive(Debug, Clone, PartialEq)]
+        struct Line {
+            item: String,
+            amount: i32,
+            total: String,
+            note: String,
+        }
+
+        impl SheetRowSerde for Line {
+            fn deserialize(row: SheetRow) -> sheet_row::Result<Self> {
+                Ok(Self {
+                    item: row.parse_cell(0, "item")?,
+                    amount: row.parse_cell(1, "amount")?,
+                    total: row.parse_cell(2, "total")?,
+                    note: row.parse_cell(3, "note")?,
+                })
+            }
+
+            fn serialize(&self) -> sheet_row::Result<SheetRow> {
+                Ok(vec![
+                    Value::String(self.item.clone()),
+                    Value::String(self.amount.to_string()),
+                    Value::String(self.total.clone()),
+                    Value::String(self.note.clone()),
+                ])
+            }
+        }
+
+        impl EntityEssentials for Line {
+            fn entity_width() -> u32 {
+                4
+            }
+
+            fn read_only_columns() -> &'static [u32] {
+                &[2]
+            }
+        }
+
+        #[test]
+        fn writable_runs__read_only_columns__skipped() {
+            assert_eq!(writable_runs(4, &[2]), vec![0..2, 3..4]);
+            assert_eq!(writable_runs(3, &[0, 1, 2]), vec![]);
+            assert_eq!(writable_runs(2, &[]), vec![0..2]);
+        }
+
+        #[tokio::test]
+        async fn update__read_only_column__formula_kept() {
+            let backend = MemoryBackend::new();
+            backend.workbook().set_sheet(
+                "orders",
+                vec![vec![
+                    Value::from("apple"),
+                    Value::from("2"),
+                    Value::from("=B1*10"),
+                    Value::from(""),
+                ]],
+            );
+            let repository = backend_repository(backend);
+            let start = SheetA1CellId::from_primitives("orders", "A", 1);
+            let mut line = repository
+                .find_by_position::<Line>(start)
+                .await
+                .expect("Test: Expected read")
+                .expect("Test: Expected line");
+
+            line.amount = 3;
+            line.total = "20".to_string();
+            line.note = "more".to_string();
+            repository
+                .update(&line)
+                .await
+                .expect("Test: Expected update");
+
+            let row = repository
+                .driver
+                .lock()
+                .await
+                .try_get_range("orders!A1:D1")
+                .await
+                .expect("Test: Expected row")
+                .into_vec();
+            assert_eq!(
+                row,
+                vec![vec![
+                    Value::from("apple"),
+                    Value::from("3"),
+                    Value::from("=B1*10"),
+                    Value::from("more"),
+                ]]
+            );
+        }
+
+        #[tokio::test]
+        async fn delete_with__clear__row_emptied_formula_kept() {
+            let backend = MemoryBackend::new();
+            backend.workbook().set_sheet(
+                "orders",
+                vec![
+                    vec![
+                        Value::from("apple"),
+                        Value::from("2"),
+                        Value::from("=B1*10"),
+                        Value::from("fresh"),
+                    ],
+                    vec![Value::from("pear")],
+                ],
+            );
+            let repository = backend_repository(backend);
+            let start = SheetA1CellId::from_primitives("orders", "A", 1);
+            let line = repository
+                .find_by_position::<Line>(start)
+                .await
+                .expect("Test: Expected read")
+                .expect("Test: Expected line");
+
+            repository
+                .delete_with(&line, DeleteMode::Clear)
+                .await
+                .expect("Test: Expected delete");
+
+            let rows = repository
+                .driver
+                .lock()
+                .await
+                .try_get_range("orders!A1:D2")
+                .await
+                .expect("Test: Expected rows")
+                .into_vec();
+            assert_eq!(
+                rows,
+                vec![
+                    vec![Value::from(""), Value::from(""), Value::from("=B1*10"),],
+                    vec![Value::from("pear")],
+                ]
+            );
+        }
+    }
+
+    #[cfg(test)]
+    mod delete_tests {
+        use super::*;
+        use crate::spread_sheet_driver::SsdResult;
+        use crate::spread_sheet_driver::backend::SheetsBackend;
+        use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+        use crate::testing::fixtures::backend_repository;
+        use serde_json::json;
+
+        /// Sheet "users" with id 7, keeps the batchUpdate requests
+        #[derive(Debug, Default)]
+        struct RecordingBackend {
+            memory: MemoryBackend,
+            batches: Arc<std::sync::Mutex<Vec<Value>>>,
+        }
+
+        impl SheetsBackend for RecordingBackend {
+            fn handle(&self, operation: &str, request: &Value) -> SsdResult<Value> {
+                match operation {
+                    "spreadsheets.get" => Ok(json!({
+                        "sheets": [{ "properties": { "sheetId": 7, "title": "users" } }]
+                    })),
+                    "spreadsheets.batchUpdate" => {
+                        self.batches.lock().unwrap().push(request.clone());
+                        Ok(json!({ "replies": [{}] }))
+                    }
+                    _ => self.memory.handle(operation, request),
+                }
+            }
+        }
+
+        #[tokio::test]
+        async fn delete_with__remove_row__delete_dimension_of_the_row() {
+            let backend = RecordingBackend::default();
+            let batches = backend.batches.clone();
+            let repository = backend_repository(backend);
+            let entity = Entity {
+                position: SheetA1CellId::from_primitives("users", "A", 3),
+                data: User {
+                    id: 2,
+                    name: "John".to_string(),
+                },
+                row_tag: None,
+            };
+
+            repository
+                .delete_with(&entity, DeleteMode::RemoveRow)
+                .await
+                .expect("Test: Expected delete");
+
+            let batches = batches.lock().unwrap();
+            assert_eq!(batches.len(), 1);
+            assert_eq!(
+                batches[0]["requests"][0]["deleteDimension"]["range"],
+                json!({ "sheetId": 7, "dimension": "ROWS", "startIndex": 2, "endIndex": 3 })
+            );
+        }
+    }
+}
+
+/// Column ranges of a row `width` columns wide left after skipping the `read_only` offsets
+pub(crate) fn writable_runs(width: u32, read_only: &[u32]) -> Vec<Range<u32>> {
+    let mut runs: Vec<Range<u32>> = vec![];
+    for column in (0..width).filter(|column| !read_only.contains(column)) {
+        match runs.last_mut() {
+            Some(run) if run.end == column => run.end += 1,
+            _ => runs.push(column..column + 1),
+        }
     }
+    runs
 }
 
 // TODO: Fix possible bug with `rows: 1` producing range of 2 rows because of 1-based indexing
@@ -0,0 +1,269 @@
+//////////////////////// Reading several tables at once ////////////////////////
+
+use crate::orm::table::Table;
+use crate::orm::{
+    PositionalParsing, Repository, RepositoryError, Result, convert_into_range,
+    parse_matched_ranges,
+};
+use crate::types::{Entity, EntityEssentials, SheetA1CellId, SheetA1Range};
+use error_stack::{ResultExt, bail};
+use google_sheets4::api::MatchedValueRange;
+use std::marker::PhantomData;
+
+/// Tables to read in a single request. Each added table gives back a key, which later
+/// takes its entities (of the right type) out of the [`MultiReadResult`]
+#[derive(Debug, Default)]
+pub struct MultiRead {
+    ranges: Vec<SheetA1Range>,
+}
+
+/// Typed handle of a table added to [`MultiRead`]
+#[derive(Debug)]
+pub struct TableKey<E> {
+    index: usize,
+    _entity: PhantomData<E>,
+}
+
+/// Raw ranges fetched by [`Repository::find_all_multi`], parsed on [`MultiReadResult::take`]
+#[derive(Debug)]
+pub struct MultiReadResult {
+    ranges: Vec<Option<MatchedValueRange>>,
+}
+
+impl MultiRead {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add<E>(&mut self, start: &SheetA1CellId, rows: u32) -> TableKey<E>
+    where
+        E: EntityEssentials,
+    {
+        self.ranges
+            .push(convert_into_range(start, rows, E::entity_width()));
+        TableKey {
+            index: self.ranges.len() - 1,
+            _entity: PhantomData,
+        }
+    }
+
+    /// Same as [`MultiRead::add`] for the range of the table, of its actual width
+    pub fn add_table<E>(&mut self, table: &Table<'_, E>) -> TableKey<E>
+    where
+        E: EntityEssentials,
+    {
+        self.ranges.push(convert_into_range(
+            table.start(),
+            table.rows(),
+            table.width(),
+        ));
+        TableKey {
+            index: self.ranges.len() - 1,
+            _entity: PhantomData,
+        }
+    }
+}
+
+impl MultiReadResult {
+    /// Entities of the table behind `key`. Each table can be taken once
+    pub fn take<E>(&mut self, key: TableKey<E>) -> Result<Vec<Entity<E>>>
+    where
+        E: EntityEssentials,
+    {
+        let Some(range) = self.ranges.get_mut(key.index).and_then(Option::take) else {
+            bail!(RepositoryError::InvalidArgument(format!(
+                "Table #{} is already taken or doesn't belong to this read",
+                key.index
+            )));
+        };
+        range.parse_positionally()
+    }
+}
+
+impl Repository {
+    /// Fetches all tables of `read` with one batch request instead of one request per table
+    pub async fn find_all_multi(&self, read: MultiRead) -> Result<MultiReadResult> {
+        if read.ranges.is_empty() {
+            return Ok(MultiReadResult { ranges: vec![] });
+        }
+
+        let ranges = self
+            .driver
+            .lock()
+            .await
+            .try_get_ranges_with(&read.ranges, &self.options.read_options())
+            .await
+            .change_context(RepositoryError::DriverError)?;
+
+        Ok(MultiReadResult {
+            ranges: ranges.into_iter().map(Some).collect(),
+        })
+    }
+
+    /// Entities of every table, in the order of `tables`, from a single batch read, so related
+    /// tables reflect the same point in time even while they are being edited. Tables of
+    /// different entity types are read together with [`MultiRead::add_table`].
+    /// Read with the repository options, as a batch has a single render option
+    pub async fn read_consistent<E>(&self, tables: &[Table<'_, E>]) -> Result<Vec<Vec<Entity<E>>>>
+    where
+        E: EntityEssentials,
+    {
+        if tables.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let ranges: Vec<SheetA1Range> = tables
+            .iter()
+            .map(|table| convert_into_range(table.start(), table.rows(), table.width()))
+            .collect();
+        let matched = self
+            .driver
+            .lock()
+            .await
+            .try_get_ranges_with(&ranges, &self.options.read_options())
+            .await
+            .change_context(RepositoryError::DriverError)?;
+        if matched.len() != tables.len() {
+            bail!(RepositoryError::InvalidArgument(format!(
+                "Batch read of {} tables returned {} ranges",
+                tables.len(),
+                matched.len()
+            )));
+        }
+
+        parse_matched_ranges(matched)
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod multi_read_tests {
+    use super::*;
+    use crate::mapper::sheet_row;
+    use crate::mapper::sheet_row::{SheetRow, SheetRowExt, SheetRowSerde};
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use crate::testing::examples_support::User;
+    use crate::testing::fixtures::backend_repository;
+    use serde_json::Value;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Order {
+        user_id: i32,
+        total: f64,
+        status: String,
+    }
+
+    impl SheetRowSerde for Order {
+        fn deserialize(row: SheetRow) -> sheet_row::Result<Self> {
+            Ok(Self {
+                user_id: row.parse_cell(0, "user_id")?,
+                total: row.parse_cell(1, "total")?,
+                status: row.parse_cell(2, "status")?,
+            })
+        }
+
+        fn serialize(&self) -> sheet_row::Result<SheetRow> {
+            Ok(vec![
+                Value::String(self.user_id.to_string()),
+                Value::String(self.total.to_string()),
+                Value::String(self.status.clone()),
+            ])
+        }
+    }
+
+    impl EntityEssentials for Order {
+        fn entity_width() -> u32 {
+            3
+        }
+    }
+
+    fn repository() -> Repository {
+        let backend = MemoryBackend::new();
+        backend.workbook().set_sheet(
+            "users",
+            vec![
+                vec![Value::from("1"), Value::from("Joe")],
+                vec![Value::from("2"), Value::from("John")],
+            ],
+        );
+        backend.workbook().set_sheet(
+            "orders",
+            vec![vec![
+                Value::from("2"),
+                Value::from("9.5"),
+                Value::from("paid"),
+            ]],
+        );
+        backend_repository(backend)
+    }
+
+    #[tokio::test]
+    async fn find_all_multi__two_entity_types__each_table_parsed() {
+        let repository = repository();
+        let mut read = MultiRead::new();
+        let users = read.add::<User>(&SheetA1CellId::from_primitives("users", "A", 1), 10);
+        let orders = read.add::<Order>(&SheetA1CellId::from_primitives("orders", "A", 1), 10);
+
+        let mut result = repository
+            .find_all_multi(read)
+            .await
+            .expect("Test: Expected batch read");
+
+        let orders = result.take(orders).expect("Test: Expected orders");
+        let users = result.take(users).expect("Test: Expected users");
+        assert_eq!(users.len(), 2);
+        assert_eq!(users[1].data().name, "John");
+        assert_eq!(
+            orders[0].data(),
+            &Order {
+                user_id: 2,
+                total: 9.5,
+                status: "paid".to_string(),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn read_consistent__two_tables__entities_of_each_in_order() {
+        let repository = repository();
+        let tables = [
+            repository.table::<User>(SheetA1CellId::from_primitives("users", "A", 2), 10),
+            repository.table::<User>(SheetA1CellId::from_primitives("users", "A", 1), 1),
+        ];
+
+        let read = repository
+            .read_consistent(&tables)
+            .await
+            .expect("Test: Expected consistent read");
+
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[0].len(), 1);
+        assert_eq!(read[0][0].data().name, "John");
+        assert_eq!(read[1][0].data().name, "Joe");
+    }
+
+    #[tokio::test]
+    async fn take__same_table_twice__err() {
+        let repository = repository();
+        let mut read = MultiRead::new();
+        let users = read.add::<User>(&SheetA1CellId::from_primitives("users", "A", 1), 10);
+        let again = TableKey::<User> {
+            index: users.index,
+            _entity: PhantomData,
+        };
+
+        let mut result = repository
+            .find_all_multi(read)
+            .await
+            .expect("Test: Expected batch read");
+
+        result.take(users).expect("Test: Expected users");
+        let err = result
+            .take(again)
+            .expect_err("Test: Expected table to be taken already");
+        assert!(matches!(
+            err.current_context(),
+            RepositoryError::InvalidArgument(_)
+        ));
+    }
+}
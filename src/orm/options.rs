@@ -0,0 +1,157 @@
+//////////////////////// Repository defaults ////////////////////////
+
+use crate::orm::Repository;
+use crate::types::{DateTimeRenderOption, InputMode, ReadOptions, ValueRenderOption};
+
+/// Options applied to every read and write of the repository
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RepositoryOptions {
+    /// How written values are interpreted (inserts, updates, sync)
+    pub input_mode: InputMode,
+    pub value_render_option: ValueRenderOption,
+    pub date_time_render_option: Option<DateTimeRenderOption>,
+}
+
+impl Default for RepositoryOptions {
+    fn default() -> Self {
+        let read = ReadOptions::default();
+        Self {
+            input_mode: InputMode::UserEntered,
+            value_render_option: read.value_render_option,
+            date_time_render_option: read.date_time_render_option,
+        }
+    }
+}
+
+impl RepositoryOptions {
+    pub fn read_options(&self) -> ReadOptions {
+        ReadOptions {
+            value_render_option: self.value_render_option,
+            date_time_render_option: self.date_time_render_option,
+            ..ReadOptions::default()
+        }
+    }
+}
+
+impl Repository {
+    pub fn with_options(mut self, options: RepositoryOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn options(&self) -> &RepositoryOptions {
+        &self.options
+    }
+
+    /// Repository over the same driver with different options, for a call or a few:
+    /// `repo.overriding(raw).insert(..)`
+    pub fn overriding(&self, options: RepositoryOptions) -> Repository {
+        Repository {
+            driver: self.driver.clone(),
+            identity: self.identity,
+            options,
+            audit: self.audit.clone(),
+            upserts: self.upserts.clone(),
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod options_tests {
+    use super::*;
+    use crate::spread_sheet_driver::cassette::Interaction;
+    use crate::testing::examples_support::User;
+    use crate::testing::fixtures::{
+        AppendValuesResponseBuilder, MatchedValueRangeBuilder, memory_repository, replay_repository,
+    };
+    use crate::types::SheetA1CellId;
+    use google_sheets4::api::BatchGetValuesByDataFilterResponse;
+    use serde::Serialize;
+    use serde_json::{Value, json};
+    use std::sync::Arc;
+
+    fn interaction<T>(operation: &str, request: Value, response: T) -> Interaction
+    where
+        T: Serialize,
+    {
+        Interaction {
+            operation: operation.to_string(),
+            request,
+            response: serde_json::to_value(response).expect("Test: Expected to serialize"),
+        }
+    }
+
+    fn raw() -> RepositoryOptions {
+        RepositoryOptions {
+            input_mode: InputMode::Raw,
+            value_render_option: ValueRenderOption::FormattedValue,
+            date_time_render_option: Some(DateTimeRenderOption::FormattedString),
+        }
+    }
+
+    #[tokio::test]
+    async fn with_options__read_and_insert__options_sent() {
+        let values = BatchGetValuesByDataFilterResponse {
+            value_ranges: Some(vec![
+                MatchedValueRangeBuilder::new("users!A1:B11")
+                    .row(["1", "Joe"])
+                    .build(),
+            ]),
+            ..Default::default()
+        };
+        let repository = replay_repository(vec![
+            interaction(
+                "values.batchGetByDataFilter",
+                json!({
+                    "range": "users!A1:B11",
+                    "valueRenderOption": "FORMATTED_VALUE",
+                    "dateTimeRenderOption": "FORMATTED_STRING"
+                }),
+                values,
+            ),
+            interaction(
+                "values.append",
+                json!({
+                    "range": "users!A1:B11",
+                    "values": [["2", "John"]],
+                    "valueInputOption": "RAW"
+                }),
+                AppendValuesResponseBuilder::new("users!A2:B2")
+                    .row(["2", "John"])
+                    .build(),
+            ),
+        ])
+        .with_options(raw());
+        let start = SheetA1CellId::from_primitives("users", "A", 1);
+
+        let users = repository
+            .find_in_range::<User>(&start, 10)
+            .await
+            .expect("Test: Expected read with formatted values");
+        assert_eq!(users.len(), 1);
+
+        repository
+            .insert(
+                start,
+                10,
+                User {
+                    id: 2,
+                    name: "John".to_string(),
+                },
+            )
+            .await
+            .expect("Test: Expected raw insert");
+    }
+
+    #[tokio::test]
+    async fn overriding__single_call__defaults_kept() {
+        let repository = memory_repository();
+
+        let overridden = repository.overriding(raw());
+
+        assert_eq!(overridden.options(), &raw());
+        assert_eq!(repository.options(), &RepositoryOptions::default());
+        assert!(Arc::ptr_eq(&overridden.driver, &repository.driver));
+    }
+}
@@ -0,0 +1,219 @@
+//////////////////////// Fetched rows with their origin ////////////////////////
+
+use crate::mapper::sheet_row::SheetRow;
+use crate::orm::{RepositoryError, Result};
+use crate::spread_sheet_driver::matched_range;
+use crate::types::{A1CellId, Entity, EntityEssentials, Letters, SheetA1CellId, SheetA1Range};
+use error_stack::{ResultExt, report};
+use google_sheets4::api::{ExtendedValue, GridData, MatchedValueRange, ValueRange};
+use serde_json::Value;
+use std::num::NonZero;
+
+/// Rows of a fetched range and the cell of its top left corner, whatever request fetched them
+/// (batchGet, values.get or grid data), so all of them share the positional parsing
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RangeData {
+    pub origin: SheetA1CellId,
+    pub rows: Vec<SheetRow>,
+}
+
+impl RangeData {
+    pub fn new(origin: SheetA1CellId, rows: Vec<SheetRow>) -> Self {
+        Self { origin, rows }
+    }
+
+    /// Range of a batchGet response, located by its A1 data filter
+    pub fn from_matched(range: MatchedValueRange) -> Result<Self> {
+        let located = matched_range(&range).change_context_lazy(|| {
+            RepositoryError::InvalidArgument("Can't locate MatchedValueRange".to_string())
+        })?;
+        let rows = range
+            .value_range
+            .and_then(|range| range.values)
+            .unwrap_or_default();
+        Ok(Self::new(origin_of(&located), rows))
+    }
+
+    /// Response of values.get, located by its `range`
+    pub fn from_value_range(range: ValueRange) -> Result<Self> {
+        let raw = range.range.ok_or_else(|| {
+            report!(RepositoryError::InvalidArgument(
+                "ValueRange has no range".to_string()
+            ))
+        })?;
+        let located = SheetA1Range::from_raw(&raw)
+            .change_context_lazy(|| RepositoryError::InvalidArgument(raw.clone()))?;
+        Ok(Self::new(
+            origin_of(&located),
+            range.values.unwrap_or_default(),
+        ))
+    }
+
+    /// Grid data of the `sheet_name` sheet (as returned with `includeGridData`). Cells take
+    /// their formatted values, unlike the values endpoints which read unformatted ones with
+    /// `ReadOptions::default()`. Trailing empty cells and rows are dropped like they do
+    #[allow(dead_code)] // No fetch path of the crate asks for grid data yet
+    pub fn from_grid_data(sheet_name: &str, grid: &GridData) -> Self {
+        let row = grid.start_row.unwrap_or_default().max(0) as u32 + 1;
+        let column = grid.start_column.unwrap_or_default().max(0) as u32 + 1;
+        let origin = SheetA1CellId::new(
+            sheet_name,
+            A1CellId::new(
+                Letters::from_column_number(column).expect("Expected a non-zero column"),
+                NonZero::new(row).expect("Expected a non-zero row"),
+            ),
+        );
+
+        let mut rows: Vec<SheetRow> = grid
+            .row_data
+            .as_deref()
+            .unwrap_or_default()
+            .iter()
+            .map(|row| {
+                let mut cells: SheetRow = row
+                    .values
+                    .as_deref()
+                    .unwrap_or_default()
+                    .iter()
+                    .map(
+                        |cell| match (&cell.formatted_value, &cell.effective_value) {
+                            (Some(text), _) => Value::String(text.clone()),
+                            (None, Some(value)) => extended_value(value),
+                            (None, None) => Value::String(String::new()),
+                        },
+                    )
+                    .collect();
+                while cells.last().is_some_and(is_empty) {
+                    cells.pop();
+                }
+                cells
+            })
+            .collect();
+        while rows.last().is_some_and(Vec::is_empty) {
+            rows.pop();
+        }
+        Self::new(origin, rows)
+    }
+
+    /// Deserializes every row, positioned down from the origin
+    pub fn parse_positionally<E>(self) -> Result<Vec<Entity<E>>>
+    where
+        E: EntityEssentials,
+    {
+        let origin = self.origin;
+        self.rows
+            .into_iter()
+            .enumerate()
+            .map(|(i, row)| {
+                E::deserialize(row)
+                    .map(|data| Entity {
+                        position: SheetA1CellId::new(
+                            &origin.sheet_name,
+                            origin.cell.delta(0, i as i32),
+                        ),
+                        data,
+                        row_tag: None,
+                    })
+                    .change_context(RepositoryError::ParsingError)
+            })
+            .collect()
+    }
+}
+
+fn origin_of(range: &SheetA1Range) -> SheetA1CellId {
+    SheetA1CellId::new(&range.sheet, range.range.start.clone())
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(text) => text.is_empty(),
+        _ => false,
+    }
+}
+
+fn extended_value(value: &ExtendedValue) -> Value {
+    if let Some(text) = &value.string_value {
+        return Value::String(text.clone());
+    }
+    if let Some(number) = value.number_value {
+        return Value::String(number.to_string());
+    }
+    if let Some(bool) = value.bool_value {
+        return Value::String(match bool {
+            true => "TRUE".to_string(),
+            false => "FALSE".to_string(),
+        });
+    }
+    Value::String(String::new())
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod range_data_tests {
+    use super::*;
+    use crate::testing::examples_support::User;
+    use crate::testing::fixtures::MatchedValueRangeBuilder;
+    use google_sheets4::api::{CellData, RowData};
+
+    fn cell(text: &str) -> CellData {
+        CellData {
+            formatted_value: Some(text.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn from_matched_and_value_range__same_rows__same_entities() {
+        let matched = MatchedValueRangeBuilder::new("users!B3:C4")
+            .rows([["1", "Joe"], ["2", "Jane"]])
+            .build();
+        let value_range = matched.value_range.clone().expect("Test: Expected values");
+
+        let from_matched = RangeData::from_matched(matched)
+            .expect("Test: Expected range data")
+            .parse_positionally::<User>()
+            .expect("Test: Expected users");
+        let from_value_range = RangeData::from_value_range(value_range)
+            .expect("Test: Expected range data")
+            .parse_positionally::<User>()
+            .expect("Test: Expected users");
+
+        assert_eq!(from_matched, from_value_range);
+        assert_eq!(
+            from_matched[1].position,
+            SheetA1CellId::from_primitives("users", "B", 4)
+        );
+        assert_eq!(from_matched[1].data.name, "Jane");
+    }
+
+    #[test]
+    fn from_grid_data__offset_grid__origin_and_trimmed_rows() {
+        let grid = GridData {
+            start_row: Some(4),
+            start_column: Some(2),
+            row_data: Some(vec![
+                RowData {
+                    values: Some(vec![cell("7"), cell("Joe"), CellData::default()]),
+                },
+                RowData { values: None },
+            ]),
+            ..Default::default()
+        };
+
+        let data = RangeData::from_grid_data("users", &grid);
+
+        assert_eq!(data.origin, SheetA1CellId::from_primitives("users", "C", 5));
+        assert_eq!(data.rows, vec![vec![Value::from("7"), Value::from("Joe")]]);
+        let users = data
+            .parse_positionally::<User>()
+            .expect("Test: Expected users");
+        assert_eq!(
+            users[0].data,
+            User {
+                id: 7,
+                name: "Joe".to_string()
+            }
+        );
+    }
+}
@@ -0,0 +1,200 @@
+//////////////////////// Table rollover into a new sheet ////////////////////////
+
+use crate::mapper::sheet_row;
+use crate::mapper::sheet_row::{SheetRow, SheetRowSerde};
+use crate::orm::table::Table;
+use crate::orm::{Repository, RepositoryError, Result};
+use crate::spread_sheet_driver::structure::{clear_values_request, duplicate_sheet_request};
+use crate::types::{A1Range, EntityEssentials, SheetA1CellId, SheetA1Range, SheetGid};
+use error_stack::{ResultExt, report};
+use tracing::info;
+
+impl Repository {
+    /// Starts a new period of the table, e.g. a new year of a finance sheet: the sheet is
+    /// duplicated as `new_sheet_name` (headers, formats, validations and anything else on it),
+    /// the table rows of the copy are cleared and the entities passing `carry_over` are written
+    /// back to its top. The original sheet is left intact.
+    ///
+    /// Returns the same table on the new sheet
+    pub async fn rollover_table<'r, E, F>(
+        &'r self,
+        table: &Table<'r, E>,
+        new_sheet_name: &str,
+        carry_over: F,
+    ) -> Result<Table<'r, E>>
+    where
+        E: EntityEssentials,
+        F: Fn(&E) -> bool,
+    {
+        let carried = table
+            .find_all()
+            .await?
+            .iter()
+            .filter(|entity| carry_over(entity.data()))
+            .map(|entity| entity.data().serialize())
+            .collect::<sheet_row::Result<Vec<SheetRow>>>()
+            .change_context(RepositoryError::DriverError)?;
+
+        let start = table.start();
+        let driver = self.driver.lock().await;
+        let sheet_id = driver
+            .try_get_sheet_id(&start.sheet_name)
+            .await
+            .change_context(RepositoryError::DriverError)?;
+        let response = driver
+            .try_batch_update(vec![duplicate_sheet_request(sheet_id, new_sheet_name)])
+            .await
+            .change_context(RepositoryError::DriverError)?;
+        driver.invalidate_sheets_cache();
+        let copy_id = response
+            .replies
+            .into_iter()
+            .flatten()
+            .find_map(|reply| reply.duplicate_sheet?.properties?.sheet_id)
+            .map(SheetGid)
+            .ok_or_else(|| report!(RepositoryError::DriverError))
+            .attach_printable("Reply to duplicateSheet has no sheet id")?;
+
+        let first_row = start.cell.row.get() - 1;
+        let first_column = start.cell.col.column_number() - 1;
+        driver
+            .try_batch_update(vec![clear_values_request(
+                copy_id,
+                first_row..first_row + table.rows(),
+                first_column..first_column + table.width(),
+            )])
+            .await
+            .change_context(RepositoryError::DriverError)?;
+
+        let new_start = SheetA1CellId::new(new_sheet_name, start.cell.clone());
+        let carried_rows = carried.len();
+        if carried_rows > 0 {
+            let end = start
+                .cell
+                .delta(table.width() as i32 - 1, carried_rows as i32 - 1);
+            let range = SheetA1Range::new(new_sheet_name, A1Range::new(start.cell.clone(), end));
+            driver
+                .try_write_range_as(
+                    range.to_string().as_str(),
+                    carried,
+                    self.options().input_mode,
+                )
+                .await
+                .change_context(RepositoryError::DriverError)?;
+        }
+
+        info!(
+            "Rolled table at {} over to {}, {} rows carried over",
+            start, new_start, carried_rows
+        );
+        Ok(table.moved_to(new_start))
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod rollover_tests {
+    use super::*;
+    use crate::spread_sheet_driver::backend::SheetsBackend;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use crate::spread_sheet_driver::{IntoStrVec, SsdResult};
+    use crate::testing::examples_support::User;
+    use crate::testing::fixtures::backend_repository;
+    use serde_json::{Value, json};
+
+    /// Memory backend which also duplicates sheets and clears values the way batchUpdate does
+    #[derive(Debug, Default)]
+    struct SheetsCopyingBackend {
+        memory: MemoryBackend,
+    }
+
+    impl SheetsBackend for SheetsCopyingBackend {
+        fn handle(&self, operation: &str, request: &Value) -> SsdResult<Value> {
+            match operation {
+                "spreadsheets.get" => Ok(json!({
+                    "sheets": [{ "properties": { "sheetId": 1, "title": "2024" } }]
+                })),
+                "spreadsheets.batchUpdate" => {
+                    let request = &request["requests"][0];
+                    let mut workbook = self.memory.workbook();
+                    if let Some(duplicate) = request.get("duplicateSheet") {
+                        let title = duplicate["newSheetName"].as_str().unwrap_or_default();
+                        let rows = workbook.sheet("2024");
+                        workbook.set_sheet(title, rows);
+                        return Ok(json!({
+                            "replies": [{
+                                "duplicateSheet": { "properties": { "sheetId": 2, "title": title } }
+                            }]
+                        }));
+                    }
+
+                    let range = &request["updateCells"]["range"];
+                    let index = |key: &str| range[key].as_u64().unwrap_or_default() as usize;
+                    let mut rows = workbook.sheet("2025");
+                    for row in rows
+                        .iter_mut()
+                        .take(index("endRowIndex"))
+                        .skip(index("startRowIndex"))
+                    {
+                        for cell in row
+                            .iter_mut()
+                            .take(index("endColumnIndex"))
+                            .skip(index("startColumnIndex"))
+                        {
+                            *cell = Value::from("");
+                        }
+                    }
+                    workbook.set_sheet("2025", rows);
+                    Ok(json!({ "replies": [{}] }))
+                }
+                _ => self.memory.handle(operation, request),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn rollover_table__carry_over_filter__copy_with_kept_rows_only() {
+        let backend = SheetsCopyingBackend::default();
+        backend.memory.workbook().set_sheet(
+            "2024",
+            vec![
+                vec![Value::from("id"), Value::from("name")],
+                vec![Value::from("1"), Value::from("Joe")],
+                vec![Value::from("2"), Value::from("John")],
+                vec![Value::from("3"), Value::from("Jane")],
+            ],
+        );
+        let repository = backend_repository(backend);
+        let table = repository.table::<User>(SheetA1CellId::from_primitives("2024", "A", 2), 10);
+
+        let rolled = repository
+            .rollover_table(&table, "2025", |user| user.id != 2)
+            .await
+            .expect("Test: Expected rollover");
+
+        assert_eq!(
+            rolled.start(),
+            &SheetA1CellId::from_primitives("2025", "A", 2)
+        );
+        let driver = repository.driver.lock().await;
+        let copy = driver
+            .try_get_range("2025!A1:B4")
+            .await
+            .expect("Test: Expected new sheet")
+            .into_vec();
+        assert_eq!(
+            copy,
+            vec![
+                vec![Value::from("id"), Value::from("name")],
+                vec![Value::from("1"), Value::from("Joe")],
+                vec![Value::from("3"), Value::from("Jane")],
+            ]
+        );
+        let original = driver
+            .try_get_range("2024!A1:B4")
+            .await
+            .expect("Test: Expected original sheet")
+            .into_vec();
+        assert_eq!(original.len(), 4);
+    }
+}
@@ -0,0 +1,198 @@
+//////////////////////// Row background colors from entity state ////////////////////////
+
+use crate::orm::table::Table;
+use crate::orm::{RepositoryError, Result};
+use crate::spread_sheet_driver::format::background_request;
+use crate::types::EntityEssentials;
+use error_stack::ResultExt;
+use google_sheets4::api::{Color, Request};
+use std::ops::Range;
+use tracing::debug;
+
+impl<E> Table<'_, E>
+where
+    E: EntityEssentials,
+{
+    /// Colors the background of every entity row by `color_of`, `None` clears it, e.g.
+    /// overdue tasks in red on a status dashboard. Adjacent rows of the same color share one
+    /// `repeatCell`, all of them are sent in a single batchUpdate
+    pub async fn apply_row_colors<F>(&self, color_of: F) -> Result<()>
+    where
+        F: Fn(&E) -> Option<Color>,
+    {
+        let mut runs: Vec<(Range<u32>, Option<Color>)> = vec![];
+        for entity in self.find_all().await? {
+            let row = entity.position().cell.row.get() - 1;
+            let color = color_of(entity.data());
+            match runs.last_mut() {
+                Some((rows, last)) if rows.end == row && same_color(last, &color) => rows.end += 1,
+                _ => runs.push((row..row + 1, color)),
+            }
+        }
+        if runs.is_empty() {
+            return Ok(());
+        }
+
+        let start = self.start();
+        let column = start.cell.col.column_number() - 1;
+        let repository = self.configured_repository();
+        let driver = repository.driver.lock().await;
+        let sheet_id = driver
+            .try_get_sheet_id(&start.sheet_name)
+            .await
+            .change_context(RepositoryError::DriverError)?;
+        let requests: Vec<Request> = runs
+            .iter()
+            .map(|(rows, color)| {
+                background_request(
+                    sheet_id,
+                    rows.clone(),
+                    column..column + self.width(),
+                    color.as_ref(),
+                )
+            })
+            .collect();
+        debug!(
+            "Coloring rows of the table at {} with {} requests",
+            start,
+            requests.len()
+        );
+        driver
+            .try_batch_update(requests)
+            .await
+            .change_context(RepositoryError::DriverError)?;
+        Ok(())
+    }
+}
+
+/// `Color` has no `PartialEq`
+fn same_color(a: &Option<Color>, b: &Option<Color>) -> bool {
+    let channels = |color: &Color| (color.red, color.green, color.blue, color.alpha);
+    match (a, b) {
+        (Some(a), Some(b)) => channels(a) == channels(b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod row_colors_tests {
+    use super::*;
+    use crate::mapper::sheet_row;
+    use crate::mapper::sheet_row::{SheetRow, SheetRowExt, SheetRowSerde};
+    use crate::spread_sheet_driver::SsdResult;
+    use crate::spread_sheet_driver::backend::SheetsBackend;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use crate::testing::fixtures::backend_repository;
+    use crate::types::SheetA1CellId;
+    use serde_json::{Value, json};
+    use std::sync::Arc;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Task {
+        id: i32,
+        overdue: bool,
+    }
+
+    impl SheetRowSerde for Task {
+        fn deserialize(row: SheetRow) -> sheet_row::Result<Self> {
+            Ok(Self {
+                id: row.parse_cell(0, "id")?,
+                overdue: row.parse_cell(1, "overdue")?,
+            })
+        }
+
+        fn serialize(&self) -> sheet_row::Result<SheetRow> {
+            Ok(vec![
+                Value::String(self.id.to_string()),
+                Value::String(self.overdue.to_string()),
+            ])
+        }
+    }
+
+    impl EntityEssentials for Task {
+        fn entity_width() -> u32 {
+            2
+        }
+    }
+
+    /// Memory backend which keeps the batchUpdate requests instead of applying them
+    #[derive(Debug, Default)]
+    struct RecordingBackend {
+        memory: MemoryBackend,
+        batches: Arc<std::sync::Mutex<Vec<Value>>>,
+    }
+
+    impl SheetsBackend for RecordingBackend {
+        fn handle(&self, operation: &str, request: &Value) -> SsdResult<Value> {
+            match operation {
+                "spreadsheets.get" => Ok(json!({
+                    "sheets": [{ "properties": { "sheetId": 5, "title": "tasks" } }]
+                })),
+                "spreadsheets.batchUpdate" => {
+                    let mut batches = self.batches.lock().expect("Test: Expected lock");
+                    batches.push(request["requests"].clone());
+                    Ok(json!({ "replies": [] }))
+                }
+                _ => self.memory.handle(operation, request),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn apply_row_colors__adjacent_rows_of_same_color__merged() {
+        let backend = RecordingBackend::default();
+        let batches = backend.batches.clone();
+        let rows = [("1", "true"), ("2", "true"), ("3", "false"), ("4", "true")];
+        backend.memory.workbook().set_sheet(
+            "tasks",
+            rows.iter()
+                .map(|(id, overdue)| vec![Value::from(""), Value::from(*id), Value::from(*overdue)])
+                .collect(),
+        );
+        let repository = backend_repository(backend);
+        let table = repository.table::<Task>(SheetA1CellId::from_primitives("tasks", "B", 1), 10);
+
+        table
+            .apply_row_colors(|task| {
+                task.overdue.then(|| Color {
+                    red: Some(1.0),
+                    ..Default::default()
+                })
+            })
+            .await
+            .expect("Test: Expected rows to be colored");
+
+        let batches = batches.lock().expect("Test: Expected lock");
+        assert_eq!(batches.len(), 1);
+        let ranges: Vec<(Value, Value)> = batches[0]
+            .as_array()
+            .expect("Test: Expected requests")
+            .iter()
+            .map(|request| {
+                let range = &request["repeatCell"]["range"];
+                (range["startRowIndex"].clone(), range["endRowIndex"].clone())
+            })
+            .collect();
+        assert_eq!(
+            ranges,
+            vec![
+                (json!(0), json!(2)),
+                (json!(2), json!(3)),
+                (json!(3), json!(4))
+            ]
+        );
+        let first = &batches[0][0]["repeatCell"];
+        assert_eq!(first["range"]["startColumnIndex"], 1);
+        assert_eq!(first["range"]["endColumnIndex"], 3);
+        assert_eq!(
+            first["cell"]["userEnteredFormat"]["backgroundColor"]["red"],
+            1.0
+        );
+        assert_eq!(first["fields"], "userEnteredFormat.backgroundColor");
+        assert!(
+            batches[0][1]["repeatCell"]["cell"]["userEnteredFormat"]["backgroundColor"].is_null()
+        );
+    }
+}
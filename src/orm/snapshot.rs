@@ -0,0 +1,202 @@
+//////////////////////// Table snapshots and their diff ////////////////////////
+
+use crate::orm::Result;
+use crate::orm::table::Table;
+use crate::types::{Entity, EntityEssentials, SheetA1CellId};
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+
+/// Immutable content of a table at the moment of the read
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot<E>
+where
+    E: EntityEssentials,
+{
+    start: SheetA1CellId,
+    taken_at: SystemTime,
+    entities: Vec<Entity<E>>,
+}
+
+/// Entity which stayed at the same position but has different data
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangedEntity<E>
+where
+    E: EntityEssentials,
+{
+    pub position: SheetA1CellId,
+    pub before: E,
+    pub after: E,
+}
+
+/// Difference between two snapshots of the same table.
+/// Rows are matched by position, so a moved row shows up as removed from the old position
+/// and added at the new one (or as changed if another row took its place)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotDiff<E>
+where
+    E: EntityEssentials,
+{
+    pub added: Vec<Entity<E>>,
+    pub removed: Vec<Entity<E>>,
+    pub changed: Vec<ChangedEntity<E>>,
+}
+
+impl<E> SnapshotDiff<E>
+where
+    E: EntityEssentials,
+{
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl<E> Table<'_, E>
+where
+    E: EntityEssentials,
+{
+    /// Reads the whole table into a snapshot
+    pub async fn snapshot(&self) -> Result<Snapshot<E>> {
+        Ok(Snapshot::new(self.start().clone(), self.find_all().await?))
+    }
+}
+
+impl<E> Snapshot<E>
+where
+    E: EntityEssentials,
+{
+    pub fn new(start: SheetA1CellId, entities: Vec<Entity<E>>) -> Self {
+        Self {
+            start,
+            taken_at: SystemTime::now(),
+            entities,
+        }
+    }
+
+    /// Top left cell of the table the snapshot is taken from
+    pub fn start(&self) -> &SheetA1CellId {
+        &self.start
+    }
+
+    pub fn taken_at(&self) -> SystemTime {
+        self.taken_at
+    }
+
+    pub fn entities(&self) -> &[Entity<E>] {
+        &self.entities
+    }
+
+    /// What happened between `self` (before) and `other` (after)
+    pub fn diff(&self, other: &Snapshot<E>) -> SnapshotDiff<E> {
+        let before = by_row(&self.entities);
+        let after = by_row(&other.entities);
+
+        let mut diff = SnapshotDiff {
+            added: vec![],
+            removed: vec![],
+            changed: vec![],
+        };
+
+        for (row, old) in &before {
+            match after.get(row) {
+                None => diff.removed.push((*old).clone()),
+                Some(new) if new.data != old.data => diff.changed.push(ChangedEntity {
+                    position: new.position.clone(),
+                    before: old.data.clone(),
+                    after: new.data.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+        diff.added = after
+            .iter()
+            .filter(|(row, _)| !before.contains_key(row))
+            .map(|(_, new)| (*new).clone())
+            .collect();
+
+        diff
+    }
+}
+
+fn by_row<E>(entities: &[Entity<E>]) -> BTreeMap<u32, &Entity<E>>
+where
+    E: EntityEssentials,
+{
+    entities
+        .iter()
+        .map(|e| (e.position.cell.row.get(), e))
+        .collect()
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod snapshot_tests {
+    use super::*;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use crate::testing::examples_support::User;
+    use crate::testing::fixtures::backend_repository;
+    use serde_json::Value;
+
+    fn user(row: u32, id: i32, name: &str) -> Entity<User> {
+        Entity {
+            position: SheetA1CellId::from_primitives("users", "A", row),
+            data: User {
+                id,
+                name: name.to_string(),
+            },
+            row_tag: None,
+        }
+    }
+
+    fn snapshot(entities: Vec<Entity<User>>) -> Snapshot<User> {
+        Snapshot::new(SheetA1CellId::from_primitives("users", "A", 1), entities)
+    }
+
+    #[test]
+    fn diff__added_removed_changed__ok() {
+        let before = snapshot(vec![user(1, 1, "Joe"), user(2, 2, "John")]);
+        let after = snapshot(vec![user(2, 2, "Johnny"), user(3, 3, "Jane")]);
+
+        let diff = before.diff(&after);
+
+        assert_eq!(diff.added, vec![user(3, 3, "Jane")]);
+        assert_eq!(diff.removed, vec![user(1, 1, "Joe")]);
+        assert_eq!(
+            diff.changed,
+            vec![ChangedEntity {
+                position: SheetA1CellId::from_primitives("users", "A", 2),
+                before: user(2, 2, "John").data,
+                after: user(2, 2, "Johnny").data,
+            }]
+        );
+    }
+
+    #[test]
+    fn diff__same_content__empty() {
+        let before = snapshot(vec![user(1, 1, "Joe")]);
+        assert!(before.diff(&before.clone()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn table_snapshot__after_insert__diff_contains_new_entity() {
+        let backend = MemoryBackend::new();
+        backend
+            .workbook()
+            .set_sheet("users", vec![vec![Value::from("1"), Value::from("Joe")]]);
+        let repository = backend_repository(backend);
+        let table = repository.table::<User>(SheetA1CellId::from_primitives("users", "A", 1), 10);
+
+        let before = table.snapshot().await.expect("Test: Expected snapshot");
+        table
+            .insert(User {
+                id: 2,
+                name: "John".to_string(),
+            })
+            .await
+            .expect("Test: Expected insert to succeed");
+        let after = table.snapshot().await.expect("Test: Expected snapshot");
+
+        let diff = before.diff(&after);
+        assert_eq!(diff.added, vec![user(2, 2, "John")]);
+        assert!(diff.removed.is_empty() && diff.changed.is_empty());
+    }
+}
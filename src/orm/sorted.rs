@@ -0,0 +1,204 @@
+//////////////////////// Inserts keeping the table sorted ////////////////////////
+
+use crate::mapper::sheet_row::SheetRowSerde;
+use crate::orm::audit::{AuditOperation, AuditRecord};
+use crate::orm::identity::{RowIdentity, tag_rows};
+use crate::orm::table::Table;
+use crate::orm::{RepositoryError, Result};
+use crate::spread_sheet_driver::structure::insert_rows_request;
+use crate::types::{A1Range, Entity, EntityEssentials, SheetA1CellId, SheetA1Range};
+use error_stack::{ResultExt, bail};
+use tracing::info;
+
+impl<E> Table<'_, E>
+where
+    E: EntityEssentials,
+{
+    /// Inserts the entity right after the last one with a key not greater than its key, so a
+    /// table sorted by `key_fn` stays sorted without a re-sort. The position is binary-searched
+    /// over the current rows, then a blank row is inserted there, shifting the rows below down.
+    /// The whole sheet row is inserted, so tables side by side with this one are shifted too.
+    ///
+    /// Serialized with the other read-then-write operations of the repository, see
+    /// [`crate::orm::Repository::find_or_insert`]
+    pub async fn insert_sorted<K, F>(&self, entity_data: E, key_fn: F) -> Result<Entity<E>>
+    where
+        K: Ord,
+        F: Fn(&E) -> K,
+    {
+        let repository = self.repository();
+        let _guard = repository.upserts.lock().await;
+
+        let entities = self.find_all().await?;
+        if entities.len() as u32 >= self.rows() {
+            bail!(RepositoryError::InvalidArgument(format!(
+                "Table at {} is full, it has {} rows",
+                self.start(),
+                self.rows()
+            )));
+        }
+        let key = key_fn(&entity_data);
+        let index = entities.partition_point(|entity| key_fn(entity.data()) <= key);
+
+        let row = entity_data
+            .serialize()
+            .change_context(RepositoryError::DriverError)?;
+        let start = self.start();
+        let position = SheetA1CellId::new(&start.sheet_name, start.cell.delta(0, index as i32));
+        let driver = repository.driver.lock().await;
+        let sheet_id = driver
+            .try_get_sheet_id(&start.sheet_name)
+            .await
+            .change_context(RepositoryError::DriverError)?;
+        let row_index = position.cell.row.get() - 1;
+        driver
+            .try_batch_update(vec![insert_rows_request(sheet_id, row_index, 1)])
+            .await
+            .change_context(RepositoryError::DriverError)?;
+
+        let end = position.cell.delta(self.width() as i32 - 1, 0);
+        let range = SheetA1Range::new(
+            &position.sheet_name,
+            A1Range::new(position.cell.clone(), end),
+        );
+        driver
+            .try_write_range_as(
+                range.to_string().as_str(),
+                vec![row.clone()],
+                self.configured_repository().options().input_mode,
+            )
+            .await
+            .change_context(RepositoryError::DriverError)?;
+
+        let row_tag = match repository.identity {
+            RowIdentity::Position => None,
+            RowIdentity::Metadata => {
+                let ids = tag_rows(&driver, &position.sheet_name, row_index + 1, 1).await?;
+                ids.into_iter().next()
+            }
+        };
+        drop(driver);
+        self.rewrite_overridden_columns(&position, &entity_data)
+            .await?;
+        info!("Inserted entity at {} of sorted table {}", position, start);
+
+        let entity = Entity {
+            position,
+            data: entity_data,
+            row_tag,
+        };
+        repository
+            .record_audit(vec![AuditRecord::new(
+                AuditOperation::Insert,
+                &entity,
+                None,
+                Some(&row),
+            )])
+            .await?;
+        Ok(entity)
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod sorted_tests {
+    use super::*;
+    use crate::spread_sheet_driver::SsdResult;
+    use crate::spread_sheet_driver::backend::SheetsBackend;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use crate::testing::examples_support::User;
+    use crate::testing::fixtures::backend_repository;
+    use serde_json::{Value, json};
+
+    /// Memory backend which also inserts rows the way insertDimension does
+    #[derive(Debug, Default)]
+    struct RowInsertingBackend {
+        memory: MemoryBackend,
+    }
+
+    impl SheetsBackend for RowInsertingBackend {
+        fn handle(&self, operation: &str, request: &Value) -> SsdResult<Value> {
+            match operation {
+                "spreadsheets.get" => Ok(json!({
+                    "sheets": [{ "properties": { "sheetId": 0, "title": "users" } }]
+                })),
+                "spreadsheets.batchUpdate" => {
+                    let range = &request["requests"][0]["insertDimension"]["range"];
+                    let index = range["startIndex"].as_u64().unwrap_or_default() as usize;
+                    let mut workbook = self.memory.workbook();
+                    let mut rows = workbook.sheet("users");
+                    rows.insert(index.min(rows.len()), vec![]);
+                    workbook.set_sheet("users", rows);
+                    Ok(json!({ "replies": [{}] }))
+                }
+                _ => self.memory.handle(operation, request),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn insert_sorted__key_between_rows__inserted_in_order() {
+        let backend = RowInsertingBackend::default();
+        backend.memory.workbook().set_sheet(
+            "users",
+            vec![
+                vec![Value::from("id"), Value::from("name")],
+                vec![Value::from("1"), Value::from("Joe")],
+                vec![Value::from("5"), Value::from("John")],
+            ],
+        );
+        let repository = backend_repository(backend);
+        let table = repository.table::<User>(SheetA1CellId::from_primitives("users", "A", 2), 10);
+
+        let inserted = table
+            .insert_sorted(
+                User {
+                    id: 3,
+                    name: "Jane".to_string(),
+                },
+                |user| user.id,
+            )
+            .await
+            .expect("Test: Expected sorted insert");
+
+        assert_eq!(
+            inserted.position(),
+            &SheetA1CellId::from_primitives("users", "A", 3)
+        );
+        let ids: Vec<i32> = table
+            .find_all()
+            .await
+            .expect("Test: Expected users")
+            .iter()
+            .map(|user| user.id)
+            .collect();
+        assert_eq!(ids, vec![1, 3, 5]);
+    }
+
+    #[tokio::test]
+    async fn insert_sorted__full_table__invalid_argument() {
+        let backend = RowInsertingBackend::default();
+        backend
+            .memory
+            .workbook()
+            .set_sheet("users", vec![vec![Value::from("1"), Value::from("Joe")]]);
+        let repository = backend_repository(backend);
+        let table = repository.table::<User>(SheetA1CellId::from_primitives("users", "A", 1), 1);
+
+        let report = table
+            .insert_sorted(
+                User {
+                    id: 2,
+                    name: "John".to_string(),
+                },
+                |user| user.id,
+            )
+            .await
+            .expect_err("Test: Expected full table");
+
+        assert!(matches!(
+            report.current_context(),
+            RepositoryError::InvalidArgument(_)
+        ));
+    }
+}
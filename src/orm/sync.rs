@@ -0,0 +1,372 @@
+//////////////////////// Plan/apply sync of a table ////////////////////////
+
+use crate::orm::audit::{AuditOperation, AuditRecord};
+use crate::orm::snapshot::Snapshot;
+use crate::orm::table::Table;
+use crate::orm::{Repository, RepositoryError, Result};
+use crate::types::{A1Range, Entity, EntityEssentials, SheetA1CellId, SheetA1Range};
+use error_stack::{ResultExt, bail};
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+use tracing::info;
+
+/// Single row write the plan consists of
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlannedWrite<E>
+where
+    E: EntityEssentials,
+{
+    /// Data is written into an empty row
+    Insert { position: SheetA1CellId, data: E },
+    /// Row content is replaced
+    Update {
+        position: SheetA1CellId,
+        before: E,
+        after: E,
+    },
+    /// Row is not part of the new data and gets cleared
+    Clear { position: SheetA1CellId, before: E },
+}
+
+impl<E> PlannedWrite<E>
+where
+    E: EntityEssentials,
+{
+    pub fn position(&self) -> &SheetA1CellId {
+        match self {
+            PlannedWrite::Insert { position, .. }
+            | PlannedWrite::Update { position, .. }
+            | PlannedWrite::Clear { position, .. } => position,
+        }
+    }
+}
+
+/// Writes needed to turn the current table content into the new one.
+/// Review it (it implements `Display`) and pass to [`Repository::apply`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncPlan<E>
+where
+    E: EntityEssentials,
+{
+    base: Snapshot<E>,
+    rows: u32,
+    writes: Vec<PlannedWrite<E>>,
+}
+
+impl<E> SyncPlan<E>
+where
+    E: EntityEssentials,
+{
+    /// Table content the plan was made against
+    pub fn base(&self) -> &Snapshot<E> {
+        &self.base
+    }
+
+    pub fn writes(&self) -> &[PlannedWrite<E>] {
+        &self.writes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+
+    /// Drops the writes the plan shouldn't perform, e.g. the conflicting ones
+    pub(crate) fn retain_writes<F>(&mut self, keep: F)
+    where
+        F: FnMut(&PlannedWrite<E>) -> bool,
+    {
+        self.writes.retain(keep);
+    }
+}
+
+impl<E> Display for SyncPlan<E>
+where
+    E: EntityEssentials,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (mut add, mut change, mut clear) = (0, 0, 0);
+        for write in &self.writes {
+            let cell = write.position();
+            match write {
+                PlannedWrite::Insert { data, .. } => {
+                    add += 1;
+                    writeln!(f, "  + {cell}: {data:?}")?;
+                }
+                PlannedWrite::Update { before, after, .. } => {
+                    change += 1;
+                    writeln!(f, "  ~ {cell}: {before:?} -> {after:?}")?;
+                }
+                PlannedWrite::Clear { before, .. } => {
+                    clear += 1;
+                    writeln!(f, "  - {cell}: {before:?}")?;
+                }
+            }
+        }
+
+        match self.writes.is_empty() {
+            true => write!(f, "No changes. The table is up to date."),
+            false => write!(
+                f,
+                "Plan: {add} to add, {change} to change, {clear} to clear."
+            ),
+        }
+    }
+}
+
+impl Repository {
+    /// Computes writes which make the table contain exactly `new_data` (row by row from the top)
+    /// without performing them
+    pub async fn plan_sync<E>(&self, table: &Table<'_, E>, new_data: Vec<E>) -> Result<SyncPlan<E>>
+    where
+        E: EntityEssentials,
+    {
+        if new_data.len() > table.rows() as usize {
+            bail!(RepositoryError::InvalidArgument(format!(
+                "{} entities don't fit table of {} rows",
+                new_data.len(),
+                table.rows()
+            )));
+        }
+
+        let base = table.snapshot().await?;
+        Ok(SyncPlan {
+            writes: plan_writes(&base, new_data),
+            rows: table.rows(),
+            base,
+        })
+    }
+
+    /// Performs the writes of the plan. Fails with `StalePlan` if the table has changed since
+    /// the plan was made, so an outdated review never overwrites someone's edits
+    pub async fn apply<E>(&self, plan: SyncPlan<E>) -> Result<()>
+    where
+        E: EntityEssentials,
+    {
+        let current = self
+            .table::<E>(plan.base.start().clone(), plan.rows)
+            .snapshot()
+            .await?;
+        if current.entities() != plan.base.entities() {
+            bail!(RepositoryError::StalePlan);
+        }
+
+        let driver = self.driver.lock().await;
+        let mut records = vec![];
+        for write in &plan.writes {
+            let row = match write {
+                PlannedWrite::Insert { data, .. } | PlannedWrite::Update { after: data, .. } => {
+                    data.serialize()
+                        .change_context(RepositoryError::DriverError)?
+                }
+                PlannedWrite::Clear { .. } => {
+                    vec![Value::String(String::new()); E::entity_width() as usize]
+                }
+            };
+            let position = write.position();
+            let end = position.cell.delta(E::entity_width() as i32 - 1, 0);
+            let range = SheetA1Range::new(
+                &position.sheet_name,
+                A1Range::new(position.cell.clone(), end),
+            );
+
+            driver
+                .try_write_range_as(
+                    range.to_string().as_str(),
+                    vec![row],
+                    self.options.input_mode,
+                )
+                .await
+                .change_context(RepositoryError::DriverError)?;
+
+            if self.is_audited() {
+                records.push(audit_record(write)?);
+            }
+        }
+        drop(driver);
+        self.record_audit(records).await?;
+        info!(
+            "Applied {} writes to {:?}",
+            plan.writes.len(),
+            plan.base.start()
+        );
+        Ok(())
+    }
+}
+
+fn audit_record<E>(write: &PlannedWrite<E>) -> Result<AuditRecord>
+where
+    E: EntityEssentials,
+{
+    let serialize = |data: &E| {
+        data.serialize()
+            .change_context(RepositoryError::DriverError)
+    };
+    let entity = |data: &E| Entity {
+        position: write.position().clone(),
+        data: data.clone(),
+        row_tag: None,
+    };
+    let record = match write {
+        PlannedWrite::Insert { data, .. } => AuditRecord::new(
+            AuditOperation::Insert,
+            &entity(data),
+            None,
+            Some(&serialize(data)?),
+        ),
+        PlannedWrite::Update { before, after, .. } => AuditRecord::new(
+            AuditOperation::Update,
+            &entity(after),
+            Some(&serialize(before)?),
+            Some(&serialize(after)?),
+        ),
+        PlannedWrite::Clear { before, .. } => AuditRecord::new(
+            AuditOperation::Delete,
+            &entity(before),
+            Some(&serialize(before)?),
+            None,
+        ),
+    };
+    Ok(record)
+}
+
+fn plan_writes<E>(base: &Snapshot<E>, new_data: Vec<E>) -> Vec<PlannedWrite<E>>
+where
+    E: EntityEssentials,
+{
+    let start = base.start();
+    let new_len = new_data.len() as u32;
+    let current = |position: &SheetA1CellId| -> Option<&Entity<E>> {
+        base.entities().iter().find(|e| e.position() == position)
+    };
+
+    let mut writes: Vec<PlannedWrite<E>> = new_data
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, data)| {
+            let position = SheetA1CellId::new(&start.sheet_name, start.cell.delta(0, i as i32));
+            match current(&position) {
+                None => Some(PlannedWrite::Insert { position, data }),
+                Some(old) if old.data() != &data => Some(PlannedWrite::Update {
+                    position,
+                    before: old.data().clone(),
+                    after: data,
+                }),
+                Some(_) => None,
+            }
+        })
+        .collect();
+
+    // Rows below the new data are left over from the old content
+    let first_leftover = start.cell.row.get() + new_len;
+    writes.extend(
+        base.entities()
+            .iter()
+            .filter(|e| e.position().cell.row.get() >= first_leftover)
+            .map(|e| PlannedWrite::Clear {
+                position: e.position().clone(),
+                before: e.data().clone(),
+            }),
+    );
+    writes
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod sync_tests {
+    use super::*;
+    use crate::mapper::sheet_row::SheetRow;
+    use crate::spread_sheet_driver::SharedSpreadSheetDriver;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use crate::testing::examples_support::User;
+    use crate::testing::fixtures::shared_driver;
+
+    fn user(id: i32, name: &str) -> User {
+        User {
+            id,
+            name: name.to_string(),
+        }
+    }
+
+    fn repository(rows: Vec<SheetRow>) -> (Repository, SharedSpreadSheetDriver) {
+        let backend = MemoryBackend::new();
+        backend.workbook().set_sheet("users", rows);
+        let driver = shared_driver(backend);
+        (Repository::new(driver.clone()), driver)
+    }
+
+    fn row(id: &str, name: &str) -> SheetRow {
+        vec![Value::from(id), Value::from(name)]
+    }
+
+    #[tokio::test]
+    async fn plan_sync__mixed_changes__rendered_and_applied() {
+        let (repository, _) = repository(vec![row("1", "Joe"), row("2", "John"), row("3", "Jane")]);
+        let table = repository.table::<User>(SheetA1CellId::from_primitives("users", "A", 1), 10);
+
+        let plan = repository
+            .plan_sync(&table, vec![user(1, "Joe"), user(2, "Johnny")])
+            .await
+            .expect("Test: Expected plan");
+        let expected = [
+            r#"  ~ users!A2: User { id: 2, name: "John" } -> User { id: 2, name: "Johnny" }"#,
+            r#"  - users!A3: User { id: 3, name: "Jane" }"#,
+            "Plan: 0 to add, 1 to change, 1 to clear.",
+        ];
+        assert_eq!(plan.to_string(), expected.join("\n"));
+
+        repository
+            .apply(plan)
+            .await
+            .expect("Test: Expected apply to succeed");
+        let users: Vec<User> = table
+            .find_all()
+            .await
+            .expect("Test: Expected read")
+            .into_iter()
+            .map(|e| e.data().clone())
+            .collect();
+        assert_eq!(users, vec![user(1, "Joe"), user(2, "Johnny")]);
+    }
+
+    #[tokio::test]
+    async fn plan_sync__into_empty_table__inserts() {
+        let (repository, _) = repository(vec![]);
+        let table = repository.table::<User>(SheetA1CellId::from_primitives("users", "A", 1), 10);
+
+        let plan = repository
+            .plan_sync(&table, vec![user(1, "Joe")])
+            .await
+            .expect("Test: Expected plan");
+
+        assert_eq!(
+            plan.writes(),
+            &[PlannedWrite::Insert {
+                position: SheetA1CellId::from_primitives("users", "A", 1),
+                data: user(1, "Joe"),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn apply__after_concurrent_edit__stale_plan() {
+        let (repository, driver) = repository(vec![row("1", "Joe")]);
+        let table = repository.table::<User>(SheetA1CellId::from_primitives("users", "A", 1), 10);
+        let plan = repository
+            .plan_sync(&table, vec![user(1, "Joseph")])
+            .await
+            .expect("Test: Expected plan");
+
+        driver
+            .lock()
+            .await
+            .try_write_range("users!A1:B1", vec![row("1", "Jo")])
+            .await
+            .expect("Test: Expected concurrent write");
+
+        let err = repository
+            .apply(plan)
+            .await
+            .expect_err("Test: Expected stale plan");
+        assert!(matches!(err.current_context(), RepositoryError::StalePlan));
+    }
+}
@@ -0,0 +1,434 @@
+//////////////////////// Table handle ////////////////////////
+
+use crate::orm::table_options::TableOptions;
+use crate::orm::{Repository, RepositoryError, Result};
+use crate::spread_sheet_driver::IntoStrVec;
+use crate::types::{
+    A1CellId, A1Range, DynamicWidthEntity, Entity, EntityEssentials, EntityTable, Letters,
+    SheetA1CellId, SheetA1Range,
+};
+use error_stack::{ResultExt, bail};
+use std::fmt::{Debug, Formatter};
+use std::marker::PhantomData;
+use std::num::NonZero;
+
+/// Entities of type `E` stored in `rows` rows starting from `start`.
+/// Cheap to create, holds nothing but coordinates and the repository reference
+pub struct Table<'r, E>
+where
+    E: EntityEssentials,
+{
+    repo: &'r Repository,
+    start: SheetA1CellId,
+    rows: u32,
+    width: u32,
+    /// Rows of the header above `start`
+    header_rows: u32,
+    options: TableOptions,
+    _entity: PhantomData<E>,
+}
+
+impl Repository {
+    pub fn table<E>(&self, start: SheetA1CellId, rows: u32) -> Table<'_, E>
+    where
+        E: EntityEssentials,
+    {
+        Table {
+            repo: self,
+            start,
+            rows,
+            width: E::entity_width(),
+            header_rows: 0,
+            options: TableOptions::default(),
+            _entity: PhantomData,
+        }
+    }
+
+    /// Table of an entity with a runtime width, which is read from the header row right above
+    /// the table start. Read it again with another call if columns are added later
+    pub async fn dynamic_table<E>(&self, start: SheetA1CellId, rows: u32) -> Result<Table<'_, E>>
+    where
+        E: DynamicWidthEntity,
+    {
+        let Some(header_row) = NonZero::new(start.cell.row.get() - 1) else {
+            bail!(RepositoryError::InvalidArgument(format!(
+                "Table at {start:?} has no header row above it"
+            )));
+        };
+        let last_col =
+            Letters::from_column_number(Letters::MAX_COLUMN_NUMBER).expect("Expected valid column");
+        let range = SheetA1Range::new(
+            &start.sheet_name,
+            A1Range::new(
+                A1CellId::new(start.cell.col.clone(), header_row),
+                A1CellId::new(last_col, header_row),
+            ),
+        );
+        let header = self
+            .driver
+            .lock()
+            .await
+            .try_get_range_with(&range, &self.options().read_options())
+            .await
+            .change_context(RepositoryError::DriverError)?
+            .into_vec()
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+
+        let mut table = self.table(start, rows);
+        table.width = E::width_from_header(&header);
+        Ok(table)
+    }
+
+    /// Table the entity is bound to by its [`EntityTable`] implementation
+    pub fn of<E>(&self) -> Table<'_, E>
+    where
+        E: EntityTable,
+    {
+        self.table(SheetA1CellId::new(E::sheet(), E::origin()), E::rows())
+    }
+}
+
+impl<'r, E> Table<'r, E>
+where
+    E: EntityEssentials,
+{
+    pub fn repository(&self) -> &'r Repository {
+        self.repo
+    }
+
+    /// Top left cell of the table data, right below the header rows
+    pub fn start(&self) -> &SheetA1CellId {
+        &self.start
+    }
+
+    /// Table created at the top of a `header_rows` rows high header, e.g. a title row above
+    /// the column names: the data starts below the header, so reads, appends and the row index
+    /// math skip it. The capacity of the table stays the same
+    pub fn with_header_rows(mut self, header_rows: u32) -> Self {
+        let header_start = self.header_start();
+        self.start = SheetA1CellId::new(
+            &header_start.sheet_name,
+            header_start.cell.delta(0, header_rows as i32),
+        );
+        self.header_rows = header_rows;
+        self
+    }
+
+    pub fn header_rows(&self) -> u32 {
+        self.header_rows
+    }
+
+    /// Top left cell of the header, the table start if there's no header
+    pub fn header_start(&self) -> SheetA1CellId {
+        SheetA1CellId::new(
+            &self.start.sheet_name,
+            self.start.cell.delta(0, -(self.header_rows as i32)),
+        )
+    }
+
+    /// 0-based index of the data row at `position`, `None` outside of the table rows
+    pub fn row_index(&self, position: &SheetA1CellId) -> Option<u32> {
+        position
+            .row_offset_from(&self.start)
+            .filter(|&index| index < self.rows)
+    }
+
+    /// Position of the data row with the 0-based `index`, `None` past the table rows
+    pub fn row_position(&self, index: u32) -> Option<SheetA1CellId> {
+        (index < self.rows).then(|| {
+            SheetA1CellId::new(
+                &self.start.sheet_name,
+                self.start.cell.delta(0, index as i32),
+            )
+        })
+    }
+
+    /// Capacity of the table in rows
+    pub fn rows(&self) -> u32 {
+        self.rows
+    }
+
+    /// Same table at another position, e.g. on a copy of the sheet
+    pub(crate) fn moved_to(&self, start: SheetA1CellId) -> Self {
+        Self {
+            start,
+            ..self.clone()
+        }
+    }
+
+    /// Width in columns: [`EntityEssentials::entity_width`], unless read from the header row
+    /// by [`Repository::dynamic_table`]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub async fn find_all(&self) -> Result<Vec<Entity<E>>> {
+        self.configured_repository()
+            .find_in_range_of_width(&self.start, self.rows, self.width)
+            .await
+    }
+
+    pub async fn insert(&self, entity_data: E) -> Result<Entity<E>> {
+        let entity = self
+            .configured_repository()
+            .insert_of_width(self.start.clone(), self.rows, self.width, entity_data)
+            .await?;
+        self.rewrite_overridden_columns(entity.position(), entity.data())
+            .await?;
+        Ok(entity)
+    }
+
+    /// First row under the table start with an empty first column, `None` if the table is full.
+    /// Handy for custom append logic, as the API append heuristics skip gaps inside the table
+    pub async fn next_insert_position(&self) -> Result<Option<SheetA1CellId>> {
+        let row = self
+            .repo
+            .driver
+            .lock()
+            .await
+            .try_find_next_empty_row(
+                &self.start.sheet_name,
+                &self.start.cell.col,
+                self.start.cell.row.get(),
+            )
+            .await
+            .change_context(RepositoryError::DriverError)?;
+
+        let first_row_after = self.start.cell.row.get() + self.rows;
+        Ok((row < first_row_after).then(|| {
+            SheetA1CellId::new(
+                &self.start.sheet_name,
+                A1CellId::from_primitives(&self.start.cell.col, row),
+            )
+        }))
+    }
+}
+
+impl<E> Clone for Table<'_, E>
+where
+    E: EntityEssentials,
+{
+    fn clone(&self) -> Self {
+        Self {
+            repo: self.repo,
+            start: self.start.clone(),
+            rows: self.rows,
+            width: self.width,
+            header_rows: self.header_rows,
+            options: self.options.clone(),
+            _entity: PhantomData,
+        }
+    }
+}
+
+impl<E> Debug for Table<'_, E>
+where
+    E: EntityEssentials,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Table")
+            .field("start", &self.start)
+            .field("rows", &self.rows)
+            .field("width", &self.width)
+            .field("header_rows", &self.header_rows)
+            .field("options", &self.options)
+            .finish()
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod table_tests {
+    use super::*;
+    use crate::mapper::sheet_row;
+    use crate::mapper::sheet_row::{SheetRow, SheetRowExt, SheetRowSerde};
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use crate::testing::examples_support::User;
+    use crate::testing::fixtures::{backend_repository, memory_repository};
+    use serde_json::Value;
+
+    impl EntityTable for User {
+        fn sheet() -> &'static str {
+            "users"
+        }
+
+        fn origin() -> A1CellId {
+            A1CellId::from_primitives("B", 2)
+        }
+    }
+
+    #[tokio::test]
+    async fn of__entity_table__reads_bound_range() {
+        let backend = MemoryBackend::new();
+        backend.workbook().set_sheet(
+            "users",
+            vec![
+                vec![
+                    Value::from("header"),
+                    Value::from("id"),
+                    Value::from("name"),
+                ],
+                vec![Value::from(""), Value::from("1"), Value::from("Joe")],
+            ],
+        );
+        let repository = backend_repository(backend);
+
+        let table = repository.of::<User>();
+        let users = table.find_all().await.expect("Test: Expected users");
+
+        assert_eq!(
+            table.start(),
+            &SheetA1CellId::from_primitives("users", "B", 2)
+        );
+        assert_eq!(table.rows(), 1000);
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].data().name, "Joe");
+    }
+
+    #[tokio::test]
+    async fn with_header_rows__two_row_header__data_below_it() {
+        let backend = MemoryBackend::new();
+        backend.workbook().set_sheet(
+            "users",
+            vec![
+                vec![Value::from("Users of 2024")],
+                vec![Value::from("id"), Value::from("name")],
+                vec![Value::from("1"), Value::from("Joe")],
+            ],
+        );
+        let repository = backend_repository(backend);
+        let table = repository
+            .table::<User>(SheetA1CellId::from_primitives("users", "A", 1), 10)
+            .with_header_rows(2);
+
+        let users = table.find_all().await.expect("Test: Expected users");
+        let inserted = table
+            .insert(User {
+                id: 2,
+                name: "John".to_string(),
+            })
+            .await
+            .expect("Test: Expected insert");
+
+        assert_eq!(
+            table.start(),
+            &SheetA1CellId::from_primitives("users", "A", 3)
+        );
+        assert_eq!(
+            table.header_start(),
+            SheetA1CellId::from_primitives("users", "A", 1)
+        );
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].data().name, "Joe");
+        assert_eq!(table.row_index(inserted.position()), Some(1));
+        assert_eq!(table.row_position(1).as_ref(), Some(inserted.position()));
+        assert_eq!(
+            table.row_index(&SheetA1CellId::from_primitives("users", "A", 2)),
+            None
+        );
+    }
+
+    /// Name and one amount per month column
+    #[derive(Debug, Clone, PartialEq)]
+    struct Report {
+        name: String,
+        months: Vec<String>,
+    }
+
+    impl SheetRowSerde for Report {
+        fn deserialize(row: SheetRow) -> sheet_row::Result<Self> {
+            Ok(Self {
+                name: row.parse_cell(0, "name")?,
+                months: (1..row.len())
+                    .map(|i| row.parse_cell(i, "month"))
+                    .collect::<sheet_row::Result<_>>()?,
+            })
+        }
+
+        fn serialize(&self) -> sheet_row::Result<SheetRow> {
+            let mut row = vec![Value::String(self.name.clone())];
+            row.extend(self.months.iter().cloned().map(Value::String));
+            Ok(row)
+        }
+    }
+
+    impl EntityEssentials for Report {
+        fn entity_width() -> u32 {
+            1
+        }
+    }
+
+    impl DynamicWidthEntity for Report {}
+
+    #[tokio::test]
+    async fn dynamic_table__width_from_header__reads_every_month() {
+        let backend = MemoryBackend::new();
+        backend.workbook().set_sheet(
+            "reports",
+            vec![
+                vec![Value::from("name"), Value::from("Jan"), Value::from("Feb")],
+                vec![Value::from("Joe"), Value::from("10"), Value::from("20")],
+            ],
+        );
+        let repository = backend_repository(backend);
+
+        let table = repository
+            .dynamic_table::<Report>(SheetA1CellId::from_primitives("reports", "A", 2), 10)
+            .await
+            .expect("Test: Expected table");
+        let reports = table.find_all().await.expect("Test: Expected reports");
+
+        assert_eq!(table.width(), 3);
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].data().months, vec!["10", "20"]);
+    }
+
+    #[tokio::test]
+    async fn dynamic_table__no_header_row__invalid_argument() {
+        let repository = memory_repository();
+
+        let report = repository
+            .dynamic_table::<Report>(SheetA1CellId::from_primitives("reports", "A", 1), 10)
+            .await
+            .expect_err("Test: Expected missing header row");
+
+        assert!(matches!(
+            report.current_context(),
+            RepositoryError::InvalidArgument(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn next_insert_position__gap_and_full_table__ok() {
+        let backend = MemoryBackend::new();
+        backend.workbook().set_sheet(
+            "users",
+            vec![
+                vec![Value::from("1"), Value::from("Joe")],
+                vec![Value::from(""), Value::from("")],
+                vec![Value::from("3"), Value::from("Jane")],
+            ],
+        );
+        let repository = backend_repository(backend);
+        let start = SheetA1CellId::from_primitives("users", "A", 1);
+
+        let position = repository
+            .table::<User>(start.clone(), 10)
+            .next_insert_position()
+            .await
+            .expect("Test: Expected position");
+        assert_eq!(
+            position,
+            Some(SheetA1CellId::from_primitives("users", "A", 2))
+        );
+
+        let position = repository
+            .table::<User>(start, 1)
+            .next_insert_position()
+            .await
+            .expect("Test: Expected position");
+        assert_eq!(position, None);
+    }
+}
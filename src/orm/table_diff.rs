@@ -0,0 +1,325 @@
+//////////////////////// Cell level diff of a table ////////////////////////
+
+use crate::mapper::sheet_row::SheetRow;
+use crate::orm::migration::cell_text;
+use crate::orm::snapshot::SnapshotDiff;
+use crate::orm::sync::{PlannedWrite, SyncPlan};
+use crate::orm::{RepositoryError, Result};
+use crate::types::{EntityEssentials, SheetA1CellId};
+use error_stack::ResultExt;
+use serde_json::Value;
+
+/// Row which is there on one side of the diff only
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowDiff {
+    pub position: SheetA1CellId,
+    pub row: SheetRow,
+}
+
+/// Cell of a changed row, compared by text: "42" and 42 are the same
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellChange {
+    pub cell: SheetA1CellId,
+    /// 0-based column within the row
+    pub column: u32,
+    pub before: Value,
+    pub after: Value,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangedRow {
+    pub position: SheetA1CellId,
+    pub cells: Vec<CellChange>,
+}
+
+/// Entity independent diff of a table, made from a [`SnapshotDiff`] or a [`SyncPlan`],
+/// e.g. to notify about changes with [`TableDiff::render_markdown`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TableDiff {
+    /// Column names of the changed cells, see [`EntityEssentials::column_headers`]
+    pub headers: Vec<String>,
+    pub added: Vec<RowDiff>,
+    pub removed: Vec<RowDiff>,
+    pub changed: Vec<ChangedRow>,
+}
+
+impl TableDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Rows which differ in no cell are left out
+    fn push_changed(&mut self, position: &SheetA1CellId, before: &SheetRow, after: &SheetRow) {
+        let width = before.len().max(after.len());
+        let cell = |row: &SheetRow, column: usize| row.get(column).cloned().unwrap_or(Value::Null);
+        let cells: Vec<CellChange> = (0..width)
+            .filter(|&column| cell_text(&cell(before, column)) != cell_text(&cell(after, column)))
+            .map(|column| CellChange {
+                cell: SheetA1CellId::new(
+                    &position.sheet_name,
+                    position.cell.delta(column as i32, 0),
+                ),
+                column: column as u32,
+                before: cell(before, column),
+                after: cell(after, column),
+            })
+            .collect();
+        if !cells.is_empty() {
+            self.changed.push(ChangedRow {
+                position: position.clone(),
+                cells,
+            });
+        }
+    }
+
+    fn summary(&self) -> String {
+        match self.is_empty() {
+            true => "No changes".to_string(),
+            false => format!(
+                "{} added, {} removed, {} changed",
+                self.added.len(),
+                self.removed.len(),
+                self.changed.len()
+            ),
+        }
+    }
+
+    /// Header of the column, the A1 of the cell if there's none
+    fn column_name(&self, change: &CellChange) -> String {
+        self.headers
+            .get(change.column as usize)
+            .cloned()
+            .unwrap_or_else(|| change.cell.cell.to_string())
+    }
+
+    /// Plain text, one line per row, e.g.
+    /// ```text
+    /// + users!A3: 3 | Jane
+    /// ~ users!A2: name John -> Johnny
+    /// 1 added, 0 removed, 1 changed
+    /// ```
+    pub fn render_text(&self) -> String {
+        let mut lines = vec![];
+        for added in &self.added {
+            lines.push(format!("+ {}: {}", added.position, join_row(&added.row)));
+        }
+        for removed in &self.removed {
+            lines.push(format!(
+                "- {}: {}",
+                removed.position,
+                join_row(&removed.row)
+            ));
+        }
+        for changed in &self.changed {
+            let cells: Vec<String> = changed
+                .cells
+                .iter()
+                .map(|change| {
+                    format!(
+                        "{} {} -> {}",
+                        self.column_name(change),
+                        cell_text(&change.before),
+                        cell_text(&change.after)
+                    )
+                })
+                .collect();
+            lines.push(format!("~ {}: {}", changed.position, cells.join(", ")));
+        }
+        lines.push(self.summary());
+        lines.join("\n")
+    }
+
+    /// Markdown table with a row per added or removed row and per changed cell, for chat
+    /// notifications
+    pub fn render_markdown(&self) -> String {
+        let mut lines = vec![format!("**{}**", self.summary())];
+        if self.is_empty() {
+            return lines.remove(0);
+        }
+        lines.push(String::new());
+        lines.push("| | Cell | Before | After |".to_string());
+        lines.push("|---|---|---|---|".to_string());
+        for added in &self.added {
+            lines.push(format!(
+                "| + | `{}` | | {} |",
+                added.position,
+                markdown_cell(&join_row(&added.row))
+            ));
+        }
+        for removed in &self.removed {
+            lines.push(format!(
+                "| - | `{}` | {} | |",
+                removed.position,
+                markdown_cell(&join_row(&removed.row))
+            ));
+        }
+        for change in self.changed.iter().flat_map(|changed| &changed.cells) {
+            lines.push(format!(
+                "| ~ | `{}` ({}) | {} | {} |",
+                change.cell,
+                markdown_cell(&self.column_name(change)),
+                markdown_cell(&cell_text(&change.before)),
+                markdown_cell(&cell_text(&change.after))
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+fn join_row(row: &SheetRow) -> String {
+    row.iter().map(cell_text).collect::<Vec<_>>().join(" | ")
+}
+
+/// Pipes and line breaks would break the markdown table
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+fn headers<E>() -> Vec<String>
+where
+    E: EntityEssentials,
+{
+    E::column_headers().iter().map(|h| h.to_string()).collect()
+}
+
+impl<E> SnapshotDiff<E>
+where
+    E: EntityEssentials,
+{
+    /// Serializes the entities, so the diff is down to the cells
+    pub fn to_table_diff(&self) -> Result<TableDiff> {
+        let row = |data: &E| {
+            data.serialize()
+                .change_context(RepositoryError::DriverError)
+        };
+        let mut diff = TableDiff {
+            headers: headers::<E>(),
+            ..TableDiff::default()
+        };
+        for added in &self.added {
+            diff.added.push(RowDiff {
+                position: added.position.clone(),
+                row: row(&added.data)?,
+            });
+        }
+        for removed in &self.removed {
+            diff.removed.push(RowDiff {
+                position: removed.position.clone(),
+                row: row(&removed.data)?,
+            });
+        }
+        for changed in &self.changed {
+            diff.push_changed(
+                &changed.position,
+                &row(&changed.before)?,
+                &row(&changed.after)?,
+            );
+        }
+        Ok(diff)
+    }
+}
+
+impl<E> SyncPlan<E>
+where
+    E: EntityEssentials,
+{
+    /// What applying the plan changes: inserts are added rows, clears are removed ones
+    pub fn to_table_diff(&self) -> Result<TableDiff> {
+        let row = |data: &E| {
+            data.serialize()
+                .change_context(RepositoryError::DriverError)
+        };
+        let mut diff = TableDiff {
+            headers: headers::<E>(),
+            ..TableDiff::default()
+        };
+        for write in self.writes() {
+            let position = write.position().clone();
+            match write {
+                PlannedWrite::Insert { data, .. } => diff.added.push(RowDiff {
+                    position,
+                    row: row(data)?,
+                }),
+                PlannedWrite::Clear { before, .. } => diff.removed.push(RowDiff {
+                    position,
+                    row: row(before)?,
+                }),
+                PlannedWrite::Update { before, after, .. } => {
+                    diff.push_changed(&position, &row(before)?, &row(after)?)
+                }
+            }
+        }
+        Ok(diff)
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod table_diff_tests {
+    use super::*;
+    use crate::orm::snapshot::Snapshot;
+    use crate::testing::examples_support::User;
+    use crate::types::Entity;
+
+    fn user(row: u32, id: i32, name: &str) -> Entity<User> {
+        Entity::new(
+            SheetA1CellId::from_primitives("users", "A", row),
+            User {
+                id,
+                name: name.to_string(),
+            },
+        )
+    }
+
+    fn diff() -> TableDiff {
+        let start = SheetA1CellId::from_primitives("users", "A", 1);
+        let before = Snapshot::new(start.clone(), vec![user(1, 1, "Joe"), user(2, 2, "John")]);
+        let after = Snapshot::new(start, vec![user(2, 2, "Johnny"), user(3, 3, "Jane")]);
+        before
+            .diff(&after)
+            .to_table_diff()
+            .expect("Test: Expected table diff")
+    }
+
+    #[test]
+    fn to_table_diff__changed_row__only_changed_cells() {
+        let diff = diff();
+
+        assert_eq!(
+            diff.changed,
+            vec![ChangedRow {
+                position: SheetA1CellId::from_primitives("users", "A", 2),
+                cells: vec![CellChange {
+                    cell: SheetA1CellId::from_primitives("users", "B", 2),
+                    column: 1,
+                    before: Value::from("John"),
+                    after: Value::from("Johnny"),
+                }],
+            }]
+        );
+    }
+
+    #[test]
+    fn render__text_and_markdown__rows_and_cells() {
+        let diff = diff();
+
+        assert_eq!(
+            diff.render_text(),
+            "+ users!A3: 3 | Jane\n\
+             - users!A1: 1 | Joe\n\
+             ~ users!A2: name John -> Johnny\n\
+             1 added, 1 removed, 1 changed"
+        );
+        assert_eq!(
+            diff.render_markdown(),
+            "**1 added, 1 removed, 1 changed**\n\
+             \n\
+             | | Cell | Before | After |\n\
+             |---|---|---|---|\n\
+             | + | `users!A3` | | 3 \\| Jane |\n\
+             | - | `users!A1` | 1 \\| Joe | |\n\
+             | ~ | `users!B2` (name) | John | Johnny |"
+        );
+        assert_eq!(TableDiff::default().render_markdown(), "**No changes**");
+    }
+}
@@ -0,0 +1,271 @@
+//////////////////////// Per-table options ////////////////////////
+
+use crate::mapper::sheet_row::SheetRowSerde;
+use crate::orm::options::RepositoryOptions;
+use crate::orm::table::Table;
+use crate::orm::{Repository, RepositoryError, Result};
+use crate::types::{
+    A1Range, DateTimeRenderOption, Entity, EntityEssentials, InputMode, SheetA1CellId,
+    SheetA1Range, ValueRenderOption,
+};
+use error_stack::ResultExt;
+use std::collections::BTreeMap;
+use tracing::debug;
+
+/// Options of a single table, unset ones fall back to the [`RepositoryOptions`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableOptions {
+    pub input_mode: Option<InputMode>,
+    pub value_render_option: Option<ValueRenderOption>,
+    pub date_time_render_option: Option<DateTimeRenderOption>,
+    /// Input modes of single columns by their offset from the table start column,
+    /// e.g. RAW for an ID column of a USER_ENTERED table
+    pub column_input_modes: BTreeMap<u32, InputMode>,
+}
+
+impl TableOptions {
+    pub fn input_mode(mut self, input_mode: InputMode) -> Self {
+        self.input_mode = Some(input_mode);
+        self
+    }
+
+    pub fn value_render_option(mut self, option: ValueRenderOption) -> Self {
+        self.value_render_option = Some(option);
+        self
+    }
+
+    pub fn date_time_render_option(mut self, option: DateTimeRenderOption) -> Self {
+        self.date_time_render_option = Some(option);
+        self
+    }
+
+    pub fn column_input_mode(mut self, column: u32, input_mode: InputMode) -> Self {
+        self.column_input_modes.insert(column, input_mode);
+        self
+    }
+
+    /// Repository options with the ones set here applied over them
+    pub fn applied_to(&self, defaults: &RepositoryOptions) -> RepositoryOptions {
+        RepositoryOptions {
+            input_mode: self.input_mode.unwrap_or(defaults.input_mode),
+            value_render_option: self
+                .value_render_option
+                .unwrap_or(defaults.value_render_option),
+            date_time_render_option: self
+                .date_time_render_option
+                .or(defaults.date_time_render_option),
+        }
+    }
+
+    /// Runs of adjacent columns of the table overridden with the same mode other than the
+    /// table one, except the `read_only` ones, as (first column, last column, mode)
+    fn overridden_runs(
+        &self,
+        table_mode: InputMode,
+        width: u32,
+        read_only: &[u32],
+    ) -> Vec<(u32, u32, InputMode)> {
+        let mut runs: Vec<(u32, u32, InputMode)> = vec![];
+        let overridden = self.column_input_modes.iter().filter(|(column, mode)| {
+            **column < width && **mode != table_mode && !read_only.contains(column)
+        });
+        for (&column, &mode) in overridden {
+            match runs.last_mut() {
+                Some((_, last, run_mode)) if *last + 1 == column && *run_mode == mode => {
+                    *last = column
+                }
+                _ => runs.push((column, column, mode)),
+            }
+        }
+        runs
+    }
+}
+
+impl<E> Table<'_, E>
+where
+    E: EntityEssentials,
+{
+    /// Reads and writes of the table use these options instead of the repository ones.
+    /// Columns with their own input mode are written once more with it after the row is
+    /// written, as a single write request has a single input mode
+    pub fn with_options(mut self, options: TableOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn options(&self) -> &TableOptions {
+        &self.options
+    }
+
+    /// Repository over the same driver with the options of this table
+    pub(crate) fn configured_repository(&self) -> Repository {
+        let repository = self.repository();
+        repository.overriding(self.options.applied_to(repository.options()))
+    }
+
+    /// Updates the entity with the table options, see [`Repository::update`]
+    pub async fn update(&self, entity: &Entity<E>) -> Result<()> {
+        let repository = self.configured_repository();
+        repository.update(entity).await?;
+        if self.overridden_runs().is_empty() {
+            return Ok(());
+        }
+
+        let position = repository.current_position(entity).await?;
+        self.rewrite_overridden_columns(&position, entity.data())
+            .await
+    }
+
+    fn overridden_runs(&self) -> Vec<(u32, u32, InputMode)> {
+        let table_mode = self
+            .options
+            .input_mode
+            .unwrap_or(self.repository().options().input_mode);
+        self.options
+            .overridden_runs(table_mode, self.width(), E::read_only_columns())
+    }
+
+    /// Writes the cells of the columns with their own input mode again, with that mode
+    pub(crate) async fn rewrite_overridden_columns(
+        &self,
+        position: &SheetA1CellId,
+        data: &E,
+    ) -> Result<()> {
+        let runs = self.overridden_runs();
+        if runs.is_empty() {
+            return Ok(());
+        }
+        let row = data
+            .serialize()
+            .change_context(RepositoryError::DriverError)?;
+
+        let driver = self.repository().driver.lock().await;
+        for (first, last, mode) in runs {
+            let Some(values) = row.get(first as usize..=last as usize) else {
+                continue;
+            };
+            let range = SheetA1Range::new(
+                &position.sheet_name,
+                A1Range::new(
+                    position.cell.delta(first as i32, 0),
+                    position.cell.delta(last as i32, 0),
+                ),
+            );
+            debug!("Rewriting {} with input mode {}", range, mode);
+            driver
+                .try_write_range_as(range.to_string().as_str(), vec![values.to_vec()], mode)
+                .await
+                .change_context(RepositoryError::DriverError)?;
+        }
+        Ok(())
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod table_options_tests {
+    use super::*;
+    use crate::spread_sheet_driver::cassette::Interaction;
+    use crate::testing::examples_support::User;
+    use crate::testing::fixtures::{
+        AppendValuesResponseBuilder, MatchedValueRangeBuilder, replay_repository,
+    };
+    use google_sheets4::api::{BatchGetValuesByDataFilterResponse, UpdateValuesResponse};
+    use serde::Serialize;
+    use serde_json::{Value, json};
+
+    fn interaction<T>(operation: &str, request: Value, response: T) -> Interaction
+    where
+        T: Serialize,
+    {
+        Interaction {
+            operation: operation.to_string(),
+            request,
+            response: serde_json::to_value(response).expect("Test: Expected to serialize"),
+        }
+    }
+
+    #[tokio::test]
+    async fn insert__raw_table_with_user_entered_column__column_rewritten() {
+        let repository = replay_repository(vec![
+            interaction(
+                "values.append",
+                json!({
+                    "range": "users!A1:B11",
+                    "values": [["2", "=UPPER(\"john\")"]],
+                    "valueInputOption": "RAW"
+                }),
+                AppendValuesResponseBuilder::new("users!A2:B2")
+                    .row(["2", "=UPPER(\"john\")"])
+                    .build(),
+            ),
+            interaction(
+                "values.update",
+                json!({
+                    "range": "users!B2:B2",
+                    "values": [["=UPPER(\"john\")"]]
+                }),
+                UpdateValuesResponse::default(),
+            ),
+        ]);
+        let table = repository
+            .table::<User>(SheetA1CellId::from_primitives("users", "A", 1), 10)
+            .with_options(
+                TableOptions::default()
+                    .input_mode(InputMode::Raw)
+                    .column_input_mode(1, InputMode::UserEntered),
+            );
+
+        let inserted = table
+            .insert(User {
+                id: 2,
+                name: "=UPPER(\"john\")".to_string(),
+            })
+            .await
+            .expect("Test: Expected insert with column override");
+
+        assert_eq!(
+            inserted.position(),
+            &SheetA1CellId::from_primitives("users", "A", 2)
+        );
+    }
+
+    #[tokio::test]
+    async fn find_all__table_render_option__sent_instead_of_repository_one() {
+        let values = BatchGetValuesByDataFilterResponse {
+            value_ranges: Some(vec![
+                MatchedValueRangeBuilder::new("users!A1:B11")
+                    .row(["1", "Joe"])
+                    .build(),
+            ]),
+            ..Default::default()
+        };
+        let repository = replay_repository(vec![interaction(
+            "values.batchGetByDataFilter",
+            json!({ "range": "users!A1:B11", "valueRenderOption": "FORMULA" }),
+            values,
+        )]);
+        let table = repository
+            .table::<User>(SheetA1CellId::from_primitives("users", "A", 1), 10)
+            .with_options(TableOptions::default().value_render_option(ValueRenderOption::Formula));
+
+        let users = table.find_all().await.expect("Test: Expected users");
+
+        assert_eq!(users.len(), 1);
+    }
+
+    #[test]
+    fn overridden_runs__adjacent_and_table_mode_columns__merged_and_skipped() {
+        let options = TableOptions::default()
+            .column_input_mode(0, InputMode::Raw)
+            .column_input_mode(1, InputMode::Raw)
+            .column_input_mode(2, InputMode::UserEntered)
+            .column_input_mode(4, InputMode::Raw)
+            .column_input_mode(9, InputMode::Raw);
+
+        assert_eq!(
+            options.overridden_runs(InputMode::UserEntered, 5, &[]),
+            vec![(0, 1, InputMode::Raw), (4, 4, InputMode::Raw)]
+        );
+    }
+}
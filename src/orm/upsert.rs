@@ -0,0 +1,124 @@
+//////////////////////// Read-then-write operations ////////////////////////
+
+use crate::orm::table::Table;
+use crate::orm::{Repository, Result};
+use crate::types::{Entity, EntityEssentials};
+use tracing::debug;
+
+impl Repository {
+    /// Entity of the table with the same key as the default one, which is inserted if there's
+    /// none.
+    ///
+    /// `default` is called on every call, also when the entity exists: the key looked for is
+    /// `key_fn(&default())`. Keep it cheap and free of side effects.
+    ///
+    /// Atomic for the callers sharing the repository of the table (and the ones created with
+    /// [`Repository::overriding`]): the read and the insert are not interleaved with other
+    /// read-then-write operations. Other processes writing the table are not excluded, use
+    /// [`crate::spread_sheet_driver::lock::SheetLock`] for that
+    pub async fn find_or_insert<E, K, F, D>(
+        &self,
+        table: &Table<'_, E>,
+        key_fn: F,
+        default: D,
+    ) -> Result<Entity<E>>
+    where
+        E: EntityEssentials,
+        K: PartialEq,
+        F: Fn(&E) -> K,
+        D: FnOnce() -> E,
+    {
+        // The table reads and writes through its own repository, so that's the one to lock
+        let _guard = table.repository().upserts.lock().await;
+        let data = default();
+        let key = key_fn(&data);
+
+        let existing = table
+            .find_all()
+            .await?
+            .into_iter()
+            .find(|entity| key_fn(entity.data()) == key);
+        if let Some(existing) = existing {
+            debug!("Found entity at {}", existing.position());
+            return Ok(existing);
+        }
+        table.insert(data).await
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod upsert_tests {
+    use super::*;
+    use crate::spread_sheet_driver::IntoStrVec;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use crate::testing::examples_support::User;
+    use crate::testing::fixtures::{backend_repository, shared_driver};
+    use crate::types::SheetA1CellId;
+    use serde_json::Value;
+    use std::time::Duration;
+
+    fn user(id: i32, name: &str) -> User {
+        User {
+            id,
+            name: name.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn find_or_insert__existing_and_missing_keys__found_then_inserted() {
+        let backend = MemoryBackend::new();
+        backend
+            .workbook()
+            .set_sheet("users", vec![vec![Value::from("1"), Value::from("Joe")]]);
+        let repository = backend_repository(backend);
+        let table = repository.table::<User>(SheetA1CellId::from_primitives("users", "A", 1), 10);
+
+        let found = repository
+            .find_or_insert(&table, |u| u.id, || user(1, "Someone else"))
+            .await
+            .expect("Test: Expected existing user");
+        let inserted = repository
+            .find_or_insert(&table, |u| u.id, || user(2, "John"))
+            .await
+            .expect("Test: Expected inserted user");
+        let found_again = repository
+            .find_or_insert(&table, |u| u.id, || user(2, "Not inserted"))
+            .await
+            .expect("Test: Expected user inserted before");
+
+        assert_eq!(found.data(), &user(1, "Joe"));
+        assert_eq!(
+            inserted.position(),
+            &SheetA1CellId::from_primitives("users", "A", 2)
+        );
+        assert_eq!(found_again, inserted);
+        let rows = repository
+            .driver
+            .lock()
+            .await
+            .try_get_range("users!A1:B10")
+            .await
+            .expect("Test: Expected table")
+            .into_vec();
+        assert_eq!(rows.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn find_or_insert__other_repository__waits_for_upserts_of_the_table() {
+        let driver = shared_driver(MemoryBackend::new());
+        let repository = Repository::new(driver.clone());
+        let other = Repository::new(driver);
+        let table = repository.table::<User>(SheetA1CellId::from_primitives("users", "A", 1), 10);
+
+        let guard = repository.upserts.lock().await;
+        let blocked = tokio::time::timeout(
+            Duration::from_millis(20),
+            other.find_or_insert(&table, |u| u.id, || user(1, "Joe")),
+        )
+        .await;
+        drop(guard);
+
+        assert!(blocked.is_err());
+    }
+}
@@ -0,0 +1,171 @@
+//////////////////////// Data validation of table columns ////////////////////////
+
+use crate::orm::table::Table;
+use crate::orm::{RepositoryError, Result};
+use crate::spread_sheet_driver::structure::one_of_list_validation_request;
+use crate::types::{EntityEssentials, SheetEnum};
+use error_stack::{ResultExt, bail};
+use tracing::info;
+
+impl<E> Table<'_, E>
+where
+    E: EntityEssentials,
+{
+    /// Offers the variants of `T` as a dropdown in the 0-based `column` over all table rows and
+    /// rejects other values typed into the sheet, so the sheet UI matches the Rust enum.
+    /// Apply it again after variants are added
+    pub async fn apply_enum_validation<T>(&self, column: u32) -> Result<()>
+    where
+        T: SheetEnum,
+    {
+        if column >= self.width() {
+            bail!(RepositoryError::InvalidArgument(format!(
+                "Column {} is out of the table width {}",
+                column,
+                self.width()
+            )));
+        }
+
+        let start = self.start();
+        let driver = self.repository().driver.lock().await;
+        let sheet_id = driver
+            .try_get_sheet_id(&start.sheet_name)
+            .await
+            .change_context(RepositoryError::DriverError)?;
+        let first_row = start.cell.row.get() - 1;
+        let column = start.cell.col.column_number() - 1 + column;
+        driver
+            .try_batch_update(vec![one_of_list_validation_request(
+                sheet_id,
+                first_row..first_row + self.rows(),
+                column..column + 1,
+                T::variants(),
+                true,
+            )])
+            .await
+            .change_context(RepositoryError::DriverError)?;
+
+        info!(
+            "Restricted column {} of the table at {} to {:?}",
+            column,
+            start,
+            T::variants()
+        );
+        Ok(())
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod validation_tests {
+    use super::*;
+    use crate::mapper::sheet_row;
+    use crate::mapper::sheet_row::{SheetRow, SheetRowExt, SheetRowSerde};
+    use crate::spread_sheet_driver::SsdResult;
+    use crate::spread_sheet_driver::backend::SheetsBackend;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use crate::testing::fixtures::backend_repository;
+    use crate::types::SheetA1CellId;
+    use serde_json::{Value, json};
+    use std::sync::Arc;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Order {
+        id: i32,
+        status: String,
+    }
+
+    impl SheetRowSerde for Order {
+        fn deserialize(row: SheetRow) -> sheet_row::Result<Self> {
+            Ok(Self {
+                id: row.parse_cell(0, "id")?,
+                status: row.parse_cell(1, "status")?,
+            })
+        }
+
+        fn serialize(&self) -> sheet_row::Result<SheetRow> {
+            Ok(vec![
+                Value::String(self.id.to_string()),
+                Value::String(self.status.clone()),
+            ])
+        }
+    }
+
+    impl EntityEssentials for Order {
+        fn entity_width() -> u32 {
+            2
+        }
+    }
+
+    enum Status {}
+
+    impl SheetEnum for Status {
+        fn variants() -> &'static [&'static str] {
+            &["new", "paid", "shipped"]
+        }
+    }
+
+    /// Keeps the batchUpdate requests
+    #[derive(Debug, Default)]
+    struct RecordingBackend {
+        memory: MemoryBackend,
+        batches: Arc<std::sync::Mutex<Vec<Value>>>,
+    }
+
+    impl SheetsBackend for RecordingBackend {
+        fn handle(&self, operation: &str, request: &Value) -> SsdResult<Value> {
+            match operation {
+                "spreadsheets.get" => Ok(json!({
+                    "sheets": [{ "properties": { "sheetId": 9, "title": "orders" } }]
+                })),
+                "spreadsheets.batchUpdate" => {
+                    let mut batches = self.batches.lock().expect("Test: Expected lock");
+                    batches.push(request["requests"].clone());
+                    Ok(json!({ "replies": [] }))
+                }
+                _ => self.memory.handle(operation, request),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn apply_enum_validation__status_column__one_of_variants() {
+        let backend = RecordingBackend::default();
+        let batches = backend.batches.clone();
+        let repository = backend_repository(backend);
+        let table =
+            repository.table::<Order>(SheetA1CellId::from_primitives("orders", "B", 2), 100);
+
+        table
+            .apply_enum_validation::<Status>(1)
+            .await
+            .expect("Test: Expected validation");
+
+        let batches = batches.lock().expect("Test: Expected lock");
+        let validation = &batches[0][0]["setDataValidation"];
+        assert_eq!(validation["range"]["sheetId"], 9);
+        assert_eq!(validation["range"]["startRowIndex"], 1);
+        assert_eq!(validation["range"]["endRowIndex"], 101);
+        assert_eq!(validation["range"]["startColumnIndex"], 2);
+        assert_eq!(
+            validation["rule"]["condition"]["values"][2]["userEnteredValue"],
+            "shipped"
+        );
+    }
+
+    #[tokio::test]
+    async fn apply_enum_validation__column_out_of_width__invalid_argument() {
+        let repository = backend_repository(RecordingBackend::default());
+        let table = repository.table::<Order>(SheetA1CellId::from_primitives("orders", "A", 2), 10);
+
+        let report = table
+            .apply_enum_validation::<Status>(2)
+            .await
+            .expect_err("Test: Expected column out of the table");
+
+        assert!(matches!(
+            report.current_context(),
+            RepositoryError::InvalidArgument(_)
+        ));
+    }
+}
@@ -0,0 +1,185 @@
+//////////////////////// Write allowlist ////////////////////////
+
+use crate::spread_sheet_driver::breaker::is_read;
+use crate::spread_sheet_driver::{
+    CallContext, SpreadSheetDriver, SpreadSheetDriverError, SsdResult,
+};
+use crate::types::{NumRange, SheetA1Range};
+use error_stack::bail;
+use std::fmt::Display;
+
+/// Sheets and ranges the driver is permitted to write to, see
+/// [`SpreadSheetDriver::with_write_allowlist`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WriteAllowlist {
+    sheets: Vec<String>,
+    ranges: Vec<SheetA1Range>,
+    structural_updates: bool,
+}
+
+impl WriteAllowlist {
+    /// Permits nothing until sheets or ranges are added
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every cell of the sheet
+    pub fn allow_sheet<N>(mut self, sheet: N) -> Self
+    where
+        N: Display,
+    {
+        self.sheets.push(sheet.to_string());
+        self
+    }
+
+    /// Writes which fit entirely into the range. Appends land below the table found in their
+    /// range, so for them only the columns and the first row are checked
+    pub fn allow_range(mut self, range: SheetA1Range) -> Self {
+        self.ranges.push(range);
+        self
+    }
+
+    /// Writes without A1 ranges: `spreadsheets.batchUpdate` (formatting, dimensions, sheets,
+    /// metadata) and writes by data filters. Their targets are not checked, so they are
+    /// rejected unless permitted
+    pub fn allow_structural_updates(mut self) -> Self {
+        self.structural_updates = true;
+        self
+    }
+
+    fn permits(&self, operation: &str, range: &str) -> bool {
+        let sheet = range
+            .rsplit_once('!')
+            .map(|(sheet, _)| sheet.trim_matches('\''));
+        if sheet.is_some_and(|sheet| self.sheets.iter().any(|allowed| allowed == sheet)) {
+            return true;
+        }
+        let Ok(range) = SheetA1Range::from_raw(range) else {
+            return false;
+        };
+        let write = NumRange::from(range.range.clone());
+        self.ranges
+            .iter()
+            .filter(|allowed| allowed.sheet == range.sheet)
+            .map(|allowed| NumRange::from(allowed.range.clone()))
+            .any(|allowed| {
+                let columns =
+                    allowed.start.col <= write.start.col && write.end.col <= allowed.end.col;
+                let top = allowed.start.row <= write.start.row;
+                match operation {
+                    "values.append" => columns && top,
+                    _ => columns && top && write.end.row <= allowed.end.row,
+                }
+            })
+    }
+}
+
+impl SpreadSheetDriver {
+    /// Fails writes outside of the allowlist with [`SpreadSheetDriverError::WriteNotAllowed`]
+    /// before they are sent, so a bug in range math can't overwrite the parts of a shared
+    /// spreadsheet the application doesn't manage. Reads are not affected
+    pub fn with_write_allowlist(mut self, allowlist: WriteAllowlist) -> Self {
+        self.write_allowlist = Some(allowlist);
+        self
+    }
+
+    pub fn write_allowlist(&self) -> Option<&WriteAllowlist> {
+        self.write_allowlist.as_ref()
+    }
+
+    pub(crate) fn check_write_allowed(&self, context: &CallContext) -> SsdResult<()> {
+        let Some(allowlist) = &self.write_allowlist else {
+            return Ok(());
+        };
+        if is_read(&context.operation) {
+            return Ok(());
+        }
+
+        if context.ranges.is_empty() {
+            if allowlist.structural_updates {
+                return Ok(());
+            }
+            bail!(SpreadSheetDriverError::WriteNotAllowed {
+                operation: context.operation.clone(),
+                range: "structural update".to_string(),
+            });
+        }
+        if let Some(range) = context
+            .ranges
+            .iter()
+            .find(|range| !allowlist.permits(&context.operation, range))
+        {
+            bail!(SpreadSheetDriverError::WriteNotAllowed {
+                operation: context.operation.clone(),
+                range: range.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod allowlist_tests {
+    use super::*;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use serde_json::Value;
+
+    fn assert_not_allowed<T>(result: SsdResult<T>, expected_range: &str) {
+        match result
+            .map(|_| ())
+            .expect_err("Test: Expected write to be rejected")
+            .current_context()
+        {
+            SpreadSheetDriverError::WriteNotAllowed { range, .. } => {
+                assert_eq!(range, expected_range)
+            }
+            other => panic!("Test: Unexpected error {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn write__outside_allowlist__rejected_before_sending() {
+        let backend = MemoryBackend::new();
+        backend
+            .workbook()
+            .set_sheet("shared", vec![vec![Value::from("keep")]]);
+        let allowlist = WriteAllowlist::new()
+            .allow_sheet("users")
+            .allow_range(SheetA1Range::from_raw("shared!B2:C10").unwrap());
+        let driver = SpreadSheetDriver::with_backend("document".to_string(), backend)
+            .with_write_allowlist(allowlist);
+
+        driver
+            .try_write_range(
+                "users!A1:B1",
+                vec![vec![Value::from("1"), Value::from("Joe")]],
+            )
+            .await
+            .expect("Test: Expected write to the allowed sheet");
+        driver
+            .try_write_range(
+                "shared!B3:C3",
+                vec![vec![Value::from("1"), Value::from("Joe")]],
+            )
+            .await
+            .expect("Test: Expected write within the allowed range");
+        driver
+            .try_append_row("shared!B2:C2", vec![Value::from("2"), Value::from("Jane")])
+            .await
+            .expect("Test: Expected append below the allowed range start");
+
+        assert_not_allowed(
+            driver
+                .try_write_range("shared!A1:B1", vec![vec![Value::from("oops")]])
+                .await,
+            "shared!A1:B1",
+        );
+        assert_not_allowed(driver.try_batch_update(vec![]).await, "structural update");
+        let values = driver
+            .try_get_values(&SheetA1Range::from_raw("shared!A1:A1").unwrap())
+            .await
+            .expect("Test: Expected reads to be allowed");
+        assert_eq!(values.values, Some(vec![vec![Value::from("keep")]]));
+    }
+}
@@ -0,0 +1,198 @@
+//////////////////////// Driver construction from credentials ////////////////////////
+
+use crate::spread_sheet_driver::{
+    SheetsClientConnector, SpreadSheetDriver, SpreadSheetDriverError, SsdResult,
+    try_create_https_client,
+};
+use error_stack::{ResultExt, bail};
+use google_sheets4::Sheets;
+use google_sheets4::hyper::client::HttpConnector;
+use google_sheets4::hyper_rustls::HttpsConnector;
+use google_sheets4::oauth2;
+use google_sheets4::oauth2::authenticator::{ApplicationDefaultCredentialsTypes, Authenticator};
+use std::path::PathBuf;
+use tracing::debug;
+
+pub type HttpsAuthenticator = Authenticator<HttpsConnector<HttpConnector>>;
+
+/// Source of the OAuth2 token sent with every request
+pub enum Credentials {
+    /// Service account key file, requests are made as the client email of the account
+    ServiceAccountKeyFile(PathBuf),
+    ServiceAccountKey(oauth2::ServiceAccountKey),
+    /// Installed application flow: the user grants access in the browser on the first run.
+    /// Tokens are kept in `token_cache` if set, otherwise the consent is asked on every start
+    InstalledFlow {
+        secret: oauth2::ApplicationSecret,
+        token_cache: Option<PathBuf>,
+    },
+    /// Key file from `GOOGLE_APPLICATION_CREDENTIALS` or the metadata server of the GCP instance
+    ApplicationDefault,
+    /// Authenticator built by the caller, e.g. with custom token storage
+    Authenticator(HttpsAuthenticator),
+    /// Token obtained elsewhere, sent as is and never refreshed
+    AccessToken(String),
+}
+
+/// Fallible alternative to [`SpreadSheetDriver::new`] which accepts any [`Credentials`]
+pub struct SpreadSheetDriverBuilder {
+    document_id: String,
+    credentials: Option<Credentials>,
+    principal: Option<String>,
+}
+
+impl SpreadSheetDriver {
+    pub fn builder(document_id: String) -> SpreadSheetDriverBuilder {
+        SpreadSheetDriverBuilder {
+            document_id,
+            credentials: None,
+            principal: None,
+        }
+    }
+}
+
+impl SpreadSheetDriverBuilder {
+    pub fn credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    /// Account named in access errors. Detected for service account keys, has to be set
+    /// explicitly for other credentials
+    pub fn principal<P>(mut self, principal: P) -> Self
+    where
+        P: Into<String>,
+    {
+        self.principal = Some(principal.into());
+        self
+    }
+
+    /// Reads the key files and runs the OAuth2 flow, no request to the spreadsheet is made
+    pub async fn build(self) -> SsdResult<SpreadSheetDriver> {
+        let Some(credentials) = self.credentials else {
+            bail!(SpreadSheetDriverError::InvalidArgument(
+                "credentials are not set".to_string()
+            ));
+        };
+        let http_client = try_create_https_client()?;
+        let mut principal = self.principal;
+
+        let client: SheetsClientConnector = match credentials {
+            Credentials::ServiceAccountKeyFile(path) => {
+                let key = oauth2::read_service_account_key(&path)
+                    .await
+                    .change_context_lazy(|| {
+                        SpreadSheetDriverError::AuthenticationFailed(format!(
+                            "can't read service account key {}",
+                            path.display()
+                        ))
+                    })?;
+                principal.get_or_insert_with(|| key.client_email.clone());
+                Sheets::new(http_client, service_account_authenticator(key).await?)
+            }
+            Credentials::ServiceAccountKey(key) => {
+                principal.get_or_insert_with(|| key.client_email.clone());
+                Sheets::new(http_client, service_account_authenticator(key).await?)
+            }
+            Credentials::InstalledFlow {
+                secret,
+                token_cache,
+            } => {
+                let mut builder = oauth2::InstalledFlowAuthenticator::builder(
+                    secret,
+                    oauth2::InstalledFlowReturnMethod::HTTPRedirect,
+                );
+                if let Some(token_cache) = token_cache {
+                    builder = builder.persist_tokens_to_disk(token_cache);
+                }
+                let auth = builder.build().await.change_context(
+                    SpreadSheetDriverError::AuthenticationFailed(
+                        "can't create installed flow authenticator".to_string(),
+                    ),
+                )?;
+                Sheets::new(http_client, auth)
+            }
+            Credentials::ApplicationDefault => {
+                let options = oauth2::ApplicationDefaultCredentialsFlowOpts::default();
+                let auth = match oauth2::ApplicationDefaultCredentialsAuthenticator::builder(
+                    options,
+                )
+                .await
+                {
+                    ApplicationDefaultCredentialsTypes::ServiceAccount(builder) => {
+                        debug!("Using application default service account key");
+                        builder.build().await
+                    }
+                    ApplicationDefaultCredentialsTypes::InstanceMetadata(builder) => {
+                        debug!("Using application default credentials of the instance");
+                        builder.build().await
+                    }
+                }
+                .change_context(SpreadSheetDriverError::AuthenticationFailed(
+                    "can't create application default credentials authenticator".to_string(),
+                ))?;
+                Sheets::new(http_client, auth)
+            }
+            Credentials::Authenticator(auth) => Sheets::new(http_client, auth),
+            Credentials::AccessToken(token) => Sheets::new(http_client, token),
+        };
+        Ok(SpreadSheetDriver::from_client(
+            self.document_id,
+            client,
+            principal,
+        ))
+    }
+}
+
+async fn service_account_authenticator(
+    key: oauth2::ServiceAccountKey,
+) -> SsdResult<HttpsAuthenticator> {
+    oauth2::ServiceAccountAuthenticator::builder(key)
+        .build()
+        .await
+        .change_context(SpreadSheetDriverError::AuthenticationFailed(
+            "can't create service account authenticator".to_string(),
+        ))
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod auth_tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn build__access_token__driver_with_explicit_principal() {
+        let driver = SpreadSheetDriver::builder("document".to_string())
+            .credentials(Credentials::AccessToken("token".to_string()))
+            .principal("user@example.com")
+            .build()
+            .await
+            .expect("Test: Expected driver");
+
+        assert_eq!(driver.principal(), Some("user@example.com"));
+    }
+
+    #[tokio::test]
+    async fn build__missing_or_unreadable_credentials__error_instead_of_panic() {
+        let missing = SpreadSheetDriver::builder("document".to_string())
+            .build()
+            .await
+            .expect_err("Test: Expected missing credentials");
+        assert!(matches!(
+            missing.current_context(),
+            SpreadSheetDriverError::InvalidArgument(_)
+        ));
+
+        let unreadable = SpreadSheetDriver::builder("document".to_string())
+            .credentials(Credentials::ServiceAccountKeyFile(
+                "no/such/key.json".into(),
+            ))
+            .build()
+            .await
+            .expect_err("Test: Expected unreadable key");
+        assert!(matches!(
+            unreadable.current_context(),
+            SpreadSheetDriverError::AuthenticationFailed(_)
+        ));
+    }
+}
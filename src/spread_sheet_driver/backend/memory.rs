@@ -0,0 +1,519 @@
+use crate::mapper::sheet_row::SheetRow;
+use crate::spread_sheet_driver::backend::SheetsBackend;
+use crate::spread_sheet_driver::{SpreadSheetDriverError, SsdResult};
+use crate::types::{
+    A1CellId, A1Range, MajorDimension, NumCellId, SheetA1Range, UserRange, UserRangeBounds,
+    parse_user_range,
+};
+use error_stack::{Report, ResultExt, bail};
+use google_sheets4::api::{
+    AppendValuesResponse, BatchGetValuesByDataFilterResponse, BatchUpdateValuesResponse,
+    DataFilter, MatchedValueRange, UpdateValuesResponse, ValueRange,
+};
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, BTreeSet};
+use std::num::NonZeroU32;
+use std::sync::{Mutex, MutexGuard};
+
+/// Sheet name -> row-major grid of values
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Workbook {
+    sheets: BTreeMap<String, Vec<SheetRow>>,
+}
+
+impl Workbook {
+    /// Returns values of the range the way the API does: trailing empty cells and rows are omitted
+    pub fn read(&self, range: &SheetA1Range) -> Vec<SheetRow> {
+        let Some(grid) = self.sheets.get(&range.sheet) else {
+            return vec![];
+        };
+        let start = NumCellId::from(range.range.start.clone());
+        let end = NumCellId::from(range.range.end.clone());
+
+        let mut rows: Vec<SheetRow> = (start.row..=end.row)
+            .map(|row| {
+                let mut cells: SheetRow = (start.col..=end.col)
+                    .map(|col| {
+                        grid.get(row as usize)
+                            .and_then(|r| r.get(col as usize))
+                            .cloned()
+                            .unwrap_or_else(empty_cell)
+                    })
+                    .collect();
+                trim_end(&mut cells, |cell| is_empty_cell(cell));
+                cells
+            })
+            .collect();
+        trim_end(&mut rows, |row| row.is_empty());
+        rows
+    }
+
+    /// Writes rows starting at `start` and returns the updated range
+    pub fn write(&mut self, sheet: &str, start: &A1CellId, rows: &[SheetRow]) -> A1Range {
+        let origin = NumCellId::from(start.clone());
+        let grid = self.sheets.entry(sheet.to_string()).or_default();
+
+        for (dy, row) in rows.iter().enumerate() {
+            let y = origin.row as usize + dy;
+            if grid.len() <= y {
+                grid.resize(y + 1, vec![]);
+            }
+            for (dx, value) in row.iter().enumerate() {
+                let x = origin.col as usize + dx;
+                if grid[y].len() <= x {
+                    grid[y].resize(x + 1, empty_cell());
+                }
+                grid[y][x] = value.clone();
+            }
+        }
+
+        let width = rows.iter().map(Vec::len).max().unwrap_or(1).max(1) as u32;
+        let height = rows.len().max(1) as u32;
+        A1Range::new(
+            start.clone(),
+            A1CellId::from(NumCellId::from_primitives(
+                origin.col + width - 1,
+                origin.row + height - 1,
+            )),
+        )
+    }
+
+    /// 0-based index of the first row below the table which starts at the range start
+    pub fn first_free_row(&self, range: &SheetA1Range) -> u32 {
+        let start = NumCellId::from(range.range.start.clone());
+        let end = NumCellId::from(range.range.end.clone());
+        let Some(grid) = self.sheets.get(&range.sheet) else {
+            return start.row;
+        };
+
+        let mut row = start.row;
+        while grid.get(row as usize).is_some_and(|r| {
+            (start.col..=end.col)
+                .filter_map(|col| r.get(col as usize))
+                .any(|cell| !is_empty_cell(cell))
+        }) {
+            row += 1;
+        }
+        row
+    }
+
+    /// Same as [`Workbook::read`] but column-major: one `Vec` per column,
+    /// trailing empty cells and columns are omitted
+    pub fn read_columns(&self, range: &SheetA1Range) -> Vec<SheetRow> {
+        let rows = self.read(range);
+        let width = rows.iter().map(Vec::len).max().unwrap_or_default();
+        let mut columns: Vec<SheetRow> = (0..width)
+            .map(|x| {
+                let mut cells: SheetRow = rows
+                    .iter()
+                    .map(|row| row.get(x).cloned().unwrap_or_else(empty_cell))
+                    .collect();
+                trim_end(&mut cells, |cell| is_empty_cell(cell));
+                cells
+            })
+            .collect();
+        trim_end(&mut columns, |column| column.is_empty());
+        columns
+    }
+
+    /// Whole content of the sheet starting from A1
+    pub fn sheet(&self, sheet: &str) -> Vec<SheetRow> {
+        self.sheets.get(sheet).cloned().unwrap_or_default()
+    }
+
+    /// Replaces whole content of the sheet
+    pub fn set_sheet(&mut self, sheet: &str, rows: Vec<SheetRow>) {
+        self.sheets.insert(sheet.to_string(), rows);
+    }
+
+    pub fn sheets(&self) -> impl Iterator<Item = (&String, &Vec<SheetRow>)> {
+        self.sheets.iter()
+    }
+}
+
+pub(crate) fn empty_cell() -> Value {
+    Value::String(String::new())
+}
+
+pub(crate) fn is_empty_cell(value: &Value) -> bool {
+    value.is_null() || value.as_str() == Some("")
+}
+
+fn trim_end<T>(vec: &mut Vec<T>, is_empty: impl Fn(&T) -> bool) {
+    while vec.last().is_some_and(&is_empty) {
+        vec.pop();
+    }
+}
+
+/// In-memory spreadsheet. Values are stored as sent, i.e. USER_ENTERED parsing,
+/// formulas and formatting are not emulated
+#[derive(Debug, Default)]
+pub struct MemoryBackend {
+    workbook: Mutex<Workbook>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_workbook(workbook: Workbook) -> Self {
+        Self {
+            workbook: Mutex::new(workbook),
+        }
+    }
+
+    pub fn workbook(&self) -> MutexGuard<'_, Workbook> {
+        // Workbook is never left in inconsistent state, so poisoning can be ignored
+        self.workbook.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn batch_get(&self, ranges: &[SheetA1Range]) -> BatchGetValuesByDataFilterResponse {
+        self.batch_get_as(ranges, MajorDimension::Rows)
+    }
+
+    pub fn batch_get_as(
+        &self,
+        ranges: &[SheetA1Range],
+        major_dimension: MajorDimension,
+    ) -> BatchGetValuesByDataFilterResponse {
+        let workbook = self.workbook();
+        let value_ranges = ranges
+            .iter()
+            .map(|range| MatchedValueRange {
+                data_filters: Some(vec![DataFilter {
+                    a1_range: Some(range.to_string()),
+                    ..Default::default()
+                }]),
+                value_range: Some(ValueRange {
+                    major_dimension: Some(major_dimension.to_string()),
+                    range: Some(range.to_string()),
+                    values: Some(match major_dimension {
+                        MajorDimension::Rows => workbook.read(range),
+                        MajorDimension::Columns => workbook.read_columns(range),
+                    }),
+                }),
+            })
+            .collect();
+
+        BatchGetValuesByDataFilterResponse {
+            value_ranges: Some(value_ranges),
+            ..Default::default()
+        }
+    }
+
+    pub fn update(&self, range: &SheetA1Range, rows: &[SheetRow]) -> UpdateValuesResponse {
+        let updated = self
+            .workbook()
+            .write(&range.sheet, &range.range.start, rows);
+        updated_values(SheetA1Range::new(&range.sheet, updated), rows)
+    }
+
+    /// Appends rows below the table which starts at the range start
+    pub fn append(&self, range: &SheetA1Range, rows: &[SheetRow]) -> AppendValuesResponse {
+        let mut workbook = self.workbook();
+        let free_row = workbook.first_free_row(range);
+        let start_col = NumCellId::from(range.range.start.clone()).col;
+        let start = A1CellId::from(NumCellId::from_primitives(start_col, free_row));
+        let updated = workbook.write(&range.sheet, &start, rows);
+
+        AppendValuesResponse {
+            table_range: Some(range.to_string()),
+            updates: Some(updated_values(
+                SheetA1Range::new(&range.sheet, updated),
+                rows,
+            )),
+            ..Default::default()
+        }
+    }
+}
+
+impl SheetsBackend for MemoryBackend {
+    fn handle(&self, operation: &str, request: &Value) -> SsdResult<Value> {
+        match operation {
+            "values.batchGetByDataFilter" => {
+                let major_dimension = match request["majorDimension"].as_str() {
+                    Some("COLUMNS") => MajorDimension::Columns,
+                    _ => MajorDimension::Rows,
+                };
+                to_json(&self.batch_get_as(&request_ranges(request)?, major_dimension))
+            }
+            "values.get" => {
+                let range = self.request_range_within_sheet(request)?;
+                let value_range = self
+                    .batch_get(&[range])
+                    .value_ranges
+                    .and_then(|ranges| ranges.into_iter().next())
+                    .and_then(|range| range.value_range);
+                to_json(&value_range.unwrap_or_default())
+            }
+            "values.update" => {
+                let mut response = self.update(&request_range(request)?, &request_rows(request)?);
+                if includes_values(request) {
+                    response = self.with_updated_data(response);
+                }
+                to_json(&response)
+            }
+            "values.batchUpdate" => {
+                let data = request["data"].as_array().cloned().unwrap_or_default();
+                let responses = data
+                    .iter()
+                    .map(|value_range| {
+                        Ok(self.update(&request_range(value_range)?, &request_rows(value_range)?))
+                    })
+                    .collect::<SsdResult<Vec<_>>>()?;
+                to_json(&batch_updated_values(responses))
+            }
+            "values.append" => {
+                let mut response = self.append(&request_range(request)?, &request_rows(request)?);
+                if includes_values(request) {
+                    response.updates = response.updates.map(|u| self.with_updated_data(u));
+                }
+                to_json(&response)
+            }
+            other => bail!(SpreadSheetDriverError::UnsupportedOperation(
+                other.to_string()
+            )),
+        }
+    }
+}
+
+impl MemoryBackend {
+    /// Same as [`request_range`], but open column ranges (`users!A2:B`) are accepted too.
+    /// They end at the last row of the sheet, as the API ones end at the last row of the grid
+    fn request_range_within_sheet(&self, request: &Value) -> SsdResult<SheetA1Range> {
+        let raw = request["range"].as_str().unwrap_or_default();
+        let Ok(UserRange {
+            sheet: Some(sheet),
+            bounds:
+                UserRangeBounds::Columns {
+                    first,
+                    last,
+                    from_row,
+                },
+        }) = parse_user_range(raw)
+        else {
+            return request_range(request);
+        };
+        let rows = self.workbook().sheet(&sheet).len() as u32;
+        let last_row = NonZeroU32::new(rows).map_or(from_row, |rows| rows.max(from_row));
+        Ok(SheetA1Range::new(
+            &sheet,
+            A1Range::new(
+                A1CellId::new(first, from_row),
+                A1CellId::new(last, last_row),
+            ),
+        ))
+    }
+
+    /// Values are stored as written, so they come back the same for every render option
+    fn with_updated_data(&self, mut response: UpdateValuesResponse) -> UpdateValuesResponse {
+        let range = response
+            .updated_range
+            .as_deref()
+            .and_then(|raw| SheetA1Range::from_raw(raw).ok());
+        if let Some(range) = range {
+            response.updated_data = Some(ValueRange {
+                major_dimension: Some(MajorDimension::Rows.to_string()),
+                range: Some(range.to_string()),
+                values: Some(self.workbook().read(&range)),
+            });
+        }
+        response
+    }
+}
+
+fn includes_values(request: &Value) -> bool {
+    request["includeValuesInResponse"].as_bool() == Some(true)
+}
+
+fn batch_updated_values(responses: Vec<UpdateValuesResponse>) -> BatchUpdateValuesResponse {
+    let total = |count: fn(&UpdateValuesResponse) -> Option<i32>| {
+        Some(responses.iter().filter_map(count).sum())
+    };
+    BatchUpdateValuesResponse {
+        total_updated_rows: total(|r| r.updated_rows),
+        total_updated_columns: total(|r| r.updated_columns),
+        total_updated_cells: total(|r| r.updated_cells),
+        total_updated_sheets: Some(
+            responses
+                .iter()
+                .filter_map(|r| r.updated_range.as_deref()?.rsplit_once('!'))
+                .map(|(sheet, _)| sheet)
+                .collect::<BTreeSet<_>>()
+                .len() as i32,
+        ),
+        responses: Some(responses),
+        ..Default::default()
+    }
+}
+
+fn updated_values(range: SheetA1Range, rows: &[SheetRow]) -> UpdateValuesResponse {
+    UpdateValuesResponse {
+        updated_range: Some(range.to_string()),
+        updated_rows: Some(rows.len() as i32),
+        updated_columns: Some(rows.iter().map(Vec::len).max().unwrap_or_default() as i32),
+        updated_cells: Some(rows.iter().map(Vec::len).sum::<usize>() as i32),
+        ..Default::default()
+    }
+}
+
+pub(crate) fn request_range(request: &Value) -> SsdResult<SheetA1Range> {
+    let Some(raw) = request.get("range").and_then(Value::as_str) else {
+        bail!(SpreadSheetDriverError::InvalidArgument(format!(
+            "Request has no range: {request}"
+        )));
+    };
+    // The API accepts reversed corners ("B2:A1") as well
+    SheetA1Range::from_raw(raw)
+        .map(|range| range.normalized())
+        .change_context_lazy(|| SpreadSheetDriverError::InvalidArgument(raw.to_string()))
+}
+
+/// Ranges of a multi-range read (`ranges`) or the single `range`
+fn request_ranges(request: &Value) -> SsdResult<Vec<SheetA1Range>> {
+    let Some(ranges) = request.get("ranges").and_then(Value::as_array) else {
+        return Ok(vec![request_range(request)?]);
+    };
+    ranges
+        .iter()
+        .map(|raw| request_range(&json!({ "range": raw })))
+        .collect()
+}
+
+fn request_rows(request: &Value) -> SsdResult<Vec<SheetRow>> {
+    match request.get("values") {
+        None | Some(Value::Null) => Ok(vec![]),
+        Some(values) => serde_json::from_value(values.clone())
+            .map_err(Report::new)
+            .change_context(SpreadSheetDriverError::InvalidArgument(
+                "Request values must be rows of cells".to_string(),
+            )),
+    }
+}
+
+fn to_json<T>(response: &T) -> SsdResult<Value>
+where
+    T: Serialize,
+{
+    serde_json::to_value(response)
+        .map_err(Report::new)
+        .change_context(SpreadSheetDriverError::ParseError(
+            "Can't serialize backend response".to_string(),
+        ))
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod workbook_tests {
+    use super::*;
+
+    fn row(values: &[&str]) -> SheetRow {
+        values.iter().map(|v| Value::from(*v)).collect()
+    }
+
+    #[test]
+    fn write_then_read__trims_trailing_empty_cells__ok() {
+        let mut workbook = Workbook::default();
+        workbook.write(
+            "users",
+            &A1CellId::from_primitives("B", 2),
+            &[row(&["1", "Joe"]), row(&["2"])],
+        );
+
+        let range = SheetA1Range::from_raw("users!A1:C4").unwrap();
+        assert_eq!(
+            workbook.read(&range),
+            vec![vec![], row(&["", "1", "Joe"]), row(&["", "2"])]
+        );
+    }
+
+    #[test]
+    fn first_free_row__below_table__ok() {
+        let mut workbook = Workbook::default();
+        workbook.write(
+            "users",
+            &A1CellId::from_primitives("A", 1),
+            &[row(&["id", "name"]), row(&["1", "Joe"])],
+        );
+
+        let range = SheetA1Range::from_raw("users!A1:B1").unwrap();
+        assert_eq!(workbook.first_free_row(&range), 2);
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod memory_backend_tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn handle__append_then_batch_get__ok() {
+        let backend = MemoryBackend::new();
+        backend
+            .handle(
+                "values.append",
+                &json!({ "range": "users!A1:B2", "values": [["1", "Joe"], ["2", "John"]] }),
+            )
+            .unwrap();
+
+        let response = backend
+            .handle(
+                "values.batchGetByDataFilter",
+                &json!({ "range": "users!A1:B3" }),
+            )
+            .unwrap();
+        let response: BatchGetValuesByDataFilterResponse =
+            serde_json::from_value(response).unwrap();
+
+        let values = response.value_ranges.unwrap()[0]
+            .value_range
+            .clone()
+            .and_then(|v| v.values);
+        assert_eq!(
+            values,
+            Some(vec![
+                vec![Value::from("1"), Value::from("Joe")],
+                vec![Value::from("2"), Value::from("John")],
+            ])
+        );
+    }
+
+    #[test]
+    fn handle__update_including_values__updated_data_returned() {
+        let backend = MemoryBackend::new();
+
+        let response = backend
+            .handle(
+                "values.update",
+                &json!({
+                    "range": "users!A1:B1",
+                    "values": [["1", "Joe"]],
+                    "includeValuesInResponse": true
+                }),
+            )
+            .unwrap();
+        let response: UpdateValuesResponse = serde_json::from_value(response).unwrap();
+
+        assert_eq!(
+            response.updated_data.and_then(|data| data.values),
+            Some(vec![vec![Value::from("1"), Value::from("Joe")]])
+        );
+    }
+
+    #[test]
+    fn handle__unknown_operation__err() {
+        let err = MemoryBackend::new()
+            .handle(
+                "spreadsheets.batchUpdate",
+                &json!({ "range": "users!A1:A1" }),
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err.current_context(),
+            SpreadSheetDriverError::UnsupportedOperation(_)
+        ));
+    }
+}
@@ -0,0 +1,17 @@
+//////////////////////// Non-HTTP backends of the driver ////////////////////////
+
+pub mod memory;
+#[cfg(feature = "xlsx")]
+pub mod xlsx;
+
+use crate::spread_sheet_driver::SsdResult;
+use serde_json::Value;
+use std::fmt::Debug;
+
+/// Serves driver API calls without Google Sheets, so the same Repository code can run
+/// against local data. `operation` and JSON shapes of `request`/response are the same as
+/// recorded by the cassette, e.g. "values.update" with `{"range": .., "values": ..}`
+/// answered by serialized `UpdateValuesResponse`
+pub trait SheetsBackend: Debug + Send + Sync {
+    fn handle(&self, operation: &str, request: &Value) -> SsdResult<Value>;
+}
@@ -0,0 +1,178 @@
+//////////////////////// Local .xlsx workbook backend ////////////////////////
+
+use crate::mapper::sheet_row::SheetRow;
+use crate::spread_sheet_driver::backend::SheetsBackend;
+use crate::spread_sheet_driver::backend::memory::{MemoryBackend, Workbook, is_empty_cell};
+use crate::spread_sheet_driver::breaker::is_read;
+use crate::spread_sheet_driver::{SpreadSheetDriverError, SsdResult};
+use calamine::{Data, Reader, Xlsx, open_workbook};
+use error_stack::{Report, ResultExt};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Serves the driver from an `.xlsx` file: the file is read into memory once (calamine)
+/// and rewritten after every mutating call (rust_xlsxwriter).
+/// Only cell values are kept, formats and formulas of the original file are not preserved
+#[derive(Debug)]
+pub struct XlsxBackend {
+    path: PathBuf,
+    memory: MemoryBackend,
+}
+
+impl XlsxBackend {
+    /// Opens existing workbook
+    pub fn open<P>(path: P) -> SsdResult<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref().to_path_buf();
+        let workbook = read_workbook(&path)?;
+        Ok(Self {
+            path,
+            memory: MemoryBackend::from_workbook(workbook),
+        })
+    }
+
+    /// Starts with an empty workbook which will be written to `path` on the first write
+    pub fn create<P>(path: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            memory: MemoryBackend::new(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes current state of the workbook to the file
+    pub fn save(&self) -> SsdResult<()> {
+        write_workbook(&self.path, &self.memory.workbook())
+    }
+}
+
+impl SheetsBackend for XlsxBackend {
+    fn handle(&self, operation: &str, request: &Value) -> SsdResult<Value> {
+        let response = self.memory.handle(operation, request)?;
+        if !is_read(operation) {
+            debug!("Saving {} after {}", self.path.display(), operation);
+            self.save()?;
+        }
+        Ok(response)
+    }
+}
+
+fn read_workbook(path: &Path) -> SsdResult<Workbook> {
+    let error = || SpreadSheetDriverError::FixtureError(format!("Can't read {}", path.display()));
+
+    let mut xlsx: Xlsx<_> = open_workbook(path)
+        .map_err(Report::new)
+        .change_context_lazy(error)?;
+
+    let mut workbook = Workbook::default();
+    for name in xlsx.sheet_names() {
+        let range = xlsx
+            .worksheet_range(&name)
+            .map_err(Report::new)
+            .change_context_lazy(error)?;
+
+        // calamine ranges start at the first used cell, Workbook grids start at A1
+        let (row_offset, col_offset) = range.start().unwrap_or((0, 0));
+        let mut rows: Vec<SheetRow> = vec![vec![]; row_offset as usize];
+        rows.extend(range.rows().map(|cells| {
+            std::iter::repeat_with(|| Value::String(String::new()))
+                .take(col_offset as usize)
+                .chain(cells.iter().map(data_to_json))
+                .collect()
+        }));
+        workbook.set_sheet(&name, rows);
+    }
+    Ok(workbook)
+}
+
+fn write_workbook(path: &Path, workbook: &Workbook) -> SsdResult<()> {
+    let error = || SpreadSheetDriverError::FixtureError(format!("Can't write {}", path.display()));
+
+    let mut xlsx = rust_xlsxwriter::Workbook::new();
+    for (name, rows) in workbook.sheets() {
+        let sheet = xlsx.add_worksheet();
+        sheet
+            .set_name(name)
+            .map_err(Report::new)
+            .change_context_lazy(error)?;
+
+        for (y, row) in rows.iter().enumerate() {
+            for (x, value) in row.iter().enumerate() {
+                let (y, x) = (y as u32, x as u16);
+                let written = match value {
+                    v if is_empty_cell(v) => continue,
+                    Value::Bool(b) => sheet.write_boolean(y, x, *b),
+                    Value::Number(n) => sheet.write_number(y, x, n.as_f64().unwrap_or_default()),
+                    Value::String(s) => sheet.write_string(y, x, s),
+                    other => sheet.write_string(y, x, other.to_string()),
+                };
+                written
+                    .map(|_| ())
+                    .map_err(Report::new)
+                    .change_context_lazy(error)?;
+            }
+        }
+    }
+
+    xlsx.save(path)
+        .map_err(Report::new)
+        .change_context_lazy(error)
+}
+
+/// Dates are converted into serial numbers, the same representation Sheets uses
+/// for UNFORMATTED_VALUE reads (see `SpreadSheetDateTime`)
+fn data_to_json(data: &Data) -> Value {
+    match data {
+        Data::Empty => Value::String(String::new()),
+        Data::Int(i) => Value::from(*i),
+        Data::Float(f) => serde_json::Number::from_f64(*f)
+            .map(Value::Number)
+            .unwrap_or_else(|| Value::String(f.to_string())),
+        Data::Bool(b) => Value::Bool(*b),
+        Data::String(s) | Data::DateTimeIso(s) | Data::DurationIso(s) => Value::String(s.clone()),
+        Data::DateTime(dt) => serde_json::Number::from_f64(dt.as_f64())
+            .map(Value::Number)
+            .unwrap_or_default(),
+        Data::Error(e) => Value::String(format!("#{e:?}")),
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod xlsx_backend_tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn save_then_open__values_preserved__ok() {
+        let path = std::env::temp_dir().join(format!("gsd_xlsx_{}.xlsx", std::process::id()));
+        let backend = XlsxBackend::create(&path);
+        backend
+            .handle(
+                "values.update",
+                &json!({ "range": "users!B2:C3", "values": [["id", "name"], [1, "Joe"]] }),
+            )
+            .unwrap();
+
+        let reopened = XlsxBackend::open(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(
+            reopened.memory.workbook().sheet("users"),
+            vec![
+                vec![],
+                vec![json!(""), json!("id"), json!("name")],
+                vec![json!(""), json!(1.0), json!("Joe")],
+            ]
+        );
+    }
+}
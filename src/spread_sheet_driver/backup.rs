@@ -0,0 +1,503 @@
+//////////////////////// Backup and restore of whole spreadsheets ////////////////////////
+
+use crate::mapper::sheet_row::SheetRow;
+use crate::spread_sheet_driver::{
+    IntoStrVec, SpreadSheetDriver, SpreadSheetDriverError, SsdResult,
+};
+use crate::types::{
+    A1CellId, A1Range, InputMode, Letters, ReadOptions, SheetA1Range, ValueRenderOption,
+};
+use error_stack::{ResultExt, report};
+use google_sheets4::api::{
+    AddSheetRequest, CellData, CellFormat, GridCoordinate, GridProperties, GridRange, Request,
+    RowData, SheetProperties, Spreadsheet, UpdateCellsRequest, UpdateSheetPropertiesRequest,
+};
+use google_sheets4::common::FieldMask;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::num::NonZero;
+use tracing::info;
+
+/// Content of every sheet of a document, serializable to JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpreadsheetDump {
+    pub sheets: Vec<SheetDump>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SheetDump {
+    pub title: String,
+    /// Grid size at the moment of the backup
+    pub rows: u32,
+    pub columns: u32,
+    /// Formulas are kept as formulas, so they are recalculated after restore
+    pub values: Vec<SheetRow>,
+    /// Cell formats, row by row. `None` unless requested by [`BackupOptions::include_formats`]
+    pub formats: Option<Vec<Vec<Option<CellFormat>>>>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BackupOptions {
+    /// Formats come with the whole grid data of the document, so it's a much bigger response
+    pub include_formats: bool,
+}
+
+impl SpreadSheetDriver {
+    pub async fn backup(&self) -> SsdResult<SpreadsheetDump> {
+        self.backup_with(BackupOptions::default()).await
+    }
+
+    pub async fn backup_with(&self, options: BackupOptions) -> SsdResult<SpreadsheetDump> {
+        let properties = self.try_get_sheets_properties().await?;
+        let sheets: Vec<(String, u32, u32)> = properties
+            .iter()
+            .map(|p| {
+                let grid = p.grid_properties.clone().unwrap_or_default();
+                (
+                    p.title.clone().unwrap_or_default(),
+                    grid.row_count.unwrap_or_default().max(0) as u32,
+                    grid.column_count.unwrap_or_default().max(0) as u32,
+                )
+            })
+            .collect();
+
+        let ranges: Vec<SheetA1Range> = sheets
+            .iter()
+            .filter(|(_, rows, columns)| *rows > 0 && *columns > 0)
+            .map(|(title, rows, columns)| grid_range_a1(title, *rows, *columns))
+            .collect();
+        let read = ReadOptions {
+            value_render_option: ValueRenderOption::Formula,
+            ..ReadOptions::default()
+        };
+        let mut values = match ranges.is_empty() {
+            true => vec![],
+            false => self.try_get_ranges_with(&ranges, &read).await?,
+        }
+        .into_iter();
+
+        let mut formats = match options.include_formats {
+            true => self.try_get_formats().await?,
+            false => vec![],
+        };
+
+        let dump = SpreadsheetDump {
+            sheets: sheets
+                .into_iter()
+                .map(|(title, rows, columns)| {
+                    let sheet_values = match rows > 0 && columns > 0 {
+                        true => values.next().map(IntoStrVec::into_vec).unwrap_or_default(),
+                        false => vec![],
+                    };
+                    let sheet_formats = options.include_formats.then(|| {
+                        formats
+                            .iter_mut()
+                            .find(|(t, _)| *t == title)
+                            .map(|(_, f)| std::mem::take(f))
+                            .unwrap_or_default()
+                    });
+                    SheetDump {
+                        title,
+                        rows,
+                        columns,
+                        values: sheet_values,
+                        formats: sheet_formats,
+                    }
+                })
+                .collect(),
+        };
+        info!("Backed up {} sheets", dump.sheets.len());
+        Ok(dump)
+    }
+
+    /// Recreates sheets missing in the document, grows the grid of existing ones if needed,
+    /// clears their values and writes the dumped ones (and formats, if dumped).
+    /// Sheets which are not in the dump are left as they are
+    pub async fn restore(&self, dump: &SpreadsheetDump) -> SsdResult<()> {
+        let existing = self.try_get_sheets_properties().await?;
+        let existing_id = |title: &str| {
+            existing
+                .iter()
+                .find(|p| p.title.as_deref() == Some(title))
+                .map(|p| (p.sheet_id.unwrap_or_default(), p))
+        };
+
+        let mut requests = vec![];
+        for sheet in &dump.sheets {
+            match existing_id(&sheet.title) {
+                None => requests.push(add_sheet_request(sheet)),
+                Some((sheet_id, properties)) => {
+                    let grid = properties.grid_properties.clone().unwrap_or_default();
+                    let rows = (grid.row_count.unwrap_or_default().max(0) as u32).max(sheet.rows);
+                    let columns =
+                        (grid.column_count.unwrap_or_default().max(0) as u32).max(sheet.columns);
+                    requests.push(resize_sheet_request(sheet_id, rows, columns));
+                    requests.push(clear_values_request(sheet_id));
+                }
+            }
+        }
+        let response = self.try_batch_update(requests).await?;
+
+        let added: Vec<SheetProperties> = response
+            .replies
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|reply| reply.add_sheet.and_then(|added| added.properties))
+            .collect();
+        let sheet_id = |title: &str| -> SsdResult<i32> {
+            existing
+                .iter()
+                .chain(&added)
+                .find(|p| p.title.as_deref() == Some(title))
+                .and_then(|p| p.sheet_id)
+                .ok_or_else(|| report!(SpreadSheetDriverError::RangeNotFound(title.to_string())))
+        };
+
+        let mut format_requests = vec![];
+        for sheet in &dump.sheets {
+            if let Some(range) = values_range(sheet) {
+                self.try_write_range_as(
+                    &range.to_string(),
+                    sheet.values.clone(),
+                    InputMode::UserEntered,
+                )
+                .await
+                .attach_printable_lazy(|| format!("Can't restore values of '{}'", sheet.title))?;
+            }
+            if let Some(formats) = &sheet.formats {
+                format_requests.push(formats_request(sheet_id(&sheet.title)?, formats));
+            }
+        }
+        if !format_requests.is_empty() {
+            self.try_batch_update(format_requests).await?;
+        }
+
+        info!("Restored {} sheets", dump.sheets.len());
+        Ok(())
+    }
+
+    /// Formats of every cell, by sheet title
+    async fn try_get_formats(&self) -> SsdResult<Vec<(String, Vec<Vec<Option<CellFormat>>>)>> {
+        let fields = "sheets(properties(title),data(rowData(values(userEnteredFormat))))";
+        let spreadsheet: Spreadsheet = self
+            .exchange(
+                "spreadsheets.get",
+                json!({ "fields": fields, "includeGridData": true }),
+                || async {
+                    self.client_ref()
+                        .spreadsheets()
+                        .get(self.document_id.as_str())
+                        .include_grid_data(true)
+                        .param("fields", fields)
+                        .doit()
+                        .await
+                        .map(|(_, response)| response)
+                        .map_err(|e| self.api_error(e))
+                },
+            )
+            .await?;
+
+        Ok(spreadsheet
+            .sheets
+            .unwrap_or_default()
+            .into_iter()
+            .map(|sheet| {
+                let title = sheet.properties.and_then(|p| p.title).unwrap_or_default();
+                let rows = sheet
+                    .data
+                    .unwrap_or_default()
+                    .into_iter()
+                    .flat_map(|grid| grid.row_data.unwrap_or_default())
+                    .map(|row| {
+                        row.values
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(|cell| cell.user_entered_format)
+                            .collect()
+                    })
+                    .collect();
+                (title, rows)
+            })
+            .collect())
+    }
+}
+
+fn grid_range_a1(title: &str, rows: u32, columns: u32) -> SheetA1Range {
+    let end = A1CellId::new(
+        Letters::from_valid("A".to_string()) + (columns - 1),
+        NonZero::new(rows).expect("Expected non-zero row count"),
+    );
+    SheetA1Range::new(title, A1Range::new(A1CellId::from_primitives("A", 1), end))
+}
+
+/// Range of the dumped values, `None` if there are none
+fn values_range(sheet: &SheetDump) -> Option<SheetA1Range> {
+    let width = sheet.values.iter().map(Vec::len).max().unwrap_or_default() as u32;
+    if width == 0 {
+        return None;
+    }
+    Some(grid_range_a1(
+        &sheet.title,
+        sheet.values.len() as u32,
+        width,
+    ))
+}
+
+fn grid_properties(rows: u32, columns: u32) -> GridProperties {
+    GridProperties {
+        row_count: Some(rows as i32),
+        column_count: Some(columns as i32),
+        ..Default::default()
+    }
+}
+
+fn add_sheet_request(sheet: &SheetDump) -> Request {
+    Request {
+        add_sheet: Some(AddSheetRequest {
+            properties: Some(SheetProperties {
+                title: Some(sheet.title.clone()),
+                grid_properties: Some(grid_properties(sheet.rows.max(1), sheet.columns.max(1))),
+                ..Default::default()
+            }),
+        }),
+        ..Default::default()
+    }
+}
+
+fn resize_sheet_request(sheet_id: i32, rows: u32, columns: u32) -> Request {
+    Request {
+        update_sheet_properties: Some(UpdateSheetPropertiesRequest {
+            fields: Some(FieldMask::new(&[
+                "gridProperties.rowCount",
+                "gridProperties.columnCount",
+            ])),
+            properties: Some(SheetProperties {
+                sheet_id: Some(sheet_id),
+                grid_properties: Some(grid_properties(rows, columns)),
+                ..Default::default()
+            }),
+        }),
+        ..Default::default()
+    }
+}
+
+/// Clears values in the whole sheet, keeping formats
+fn clear_values_request(sheet_id: i32) -> Request {
+    Request {
+        update_cells: Some(UpdateCellsRequest {
+            fields: Some(FieldMask::new(&["userEnteredValue"])),
+            range: Some(GridRange {
+                sheet_id: Some(sheet_id),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+fn formats_request(sheet_id: i32, formats: &[Vec<Option<CellFormat>>]) -> Request {
+    let rows = formats
+        .iter()
+        .map(|row| RowData {
+            values: Some(
+                row.iter()
+                    .map(|format| CellData {
+                        user_entered_format: format.clone(),
+                        ..Default::default()
+                    })
+                    .collect(),
+            ),
+        })
+        .collect();
+    Request {
+        update_cells: Some(UpdateCellsRequest {
+            fields: Some(FieldMask::new(&["userEnteredFormat"])),
+            rows: Some(rows),
+            start: Some(GridCoordinate {
+                sheet_id: Some(sheet_id),
+                row_index: Some(0),
+                column_index: Some(0),
+            }),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod backup_tests {
+    use super::*;
+    use crate::spread_sheet_driver::cassette::{Cassette, Interaction};
+    use crate::testing::fixtures::MatchedValueRangeBuilder;
+    use google_sheets4::api::{
+        AddSheetResponse, BatchGetValuesByDataFilterResponse, BatchUpdateSpreadsheetResponse,
+        Response, Sheet, TextFormat, UpdateValuesResponse,
+    };
+    use serde_json::Value;
+
+    fn interaction<T>(operation: &str, request: Value, response: T) -> Interaction
+    where
+        T: Serialize,
+    {
+        Interaction {
+            operation: operation.to_string(),
+            request,
+            response: serde_json::to_value(response).expect("Test: Expected to serialize"),
+        }
+    }
+
+    fn properties_interaction(sheets: Vec<(i32, &str, i32, i32)>) -> Interaction {
+        let spreadsheet = Spreadsheet {
+            sheets: Some(
+                sheets
+                    .into_iter()
+                    .map(|(sheet_id, title, rows, columns)| Sheet {
+                        properties: Some(SheetProperties {
+                            sheet_id: Some(sheet_id),
+                            title: Some(title.to_string()),
+                            grid_properties: Some(grid_properties(rows as u32, columns as u32)),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    })
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+        interaction(
+            "spreadsheets.get",
+            json!({ "fields": "sheets.properties" }),
+            spreadsheet,
+        )
+    }
+
+    fn users_dump() -> SheetDump {
+        SheetDump {
+            title: "users".to_string(),
+            rows: 100,
+            columns: 3,
+            values: vec![
+                vec![Value::from("1"), Value::from("Joe")],
+                vec![Value::from("2"), Value::from("John"), Value::from("=A2*2")],
+            ],
+            formats: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn backup__two_sheets__values_read_as_formulas_in_one_request() {
+        let values = BatchGetValuesByDataFilterResponse {
+            value_ranges: Some(vec![
+                MatchedValueRangeBuilder::new("users!A1:C100")
+                    .row(["1", "Joe"])
+                    .row(["2", "John", "=A2*2"])
+                    .build(),
+                MatchedValueRangeBuilder::new("empty!A1:A1").build(),
+            ]),
+            ..Default::default()
+        };
+        let driver = SpreadSheetDriver::replay(
+            "document".to_string(),
+            Cassette::replay_from(
+                "unused.json",
+                vec![
+                    properties_interaction(vec![(0, "users", 100, 3), (5, "empty", 1, 1)]),
+                    interaction(
+                        "values.batchGetByDataFilter",
+                        json!({
+                            "ranges": ["users!A1:C100", "empty!A1:A1"],
+                            "valueRenderOption": "FORMULA"
+                        }),
+                        values,
+                    ),
+                ],
+            ),
+        );
+
+        let dump = driver.backup().await.expect("Test: Expected backup");
+
+        assert_eq!(dump.sheets.len(), 2);
+        let users = &dump.sheets[0];
+        assert_eq!((users.rows, users.columns), (100, 3));
+        assert_eq!(users.values, users_dump().values);
+        assert!(users.formats.is_none());
+        assert_eq!(dump.sheets[1].values, Vec::<SheetRow>::new());
+
+        let json = serde_json::to_value(&dump).expect("Test: Expected to serialize");
+        let parsed: SpreadsheetDump =
+            serde_json::from_value(json.clone()).expect("Test: Expected to deserialize");
+        assert_eq!(serde_json::to_value(&parsed).ok(), Some(json));
+    }
+
+    #[tokio::test]
+    async fn restore__missing_sheet__added_and_filled() {
+        let added = BatchUpdateSpreadsheetResponse {
+            replies: Some(vec![Response {
+                add_sheet: Some(AddSheetResponse {
+                    properties: Some(SheetProperties {
+                        sheet_id: Some(9),
+                        title: Some("users".to_string()),
+                        ..Default::default()
+                    }),
+                }),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+        let dump = SpreadsheetDump {
+            sheets: vec![users_dump()],
+        };
+        // Other sheets of the document are not touched
+        let driver = SpreadSheetDriver::replay(
+            "document".to_string(),
+            Cassette::replay_from(
+                "unused.json",
+                vec![
+                    properties_interaction(vec![(0, "orders", 10, 2)]),
+                    interaction(
+                        "spreadsheets.batchUpdate",
+                        json!({ "requests": [add_sheet_request(&users_dump())] }),
+                        added,
+                    ),
+                    interaction(
+                        "values.update",
+                        json!({
+                            "range": "users!A1:C2",
+                            "values": [["1", "Joe"], ["2", "John", "=A2*2"]]
+                        }),
+                        UpdateValuesResponse::default(),
+                    ),
+                ],
+            ),
+        );
+
+        driver
+            .restore(&dump)
+            .await
+            .expect("Test: Expected restore to succeed");
+    }
+
+    #[test]
+    fn formats_request__serialized__starts_at_sheet_origin() {
+        let bold = CellFormat {
+            text_format: Some(TextFormat {
+                bold: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let request = serde_json::to_value(formats_request(3, &[vec![Some(bold), None]]))
+            .expect("Test: Expected to serialize");
+
+        let update = &request["updateCells"];
+        assert_eq!(update["fields"], "userEnteredFormat");
+        assert_eq!(update["start"]["sheetId"], 3);
+        assert_eq!(update["start"]["rowIndex"], 0);
+        assert_eq!(
+            update["rows"][0]["values"].as_array().map(Vec::len),
+            Some(2)
+        );
+    }
+}
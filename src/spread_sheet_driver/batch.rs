@@ -0,0 +1,164 @@
+//////////////////////// Partial application of batchUpdate ////////////////////////
+
+use crate::spread_sheet_driver::{SpreadSheetDriver, SpreadSheetDriverError, SsdResult};
+use error_stack::Report;
+use google_sheets4::api::{Request, Response};
+
+/// Sub-request rejected by the API
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailedRequest {
+    /// Index in the submitted requests
+    pub index: usize,
+    pub cause: String,
+}
+
+/// Outcome of [`SpreadSheetDriver::try_batch_update_partial`]
+#[derive(Debug, Clone, Default)]
+pub struct BatchReport {
+    /// Indices of the applied requests, ascending
+    pub succeeded: Vec<usize>,
+    /// Replies of the applied requests, in the order of `succeeded`
+    pub replies: Vec<Response>,
+    /// Rejected requests, ascending by index
+    pub failed: Vec<FailedRequest>,
+}
+
+impl BatchReport {
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// Failed requests picked from the submitted ones, ready to be fixed and resent
+    pub fn failed_requests(&self, requests: &[Request]) -> Vec<Request> {
+        self.failed
+            .iter()
+            .filter_map(|failed| requests.get(failed.index).cloned())
+            .collect()
+    }
+}
+
+impl SpreadSheetDriver {
+    /// Same as [`SpreadSheetDriver::try_batch_update`], but a rejected sub-request doesn't fail
+    /// the others. The API applies a batch atomically and names the first invalid request
+    /// ("Invalid requests[2].deleteDimension: .."), so the rejected one is dropped and the rest
+    /// is resent until the API accepts it: one extra call per failed request.
+    ///
+    /// Errors not tied to a sub-request (access, network, ...) are returned as is,
+    /// nothing is applied in that case
+    pub async fn try_batch_update_partial(&self, requests: Vec<Request>) -> SsdResult<BatchReport> {
+        let mut pending: Vec<usize> = (0..requests.len()).collect();
+        let mut report = BatchReport::default();
+
+        while !pending.is_empty() {
+            let batch = pending.iter().map(|&i| requests[i].clone()).collect();
+            match self.try_batch_update(batch).await {
+                Ok(response) => {
+                    report.replies = response.replies.unwrap_or_default();
+                    report.succeeded = pending;
+                    break;
+                }
+                Err(error) => match failed_request_index(&error) {
+                    Some(position) if position < pending.len() => {
+                        report.failed.push(FailedRequest {
+                            index: pending.remove(position),
+                            cause: error.current_context().to_string(),
+                        });
+                    }
+                    _ => return Err(error),
+                },
+            }
+        }
+
+        report.failed.sort_by_key(|failed| failed.index);
+        Ok(report)
+    }
+}
+
+/// Position of the rejected request in the sent batch, parsed from the API error message
+fn failed_request_index(error: &Report<SpreadSheetDriverError>) -> Option<usize> {
+    let SpreadSheetDriverError::ApiError(message) = error.current_context() else {
+        return None;
+    };
+    let (_, rest) = message.split_once("requests[")?;
+    let (index, _) = rest.split_once(']')?;
+    index.parse().ok()
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod batch_tests {
+    use super::*;
+    use crate::spread_sheet_driver::backend::SheetsBackend;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use crate::spread_sheet_driver::structure::delete_rows_request;
+    use crate::types::SheetGid;
+    use error_stack::{bail, report};
+    use serde_json::{Value, json};
+    use std::sync::{Arc, Mutex};
+
+    /// Rejects deletions on the sheet 99 the way the API does, logs sizes of the sent batches
+    #[derive(Debug, Default)]
+    struct RejectingBackend {
+        batches: Arc<Mutex<Vec<usize>>>,
+    }
+
+    impl SheetsBackend for RejectingBackend {
+        fn handle(&self, operation: &str, request: &Value) -> SsdResult<Value> {
+            if operation != "spreadsheets.batchUpdate" {
+                bail!(SpreadSheetDriverError::UnsupportedOperation(
+                    operation.to_string()
+                ));
+            }
+            let requests = request["requests"].as_array().cloned().unwrap_or_default();
+            self.batches.lock().unwrap().push(requests.len());
+
+            let rejected = requests
+                .iter()
+                .position(|r| r["deleteDimension"]["range"]["sheetId"] == 99);
+            match rejected {
+                Some(i) => Err(report!(SpreadSheetDriverError::ApiError(format!(
+                    "Bad Request: Invalid requests[{i}].deleteDimension: No grid with id: 99"
+                )))),
+                None => Ok(json!({ "replies": vec![json!({}); requests.len()] })),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn try_batch_update_partial__invalid_requests__others_applied() {
+        let backend = RejectingBackend::default();
+        let batches = backend.batches.clone();
+        let driver = SpreadSheetDriver::with_backend("document".to_string(), backend);
+        let requests = vec![
+            delete_rows_request(SheetGid(0), 1, 1),
+            delete_rows_request(SheetGid(99), 1, 1),
+            delete_rows_request(SheetGid(0), 5, 1),
+            delete_rows_request(SheetGid(99), 2, 1),
+        ];
+
+        let report = driver
+            .try_batch_update_partial(requests.clone())
+            .await
+            .expect("Test: Expected partial success");
+
+        assert_eq!(report.succeeded, vec![0, 2]);
+        assert_eq!(report.replies.len(), 2);
+        assert_eq!(
+            report.failed.iter().map(|f| f.index).collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+        assert!(report.failed[0].cause.contains("No grid with id: 99"));
+        assert_eq!(report.failed_requests(&requests).len(), 2);
+        assert_eq!(*batches.lock().unwrap(), vec![4, 3, 2]);
+    }
+
+    #[tokio::test]
+    async fn try_batch_update_partial__not_request_specific__error() {
+        let driver = SpreadSheetDriver::with_backend("document".to_string(), MemoryBackend::new());
+
+        driver
+            .try_batch_update_partial(vec![delete_rows_request(SheetGid(0), 1, 1)])
+            .await
+            .expect_err("Test: Expected unsupported operation");
+    }
+}
@@ -0,0 +1,257 @@
+//////////////////////// Circuit breaker for API outages ////////////////////////
+
+use crate::spread_sheet_driver::{SpreadSheetDriver, SpreadSheetDriverError, SsdResult};
+use error_stack::bail;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive API failures which open the circuit
+    pub failure_threshold: u32,
+    /// How long calls are short-circuited before a single trial call is let through
+    pub cooldown: Duration,
+    /// Answer reads from the last successful response to the same request while open.
+    /// Keeps every distinct read response in memory
+    pub serve_cached_reads: bool,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            cooldown: Duration::from_secs(30),
+            serve_cached_reads: false,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    /// Last successful response by operation and request
+    reads: HashMap<String, Value>,
+}
+
+#[derive(Debug)]
+pub(crate) struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, BreakerState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub(crate) fn key(operation: &str, request: &Value) -> String {
+        format!("{operation} {request}")
+    }
+
+    /// `Ok(None)` lets the call through, `Ok(Some(..))` is a cached response to serve instead
+    pub(crate) fn admit(&self, operation: &str, key: &str) -> SsdResult<Option<Value>> {
+        let state = self.state();
+        let Some(open_until) = state.open_until else {
+            return Ok(None);
+        };
+        let now = Instant::now();
+        if now >= open_until {
+            // Half-open: the trial call decides whether the circuit closes
+            return Ok(None);
+        }
+
+        if self.config.serve_cached_reads
+            && is_read(operation)
+            && let Some(cached) = state.reads.get(key)
+        {
+            return Ok(Some(cached.clone()));
+        }
+        bail!(SpreadSheetDriverError::CircuitOpen {
+            operation: operation.to_string(),
+            retry_after: open_until - now,
+        })
+    }
+
+    pub(crate) fn record<T>(&self, operation: &str, key: String, result: &SsdResult<T>)
+    where
+        T: Serialize,
+    {
+        let mut state = self.state();
+        match result {
+            Ok(response) => {
+                state.consecutive_failures = 0;
+                state.open_until = None;
+                if self.config.serve_cached_reads
+                    && is_read(operation)
+                    && let Ok(response) = serde_json::to_value(response)
+                {
+                    state.reads.insert(key, response);
+                }
+            }
+            // Only failures of the API itself, not rejected arguments or missing ranges
+            Err(error)
+                if matches!(error.current_context(), SpreadSheetDriverError::ApiError(_)) =>
+            {
+                state.consecutive_failures += 1;
+                if state.consecutive_failures >= self.config.failure_threshold {
+                    warn!(
+                        "{} consecutive API failures, opening the circuit for {:?}",
+                        state.consecutive_failures, self.config.cooldown
+                    );
+                    state.open_until = Some(Instant::now() + self.config.cooldown);
+                }
+            }
+            Err(_) => {}
+        }
+    }
+
+    fn is_open(&self) -> bool {
+        self.state()
+            .open_until
+            .is_some_and(|open_until| Instant::now() < open_until)
+    }
+}
+
+/// Operations which don't change the document
+pub(crate) fn is_read(operation: &str) -> bool {
+    operation.starts_with("values.batchGet")
+        || operation == "values.get"
+        || operation.starts_with("spreadsheets.get")
+}
+
+impl SpreadSheetDriver {
+    /// Short-circuits calls for `config.cooldown` after `config.failure_threshold` consecutive
+    /// API failures: they fail with [`SpreadSheetDriverError::CircuitOpen`] without being sent,
+    /// optionally serving reads from the last successful response.
+    /// Lets dependent services degrade gracefully during Google incidents
+    pub fn with_circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        self.breaker = Some(CircuitBreaker::new(config));
+        self
+    }
+
+    /// `false` if there's no circuit breaker
+    pub fn is_circuit_open(&self) -> bool {
+        self.breaker.as_ref().is_some_and(CircuitBreaker::is_open)
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod breaker_tests {
+    use super::*;
+    use crate::spread_sheet_driver::IntoStrVec;
+    use crate::spread_sheet_driver::backend::SheetsBackend;
+    use crate::testing::fixtures::MatchedValueRangeBuilder;
+    use error_stack::report;
+    use google_sheets4::api::{BatchGetValuesByDataFilterResponse, UpdateValuesResponse};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    /// Answers reads and updates until told to fail, counts calls reaching it
+    #[derive(Debug, Default)]
+    struct FlakyBackend {
+        down: Arc<AtomicBool>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl SheetsBackend for FlakyBackend {
+        fn handle(&self, operation: &str, _request: &Value) -> SsdResult<Value> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.down.load(Ordering::SeqCst) {
+                return Err(report!(SpreadSheetDriverError::ApiError(
+                    "Service Unavailable".to_string()
+                )));
+            }
+            let response = match operation {
+                "values.update" => serde_json::to_value(UpdateValuesResponse::default()),
+                _ => serde_json::to_value(BatchGetValuesByDataFilterResponse {
+                    value_ranges: Some(vec![
+                        MatchedValueRangeBuilder::new("users!A1:B1")
+                            .row(["1", "Joe"])
+                            .build(),
+                    ]),
+                    ..Default::default()
+                }),
+            };
+            Ok(response.expect("Test: Expected to serialize"))
+        }
+    }
+
+    fn config(cooldown: Duration) -> CircuitBreakerConfig {
+        CircuitBreakerConfig {
+            failure_threshold: 2,
+            cooldown,
+            serve_cached_reads: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn with_circuit_breaker__consecutive_failures__cached_reads_and_circuit_open_writes() {
+        let backend = FlakyBackend::default();
+        let (down, calls) = (backend.down.clone(), backend.calls.clone());
+        let driver = SpreadSheetDriver::with_backend("document".to_string(), backend)
+            .with_circuit_breaker(config(Duration::from_secs(600)));
+        driver
+            .try_get_range("users!A1:B1")
+            .await
+            .expect("Test: Expected healthy read");
+
+        down.store(true, Ordering::SeqCst);
+        for _ in 0..2 {
+            driver
+                .try_get_range("users!A1:B2")
+                .await
+                .expect_err("Test: Expected API failure");
+        }
+        assert!(driver.is_circuit_open());
+        let sent = calls.load(Ordering::SeqCst);
+
+        let cached = driver
+            .try_get_range("users!A1:B1")
+            .await
+            .expect("Test: Expected cached read")
+            .into_vec();
+        assert_eq!(cached, vec![vec![Value::from("1"), Value::from("Joe")]]);
+        let write = driver
+            .try_write_range("users!A1:B1", vec![vec![Value::from("2")]])
+            .await
+            .expect_err("Test: Expected short-circuited write");
+        assert!(matches!(
+            write.current_context(),
+            SpreadSheetDriverError::CircuitOpen { .. }
+        ));
+        assert_eq!(calls.load(Ordering::SeqCst), sent);
+    }
+
+    #[tokio::test]
+    async fn with_circuit_breaker__cooldown_passed__trial_call_closes_circuit() {
+        let backend = FlakyBackend::default();
+        let down = backend.down.clone();
+        let driver = SpreadSheetDriver::with_backend("document".to_string(), backend)
+            .with_circuit_breaker(config(Duration::ZERO));
+
+        down.store(true, Ordering::SeqCst);
+        for _ in 0..2 {
+            let _ = driver.try_get_range("users!A1:B1").await;
+        }
+        down.store(false, Ordering::SeqCst);
+
+        driver
+            .try_get_range("users!A1:B1")
+            .await
+            .expect("Test: Expected trial call to pass");
+        assert!(!driver.is_circuit_open());
+    }
+}
@@ -0,0 +1,291 @@
+//////////////////////// Record & replay of API interactions ////////////////////////
+
+use crate::spread_sheet_driver::{SpreadSheetDriverError, SsdResult};
+use error_stack::{Report, ResultExt, bail};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::debug;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// Calls go to the API and every successful response is captured
+    Record,
+    /// Calls never leave the process, responses are served from the fixture
+    Replay,
+}
+
+/// Single captured API call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub operation: String,
+    pub request: Value,
+    pub response: Value,
+}
+
+#[derive(Debug, Default)]
+struct Tape {
+    interactions: Vec<Interaction>,
+    played: Vec<bool>,
+}
+
+/// VCR-like fixture storage backed by a JSON file.
+/// In replay mode each recorded interaction is served exactly once, in recording order
+/// for identical requests, so read-after-write scenarios replay deterministically.
+#[derive(Debug)]
+pub struct Cassette {
+    mode: CassetteMode,
+    path: PathBuf,
+    tape: Mutex<Tape>,
+}
+
+impl Cassette {
+    /// Creates an empty cassette which will be written to `path` on [`Cassette::save`]
+    pub fn record<P>(path: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        Self {
+            mode: CassetteMode::Record,
+            path: path.as_ref().to_path_buf(),
+            tape: Mutex::new(Tape::default()),
+        }
+    }
+
+    /// Loads previously recorded interactions from `path`
+    pub fn replay<P>(path: P) -> SsdResult<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)
+            .map_err(Report::new)
+            .change_context(SpreadSheetDriverError::FixtureError(format!(
+                "Can't read cassette {}",
+                path.display()
+            )))?;
+        let interactions: Vec<Interaction> = serde_json::from_str(&content)
+            .map_err(Report::new)
+            .change_context(SpreadSheetDriverError::FixtureError(format!(
+                "Can't parse cassette {}",
+                path.display()
+            )))?;
+
+        Ok(Self::replay_from(path, interactions))
+    }
+
+    /// Replays interactions provided in-memory (e.g. built in tests)
+    pub fn replay_from<P>(path: P, interactions: Vec<Interaction>) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        let played = vec![false; interactions.len()];
+        Self {
+            mode: CassetteMode::Replay,
+            path: path.as_ref().to_path_buf(),
+            tape: Mutex::new(Tape {
+                interactions,
+                played,
+            }),
+        }
+    }
+
+    pub fn mode(&self) -> CassetteMode {
+        self.mode
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn interactions(&self) -> Vec<Interaction> {
+        self.lock_tape().interactions.clone()
+    }
+
+    /// Writes recorded interactions to the cassette file. No-op in replay mode
+    pub fn save(&self) -> SsdResult<()> {
+        if self.mode == CassetteMode::Replay {
+            return Ok(());
+        }
+
+        let json = serde_json::to_string_pretty(&self.lock_tape().interactions)
+            .map_err(Report::new)
+            .change_context(SpreadSheetDriverError::FixtureError(
+                "Can't serialize interactions".to_string(),
+            ))?;
+
+        fs::write(&self.path, json)
+            .map_err(Report::new)
+            .change_context(SpreadSheetDriverError::FixtureError(format!(
+                "Can't write cassette {}",
+                self.path.display()
+            )))
+    }
+
+    /// Either performs the `call` and records its response, or serves the recorded one
+    pub(crate) async fn exchange<Resp, F, Fut>(
+        &self,
+        operation: &str,
+        request: Value,
+        call: F,
+    ) -> SsdResult<Resp>
+    where
+        Resp: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = SsdResult<Resp>>,
+    {
+        match self.mode {
+            CassetteMode::Replay => self.play(operation, &request),
+            CassetteMode::Record => {
+                let response = call().await?;
+                self.push(operation, request, &response)?;
+                Ok(response)
+            }
+        }
+    }
+
+    fn play<Resp>(&self, operation: &str, request: &Value) -> SsdResult<Resp>
+    where
+        Resp: DeserializeOwned,
+    {
+        let mut tape = self.lock_tape();
+        let Tape {
+            interactions,
+            played,
+        } = &mut *tape;
+
+        let Some((interaction, played)) = interactions
+            .iter()
+            .zip(played.iter_mut())
+            .find(|(i, played)| !**played && i.operation == operation && &i.request == request)
+        else {
+            bail!(SpreadSheetDriverError::ReplayMiss(format!(
+                "{operation} {request}"
+            )));
+        };
+
+        debug!("Replaying {} {}", operation, request);
+        *played = true;
+        serde_json::from_value(interaction.response.clone())
+            .map_err(Report::new)
+            .change_context(SpreadSheetDriverError::FixtureError(format!(
+                "Can't deserialize recorded response of {operation}"
+            )))
+    }
+
+    fn push<Resp>(&self, operation: &str, request: Value, response: &Resp) -> SsdResult<()>
+    where
+        Resp: Serialize,
+    {
+        let response = serde_json::to_value(response)
+            .map_err(Report::new)
+            .change_context(SpreadSheetDriverError::FixtureError(format!(
+                "Can't serialize response of {operation}"
+            )))?;
+
+        let mut tape = self.lock_tape();
+        tape.interactions.push(Interaction {
+            operation: operation.to_string(),
+            request,
+            response,
+        });
+        tape.played.push(true);
+        Ok(())
+    }
+
+    fn lock_tape(&self) -> std::sync::MutexGuard<'_, Tape> {
+        // Tape is never left in inconsistent state, so poisoning can be ignored
+        self.tape.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod cassette_tests {
+    use super::*;
+    use error_stack::report;
+    use serde_json::json;
+
+    fn interaction(request: Value, response: Value) -> Interaction {
+        Interaction {
+            operation: "values.get".to_string(),
+            request,
+            response,
+        }
+    }
+
+    #[tokio::test]
+    async fn replay__on_recorded_request__returns_response() {
+        let cassette = Cassette::replay_from(
+            "unused.json",
+            vec![interaction(json!({"range": "A1"}), json!(42))],
+        );
+
+        let result: SsdResult<i32> = cassette
+            .exchange("values.get", json!({"range": "A1"}), || async {
+                Err(report!(SpreadSheetDriverError::ApiError(
+                    "Replay must not call the API".to_string()
+                )))
+            })
+            .await;
+
+        assert_eq!(result.expect("Test: Expected recorded response"), 42);
+    }
+
+    #[tokio::test]
+    async fn replay__on_repeated_request__serves_in_recording_order() {
+        let cassette = Cassette::replay_from(
+            "unused.json",
+            vec![
+                interaction(json!({"range": "A1"}), json!(1)),
+                interaction(json!({"range": "A1"}), json!(2)),
+            ],
+        );
+
+        for expected in [1, 2] {
+            let actual: i32 = cassette
+                .exchange("values.get", json!({"range": "A1"}), || async { Ok(0) })
+                .await
+                .expect("Test: Expected recorded response");
+            assert_eq!(actual, expected);
+        }
+
+        let exhausted: SsdResult<i32> = cassette
+            .exchange("values.get", json!({"range": "A1"}), || async { Ok(0) })
+            .await;
+        assert!(exhausted.is_err());
+    }
+
+    #[tokio::test]
+    async fn replay__on_unknown_request__err() {
+        let cassette = Cassette::replay_from("unused.json", vec![]);
+
+        let result: SsdResult<i32> = cassette
+            .exchange("values.get", json!({"range": "B2"}), || async { Ok(0) })
+            .await;
+
+        let err = result.expect_err("Test: Expected replay miss");
+        assert!(matches!(
+            err.current_context(),
+            SpreadSheetDriverError::ReplayMiss(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn record__on_success__captures_interaction() {
+        let cassette = Cassette::record("unused.json");
+
+        let result: SsdResult<i32> = cassette
+            .exchange("values.get", json!({"range": "A1"}), || async { Ok(7) })
+            .await;
+
+        assert_eq!(result.expect("Test: Expected response"), 7);
+        assert_eq!(
+            cassette.interactions(),
+            vec![interaction(json!({"range": "A1"}), json!(7))]
+        );
+    }
+}
@@ -0,0 +1,436 @@
+//////////////////////// Cell level helpers ////////////////////////
+
+use crate::mapper::sheet_cell::SheetRawCellSerde;
+use crate::mapper::sheet_row::SheetRowExt;
+use crate::spread_sheet_driver::{
+    IntoStrVec, SpreadSheetDriver, SpreadSheetDriverError, SsdResult,
+};
+use crate::types::{
+    A1CellId, A1Range, InputMode, Letters, MajorDimension, ReadOptions, SheetA1CellId, SheetA1Range,
+};
+use error_stack::ResultExt;
+use google_sheets4::api::BatchUpdateValuesResponse;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::num::NonZero;
+use tracing::debug;
+
+/// Rows read per request while looking for an empty cell
+const SCAN_WINDOW: u32 = 500;
+
+impl SpreadSheetDriver {
+    /// Reads and parses a single cell, `None` if it is empty
+    pub async fn try_get_cell<T>(&self, cell: &SheetA1CellId) -> SsdResult<Option<T>>
+    where
+        T: SheetRawCellSerde,
+    {
+        let row = self
+            .try_get_range(cell_range(cell))
+            .await?
+            .into_vec()
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+
+        row.parse_optional_cell(0, "cell")
+            .change_context_lazy(|| SpreadSheetDriverError::ParseError(format!("{cell:?}")))
+    }
+
+    /// Writes a single value as if it was typed into the cell
+    pub async fn try_set_cell<V>(&self, cell: &SheetA1CellId, value: V) -> SsdResult<()>
+    where
+        V: Into<Value>,
+    {
+        self.try_write_range_as(
+            &cell_range(cell).to_string(),
+            vec![vec![value.into()]],
+            InputMode::UserEntered,
+        )
+        .await
+    }
+
+    /// 1-based number of the first row at or below `from_row` with an empty cell in `column`.
+    /// Reads only that column, a window of rows at a time
+    pub async fn try_find_next_empty_row(
+        &self,
+        sheet: &str,
+        column: &Letters,
+        from_row: u32,
+    ) -> SsdResult<u32> {
+        let mut first = from_row.max(1);
+        loop {
+            let range = column_range(sheet, column, first, SCAN_WINDOW);
+            let cells: Vec<Option<Value>> = self
+                .try_get_range(&range)
+                .await?
+                .into_vec()
+                .into_iter()
+                .map(|row| row.into_iter().next())
+                .collect();
+
+            if let Some(offset) = cells.iter().position(|cell| is_blank(cell.as_ref())) {
+                return Ok(first + offset as u32);
+            }
+            // Trailing empty rows are not returned
+            if (cells.len() as u32) < SCAN_WINDOW {
+                return Ok(first + cells.len() as u32);
+            }
+            first += SCAN_WINDOW;
+        }
+    }
+
+    /// Cells of the column from `from_row` (1-based) down to the last non-empty one,
+    /// `None` for empty cells in between. Only the column is read, a window of rows at a time,
+    /// each window as a single column-major `Vec`
+    pub async fn read_column_deserialized<T>(
+        &self,
+        sheet: &str,
+        column: &Letters,
+        from_row: u32,
+    ) -> SsdResult<Vec<Option<T>>>
+    where
+        T: SheetRawCellSerde,
+    {
+        let options = ReadOptions {
+            major_dimension: MajorDimension::Columns,
+            ..ReadOptions::default()
+        };
+        let first_row = from_row.max(1);
+        let mut cells: Vec<Value> = vec![];
+        loop {
+            let first = first_row + cells.len() as u32;
+            let range = column_range(sheet, column, first, SCAN_WINDOW);
+            let window: Vec<Value> = self
+                .try_get_range_with(&range, &options)
+                .await?
+                .into_vec()
+                .into_iter()
+                .next()
+                .unwrap_or_default();
+            let full = window.len() as u32 == SCAN_WINDOW;
+            cells.extend(window);
+            if !full {
+                break;
+            }
+        }
+
+        cells
+            .into_iter()
+            .enumerate()
+            .map(|(i, cell)| {
+                vec![cell]
+                    .parse_optional_cell(0, "cell")
+                    .change_context_lazy(|| {
+                        SpreadSheetDriverError::ParseError(format!(
+                            "{sheet}!{column}{}",
+                            first_row + i as u32
+                        ))
+                    })
+            })
+            .collect()
+    }
+}
+
+/// Single cell writes collected to be sent together, e.g. a status cell per row.
+/// A later write to the same cell replaces the earlier one
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CellWriteBuffer {
+    cells: BTreeMap<SheetA1CellId, Value>,
+}
+
+impl CellWriteBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set<V>(&mut self, cell: SheetA1CellId, value: V)
+    where
+        V: Into<Value>,
+    {
+        self.cells.insert(cell, value.into());
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Cells merged into rectangles: adjacent cells of a row first, then rows spanning the
+    /// same columns one under another. A column of statuses becomes a single range
+    pub fn coalesced(&self) -> Vec<(SheetA1Range, Vec<Vec<Value>>)> {
+        // (sheet, first column, last column) -> rectangle growing down, 1-based
+        let mut open: BTreeMap<(&str, u32, u32), (u32, Vec<Vec<Value>>)> = BTreeMap::new();
+        let mut done = vec![];
+        let mut close = |key: (&str, u32, u32), (first_row, rows): (u32, Vec<Vec<Value>>)| {
+            let (sheet, first_column, last_column) = key;
+            let cell = |column: u32, row: u32| {
+                A1CellId::new(
+                    Letters::from_column_number(column).expect("Expected column of a cell"),
+                    NonZero::new(row).expect("Expected a non-zero row number"),
+                )
+            };
+            let last_row = first_row + rows.len() as u32 - 1;
+            let range = A1Range::new(cell(first_column, first_row), cell(last_column, last_row));
+            done.push((SheetA1Range::new(sheet, range), rows));
+        };
+
+        // Cells are ordered by sheet and then row by row, so runs of a row come in order
+        let mut runs: Vec<(&str, u32, u32, u32, Vec<Value>)> = vec![];
+        for (cell, value) in &self.cells {
+            let (row, column) = (cell.cell.row.get(), cell.cell.col.column_number());
+            match runs.last_mut() {
+                Some((sheet, run_row, _, last, values))
+                    if *sheet == cell.sheet_name && *run_row == row && *last + 1 == column =>
+                {
+                    *last = column;
+                    values.push(value.clone());
+                }
+                _ => runs.push((
+                    cell.sheet_name.as_str(),
+                    row,
+                    column,
+                    column,
+                    vec![value.clone()],
+                )),
+            }
+        }
+
+        for (sheet, row, first, last, values) in runs {
+            let key = (sheet, first, last);
+            match open.remove(&key) {
+                Some((first_row, mut rows)) if first_row + rows.len() as u32 == row => {
+                    rows.push(values);
+                    open.insert(key, (first_row, rows));
+                }
+                previous => {
+                    if let Some(previous) = previous {
+                        close(key, previous);
+                    }
+                    open.insert(key, (row, vec![values]));
+                }
+            }
+        }
+        for (key, rectangle) in open {
+            close(key, rectangle);
+        }
+        done.sort_by_key(|(range, _)| range.start());
+        done
+    }
+}
+
+impl SpreadSheetDriver {
+    /// Writes the buffered cells coalesced into ranges (see [`CellWriteBuffer::coalesced`])
+    /// with one `values.batchUpdate`, so a cell per row costs a single request.
+    /// The buffer is emptied once the write succeeds
+    pub async fn try_flush_cells(
+        &self,
+        buffer: &mut CellWriteBuffer,
+    ) -> SsdResult<BatchUpdateValuesResponse> {
+        if buffer.is_empty() {
+            return Ok(BatchUpdateValuesResponse::default());
+        }
+        let ranges = buffer.coalesced();
+        debug!(
+            "Writing {} buffered cells as {} ranges",
+            buffer.len(),
+            ranges.len()
+        );
+        let response = self.try_write_ranges(ranges).await?;
+        buffer.cells.clear();
+        Ok(response)
+    }
+}
+
+/// 1x1 range of the cell
+pub(crate) fn cell_range(cell: &SheetA1CellId) -> SheetA1Range {
+    SheetA1Range::new(
+        &cell.sheet_name,
+        A1Range::new(cell.cell.clone(), cell.cell.clone()),
+    )
+}
+
+fn column_range(sheet: &str, column: &Letters, first_row: u32, rows: u32) -> SheetA1Range {
+    let row = |number: u32| NonZero::new(number).expect("Expected a non-zero row number");
+    SheetA1Range::new(
+        sheet,
+        A1Range::new(
+            A1CellId::new(column.clone(), row(first_row)),
+            A1CellId::new(column.clone(), row(first_row + rows - 1)),
+        ),
+    )
+}
+
+fn is_blank(cell: Option<&Value>) -> bool {
+    match cell {
+        None | Some(Value::Null) => true,
+        Some(Value::String(s)) => s.is_empty(),
+        Some(_) => false,
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod cells_tests {
+    use super::*;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+
+    fn driver(rows: Vec<Vec<Value>>) -> SpreadSheetDriver {
+        let backend = MemoryBackend::new();
+        backend.workbook().set_sheet("users", rows);
+        SpreadSheetDriver::with_backend("document".to_string(), backend)
+    }
+
+    #[tokio::test]
+    async fn try_set_cell__then_get__typed_value() {
+        let driver = driver(vec![]);
+        let cell = SheetA1CellId::from_primitives("config", "B", 2);
+
+        let empty: Option<u32> = driver
+            .try_get_cell(&cell)
+            .await
+            .expect("Test: Expected empty cell to be read");
+        assert_eq!(empty, None);
+
+        driver
+            .try_set_cell(&cell, "42")
+            .await
+            .expect("Test: Expected cell to be written");
+        let value: Option<u32> = driver
+            .try_get_cell(&cell)
+            .await
+            .expect("Test: Expected cell to be read");
+        assert_eq!(value, Some(42));
+
+        let err = driver
+            .try_get_cell::<bool>(&cell)
+            .await
+            .expect_err("Test: Expected number not to parse as bool");
+        assert!(matches!(
+            err.current_context(),
+            SpreadSheetDriverError::ParseError(_)
+        ));
+    }
+
+    #[test]
+    fn coalesced__cell_per_row_and_adjacent_cells__rectangles() {
+        let mut buffer = CellWriteBuffer::new();
+        for row in 2..=4 {
+            buffer.set(
+                SheetA1CellId::from_primitives("users", "C", row),
+                format!("done {row}"),
+            );
+        }
+        buffer.set(SheetA1CellId::from_primitives("users", "A", 9), "x");
+        buffer.set(SheetA1CellId::from_primitives("users", "B", 9), "y");
+        buffer.set(SheetA1CellId::from_primitives("orders", "A", 1), 1);
+        buffer.set(SheetA1CellId::from_primitives("users", "C", 6), "late");
+        buffer.set(SheetA1CellId::from_primitives("users", "C", 6), "later");
+
+        let ranges: Vec<(String, Vec<Vec<Value>>)> = buffer
+            .coalesced()
+            .into_iter()
+            .map(|(range, values)| (range.to_string(), values))
+            .collect();
+
+        assert_eq!(
+            ranges,
+            vec![
+                ("orders!A1:A1".to_string(), vec![vec![Value::from(1)]]),
+                (
+                    "users!C2:C4".to_string(),
+                    vec![
+                        vec![Value::from("done 2")],
+                        vec![Value::from("done 3")],
+                        vec![Value::from("done 4")],
+                    ]
+                ),
+                ("users!C6:C6".to_string(), vec![vec![Value::from("later")]]),
+                (
+                    "users!A9:B9".to_string(),
+                    vec![vec![Value::from("x"), Value::from("y")]]
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn try_flush_cells__buffered_cells__written_and_emptied() {
+        let driver = driver(vec![]);
+        let mut buffer = CellWriteBuffer::new();
+        buffer.set(SheetA1CellId::from_primitives("users", "B", 1), "active");
+        buffer.set(SheetA1CellId::from_primitives("users", "B", 2), "blocked");
+
+        let response = driver
+            .try_flush_cells(&mut buffer)
+            .await
+            .expect("Test: Expected cells to be written");
+
+        assert!(buffer.is_empty());
+        assert_eq!(response.responses.map(|r| r.len()), Some(1));
+        let status: Option<String> = driver
+            .try_get_cell(&SheetA1CellId::from_primitives("users", "B", 2))
+            .await
+            .expect("Test: Expected cell to be read");
+        assert_eq!(status.as_deref(), Some("blocked"));
+    }
+
+    fn column(letters: &str) -> Letters {
+        Letters::from_valid(letters.to_string())
+    }
+
+    #[tokio::test]
+    async fn try_find_next_empty_row__gap_in_column__first_gap() {
+        let driver = driver(vec![
+            vec![Value::from("id"), Value::from("name")],
+            vec![Value::from("1"), Value::from("Joe")],
+            vec![Value::from(""), Value::from("orphan")],
+            vec![Value::from("3"), Value::from("Jane")],
+        ]);
+
+        let row = driver
+            .try_find_next_empty_row("users", &column("A"), 1)
+            .await
+            .expect("Test: Expected empty row");
+        assert_eq!(row, 3);
+
+        let row = driver
+            .try_find_next_empty_row("users", &column("B"), 1)
+            .await
+            .expect("Test: Expected empty row");
+        assert_eq!(row, 5);
+    }
+
+    #[tokio::test]
+    async fn read_column_deserialized__gaps_and_header__typed_cells() {
+        let driver = driver(vec![
+            vec![Value::from("id"), Value::from("name")],
+            vec![Value::from(1), Value::from("Joe")],
+            vec![Value::from(""), Value::from("orphan")],
+            vec![Value::from(3), Value::from("Jane")],
+        ]);
+
+        let ids: Vec<Option<u32>> = driver
+            .read_column_deserialized("users", &column("A"), 2)
+            .await
+            .expect("Test: Expected column to be read");
+
+        assert_eq!(ids, vec![Some(1), None, Some(3)]);
+    }
+
+    #[tokio::test]
+    async fn try_find_next_empty_row__column_longer_than_window__next_window_read() {
+        let rows = (0..SCAN_WINDOW + 2)
+            .map(|i| vec![Value::from(i.to_string())])
+            .collect();
+        let driver = driver(rows);
+
+        let row = driver
+            .try_find_next_empty_row("users", &column("A"), 2)
+            .await
+            .expect("Test: Expected empty row");
+        assert_eq!(row, SCAN_WINDOW + 3);
+    }
+}
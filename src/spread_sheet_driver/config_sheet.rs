@@ -0,0 +1,398 @@
+//////////////////////// Key-value config stored in a sheet ////////////////////////
+
+use crate::mapper::sheet_cell::SheetRawCellSerde;
+use crate::mapper::sheet_row::SheetRowExt;
+use crate::spread_sheet_driver::{
+    IntoStrVec, SharedSpreadSheetDriver, SpreadSheetDriverError, SsdResult,
+};
+use crate::types::{A1CellId, A1Range, InputMode, SheetA1CellId, SheetA1Range};
+use error_stack::{ResultExt, bail};
+use serde_json::Value;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Value of a key that was changed, added (`old` is `None`) or removed (`new` is `None`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigChange {
+    pub key: String,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+type ChangeListener = Box<dyn Fn(&ConfigChange) + Send + Sync>;
+
+#[derive(Debug, Clone, PartialEq)]
+struct ConfigEntry {
+    /// 1-based sheet row
+    row: u32,
+    key: String,
+    value: Value,
+}
+
+struct Cache {
+    read_at: Instant,
+    entries: Vec<ConfigEntry>,
+}
+
+/// Two-column (key, value) table with typed access: `config.get::<u32>("max_retries")`.
+/// Rows with a blank key are ignored
+pub struct ConfigSheet {
+    driver: SharedSpreadSheetDriver,
+    start: SheetA1CellId,
+    rows: u32,
+    cache_ttl: Option<Duration>,
+    cache: Mutex<Option<Cache>>,
+    listeners: Vec<ChangeListener>,
+}
+
+impl ConfigSheet {
+    /// `start` is the top left (first key) cell, `rows` is the capacity of the table
+    pub fn new(driver: SharedSpreadSheetDriver, start: SheetA1CellId, rows: u32) -> Self {
+        Self {
+            driver,
+            start,
+            rows,
+            cache_ttl: None,
+            cache: Mutex::new(None),
+            listeners: vec![],
+        }
+    }
+
+    /// Reuses the last read for `ttl` instead of reading the sheet on every access
+    pub fn with_cache(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = Some(ttl);
+        self
+    }
+
+    /// Called for every key whose value differs from the previous read or is changed by `set`.
+    /// The first read only establishes the baseline, so it notifies nothing
+    pub fn on_change<F>(mut self, listener: F) -> Self
+    where
+        F: Fn(&ConfigChange) + Send + Sync + 'static,
+    {
+        self.listeners.push(Box::new(listener));
+        self
+    }
+
+    pub async fn get<T>(&self, key: &str) -> SsdResult<Option<T>>
+    where
+        T: SheetRawCellSerde,
+    {
+        let entries = self.entries().await?;
+        let Some(entry) = entries.iter().find(|e| e.key == key) else {
+            return Ok(None);
+        };
+
+        vec![entry.value.clone()]
+            .parse_optional_cell(0, "value")
+            .change_context_lazy(|| SpreadSheetDriverError::ParseError(format!("Config '{key}'")))
+    }
+
+    /// All keys with their raw values, in the sheet order
+    pub async fn values(&self) -> SsdResult<Vec<(String, Value)>> {
+        Ok(self
+            .entries()
+            .await?
+            .into_iter()
+            .map(|e| (e.key, e.value))
+            .collect())
+    }
+
+    /// Overwrites the value of `key` or adds the key below the existing ones
+    pub async fn set<V>(&self, key: &str, value: V) -> SsdResult<()>
+    where
+        V: Into<Value>,
+    {
+        let value = value.into();
+        let entries = self.read().await?;
+        let existing = entries.iter().find(|e| e.key == key);
+
+        let (row, cells) = match existing {
+            Some(entry) => (entry.row, vec![value.clone()]),
+            None => {
+                let row = self.free_row(&entries)?;
+                (row, vec![Value::String(key.to_string()), value.clone()])
+            }
+        };
+        let first_col = match existing {
+            Some(_) => self.start.cell.col.clone() + 1,
+            None => self.start.cell.col.clone(),
+        };
+        let first = A1CellId::from_primitives(&first_col, row);
+        let last = first.delta(cells.len() as i32 - 1, 0);
+        let range = SheetA1Range::new(&self.start.sheet_name, A1Range::new(first, last));
+
+        self.driver
+            .lock()
+            .await
+            .try_write_range_as(&range.to_string(), vec![cells], InputMode::UserEntered)
+            .await?;
+
+        let change = ConfigChange {
+            key: key.to_string(),
+            old: existing.map(|e| e.value.clone()),
+            new: Some(value.clone()),
+        };
+        let mut updated = entries.clone();
+        match updated.iter_mut().find(|e| e.key == key) {
+            Some(entry) => entry.value = value,
+            None => updated.push(ConfigEntry {
+                row,
+                key: key.to_string(),
+                value,
+            }),
+        }
+        self.store(updated);
+        if change.old != change.new {
+            self.notify(&change);
+        }
+        Ok(())
+    }
+
+    /// Reads the sheet regardless of the cache, notifying about changes made outside
+    pub async fn refresh(&self) -> SsdResult<()> {
+        self.read().await.map(|_| ())
+    }
+
+    async fn entries(&self) -> SsdResult<Vec<ConfigEntry>> {
+        if let Some(ttl) = self.cache_ttl
+            && let Some(cache) = self.cache().as_ref()
+            && cache.read_at.elapsed() < ttl
+        {
+            return Ok(cache.entries.clone());
+        }
+        self.read().await
+    }
+
+    async fn read(&self) -> SsdResult<Vec<ConfigEntry>> {
+        let first = self.start.cell.clone();
+        let last = first.delta(1, self.rows as i32 - 1);
+        let range = SheetA1Range::new(&self.start.sheet_name, A1Range::new(first, last));
+        let rows = self
+            .driver
+            .lock()
+            .await
+            .try_get_range(&range)
+            .await?
+            .into_vec();
+
+        let entries: Vec<ConfigEntry> = rows
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, mut row)| {
+                let key = match row.first() {
+                    Some(Value::String(key)) if !key.trim().is_empty() => key.trim().to_string(),
+                    _ => return None,
+                };
+                row.resize(2, Value::Null);
+                Some(ConfigEntry {
+                    row: self.start.cell.row.get() + i as u32,
+                    key,
+                    value: row.swap_remove(1),
+                })
+            })
+            .collect();
+
+        let previous = self.cache().take().map(|c| c.entries);
+        if let Some(previous) = previous {
+            for change in diff(&previous, &entries) {
+                self.notify(&change);
+            }
+        }
+        self.store(entries.clone());
+        Ok(entries)
+    }
+
+    fn free_row(&self, entries: &[ConfigEntry]) -> SsdResult<u32> {
+        let first_row = self.start.cell.row.get();
+        let row = entries.iter().map(|e| e.row + 1).max().unwrap_or(first_row);
+        if row >= first_row + self.rows {
+            bail!(SpreadSheetDriverError::InvalidArgument(format!(
+                "Config table at {:?} is full ({} rows)",
+                self.start, self.rows
+            )));
+        }
+        Ok(row)
+    }
+
+    fn cache(&self) -> MutexGuard<'_, Option<Cache>> {
+        self.cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn store(&self, entries: Vec<ConfigEntry>) {
+        *self.cache() = Some(Cache {
+            read_at: Instant::now(),
+            entries,
+        });
+    }
+
+    fn notify(&self, change: &ConfigChange) {
+        debug!("Config change: {:?}", change);
+        for listener in &self.listeners {
+            listener(change);
+        }
+    }
+}
+
+impl Debug for ConfigSheet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConfigSheet")
+            .field("start", &self.start)
+            .field("rows", &self.rows)
+            .field("cache_ttl", &self.cache_ttl)
+            .field("listeners", &self.listeners.len())
+            .finish()
+    }
+}
+
+fn diff(before: &[ConfigEntry], after: &[ConfigEntry]) -> Vec<ConfigChange> {
+    let find = |entries: &[ConfigEntry], key: &str| {
+        entries
+            .iter()
+            .find(|e| e.key == key)
+            .map(|e| e.value.clone())
+    };
+
+    let mut changes: Vec<ConfigChange> = after
+        .iter()
+        .map(|entry| ConfigChange {
+            key: entry.key.clone(),
+            old: find(before, &entry.key),
+            new: Some(entry.value.clone()),
+        })
+        .filter(|change| change.old != change.new)
+        .collect();
+    changes.extend(
+        before
+            .iter()
+            .filter(|entry| find(after, &entry.key).is_none())
+            .map(|entry| ConfigChange {
+                key: entry.key.clone(),
+                old: Some(entry.value.clone()),
+                new: None,
+            }),
+    );
+    changes
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod config_sheet_tests {
+    use super::*;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use crate::testing::fixtures::shared_driver;
+    use std::sync::Arc;
+
+    fn config_sheet(rows: Vec<Vec<Value>>) -> (ConfigSheet, SharedSpreadSheetDriver) {
+        let backend = MemoryBackend::new();
+        backend.workbook().set_sheet("config", rows);
+        let driver = shared_driver(backend);
+        let config = ConfigSheet::new(
+            driver.clone(),
+            SheetA1CellId::from_primitives("config", "A", 1),
+            20,
+        );
+        (config, driver)
+    }
+
+    fn entry(key: &str, value: &str) -> Vec<Value> {
+        vec![Value::from(key), Value::from(value)]
+    }
+
+    #[tokio::test]
+    async fn get__typed_values__ok() {
+        let (config, _) = config_sheet(vec![
+            entry("max_retries", "3"),
+            entry("", "ignored"),
+            entry("greeting", "hello"),
+        ]);
+
+        let retries: Option<u32> = config
+            .get("max_retries")
+            .await
+            .expect("Test: Expected value");
+        let greeting: Option<String> = config.get("greeting").await.expect("Test: Expected value");
+        let missing: Option<u32> = config.get("timeout").await.expect("Test: Expected value");
+
+        assert_eq!(retries, Some(3));
+        assert_eq!(greeting, Some("hello".to_string()));
+        assert_eq!(missing, None);
+        assert!(config.get::<u32>("greeting").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn set__existing_and_new_keys__written_and_notified() {
+        let changes = Arc::new(Mutex::new(vec![]));
+        let sink = changes.clone();
+        let (config, driver) = config_sheet(vec![entry("max_retries", "3")]);
+        let config = config.on_change(move |change| sink.lock().unwrap().push(change.clone()));
+
+        config
+            .set("max_retries", "5")
+            .await
+            .expect("Test: Expected set");
+        config
+            .set("timeout", "30")
+            .await
+            .expect("Test: Expected set");
+
+        let rows = driver
+            .lock()
+            .await
+            .try_get_range("config!A1:B2")
+            .await
+            .expect("Test: Expected read")
+            .into_vec();
+        assert_eq!(
+            rows,
+            vec![entry("max_retries", "5"), entry("timeout", "30")]
+        );
+
+        let keys: Vec<String> = changes
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|c| c.key.clone())
+            .collect();
+        assert_eq!(keys, vec!["max_retries", "timeout"]);
+    }
+
+    #[tokio::test]
+    async fn refresh__with_cache__picks_up_outside_edits() {
+        let changes = Arc::new(Mutex::new(vec![]));
+        let sink = changes.clone();
+        let (config, driver) = config_sheet(vec![entry("mode", "dry-run")]);
+        let config = config
+            .with_cache(Duration::from_secs(3600))
+            .on_change(move |change| sink.lock().unwrap().push(change.clone()));
+
+        let mode: Option<String> = config.get("mode").await.expect("Test: Expected value");
+        assert_eq!(mode.as_deref(), Some("dry-run"));
+
+        driver
+            .lock()
+            .await
+            .try_write_range("config!B1:B1", vec![vec![Value::from("live")]])
+            .await
+            .expect("Test: Expected outside edit");
+
+        let cached: Option<String> = config.get("mode").await.expect("Test: Expected value");
+        assert_eq!(cached.as_deref(), Some("dry-run"));
+
+        config.refresh().await.expect("Test: Expected refresh");
+        let fresh: Option<String> = config.get("mode").await.expect("Test: Expected value");
+        assert_eq!(fresh.as_deref(), Some("live"));
+        assert_eq!(
+            *changes.lock().unwrap(),
+            vec![ConfigChange {
+                key: "mode".to_string(),
+                old: Some(Value::from("dry-run")),
+                new: Some(Value::from("live")),
+            }]
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//////////////////////// CSV import into a sheet range ////////////////////////
+
+use crate::mapper::sheet_row::SheetRow;
+use crate::spread_sheet_driver::{SpreadSheetDriver, SpreadSheetDriverError, SsdResult};
+use crate::types::{A1Range, InputMode, SheetA1CellId, SheetA1Range};
+use error_stack::{Report, ResultExt, bail};
+use serde_json::Value;
+use std::io::Read;
+use tracing::debug;
+
+#[derive(Debug, Clone)]
+pub struct CsvImportOptions {
+    /// RAW stores every field as text, USER_ENTERED lets Sheets infer numbers, dates and formulas
+    pub input_mode: InputMode,
+    /// Skip the first record of the CSV (e.g. when the sheet already has its own header row)
+    pub skip_header: bool,
+    pub delimiter: u8,
+    /// Number of rows written per API call
+    pub chunk_rows: usize,
+}
+
+impl Default for CsvImportOptions {
+    fn default() -> Self {
+        Self {
+            input_mode: InputMode::UserEntered,
+            skip_header: false,
+            delimiter: b',',
+            chunk_rows: 1000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvImportSummary {
+    pub rows: usize,
+    pub chunks: usize,
+    /// Whole written area, as wide as the longest record. None if the CSV had no records
+    pub range: Option<SheetA1Range>,
+}
+
+impl SpreadSheetDriver {
+    /// Writes CSV records into the sheet starting at `start`, `options.chunk_rows` rows per request.
+    /// Existing values in the target area are overwritten
+    pub async fn import_csv<R>(
+        &self,
+        reader: R,
+        start: &SheetA1CellId,
+        options: CsvImportOptions,
+    ) -> SsdResult<CsvImportSummary>
+    where
+        R: Read,
+    {
+        if options.chunk_rows == 0 {
+            bail!(SpreadSheetDriverError::InvalidArgument(
+                "chunk_rows must be greater than 0".to_string()
+            ));
+        }
+
+        let rows = read_csv_rows(reader, &options)?;
+        let mut summary = CsvImportSummary {
+            rows: rows.len(),
+            chunks: 0,
+            range: (!rows.is_empty()).then(|| chunk_range(start, 0, &rows)),
+        };
+
+        for (i, chunk) in rows.chunks(options.chunk_rows).enumerate() {
+            let range = chunk_range(start, i * options.chunk_rows, chunk);
+            debug!("Importing CSV chunk #{} into {}", i, range);
+
+            self.try_write_range_as(
+                range.to_string().as_str(),
+                chunk.to_vec(),
+                options.input_mode.clone(),
+            )
+            .await
+            .attach_printable_lazy(|| format!("CSV chunk #{i} into {range}"))?;
+            summary.chunks += 1;
+        }
+
+        Ok(summary)
+    }
+}
+
+fn read_csv_rows<R>(reader: R, options: &CsvImportOptions) -> SsdResult<Vec<SheetRow>>
+where
+    R: Read,
+{
+    csv::ReaderBuilder::new()
+        .has_headers(options.skip_header)
+        .delimiter(options.delimiter)
+        .flexible(true)
+        .from_reader(reader)
+        .records()
+        .enumerate()
+        .map(|(i, record)| {
+            record
+                .map(|r| {
+                    r.iter()
+                        .map(|field| Value::String(field.to_string()))
+                        .collect()
+                })
+                .map_err(Report::new)
+                .change_context(SpreadSheetDriverError::ParseError(format!(
+                    "CSV record #{i}"
+                )))
+        })
+        .collect()
+}
+
+/// Range covered by the chunk which starts `row_offset` rows below `start`
+fn chunk_range(start: &SheetA1CellId, row_offset: usize, chunk: &[SheetRow]) -> SheetA1Range {
+    let width = chunk.iter().map(Vec::len).max().unwrap_or(1).max(1) as i32;
+    let from = start.cell.delta(0, row_offset as i32);
+    let to = from.delta(width - 1, chunk.len() as i32 - 1);
+    SheetA1Range::new(&start.sheet_name, A1Range::new(from, to))
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod csv_import_tests {
+    use super::*;
+
+    #[test]
+    fn read_csv_rows__quoted_and_ragged__ok() {
+        let csv = "id,name\n1,\"Doe, Joe\"\n2\n";
+        let rows = read_csv_rows(csv.as_bytes(), &CsvImportOptions::default()).unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                vec![Value::from("id"), Value::from("name")],
+                vec![Value::from("1"), Value::from("Doe, Joe")],
+                vec![Value::from("2")],
+            ]
+        );
+    }
+
+    #[test]
+    fn read_csv_rows__skip_header__ok() {
+        let options = CsvImportOptions {
+            skip_header: true,
+            delimiter: b';',
+            ..Default::default()
+        };
+        let rows = read_csv_rows("id;name\n1;Joe\n".as_bytes(), &options).unwrap();
+        assert_eq!(rows, vec![vec![Value::from("1"), Value::from("Joe")]]);
+    }
+
+    #[test]
+    fn chunk_range__second_chunk__ok() {
+        let start = SheetA1CellId::from_primitives("users", "B", 2);
+        let chunk = vec![vec![Value::from("1"), Value::from("Joe")]; 3];
+        let range = chunk_range(&start, 3, &chunk);
+        assert_eq!(range.to_string(), "users!B5:C7");
+    }
+}
@@ -0,0 +1,249 @@
+//////////////////////// Polars DataFrame conversions ////////////////////////
+
+use crate::mapper::sheet_row::SheetRow;
+use crate::spread_sheet_driver::{
+    IntoStrVec, SpreadSheetDriver, SpreadSheetDriverError, SsdResult,
+};
+use crate::types::{A1Range, InputMode, SheetA1CellId, SheetA1Range};
+use error_stack::{Report, ResultExt};
+use polars::prelude::{AnyValue, Column, DataFrame, NamedFrom, Series};
+use serde_json::Value;
+use std::collections::HashSet;
+
+impl SpreadSheetDriver {
+    /// Reads the range into a DataFrame. The first row is used as column names,
+    /// column dtypes are inferred from the values (Boolean, Int64, Float64, otherwise String).
+    /// Empty cells become nulls
+    pub async fn try_get_range_df<R>(&self, range: R) -> SsdResult<DataFrame>
+    where
+        R: ToString,
+    {
+        let rows = self.try_get_range(range).await?.into_vec();
+        rows_to_df(rows)
+    }
+
+    /// Writes the DataFrame with its header row starting at `start`.
+    /// Values are written as RAW, so strings aren't re-interpreted by Sheets. Nulls are written as empty cells
+    pub async fn write_df(&self, start: &SheetA1CellId, df: &DataFrame) -> SsdResult<()> {
+        let rows = df_to_rows(df)?;
+        let end = start
+            .cell
+            .delta(df.width().max(1) as i32 - 1, rows.len() as i32 - 1);
+        let range = SheetA1Range::new(&start.sheet_name, A1Range::new(start.cell.clone(), end));
+
+        self.try_write_range_as(range.to_string().as_str(), rows, InputMode::Raw)
+            .await
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ColumnKind {
+    Boolean,
+    Int,
+    Float,
+    Text,
+}
+
+fn rows_to_df(rows: Vec<SheetRow>) -> SsdResult<DataFrame> {
+    let mut rows = rows.into_iter();
+    let headers = rows.next().unwrap_or_default();
+    let data: Vec<SheetRow> = rows.collect();
+    let width = data
+        .iter()
+        .map(Vec::len)
+        .chain([headers.len()])
+        .max()
+        .unwrap_or_default();
+
+    let mut taken_names = HashSet::new();
+    let columns: Vec<Column> = (0..width)
+        .map(|col| {
+            let name = column_name(headers.get(col), col, &mut taken_names);
+            let cells: Vec<Option<&Value>> = data
+                .iter()
+                .map(|row| row.get(col).filter(|v| !is_blank(v)))
+                .collect();
+            build_series(&name, &cells).into()
+        })
+        .collect();
+
+    DataFrame::new(columns)
+        .map_err(Report::new)
+        .change_context(SpreadSheetDriverError::ParseError(
+            "Can't build DataFrame from rows".to_string(),
+        ))
+}
+
+/// Header text or `column_<index>` for blank headers. Duplicates get the column index as suffix
+fn column_name(header: Option<&Value>, col: usize, taken: &mut HashSet<String>) -> String {
+    let name = match header.filter(|v| !is_blank(v)) {
+        Some(Value::String(s)) => s.clone(),
+        Some(v) => v.to_string(),
+        None => format!("column_{col}"),
+    };
+    let name = if taken.contains(&name) {
+        format!("{name}_{col}")
+    } else {
+        name
+    };
+    taken.insert(name.clone());
+    name
+}
+
+fn infer_kind(cells: &[Option<&Value>]) -> ColumnKind {
+    let mut values = cells.iter().flatten().peekable();
+    if values.peek().is_none() {
+        return ColumnKind::Text;
+    }
+
+    let values: Vec<&Value> = values.copied().collect();
+    if values.iter().all(|v| v.is_boolean()) {
+        ColumnKind::Boolean
+    } else if values.iter().all(|v| v.is_i64()) {
+        ColumnKind::Int
+    } else if values.iter().all(|v| v.is_number()) {
+        ColumnKind::Float
+    } else {
+        ColumnKind::Text
+    }
+}
+
+fn build_series(name: &str, cells: &[Option<&Value>]) -> Series {
+    match infer_kind(cells) {
+        ColumnKind::Boolean => Series::new(
+            name.into(),
+            cells
+                .iter()
+                .map(|c| c.and_then(Value::as_bool))
+                .collect::<Vec<_>>(),
+        ),
+        ColumnKind::Int => Series::new(
+            name.into(),
+            cells
+                .iter()
+                .map(|c| c.and_then(Value::as_i64))
+                .collect::<Vec<_>>(),
+        ),
+        ColumnKind::Float => Series::new(
+            name.into(),
+            cells
+                .iter()
+                .map(|c| c.and_then(Value::as_f64))
+                .collect::<Vec<_>>(),
+        ),
+        ColumnKind::Text => Series::new(
+            name.into(),
+            cells
+                .iter()
+                .map(|c| c.map(stringify))
+                .collect::<Vec<Option<String>>>(),
+        ),
+    }
+}
+
+fn df_to_rows(df: &DataFrame) -> SsdResult<Vec<SheetRow>> {
+    let header: SheetRow = df
+        .get_column_names()
+        .into_iter()
+        .map(|name| Value::String(name.to_string()))
+        .collect();
+
+    let mut rows = Vec::with_capacity(df.height() + 1);
+    rows.push(header);
+    for i in 0..df.height() {
+        let row = df
+            .get_columns()
+            .iter()
+            .map(|column| column.get(i).map(any_value_to_json))
+            .collect::<Result<SheetRow, _>>()
+            .map_err(Report::new)
+            .change_context(SpreadSheetDriverError::ParseError(format!(
+                "Can't read DataFrame row #{i}"
+            )))?;
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+fn any_value_to_json(value: AnyValue) -> Value {
+    match value {
+        AnyValue::Null => Value::String(String::new()),
+        AnyValue::Boolean(b) => Value::Bool(b),
+        AnyValue::String(s) => Value::String(s.to_string()),
+        AnyValue::StringOwned(s) => Value::String(s.to_string()),
+        AnyValue::Int32(i) => Value::from(i),
+        AnyValue::Int64(i) => Value::from(i),
+        AnyValue::UInt32(i) => Value::from(i),
+        AnyValue::UInt64(i) => Value::from(i),
+        AnyValue::Float32(f) => float_to_json(f as f64),
+        AnyValue::Float64(f) => float_to_json(f),
+        other => Value::String(other.to_string()),
+    }
+}
+
+/// NaN and infinities are not representable in JSON, so they are written as empty cells
+fn float_to_json(f: f64) -> Value {
+    serde_json::Number::from_f64(f)
+        .map(Value::Number)
+        .unwrap_or_else(|| Value::String(String::new()))
+}
+
+fn is_blank(value: &Value) -> bool {
+    value.is_null() || value.as_str() == Some("")
+}
+
+fn stringify(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        _ => value.to_string(),
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod dataframe_tests {
+    use super::*;
+    use polars::prelude::DataType;
+    use serde_json::json;
+
+    fn rows(value: Value) -> Vec<SheetRow> {
+        serde_json::from_value(value).expect("Test: Expected rows")
+    }
+
+    #[test]
+    fn rows_to_df__infers_dtypes__ok() {
+        let df = rows_to_df(rows(json!([
+            ["id", "price", "active", "name"],
+            [1, 1.5, true, "Joe"],
+            [2, 2, false, ""],
+        ])))
+        .unwrap();
+
+        assert_eq!(df.shape(), (2, 4));
+        assert_eq!(df.column("id").unwrap().dtype(), &DataType::Int64);
+        assert_eq!(df.column("price").unwrap().dtype(), &DataType::Float64);
+        assert_eq!(df.column("active").unwrap().dtype(), &DataType::Boolean);
+        assert_eq!(df.column("name").unwrap().dtype(), &DataType::String);
+        assert_eq!(df.column("name").unwrap().null_count(), 1);
+    }
+
+    #[test]
+    fn rows_to_df__blank_and_duplicate_headers__ok() {
+        let df = rows_to_df(rows(json!([["id", "", "id"], [1, 2, 3]]))).unwrap();
+
+        let names: Vec<String> = df
+            .get_column_names()
+            .into_iter()
+            .map(|n| n.to_string())
+            .collect();
+        assert_eq!(names, vec!["id", "column_1", "id_2"]);
+    }
+
+    #[test]
+    fn df_to_rows__round_trip__ok() {
+        let input = rows(json!([["id", "name"], [1, "Joe"], [2, "John"]]));
+        let df = rows_to_df(input.clone()).unwrap();
+
+        assert_eq!(df_to_rows(&df).unwrap(), input);
+    }
+}
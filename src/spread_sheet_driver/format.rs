@@ -0,0 +1,295 @@
+//////////////////////// Values and formats in one batchUpdate ////////////////////////
+
+use crate::mapper::sheet_row::{MAX_EXACT_INTEGER, SheetRow};
+use crate::types::{InputMode, SheetGid};
+use google_sheets4::api::{
+    CellData, CellFormat, Color, ExtendedValue, GridCoordinate, GridRange, RepeatCellRequest,
+    Request, RowData, UpdateCellsRequest,
+};
+use google_sheets4::common::FieldMask;
+use serde_json::Value;
+use std::ops::Range;
+
+/// Writes `rows` from the 0-based `row` and `column` through `updateCells`, so the write can
+/// share a batch with formatting requests. The API doesn't parse the values of this request:
+/// with `InputMode::UserEntered` formulas, numbers and booleans are recognized here, anything
+/// else (dates included) is written as text
+pub fn write_cells_request(
+    sheet_id: SheetGid,
+    row: u32,
+    column: u32,
+    rows: &[SheetRow],
+    mode: InputMode,
+) -> Request {
+    let rows = rows
+        .iter()
+        .map(|row| RowData {
+            values: Some(
+                row.iter()
+                    .map(|value| CellData {
+                        user_entered_value: cell_value(value, mode),
+                        ..Default::default()
+                    })
+                    .collect(),
+            ),
+        })
+        .collect();
+    Request {
+        update_cells: Some(UpdateCellsRequest {
+            fields: Some(FieldMask::new(&["userEnteredValue"])),
+            rows: Some(rows),
+            start: Some(GridCoordinate {
+                sheet_id: Some(sheet_id.0),
+                row_index: Some(row as i32),
+                column_index: Some(column as i32),
+            }),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+/// Applies `format` to the 0-based rectangle. Only the properties set in the format are
+/// updated, so e.g. a background color and a number format applied one after another combine
+pub fn repeat_format_request(
+    sheet_id: SheetGid,
+    rows: Range<u32>,
+    columns: Range<u32>,
+    format: &CellFormat,
+) -> Request {
+    Request {
+        repeat_cell: Some(RepeatCellRequest {
+            cell: Some(CellData {
+                user_entered_format: Some(format.clone()),
+                ..Default::default()
+            }),
+            fields: Some(format_mask(format)),
+            range: Some(GridRange {
+                sheet_id: Some(sheet_id.0),
+                start_row_index: Some(rows.start as i32),
+                end_row_index: Some(rows.end as i32),
+                start_column_index: Some(columns.start as i32),
+                end_column_index: Some(columns.end as i32),
+            }),
+        }),
+        ..Default::default()
+    }
+}
+
+/// Sets the background of the 0-based rectangle to `color`, `None` clears it.
+/// Other format properties of the cells are kept
+pub fn background_request(
+    sheet_id: SheetGid,
+    rows: Range<u32>,
+    columns: Range<u32>,
+    color: Option<&Color>,
+) -> Request {
+    Request {
+        repeat_cell: Some(RepeatCellRequest {
+            cell: Some(CellData {
+                user_entered_format: Some(CellFormat {
+                    background_color: color.cloned(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            fields: Some(FieldMask::new(&["userEnteredFormat.backgroundColor"])),
+            range: Some(GridRange {
+                sheet_id: Some(sheet_id.0),
+                start_row_index: Some(rows.start as i32),
+                end_row_index: Some(rows.end as i32),
+                start_column_index: Some(columns.start as i32),
+                end_column_index: Some(columns.end as i32),
+            }),
+        }),
+        ..Default::default()
+    }
+}
+
+/// Typed value of the cell the way the input mode would store it, except integers from
+/// [`MAX_EXACT_INTEGER`] up: they are kept as text, as a number would lose their last digits
+pub fn cell_value(value: &Value, mode: InputMode) -> Option<ExtendedValue> {
+    let extended = match (value, mode) {
+        (Value::Null, _) => return None,
+        (Value::Bool(bool), _) => ExtendedValue {
+            bool_value: Some(*bool),
+            ..Default::default()
+        },
+        (Value::Number(number), _)
+            if !number.is_f64() && is_inexact_integer(&number.to_string()) =>
+        {
+            string_value(&number.to_string())
+        }
+        (Value::Number(number), _) => ExtendedValue {
+            number_value: number.as_f64(),
+            ..Default::default()
+        },
+        (Value::String(text), InputMode::UserEntered) if text.starts_with('=') => ExtendedValue {
+            formula_value: Some(text.clone()),
+            ..Default::default()
+        },
+        (Value::String(text), InputMode::UserEntered) => match text.trim() {
+            "TRUE" | "true" => ExtendedValue {
+                bool_value: Some(true),
+                ..Default::default()
+            },
+            "FALSE" | "false" => ExtendedValue {
+                bool_value: Some(false),
+                ..Default::default()
+            },
+            trimmed if is_inexact_integer(trimmed) => string_value(text),
+            trimmed => match trimmed.parse::<f64>() {
+                // Rust parses "NaN" and "inf" as well, the sheet keeps them as text
+                Ok(number) if number.is_finite() => ExtendedValue {
+                    number_value: Some(number),
+                    ..Default::default()
+                },
+                _ => string_value(text),
+            },
+        },
+        (Value::String(text), InputMode::Raw) => string_value(text),
+        (other, _) => string_value(&other.to_string()),
+    };
+    Some(extended)
+}
+
+fn is_inexact_integer(text: &str) -> bool {
+    text.parse::<i128>()
+        .is_ok_and(|integer| integer.unsigned_abs() >= MAX_EXACT_INTEGER as u128)
+}
+
+/// Rows as sent with the input mode. `USER_ENTERED` would parse integers from
+/// [`MAX_EXACT_INTEGER`] up into rounded numbers, so they get the `'` prefix which makes the
+/// sheet store them as text. `RAW` rows are sent as they are
+pub(crate) fn input_rows(rows: &[SheetRow], mode: InputMode) -> Vec<SheetRow> {
+    match mode {
+        InputMode::UserEntered => rows
+            .iter()
+            .map(|row| row.iter().map(user_entered_value).collect())
+            .collect(),
+        InputMode::Raw => rows.to_vec(),
+    }
+}
+
+fn user_entered_value(value: &Value) -> Value {
+    let text = match value {
+        Value::String(text) => text.clone(),
+        Value::Number(number) if !number.is_f64() => number.to_string(),
+        other => return other.clone(),
+    };
+    match is_inexact_integer(text.trim()) {
+        true => Value::String(format!("'{text}")),
+        false => value.clone(),
+    }
+}
+
+fn string_value(text: &str) -> ExtendedValue {
+    ExtendedValue {
+        string_value: Some(text.to_string()),
+        ..Default::default()
+    }
+}
+
+/// `userEnteredFormat.<property>` of every property set in the format
+fn format_mask(format: &CellFormat) -> FieldMask {
+    let properties: Vec<String> = match serde_json::to_value(format) {
+        Ok(Value::Object(properties)) => properties
+            .iter()
+            .filter(|(_, value)| !value.is_null())
+            .map(|(property, _)| format!("userEnteredFormat.{property}"))
+            .collect(),
+        _ => vec![],
+    };
+    match properties.is_empty() {
+        true => FieldMask::new(&["userEnteredFormat"]),
+        false => FieldMask::new(&properties),
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod format_tests {
+    use super::*;
+    use google_sheets4::api::NumberFormat;
+
+    #[test]
+    fn cell_value__user_entered_and_raw__typed_like_the_api() {
+        let number =
+            cell_value(&Value::from("42"), InputMode::UserEntered).expect("Test: Expected value");
+        let formula = cell_value(&Value::from("=A1*2"), InputMode::UserEntered)
+            .expect("Test: Expected value");
+        let raw = cell_value(&Value::from("=A1*2"), InputMode::Raw).expect("Test: Expected value");
+
+        assert_eq!(number.number_value, Some(42.0));
+        assert_eq!(formula.formula_value.as_deref(), Some("=A1*2"));
+        assert_eq!(raw.string_value.as_deref(), Some("=A1*2"));
+        assert!(cell_value(&Value::Null, InputMode::Raw).is_none());
+    }
+
+    #[test]
+    fn cell_value__nan_and_inf_text__kept_as_text() {
+        for text in ["NaN", "Nan", "inf", "Infinity"] {
+            let value = cell_value(&Value::from(text), InputMode::UserEntered)
+                .expect("Test: Expected value");
+
+            assert_eq!(value.string_value.as_deref(), Some(text));
+            assert_eq!(value.number_value, None);
+        }
+    }
+
+    #[test]
+    fn cell_value__integer_beyond_exact__kept_as_text() {
+        let id = cell_value(&Value::from("1234567890123456789"), InputMode::UserEntered)
+            .expect("Test: Expected value");
+        let ambiguous = cell_value(&Value::from("9007199254740992"), InputMode::UserEntered)
+            .expect("Test: Expected value");
+        let exact = cell_value(&Value::from("9007199254740991"), InputMode::UserEntered)
+            .expect("Test: Expected value");
+
+        assert_eq!(id.string_value.as_deref(), Some("1234567890123456789"));
+        assert_eq!(ambiguous.string_value.as_deref(), Some("9007199254740992"));
+        assert_eq!(exact.number_value, Some(9007199254740991.0));
+    }
+
+    #[test]
+    fn cell_value__json_integer_beyond_exact__kept_as_text() {
+        let id = cell_value(&Value::from(9007199254740993u64), InputMode::Raw)
+            .expect("Test: Expected value");
+        let exact = cell_value(&Value::from(9007199254740991u64), InputMode::Raw)
+            .expect("Test: Expected value");
+
+        assert_eq!(id.string_value.as_deref(), Some("9007199254740993"));
+        assert_eq!(id.number_value, None);
+        assert_eq!(exact.number_value, Some(9007199254740991.0));
+    }
+
+    #[test]
+    fn repeat_format_request__set_properties__only_them_in_the_mask() {
+        let format = CellFormat {
+            background_color: Some(Color {
+                green: Some(1.0),
+                ..Default::default()
+            }),
+            number_format: Some(NumberFormat {
+                pattern: Some("yyyy-mm-dd".to_string()),
+                type_: Some("DATE".to_string()),
+            }),
+            ..Default::default()
+        };
+
+        let request = serde_json::to_value(repeat_format_request(SheetGid(3), 1..3, 0..2, &format))
+            .expect("Test: Expected to serialize");
+
+        let repeat = &request["repeatCell"];
+        assert_eq!(
+            repeat["fields"],
+            "userEnteredFormat.backgroundColor,userEnteredFormat.numberFormat"
+        );
+        assert_eq!(repeat["range"]["sheetId"], 3);
+        assert_eq!(repeat["range"]["endRowIndex"], 3);
+        assert_eq!(
+            repeat["cell"]["userEnteredFormat"]["numberFormat"]["pattern"],
+            "yyyy-mm-dd"
+        );
+    }
+}
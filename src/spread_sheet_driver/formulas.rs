@@ -0,0 +1,337 @@
+//////////////////////// Formula dependency inspection ////////////////////////
+
+use crate::spread_sheet_driver::{IntoStrVec, SpreadSheetDriver, SsdResult};
+use crate::types::{Letters, ReadOptions, SheetA1CellId, SheetA1Range, ValueRenderOption};
+use serde_json::Value;
+
+/// Longest column name treated as a reference, longer words are function or range names
+const MAX_COLUMN_LETTERS: usize = 3;
+
+/// Formula of a single cell with the references it contains
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellFormula {
+    pub cell: SheetA1CellId,
+    /// As entered, including the leading '='
+    pub formula: String,
+    pub references: Vec<FormulaReference>,
+}
+
+impl CellFormula {
+    pub fn depends_on(&self, range: &SheetA1Range) -> bool {
+        self.references.iter().any(|r| r.overlaps(range))
+    }
+}
+
+/// Rectangle referenced by a formula: "A1", "Sheet2!B2:C10", "'My sheet'!A:A"
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FormulaReference {
+    /// Sheet of the formula itself when the reference is not qualified
+    pub sheet: String,
+    pub first_column: Letters,
+    pub last_column: Letters,
+    /// `None` for whole column references
+    pub first_row: Option<u32>,
+    /// `None` when the reference is open-ended ("A2:B" or "A:B")
+    pub last_row: Option<u32>,
+}
+
+impl FormulaReference {
+    pub fn overlaps(&self, range: &SheetA1Range) -> bool {
+        let range = range.normalized();
+        let (start, end) = (&range.range.start, &range.range.end);
+        let first_row = self.first_row.unwrap_or(1);
+        let last_row = self.last_row.unwrap_or(u32::MAX);
+
+        self.sheet == range.sheet
+            && self.first_column <= end.col
+            && start.col <= self.last_column
+            && first_row <= end.row.get()
+            && start.row.get() <= last_row
+    }
+}
+
+impl SpreadSheetDriver {
+    /// Formulas of the cells in the range, cells with plain values are skipped
+    pub async fn get_formulas(&self, range: &SheetA1Range) -> SsdResult<Vec<CellFormula>> {
+        let options = ReadOptions {
+            value_render_option: ValueRenderOption::Formula,
+            ..ReadOptions::default()
+        };
+        let range = range.normalized();
+        let rows = self.try_get_range_with(&range, &options).await?.into_vec();
+
+        let start = &range.range.start;
+        let mut formulas = vec![];
+        for (y, row) in rows.into_iter().enumerate() {
+            for (x, value) in row.into_iter().enumerate() {
+                let Value::String(formula) = value else {
+                    continue;
+                };
+                if !formula.starts_with('=') {
+                    continue;
+                }
+                formulas.push(CellFormula {
+                    cell: SheetA1CellId::new(&range.sheet, start.delta(x as i32, y as i32)),
+                    references: parse_references(&formula, &range.sheet),
+                    formula,
+                });
+            }
+        }
+        Ok(formulas)
+    }
+
+    /// Distinct ranges the formulas of `range` depend on, in order of appearance
+    pub async fn get_formula_dependencies(
+        &self,
+        range: &SheetA1Range,
+    ) -> SsdResult<Vec<FormulaReference>> {
+        let mut dependencies: Vec<FormulaReference> = vec![];
+        for reference in self
+            .get_formulas(range)
+            .await?
+            .into_iter()
+            .flat_map(|formula| formula.references)
+        {
+            if !dependencies.contains(&reference) {
+                dependencies.push(reference);
+            }
+        }
+        Ok(dependencies)
+    }
+}
+
+/// Extracts A1 references from the formula. Whole row references ("1:3"), named ranges
+/// and R1C1 notation are not recognized
+pub fn parse_references(formula: &str, own_sheet: &str) -> Vec<FormulaReference> {
+    let chars: Vec<char> = formula.chars().collect();
+    let mut references = vec![];
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            // String literal, "" is an escaped quote
+            '"' => {
+                i += 1;
+                while i < chars.len() {
+                    if chars[i] == '"' && chars.get(i + 1) != Some(&'"') {
+                        break;
+                    }
+                    i += if chars[i] == '"' { 2 } else { 1 };
+                }
+                i += 1;
+            }
+            '\'' => {
+                let mut sheet = String::new();
+                i += 1;
+                while i < chars.len() {
+                    if chars[i] == '\'' {
+                        if chars.get(i + 1) != Some(&'\'') {
+                            break;
+                        }
+                        i += 1;
+                    }
+                    sheet.push(chars[i]);
+                    i += 1;
+                }
+                i += 1;
+                if chars.get(i) == Some(&'!') {
+                    i += 1;
+                    if let Some((reference, end)) = parse_reference(&chars, i, &sheet) {
+                        references.push(reference);
+                        i = end;
+                    }
+                }
+            }
+            c if is_word_char(c) => {
+                let word_start = i;
+                while i < chars.len() && is_word_char(chars[i]) {
+                    i += 1;
+                }
+                if chars.get(i) == Some(&'!') {
+                    let sheet: String = chars[word_start..i].iter().collect();
+                    if let Some((reference, end)) = parse_reference(&chars, i + 1, &sheet) {
+                        references.push(reference);
+                        i = end;
+                    }
+                } else if let Some((reference, end)) =
+                    parse_reference(&chars, word_start, own_sheet)
+                {
+                    references.push(reference);
+                    i = end;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    references
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '$')
+}
+
+/// Reference starting at `i`, with the index right after it
+fn parse_reference(chars: &[char], i: usize, sheet: &str) -> Option<(FormulaReference, usize)> {
+    let (first_column, first_row, mut end) = parse_endpoint(chars, i)?;
+    let (last_column, last_row) = match chars.get(end) {
+        Some(':') => {
+            let (column, row, last) = parse_endpoint(chars, end + 1)?;
+            end = last;
+            (column, row)
+        }
+        // Single cell, plain letters are names or booleans
+        _ => (first_column.clone(), Some(first_row?)),
+    };
+    // Part of a longer name or a function call
+    if chars
+        .get(end)
+        .is_some_and(|&c| is_word_char(c) || c == '(' || c == '!')
+    {
+        return None;
+    }
+    // "A:B2" is not a valid reference, "A2:B" is open-ended
+    if first_row.is_none() && last_row.is_some() {
+        return None;
+    }
+
+    let rows = match (first_row, last_row) {
+        (Some(a), Some(b)) => (Some(a.min(b)), Some(a.max(b))),
+        other => other,
+    };
+    let columns = match first_column <= last_column {
+        true => (first_column, last_column),
+        false => (last_column, first_column),
+    };
+    Some((
+        FormulaReference {
+            sheet: sheet.to_string(),
+            first_column: columns.0,
+            last_column: columns.1,
+            first_row: rows.0,
+            last_row: rows.1,
+        },
+        end,
+    ))
+}
+
+/// `$A$1`, `A1` or `A`, with the index right after it
+fn parse_endpoint(chars: &[char], mut i: usize) -> Option<(Letters, Option<u32>, usize)> {
+    if chars.get(i) == Some(&'$') {
+        i += 1;
+    }
+    let letters_start = i;
+    while chars.get(i).is_some_and(char::is_ascii_alphabetic) {
+        i += 1;
+    }
+    let letters: String = chars[letters_start..i].iter().collect();
+    if letters.is_empty() || letters.len() > MAX_COLUMN_LETTERS {
+        return None;
+    }
+
+    if chars.get(i) == Some(&'$') {
+        i += 1;
+    }
+    let digits_start = i;
+    while chars.get(i).is_some_and(char::is_ascii_digit) {
+        i += 1;
+    }
+    let row = match digits_start == i {
+        true => None,
+        false => Some(
+            chars[digits_start..i]
+                .iter()
+                .collect::<String>()
+                .parse::<u32>()
+                .ok()
+                .filter(|&row| row > 0)?,
+        ),
+    };
+    Some((Letters::from_valid(letters.to_uppercase()), row, i))
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod formulas_tests {
+    use super::*;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+
+    fn reference(
+        sheet: &str,
+        columns: (&str, &str),
+        first_row: Option<u32>,
+        last_row: Option<u32>,
+    ) -> FormulaReference {
+        FormulaReference {
+            sheet: sheet.to_string(),
+            first_column: Letters::from_valid(columns.0.to_string()),
+            last_column: Letters::from_valid(columns.1.to_string()),
+            first_row,
+            last_row,
+        }
+    }
+
+    #[test]
+    fn parse_references__mixed_formula__references_only() {
+        let references = parse_references(
+            r#"=SUM($A$2:B10) + 'My ''sheet'''!C3 * LOG10(orders!D:D) & "E5" & TRUE"#,
+            "users",
+        );
+
+        assert_eq!(
+            references,
+            vec![
+                reference("users", ("A", "B"), Some(2), Some(10)),
+                reference("My 'sheet'", ("C", "C"), Some(3), Some(3)),
+                reference("orders", ("D", "D"), None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_references__open_ended_and_names__ok() {
+        let references = parse_references("=COUNTA(a2:b) + Total2024 + ATAN2(1, 2)", "users");
+
+        assert_eq!(
+            references,
+            vec![reference("users", ("A", "B"), Some(2), None)]
+        );
+    }
+
+    #[tokio::test]
+    async fn get_formula_dependencies__formulas_and_values__dependencies_of_formulas() {
+        let backend = MemoryBackend::new();
+        backend.workbook().set_sheet(
+            "users",
+            vec![
+                vec![Value::from("1"), Value::from("=A1*2")],
+                vec![Value::from("2"), Value::from("=A2*2+A1")],
+            ],
+        );
+        let driver = SpreadSheetDriver::with_backend("document".to_string(), backend);
+        let range = SheetA1Range::from_str("users", "A1:B2").expect("Test: Expected range");
+
+        let formulas = driver
+            .get_formulas(&range)
+            .await
+            .expect("Test: Expected formulas");
+        assert_eq!(formulas.len(), 2);
+        assert_eq!(
+            formulas[1].cell,
+            SheetA1CellId::from_primitives("users", "B", 2)
+        );
+        let column_a = SheetA1Range::from_str("users", "A1:A100").expect("Test: Expected range");
+        assert!(formulas.iter().all(|f| f.depends_on(&column_a)));
+
+        let dependencies = driver
+            .get_formula_dependencies(&range)
+            .await
+            .expect("Test: Expected dependencies");
+        assert_eq!(
+            dependencies,
+            vec![
+                reference("users", ("A", "A"), Some(1), Some(1)),
+                reference("users", ("A", "A"), Some(2), Some(2)),
+            ]
+        );
+    }
+}
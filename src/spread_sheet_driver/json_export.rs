@@ -0,0 +1,170 @@
+//////////////////////// JSON objects keyed by header row ////////////////////////
+
+use crate::mapper::sheet_row::SheetRow;
+use crate::spread_sheet_driver::{IntoStrVec, SpreadSheetDriver, SsdResult};
+use crate::types::{A1Range, InputMode, SheetA1CellId, SheetA1Range};
+use serde_json::{Map, Value};
+
+pub type JsonObject = Map<String, Value>;
+
+impl SpreadSheetDriver {
+    /// Reads the range and turns each row into an object keyed by the first (header) row.
+    /// Columns with blank headers are skipped, empty cells become `null`, fully empty rows are dropped
+    pub async fn export_json<R>(&self, range: R) -> SsdResult<Vec<JsonObject>>
+    where
+        R: ToString,
+    {
+        let rows = self.try_get_range(range).await?.into_vec();
+        Ok(rows_to_objects(rows))
+    }
+
+    /// Inverse of [`SpreadSheetDriver::export_json`]: writes a header row made of all keys
+    /// (in order of first appearance) at `start`, followed by one row per object.
+    /// Nested objects and arrays are stored as JSON strings
+    pub async fn import_json(
+        &self,
+        start: &SheetA1CellId,
+        objects: &[JsonObject],
+    ) -> SsdResult<()> {
+        let rows = objects_to_rows(objects);
+        if rows.is_empty() {
+            return Ok(());
+        }
+        let width = rows.first().map(Vec::len).unwrap_or_default();
+        let end = start.cell.delta(width as i32 - 1, rows.len() as i32 - 1);
+        let range = SheetA1Range::new(&start.sheet_name, A1Range::new(start.cell.clone(), end));
+
+        self.try_write_range_as(range.to_string().as_str(), rows, InputMode::Raw)
+            .await
+    }
+}
+
+fn rows_to_objects(rows: Vec<SheetRow>) -> Vec<JsonObject> {
+    let mut rows = rows.into_iter();
+    let headers: Vec<Option<String>> = rows
+        .next()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|header| match header {
+            Value::String(s) if s.is_empty() => None,
+            Value::String(s) => Some(s),
+            Value::Null => None,
+            other => Some(other.to_string()),
+        })
+        .collect();
+
+    rows.filter(|row| !row.iter().all(is_blank))
+        .map(|row| {
+            headers
+                .iter()
+                .enumerate()
+                .filter_map(|(i, header)| {
+                    let value = row.get(i).filter(|v| !is_blank(v)).cloned();
+                    header.clone().map(|h| (h, value.unwrap_or(Value::Null)))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn objects_to_rows(objects: &[JsonObject]) -> Vec<SheetRow> {
+    let mut headers: Vec<&String> = vec![];
+    for key in objects.iter().flat_map(|o| o.keys()) {
+        if !headers.contains(&key) {
+            headers.push(key);
+        }
+    }
+    if headers.is_empty() {
+        return vec![];
+    }
+
+    let header_row = headers
+        .iter()
+        .map(|h| Value::String(h.to_string()))
+        .collect();
+    let data_rows = objects.iter().map(|object| {
+        headers
+            .iter()
+            .map(|h| match object.get(*h) {
+                None | Some(Value::Null) => Value::String(String::new()),
+                Some(v @ (Value::Object(_) | Value::Array(_))) => Value::String(v.to_string()),
+                Some(v) => v.clone(),
+            })
+            .collect()
+    });
+
+    std::iter::once(header_row).chain(data_rows).collect()
+}
+
+fn is_blank(value: &Value) -> bool {
+    value.is_null() || value.as_str() == Some("")
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod json_export_tests {
+    use super::*;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use serde_json::json;
+
+    fn rows(value: Value) -> Vec<SheetRow> {
+        serde_json::from_value(value).expect("Test: Expected rows")
+    }
+
+    fn objects(value: Value) -> Vec<JsonObject> {
+        serde_json::from_value(value).expect("Test: Expected objects")
+    }
+
+    #[test]
+    fn rows_to_objects__keyed_by_headers__ok() {
+        let actual = rows_to_objects(rows(json!([
+            ["id", "", "name"],
+            [1, "ignored", "Joe"],
+            ["", "", ""],
+            [2],
+        ])));
+
+        assert_eq!(
+            actual,
+            objects(json!([{"id": 1, "name": "Joe"}, {"id": 2, "name": null}]))
+        );
+    }
+
+    #[test]
+    fn objects_to_rows__union_of_keys__ok() {
+        let actual = objects_to_rows(&objects(json!([
+            {"id": 1, "tags": ["a"]},
+            {"id": 2, "name": "John", "tags": null},
+        ])));
+
+        assert_eq!(
+            actual,
+            rows(json!([
+                ["id", "tags", "name"],
+                [1, "[\"a\"]", ""],
+                [2, "", "John"],
+            ]))
+        );
+    }
+
+    #[test]
+    fn objects_to_rows__empty__ok() {
+        assert!(objects_to_rows(&[]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn import_json__no_objects__nothing_written() {
+        let driver = SpreadSheetDriver::with_backend("document".to_string(), MemoryBackend::new());
+
+        driver
+            .import_json(&SheetA1CellId::from_primitives("users", "A", 1), &[])
+            .await
+            .expect("Test: Expected empty import");
+
+        let exported = driver
+            .export_json("users!A1:B2")
+            .await
+            .expect("Test: Expected export");
+        assert!(exported.is_empty());
+    }
+}
@@ -0,0 +1,212 @@
+//////////////////////// Response size limits ////////////////////////
+
+use crate::spread_sheet_driver::breaker::is_read;
+use crate::spread_sheet_driver::{
+    CallContext, SpreadSheetDriver, SpreadSheetDriverError, SsdResult,
+};
+use crate::types::{NumRange, SheetA1Range};
+use error_stack::bail;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Guards of reads against unexpectedly large responses, no limits by default
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResponseLimits {
+    /// Cells per read. Bounded A1 ranges over the limit are rejected without being sent,
+    /// the rest is checked once the response arrives
+    pub max_cells: Option<usize>,
+    /// Size of the response serialized as JSON
+    pub max_payload_bytes: Option<usize>,
+}
+
+impl ResponseLimits {
+    fn is_unlimited(&self) -> bool {
+        self.max_cells.is_none() && self.max_payload_bytes.is_none()
+    }
+}
+
+impl SpreadSheetDriver {
+    /// Fails reads over the limits with [`SpreadSheetDriverError::ResponseTooLarge`], so a
+    /// memory-constrained service doesn't fetch a million-cell range by accident.
+    /// Checking a response costs its serialization, which is skipped without limits
+    pub fn with_response_limits(mut self, limits: ResponseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn response_limits(&self) -> ResponseLimits {
+        self.limits
+    }
+
+    /// Rejects reads of bounded ranges which can't fit into `max_cells`
+    pub(crate) fn check_requested_size(&self, context: &CallContext) -> SsdResult<()> {
+        let Some(max_cells) = self.limits.max_cells else {
+            return Ok(());
+        };
+        if !is_read(&context.operation) {
+            return Ok(());
+        }
+
+        let cells: usize = context
+            .ranges
+            .iter()
+            .filter_map(|range| SheetA1Range::from_raw(range).ok())
+            .map(|range| {
+                let range = NumRange::from(range.range);
+                range.width() as usize * range.height() as usize
+            })
+            .sum();
+        too_large(&context.operation, "cells", cells, max_cells)
+    }
+
+    pub(crate) fn check_response_size<T>(&self, operation: &str, response: &T) -> SsdResult<()>
+    where
+        T: Serialize,
+    {
+        if self.limits.is_unlimited() || !is_read(operation) {
+            return Ok(());
+        }
+        let Ok(response) = serde_json::to_value(response) else {
+            return Ok(());
+        };
+
+        if let Some(max_cells) = self.limits.max_cells {
+            too_large(operation, "cells", count_cells(&response), max_cells)?;
+        }
+        if let Some(max_bytes) = self.limits.max_payload_bytes {
+            too_large(operation, "bytes", response.to_string().len(), max_bytes)?;
+        }
+        Ok(())
+    }
+}
+
+fn too_large(operation: &str, unit: &'static str, size: usize, limit: usize) -> SsdResult<()> {
+    if size > limit {
+        bail!(SpreadSheetDriverError::ResponseTooLarge {
+            operation: operation.to_string(),
+            unit,
+            size,
+            limit,
+        });
+    }
+    Ok(())
+}
+
+/// Cells of every `values` grid in the response, wherever it is nested
+fn count_cells(response: &Value) -> usize {
+    match response {
+        Value::Object(fields) => fields
+            .iter()
+            .map(|(key, value)| match (key.as_str(), value) {
+                ("values", Value::Array(rows)) => rows
+                    .iter()
+                    .map(|row| row.as_array().map_or(1, Vec::len))
+                    .sum(),
+                _ => count_cells(value),
+            })
+            .sum(),
+        Value::Array(items) => items.iter().map(count_cells).sum(),
+        _ => 0,
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod limits_tests {
+    use super::*;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use crate::spread_sheet_driver::cassette::{Cassette, Interaction};
+    use crate::testing::fixtures::MatchedValueRangeBuilder;
+    use google_sheets4::api::BatchGetValuesByDataFilterResponse;
+    use serde_json::json;
+
+    fn users_driver(limits: ResponseLimits) -> SpreadSheetDriver {
+        let backend = MemoryBackend::new();
+        backend.workbook().set_sheet(
+            "users",
+            vec![
+                vec![Value::from("1"), Value::from("Joe")],
+                vec![Value::from("2"), Value::from("John")],
+            ],
+        );
+        SpreadSheetDriver::with_backend("document".to_string(), backend)
+            .with_response_limits(limits)
+    }
+
+    fn assert_too_large<T>(result: SsdResult<T>, expected_unit: &str) {
+        match result
+            .map(|_| ())
+            .expect_err("Test: Expected too large response")
+            .current_context()
+        {
+            SpreadSheetDriverError::ResponseTooLarge { unit, .. } => {
+                assert_eq!(*unit, expected_unit)
+            }
+            other => panic!("Test: Unexpected error {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn try_get_range__bounded_range_over_max_cells__rejected_before_sending() {
+        let driver = users_driver(ResponseLimits {
+            max_cells: Some(100),
+            ..Default::default()
+        });
+
+        driver
+            .try_get_range("users!A1:B50")
+            .await
+            .expect("Test: Expected range within the limit");
+        assert_too_large(driver.try_get_range("users!A1:Z1000").await, "cells");
+    }
+
+    #[tokio::test]
+    async fn try_get_range__unbounded_range_over_max_cells__too_large() {
+        let response = BatchGetValuesByDataFilterResponse {
+            value_ranges: Some(vec![
+                MatchedValueRangeBuilder::new("users!A1:B2")
+                    .row(["1", "Joe"])
+                    .row(["2", "John"])
+                    .build(),
+            ]),
+            ..Default::default()
+        };
+        let cassette = Cassette::replay_from(
+            "unused.json",
+            vec![Interaction {
+                operation: "values.batchGetByDataFilter".to_string(),
+                request: json!({ "range": "users!A:B" }),
+                response: serde_json::to_value(response).expect("Test: Expected to serialize"),
+            }],
+        );
+        let driver = SpreadSheetDriver::replay("document".to_string(), cassette)
+            .with_response_limits(ResponseLimits {
+                max_cells: Some(3),
+                ..Default::default()
+            });
+
+        assert_too_large(driver.try_get_range("users!A:B").await, "cells");
+    }
+
+    #[tokio::test]
+    async fn try_get_range__payload_over_max_bytes__too_large() {
+        let driver = users_driver(ResponseLimits {
+            max_payload_bytes: Some(16),
+            ..Default::default()
+        });
+
+        assert_too_large(driver.try_get_range("users!A1:B2").await, "bytes");
+    }
+
+    #[test]
+    fn count_cells__nested_value_ranges__all_counted() {
+        let response = json!({
+            "valueRanges": [
+                { "valueRange": { "values": [["1", "Joe"], ["2"]] } },
+                { "valueRange": { "values": [["3", "Jane", "jane@mail.com"]] } },
+            ]
+        });
+
+        assert_eq!(count_cells(&response), 6);
+    }
+}
@@ -0,0 +1,333 @@
+//////////////////////// Lease-based lock over a spreadsheet cell ////////////////////////
+
+use crate::spread_sheet_driver::cells::cell_range;
+use crate::spread_sheet_driver::metadata::unique_token;
+use crate::spread_sheet_driver::{
+    IntoStrVec, SharedSpreadSheetDriver, SpreadSheetDriverError, SsdResult,
+};
+use crate::types::{InputMode, SheetA1CellId};
+use error_stack::bail;
+use serde_json::Value;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn};
+
+/// Lease kept in the lock cell as "<owner>;<expiry in unix millis>"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lease {
+    pub owner: String,
+    pub expires_at_ms: u64,
+}
+
+impl Lease {
+    fn parse(value: &Value) -> Option<Self> {
+        let (owner, expires_at) = value.as_str()?.split_once(';')?;
+        Some(Self {
+            owner: owner.to_string(),
+            expires_at_ms: expires_at.parse().ok()?,
+        })
+    }
+
+    fn to_cell(&self) -> Value {
+        Value::String(format!("{};{}", self.owner, self.expires_at_ms))
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at_ms <= now_ms()
+    }
+
+    fn remaining(&self) -> Duration {
+        Duration::from_millis(self.expires_at_ms.saturating_sub(now_ms()))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LockOptions {
+    /// For how long the lease is valid unless renewed
+    pub ttl: Duration,
+    /// Pause between writing the lease and reading it back. Gives concurrent writers time to
+    /// overwrite the cell, so only the last one considers itself the owner
+    pub settle_delay: Duration,
+    /// Pause between attempts while the lock is held by someone else
+    pub poll_interval: Duration,
+}
+
+impl Default for LockOptions {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(30),
+            settle_delay: Duration::from_millis(500),
+            poll_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Lock shared by workers through a designated cell of the spreadsheet.
+/// The API has no compare-and-swap, so the lock is taken by writing the lease and reading
+/// it back after `settle_delay`: the last writer wins. Good enough to serialize jobs which
+/// run seconds apart, not a replacement for a coordination service.
+/// Expiry is compared with the local clock, so workers' clocks are expected to be in sync.
+/// Not released on drop: call [`SheetLock::release`] or let the lease expire
+#[derive(Debug)]
+pub struct SheetLock {
+    driver: SharedSpreadSheetDriver,
+    cell: SheetA1CellId,
+    lease: Lease,
+    ttl: Duration,
+}
+
+impl SheetLock {
+    /// Waits until the lock is free (or the lease of the holder expires) and takes it for `ttl`
+    pub async fn acquire(
+        driver: SharedSpreadSheetDriver,
+        lock_cell: SheetA1CellId,
+        ttl: Duration,
+    ) -> SsdResult<Self> {
+        let options = LockOptions {
+            ttl,
+            ..Default::default()
+        };
+        Self::acquire_with(driver, lock_cell, options).await
+    }
+
+    pub async fn acquire_with(
+        driver: SharedSpreadSheetDriver,
+        lock_cell: SheetA1CellId,
+        options: LockOptions,
+    ) -> SsdResult<Self> {
+        let owner = unique_token();
+        loop {
+            match attempt(&driver, &lock_cell, &owner, &options).await? {
+                Ok(lease) => {
+                    return Ok(Self {
+                        driver,
+                        cell: lock_cell,
+                        lease,
+                        ttl: options.ttl,
+                    });
+                }
+                Err(holder) => {
+                    debug!("Lock {:?} is held by {}", lock_cell, holder.owner);
+                    tokio::time::sleep(holder.remaining().min(options.poll_interval)).await;
+                }
+            }
+        }
+    }
+
+    /// Single attempt, `None` if the lock is held by someone else
+    pub async fn try_acquire(
+        driver: SharedSpreadSheetDriver,
+        lock_cell: SheetA1CellId,
+        options: LockOptions,
+    ) -> SsdResult<Option<Self>> {
+        let owner = unique_token();
+        let lock = attempt(&driver, &lock_cell, &owner, &options)
+            .await?
+            .ok()
+            .map(|lease| Self {
+                driver,
+                cell: lock_cell,
+                lease,
+                ttl: options.ttl,
+            });
+        Ok(lock)
+    }
+
+    pub fn lease(&self) -> &Lease {
+        &self.lease
+    }
+
+    /// Extends the lease by `ttl` from now. Fails with `LockLost` if someone took the lock over
+    pub async fn renew(&mut self) -> SsdResult<()> {
+        self.ensure_held().await?;
+
+        let lease = Lease {
+            owner: self.lease.owner.clone(),
+            expires_at_ms: now_ms() + self.ttl.as_millis() as u64,
+        };
+        write_lease(&self.driver, &self.cell, lease.to_cell()).await?;
+        self.lease = lease;
+        Ok(())
+    }
+
+    /// Clears the lock cell if the lock is still held
+    pub async fn release(self) -> SsdResult<()> {
+        if let Err(e) = self.ensure_held().await {
+            warn!("Lock {:?} was lost before release: {:?}", self.cell, e);
+            return Ok(());
+        }
+        write_lease(&self.driver, &self.cell, Value::String(String::new())).await
+    }
+
+    async fn ensure_held(&self) -> SsdResult<()> {
+        let current = read_lease(&self.driver, &self.cell).await?;
+        if current.as_ref() != Some(&self.lease) {
+            bail!(SpreadSheetDriverError::LockLost(format!(
+                "{:?} is taken over by {:?}",
+                self.cell,
+                current.map(|l| l.owner)
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Either our lease or the one of the current holder
+async fn attempt(
+    driver: &SharedSpreadSheetDriver,
+    cell: &SheetA1CellId,
+    owner: &str,
+    options: &LockOptions,
+) -> SsdResult<Result<Lease, Lease>> {
+    if let Some(holder) = read_lease(driver, cell).await?
+        && !holder.is_expired()
+    {
+        return Ok(Err(holder));
+    }
+
+    let lease = Lease {
+        owner: owner.to_string(),
+        expires_at_ms: now_ms() + options.ttl.as_millis() as u64,
+    };
+    write_lease(driver, cell, lease.to_cell()).await?;
+    if !options.settle_delay.is_zero() {
+        tokio::time::sleep(options.settle_delay).await;
+    }
+
+    match read_lease(driver, cell).await? {
+        Some(current) if current == lease => Ok(Ok(lease)),
+        Some(current) => Ok(Err(current)),
+        // Released by a concurrent owner right after our write, retry
+        None => Ok(Err(Lease {
+            owner: String::new(),
+            expires_at_ms: 0,
+        })),
+    }
+}
+
+async fn read_lease(
+    driver: &SharedSpreadSheetDriver,
+    cell: &SheetA1CellId,
+) -> SsdResult<Option<Lease>> {
+    let rows = driver
+        .lock()
+        .await
+        .try_get_range(cell_range(cell))
+        .await?
+        .into_vec();
+    Ok(rows
+        .first()
+        .and_then(|row| row.first())
+        .and_then(Lease::parse))
+}
+
+async fn write_lease(
+    driver: &SharedSpreadSheetDriver,
+    cell: &SheetA1CellId,
+    value: Value,
+) -> SsdResult<()> {
+    driver
+        .lock()
+        .await
+        .try_write_range_as(
+            &cell_range(cell).to_string(),
+            vec![vec![value]],
+            InputMode::Raw,
+        )
+        .await
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod lock_tests {
+    use super::*;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use crate::testing::fixtures::shared_driver;
+
+    fn options(ttl: Duration) -> LockOptions {
+        LockOptions {
+            ttl,
+            settle_delay: Duration::ZERO,
+            poll_interval: Duration::from_millis(10),
+        }
+    }
+
+    fn lock_cell() -> SheetA1CellId {
+        SheetA1CellId::from_primitives("locks", "A", 1)
+    }
+
+    #[test]
+    fn lease__parse__ok() {
+        let lease = Lease::parse(&Value::from("worker;1700000000000"));
+        assert_eq!(
+            lease,
+            Some(Lease {
+                owner: "worker".to_string(),
+                expires_at_ms: 1700000000000,
+            })
+        );
+        assert_eq!(Lease::parse(&Value::from("")), None);
+        assert_eq!(Lease::parse(&Value::from("worker;soon")), None);
+    }
+
+    #[tokio::test]
+    async fn try_acquire__held_lock__none_until_released() {
+        let driver = shared_driver(MemoryBackend::new());
+        let held = SheetLock::try_acquire(
+            driver.clone(),
+            lock_cell(),
+            options(Duration::from_secs(60)),
+        )
+        .await
+        .expect("Test: Expected attempt to succeed")
+        .expect("Test: Expected free lock to be acquired");
+
+        let contender = SheetLock::try_acquire(
+            driver.clone(),
+            lock_cell(),
+            options(Duration::from_secs(60)),
+        )
+        .await
+        .expect("Test: Expected attempt to succeed");
+        assert!(contender.is_none());
+
+        held.release()
+            .await
+            .expect("Test: Expected release to succeed");
+        let contender =
+            SheetLock::try_acquire(driver, lock_cell(), options(Duration::from_secs(60)))
+                .await
+                .expect("Test: Expected attempt to succeed");
+        assert!(contender.is_some());
+    }
+
+    #[tokio::test]
+    async fn renew__after_lease_expired_and_taken_over__lock_lost() {
+        let driver = shared_driver(MemoryBackend::new());
+        let mut expired =
+            SheetLock::try_acquire(driver.clone(), lock_cell(), options(Duration::ZERO))
+                .await
+                .expect("Test: Expected attempt to succeed")
+                .expect("Test: Expected free lock to be acquired");
+
+        let _holder =
+            SheetLock::acquire_with(driver, lock_cell(), options(Duration::from_secs(60)))
+                .await
+                .expect("Test: Expected expired lock to be taken over");
+
+        let err = expired
+            .renew()
+            .await
+            .expect_err("Test: Expected lock to be lost");
+        assert!(matches!(
+            err.current_context(),
+            SpreadSheetDriverError::LockLost(_)
+        ));
+    }
+}
@@ -0,0 +1,237 @@
+//////////////////////// Developer metadata ////////////////////////
+// Key/value tags attached to rows which follow them when rows are moved, sorted or shifted
+// by insertions above, so they identify a location independently of its A1 address.
+
+use crate::mapper::sheet_row::SheetRow;
+use crate::spread_sheet_driver::structure::rows_range;
+use crate::spread_sheet_driver::{IntoStrVec, SpreadSheetDriver, SsdResult, matched_range};
+use crate::types::{SheetA1Range, SheetGid};
+use google_sheets4::api::{
+    CreateDeveloperMetadataRequest, DataFilter, DeleteDeveloperMetadataRequest, DeveloperMetadata,
+    DeveloperMetadataLocation, DeveloperMetadataLookup, Request, SearchDeveloperMetadataRequest,
+    SearchDeveloperMetadataResponse,
+};
+use serde_json::json;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Metadata visible to every app with access to the document (the other option is PROJECT)
+const DOCUMENT_VISIBILITY: &str = "DOCUMENT";
+
+/// Values of a location tagged with the looked up metadata
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetadataMatch {
+    /// Current A1 location of the tagged rows or columns
+    pub location: SheetA1Range,
+    pub values: Vec<SheetRow>,
+}
+
+impl SpreadSheetDriver {
+    /// Metadata entries matched by any of the filters, with their current locations
+    pub async fn try_search_metadata(
+        &self,
+        filters: Vec<DataFilter>,
+    ) -> SsdResult<Vec<DeveloperMetadata>> {
+        let req = SearchDeveloperMetadataRequest {
+            data_filters: Some(filters),
+        };
+        let response: SearchDeveloperMetadataResponse = self
+            .exchange(
+                "developerMetadata.search",
+                json!({ "dataFilters": req.data_filters }),
+                || async {
+                    self.client_ref()
+                        .spreadsheets()
+                        .developer_metadata_search(req.clone(), self.document_id.as_str())
+                        .doit()
+                        .await
+                        .map(|(_, response)| response)
+                        .map_err(|e| self.api_error(e))
+                },
+            )
+            .await?;
+
+        Ok(response
+            .matched_developer_metadata
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|matched| matched.developer_metadata)
+            .collect())
+    }
+
+    /// Values of every location tagged with metadata matching the lookup,
+    /// wherever the tagged rows were moved to
+    pub async fn try_get_by_metadata(
+        &self,
+        lookup: DeveloperMetadataLookup,
+    ) -> SsdResult<Vec<MetadataMatch>> {
+        let filter = DataFilter {
+            developer_metadata_lookup: Some(lookup),
+            ..Default::default()
+        };
+        self.try_query(vec![filter])
+            .await?
+            .into_iter()
+            .map(|matched| {
+                Ok(MetadataMatch {
+                    location: matched_range(&matched)?,
+                    values: matched.into_vec(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Sheet id and 0-based index of the first row the metadata is attached to
+pub fn tagged_row(metadata: &DeveloperMetadata) -> Option<(SheetGid, u32)> {
+    let range = metadata.location.as_ref()?.dimension_range.as_ref()?;
+    Some((SheetGid(range.sheet_id?), range.start_index? as u32))
+}
+
+/// Tags 0-based rows `[start_index, start_index + count)` with `key=value`
+pub fn tag_rows_request(
+    sheet_id: SheetGid,
+    start_index: u32,
+    count: u32,
+    key: &str,
+    value: &str,
+) -> Request {
+    Request {
+        create_developer_metadata: Some(CreateDeveloperMetadataRequest {
+            developer_metadata: Some(DeveloperMetadata {
+                metadata_key: Some(key.to_string()),
+                metadata_value: Some(value.to_string()),
+                visibility: Some(DOCUMENT_VISIBILITY.to_string()),
+                location: Some(DeveloperMetadataLocation {
+                    dimension_range: Some(rows_range(sheet_id, start_index, count)),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+        }),
+        ..Default::default()
+    }
+}
+
+/// Removes every metadata entry matched by the filter (the tagged rows stay intact)
+pub fn untag_request(filter: DataFilter) -> Request {
+    Request {
+        delete_developer_metadata: Some(DeleteDeveloperMetadataRequest {
+            data_filter: Some(filter),
+        }),
+        ..Default::default()
+    }
+}
+
+/// Matches the location(s) tagged with `key=value`
+pub fn metadata_filter(key: &str, value: &str) -> DataFilter {
+    DataFilter {
+        developer_metadata_lookup: Some(DeveloperMetadataLookup {
+            metadata_key: Some(key.to_string()),
+            metadata_value: Some(value.to_string()),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+/// Value unique across processes and calls, good enough to tag a location.
+/// Made of process id, current time and a per-process counter
+pub fn unique_token() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!("{:x}-{:x}-{:x}", std::process::id(), nanos, count)
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod metadata_tests {
+    use super::*;
+    use crate::spread_sheet_driver::cassette::{Cassette, Interaction};
+    use crate::testing::fixtures::MatchedValueRangeBuilder;
+    use google_sheets4::api::BatchGetValuesByDataFilterResponse;
+
+    #[test]
+    fn tag_rows_request__serialized__ok() {
+        let request = serde_json::to_value(tag_rows_request(SheetGid(3), 5, 2, "key", "value"))
+            .expect("Test: Expected to serialize");
+
+        let metadata = &request["createDeveloperMetadata"]["developerMetadata"];
+        assert_eq!(metadata["metadataKey"], "key");
+        assert_eq!(metadata["metadataValue"], "value");
+        assert_eq!(metadata["visibility"], "DOCUMENT");
+        let range = &metadata["location"]["dimensionRange"];
+        assert_eq!(range["sheetId"], 3);
+        assert_eq!(range["startIndex"], 5);
+        assert_eq!(range["endIndex"], 7);
+    }
+
+    #[test]
+    fn tagged_row__on_row_location__ok() {
+        let metadata = DeveloperMetadata {
+            location: Some(DeveloperMetadataLocation {
+                dimension_range: Some(rows_range(SheetGid(3), 5, 1)),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(tagged_row(&metadata), Some((SheetGid(3), 5)));
+        assert_eq!(tagged_row(&DeveloperMetadata::default()), None);
+    }
+
+    #[tokio::test]
+    async fn try_get_by_metadata__tagged_rows__location_from_values_range() {
+        let lookup = DeveloperMetadataLookup {
+            metadata_key: Some("row_id".to_string()),
+            metadata_value: Some("42".to_string()),
+            ..Default::default()
+        };
+        let filter = DataFilter {
+            developer_metadata_lookup: Some(lookup.clone()),
+            ..Default::default()
+        };
+        let mut matched = MatchedValueRangeBuilder::new("users!A7:B7")
+            .row(["42", "Joe"])
+            .build();
+        matched.data_filters = Some(vec![filter.clone()]);
+        let response = BatchGetValuesByDataFilterResponse {
+            value_ranges: Some(vec![matched]),
+            ..Default::default()
+        };
+        let driver = SpreadSheetDriver::replay(
+            "document".to_string(),
+            Cassette::replay_from(
+                "unused.json",
+                vec![Interaction {
+                    operation: "values.batchGetByDataFilter".to_string(),
+                    request: json!({ "dataFilters": [filter] }),
+                    response: serde_json::to_value(response).expect("Test: Expected to serialize"),
+                }],
+            ),
+        );
+
+        let matches = driver
+            .try_get_by_metadata(lookup)
+            .await
+            .expect("Test: Expected metadata lookup");
+
+        assert_eq!(
+            matches,
+            vec![MetadataMatch {
+                location: SheetA1Range::from_str("users", "A7:B7").expect("Test: Expected range"),
+                values: vec![vec![json!("42"), json!("Joe")]],
+            }]
+        );
+    }
+
+    #[test]
+    fn unique_token__consecutive_calls__differ() {
+        assert_ne!(unique_token(), unique_token());
+    }
+}
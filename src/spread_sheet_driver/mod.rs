@@ -0,0 +1,1786 @@
+pub mod allowlist;
+pub mod auth;
+pub mod backend;
+pub mod backup;
+pub mod batch;
+pub mod breaker;
+pub mod cassette;
+pub mod cells;
+pub mod config_sheet;
+#[cfg(feature = "csv")]
+pub mod csv_import;
+#[cfg(feature = "polars")]
+pub mod dataframe;
+pub mod format;
+pub mod formulas;
+pub mod json_export;
+pub mod limits;
+pub mod lock;
+pub mod metadata;
+pub mod rate_limit;
+pub mod request_log;
+pub mod responses;
+pub mod retry;
+pub mod structure;
+pub mod transform;
+pub mod verify;
+
+use error_stack::{Report, ResultExt, bail, report};
+use google_sheets4::api::{
+    AppendValuesResponse, BatchGetValuesByDataFilterRequest, BatchGetValuesByDataFilterResponse,
+    BatchUpdateValuesRequest, BatchUpdateValuesResponse, DataFilter, SheetProperties,
+    UpdateValuesResponse, ValueRange,
+};
+use google_sheets4::common::NoToken;
+use google_sheets4::hyper::client::HttpConnector;
+use google_sheets4::hyper::{Body, Client, Response};
+use google_sheets4::hyper_rustls::HttpsConnector;
+use google_sheets4::oauth2::ServiceAccountAuthenticator;
+use google_sheets4::{Error, Sheets, hyper, hyper_rustls, oauth2};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::any::type_name;
+use std::fmt::{Debug, Display, Formatter};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::mapper::sheet_row::{ParseError, SheetRow, SheetRowSerde};
+use crate::spread_sheet_driver::allowlist::WriteAllowlist;
+use crate::spread_sheet_driver::auth::Credentials;
+use crate::spread_sheet_driver::backend::SheetsBackend;
+use crate::spread_sheet_driver::breaker::CircuitBreaker;
+use crate::spread_sheet_driver::cassette::Cassette;
+use crate::spread_sheet_driver::format::input_rows;
+use crate::spread_sheet_driver::limits::ResponseLimits;
+use crate::spread_sheet_driver::rate_limit::RateLimiter;
+use crate::spread_sheet_driver::request_log::{RequestRecord, RequestSink};
+use crate::spread_sheet_driver::responses::WriteSummary;
+use crate::spread_sheet_driver::retry::Retrier;
+use crate::spread_sheet_driver::structure::GridCheck;
+use crate::spread_sheet_driver::transform::CellTransform;
+use crate::spread_sheet_driver::verify::WriteDiff;
+use crate::types::{
+    A1CellId, A1Range, AppendOptions, InputMode, InsertDataOption, Letters, MajorDimension,
+    ReadOptions, ResponseValueRenderOption, SheetA1CellId, SheetA1Range, ValueRenderOption,
+    WriteOptions,
+};
+pub use google_sheets4::api::MatchedValueRange;
+use google_sheets4::oauth2::authenticator::Authenticator;
+use huh::{AMShared, ErrorStackExt};
+use tracing::{debug, error, trace};
+
+#[derive(Debug, thiserror::Error)]
+pub enum SpreadSheetDriverError {
+    #[error("Range {0} not found")]
+    RangeNotFound(String),
+    #[error("Spreadsheet API error ({0})")]
+    ApiError(String),
+    #[error("No access to spreadsheet {document_id} for {principal}. {hint}")]
+    PermissionDenied {
+        document_id: String,
+        principal: String,
+        hint: String,
+    },
+    #[error("Spreadsheet {document_id} is not found for {principal}. {hint}")]
+    SpreadsheetNotFound {
+        document_id: String,
+        principal: String,
+        hint: String,
+    },
+    #[error("Can't authenticate ({0})")]
+    AuthenticationFailed(String),
+    #[error("Can't parse row ({0})")]
+    ParseError(String),
+    #[error("Invalid argument {0}")]
+    InvalidArgument(String),
+    #[error("Fixture error ({0})")]
+    FixtureError(String),
+    #[error("No recorded interaction for {0}")]
+    ReplayMiss(String),
+    #[error("Operation {0} is not supported by the backend")]
+    UnsupportedOperation(String),
+    #[error("Lock is lost ({0})")]
+    LockLost(String),
+    #[error("Range {range} is out of the sheet grid of {rows} rows and {columns} columns")]
+    RangeOutOfBounds {
+        range: String,
+        rows: u32,
+        columns: u32,
+    },
+    #[error("Values stored in {range} differ from the written ones: {diff}")]
+    WriteVerificationFailed { range: String, diff: WriteDiff },
+    #[error(
+        "Circuit is open after repeated API failures, {operation} is not sent for {retry_after:?}"
+    )]
+    CircuitOpen {
+        operation: String,
+        retry_after: Duration,
+    },
+    #[error(
+        "Response to {operation} has {size} {unit}, over the limit of {limit}. Read smaller ranges"
+    )]
+    ResponseTooLarge {
+        operation: String,
+        unit: &'static str,
+        size: usize,
+        limit: usize,
+    },
+    #[error("Unexpected response ({0})")]
+    UnexpectedResponse(String),
+    #[error("{operation} of {range} is outside of the write allowlist")]
+    WriteNotAllowed { operation: String, range: String },
+}
+
+pub type SsdResult<T> = error_stack::Result<T, SpreadSheetDriverError>;
+
+pub type SharedSpreadSheetDriver = AMShared<SpreadSheetDriver>;
+
+#[derive(Debug)]
+pub struct SpreadSheetDriver {
+    document_id: String,
+    pub sheets_client: SheetsClient,
+    cassette: Option<Cassette>,
+    backend: Option<Box<dyn SheetsBackend>>,
+    grid_check: GridCheck,
+    sheets_cache: Mutex<Option<Vec<SheetProperties>>>,
+    /// Client email of the service account, `None` for unauthenticated drivers
+    principal: Option<String>,
+    /// Read every write back, see [`SpreadSheetDriver::with_verify_writes`]
+    verify_writes: bool,
+    breaker: Option<CircuitBreaker>,
+    retrier: Option<Retrier>,
+    rate_limiter: Option<RateLimiter>,
+    request_sink: Option<Box<dyn RequestSink>>,
+    limits: ResponseLimits,
+    write_allowlist: Option<WriteAllowlist>,
+    cell_transforms: Vec<Box<dyn CellTransform>>,
+}
+
+pub type SheetsClientConnector = Sheets<HttpsConnector<HttpConnector>>;
+
+impl SpreadSheetDriver {
+    /// Panics if secret is not provided or is invalid.
+    /// Use [`SpreadSheetDriver::builder`] for other credentials and errors instead of panics
+    pub async fn new(document_id: String, path_to_secret_json: &str) -> Self {
+        Self::builder(document_id)
+            .credentials(Credentials::ServiceAccountKeyFile(
+                path_to_secret_json.into(),
+            ))
+            .build()
+            .await
+            .expect("Expected to create driver from service account key")
+    }
+
+    /// Creates driver which sends requests without any credentials.
+    /// Only useful against an emulator (see [`SpreadSheetDriver::with_base_url`]) or in replay mode
+    pub fn unauthenticated(document_id: String) -> Self {
+        let sheet_client = Sheets::new(create_https_client(), NoToken);
+        Self::from_client(document_id, sheet_client, None)
+    }
+
+    fn from_client(
+        document_id: String,
+        sheet_client: SheetsClientConnector,
+        principal: Option<String>,
+    ) -> Self {
+        Self {
+            document_id,
+            sheets_client: SheetsClient(sheet_client),
+            cassette: None,
+            backend: None,
+            grid_check: GridCheck::default(),
+            sheets_cache: Mutex::new(None),
+            principal,
+            verify_writes: false,
+            breaker: None,
+            retrier: None,
+            rate_limiter: None,
+            request_sink: None,
+            limits: ResponseLimits::default(),
+            write_allowlist: None,
+            cell_transforms: vec![],
+        }
+    }
+
+    /// Creates credential-less driver which serves every call from the cassette.
+    /// Intended for offline and deterministic tests of the code built on top of the driver
+    pub fn replay(document_id: String, cassette: Cassette) -> Self {
+        Self::unauthenticated(document_id).with_cassette(cassette)
+    }
+
+    /// Creates driver which serves every call from the local backend instead of Google Sheets,
+    /// e.g. `MemoryBackend` or `XlsxBackend` (feature `xlsx`)
+    pub fn with_backend<B>(document_id: String, backend: B) -> Self
+    where
+        B: SheetsBackend + 'static,
+    {
+        let mut driver = Self::unauthenticated(document_id);
+        driver.backend = Some(Box::new(backend));
+        driver
+    }
+
+    pub fn backend(&self) -> Option<&dyn SheetsBackend> {
+        self.backend.as_deref()
+    }
+
+    /// Points the driver to another API host, e.g. a local emulator.
+    /// Expects URL with trailing slash, e.g. "http://127.0.0.1:8080/"
+    pub fn with_base_url<U>(mut self, base_url: U) -> Self
+    where
+        U: Display,
+    {
+        let base_url = base_url.to_string();
+        self.sheets_client.0.base_url(base_url.clone());
+        self.sheets_client.0.root_url(base_url);
+        self
+    }
+
+    /// Routes all API calls through the cassette (records or replays depending on its mode)
+    pub fn with_cassette(mut self, cassette: Cassette) -> Self {
+        self.cassette = Some(cassette);
+        self
+    }
+
+    pub fn cassette(&self) -> Option<&Cassette> {
+        self.cassette.as_ref()
+    }
+
+    /// Persists recorded interactions if the driver is in record mode
+    pub fn save_cassette(&self) -> SsdResult<()> {
+        match &self.cassette {
+            Some(cassette) => cassette.save(),
+            None => Ok(()),
+        }
+    }
+
+    fn client_ref(&self) -> &SheetsClientConnector {
+        &self.sheets_client.0
+    }
+
+    /// Account the requests are authenticated as (client email of the service account key)
+    pub fn principal(&self) -> Option<&str> {
+        self.principal.as_deref()
+    }
+
+    /// Maps the API failure to the driver error. Access and not found responses (the most
+    /// common setup failures) get dedicated variants with a hint on how to fix them
+    pub(crate) fn api_error(&self, error: Error) -> Report<SpreadSheetDriverError> {
+        let status = match &error {
+            Error::BadRequest(body) => body["error"]["code"].as_u64(),
+            Error::Failure(response) => Some(response.status().as_u16() as u64),
+            _ => None,
+        };
+        let failure = match (&error, status) {
+            (_, Some(status)) => Some(ApiFailure::Status(status as u16)),
+            (Error::HttpError(_) | Error::Io(_), None) => Some(ApiFailure::Transport),
+            _ => None,
+        };
+        let document_id = self.document_id.clone();
+        let principal = self
+            .principal
+            .clone()
+            .unwrap_or_else(|| "unauthenticated client".to_string());
+        let context = match status {
+            Some(403) => SpreadSheetDriverError::PermissionDenied {
+                hint: format!("Share the spreadsheet with {principal}"),
+                document_id,
+                principal,
+            },
+            Some(404) => SpreadSheetDriverError::SpreadsheetNotFound {
+                hint: format!(
+                    "Check the document id (the part of the URL between /d/ and /edit) \
+                     and that the spreadsheet is shared with {principal}"
+                ),
+                document_id,
+                principal,
+            },
+            _ => SpreadSheetDriverError::ApiError(error.to_string()),
+        };
+        let report = report!(context).attach_printable(error.to_string());
+        match failure {
+            Some(failure) => report.attach(failure),
+            None => report,
+        }
+    }
+
+    /// Single entry point for every API call, so the cassette and local backends are able to intercept it
+    async fn exchange<Resp, F, Fut>(
+        &self,
+        operation: &str,
+        request: Value,
+        call: F,
+    ) -> SsdResult<Resp>
+    where
+        Resp: Serialize + DeserializeOwned,
+        F: Fn() -> Fut,
+        Fut: Future<Output = SsdResult<Resp>>,
+    {
+        let Some(sink) = &self.request_sink else {
+            return self.exchange_retried(operation, request, call).await.0;
+        };
+        let context = CallContext::new(&self.document_id, operation, &request);
+        let started = Instant::now();
+        let (result, retries) = self.exchange_retried(operation, request, call).await;
+        sink.record(&RequestRecord::new(
+            context,
+            started.elapsed(),
+            retries,
+            &result,
+        ));
+        result
+    }
+
+    /// Short-circuited by the circuit breaker, if any
+    async fn exchange_guarded<Resp, F, Fut>(
+        &self,
+        operation: &str,
+        request: Value,
+        call: F,
+    ) -> SsdResult<Resp>
+    where
+        Resp: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = SsdResult<Resp>>,
+    {
+        let Some(breaker) = &self.breaker else {
+            return self.exchange_unguarded(operation, request, call).await;
+        };
+        let key = CircuitBreaker::key(operation, &request);
+        if let Some(cached) = breaker.admit(operation, &key)? {
+            debug!("Serving {operation} from cache, the circuit is open");
+            return serde_json::from_value(cached)
+                .map_err(Report::new)
+                .change_context(SpreadSheetDriverError::ParseError(format!(
+                    "Unexpected cached response to {operation}"
+                )));
+        }
+
+        let result = self.exchange_unguarded(operation, request, call).await;
+        breaker.record(operation, key, &result);
+        result
+    }
+
+    async fn exchange_unguarded<Resp, F, Fut>(
+        &self,
+        operation: &str,
+        request: Value,
+        call: F,
+    ) -> SsdResult<Resp>
+    where
+        Resp: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = SsdResult<Resp>>,
+    {
+        let backend_request = request.clone();
+        let transport = move || async move {
+            if let Some(limiter) = &self.rate_limiter {
+                limiter.acquire().await;
+            }
+            match &self.backend {
+                Some(backend) => backend
+                    .handle(operation, &backend_request)
+                    .and_then(|response| {
+                        serde_json::from_value(response)
+                            .map_err(Report::new)
+                            .change_context(SpreadSheetDriverError::ParseError(format!(
+                                "Unexpected backend response to {operation}"
+                            )))
+                    }),
+                None => call().await,
+            }
+        };
+
+        let context = CallContext::new(&self.document_id, operation, &request);
+        let checks = self
+            .check_write_allowed(&context)
+            .and_then(|()| self.check_requested_size(&context));
+        if let Err(error) = checks {
+            return Err(error.attach_printable(context));
+        }
+        match &self.cassette {
+            Some(cassette) => cassette.exchange(operation, request, transport).await,
+            None => transport().await,
+        }
+        .and_then(|response| {
+            self.check_response_size(operation, &response)
+                .map(|()| response)
+        })
+        .attach_printable(context)
+    }
+}
+
+/// Attached to [`SpreadSheetDriverError::ApiError`] reports of the calls which reached the API
+/// or failed on the way. Can be extracted with `report.downcast_ref::<ApiFailure>()`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiFailure {
+    /// HTTP status of the error response
+    Status(u16),
+    /// Connection or IO failure, no response was received
+    Transport,
+}
+
+/// Attached to every error of an API call, so a single log line pinpoints the failing call.
+/// Can be extracted with `report.request_ref::<CallContext>()`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallContext {
+    pub document_id: String,
+    pub operation: String,
+    /// Sheets of the ranges, in order, without duplicates
+    pub sheets: Vec<String>,
+    /// A1 ranges of the request, empty for calls addressed by data filters or to the whole document
+    pub ranges: Vec<String>,
+}
+
+impl CallContext {
+    fn new(document_id: &str, operation: &str, request: &Value) -> Self {
+        let ranges: Vec<String> = match (&request["range"], &request["ranges"], &request["data"]) {
+            (Value::String(range), _, _) => vec![range.clone()],
+            (_, Value::Array(ranges), _) => ranges
+                .iter()
+                .filter_map(|r| r.as_str().map(str::to_string))
+                .collect(),
+            // Value ranges of a batch write, data filters have no `range`
+            (_, _, Value::Array(data)) => data
+                .iter()
+                .filter_map(|d| d["range"].as_str().map(str::to_string))
+                .collect(),
+            _ => vec![],
+        };
+        let mut sheets: Vec<String> = vec![];
+        for range in &ranges {
+            let Some((sheet, _)) = range.rsplit_once('!') else {
+                continue;
+            };
+            let sheet = sheet.trim_matches('\'').to_string();
+            if !sheets.contains(&sheet) {
+                sheets.push(sheet);
+            }
+        }
+        Self {
+            document_id: document_id.to_string(),
+            operation: operation.to_string(),
+            sheets,
+            ranges,
+        }
+    }
+}
+
+impl Display for CallContext {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} on document {}", self.operation, self.document_id)?;
+        if !self.sheets.is_empty() {
+            write!(f, ", sheet {}", self.sheets.join(", "))?;
+        }
+        if !self.ranges.is_empty() {
+            write!(f, ", range {}", self.ranges.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Row which failed to deserialize, see [`SpreadSheetDriver::read_rows_with_errors`]
+#[derive(Debug, thiserror::Error)]
+#[error("Can't deserialize row at {position}: {}", .error.current_context())]
+pub struct RowError {
+    /// First cell of the row
+    pub position: SheetA1CellId,
+    pub row: SheetRow,
+    pub error: Report<ParseError>,
+}
+
+pub struct SheetsClient(pub SheetsClientConnector);
+
+impl Debug for SheetsClient {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SheetsClient").finish()
+    }
+}
+
+pub async fn create_http_client_from_secret_json(
+    path: &str,
+) -> (
+    Authenticator<HttpsConnector<HttpConnector>>,
+    Client<HttpsConnector<HttpConnector>>,
+) {
+    // Load the service account key from a file
+    let key = oauth2::read_service_account_key(path)
+        .await
+        .expect("Expected to read service account key");
+
+    create_http_client_from_key(key).await
+}
+
+pub async fn create_http_client_from_key(
+    key: oauth2::ServiceAccountKey,
+) -> (
+    Authenticator<HttpsConnector<HttpConnector>>,
+    Client<HttpsConnector<HttpConnector>>,
+) {
+    // Create a new authenticator
+    let auth = ServiceAccountAuthenticator::builder(key)
+        .build()
+        .await
+        .expect("Expected to create authenticator");
+
+    (auth, create_https_client())
+}
+
+pub fn create_https_client() -> Client<HttpsConnector<HttpConnector>> {
+    try_create_https_client().expect("Expected to create HTTPS client")
+}
+
+pub fn try_create_https_client() -> SsdResult<Client<HttpsConnector<HttpConnector>>> {
+    // Create a new HTTPS connector
+    let connector = hyper_rustls::HttpsConnectorBuilder::new()
+        .with_native_roots()
+        .change_context(SpreadSheetDriverError::AuthenticationFailed(
+            "can't load native root certificates".to_string(),
+        ))?
+        .https_or_http()
+        .enable_http1()
+        .enable_http2()
+        .build();
+
+    // Create a new hyper client
+    Ok(hyper::Client::builder().build(connector))
+}
+
+// TODO: Add API which deserialize `Vec<Vec<Value>>` into structs
+// APIs //
+impl SpreadSheetDriver {
+    /// Read API
+    #[deprecated(note = "Use `try_get_range`, it doesn't panic on API errors")]
+    pub async fn get_range<R>(&self, range: R) -> MatchedValueRange
+    where
+        R: ToString,
+    {
+        self.try_get_range(range)
+            .await
+            .expect("Expected to get range")
+    }
+
+    pub async fn try_get_range<R>(&self, range: R) -> SsdResult<MatchedValueRange>
+    where
+        R: ToString,
+    {
+        self.try_get_range_with(range, &ReadOptions::default())
+            .await
+    }
+
+    /// Same as [`SpreadSheetDriver::try_get_range`] but with explicit render options
+    pub async fn try_get_range_with<R>(
+        &self,
+        range: R,
+        options: &ReadOptions,
+    ) -> SsdResult<MatchedValueRange>
+    where
+        R: ToString,
+    {
+        let range_str = range.to_string();
+        let data: BatchGetValuesByDataFilterResponse = self
+            .exchange(
+                "values.batchGetByDataFilter",
+                read_request(json!({ "range": range_str }), options),
+                || async {
+                    get_ranges_as_rows(
+                        self.client_ref(),
+                        &self.document_id,
+                        vec![range_str.clone()],
+                        options,
+                    )
+                    .await
+                    .map(|(_, response)| response)
+                    .map_err(|e| self.api_error(e))
+                },
+            )
+            .await?;
+        // Moved out of the response: ranges may be large, and an empty list is no reason to panic
+        let Some(mut range) = data
+            .value_ranges
+            .and_then(|ranges| ranges.into_iter().next())
+        else {
+            bail!(SpreadSheetDriverError::RangeNotFound(range_str));
+        };
+        debug!(
+            "Range: {:?} result: {} rows",
+            range_str,
+            range
+                .value_range
+                .as_ref()
+                .and_then(|r| r.values.as_ref())
+                .map_or(0, Vec::len)
+        );
+        trace!("Range: {:?} result: {:#?}", range_str, range);
+        self.transform_matched_ranges(std::slice::from_mut(&mut range));
+        Ok(range)
+    }
+
+    /// Reads a single range through `values.get`: lighter than
+    /// [`SpreadSheetDriver::try_get_range`], no data filters are built and the plain
+    /// `ValueRange` comes back
+    pub async fn try_get_values<R>(&self, range: R) -> SsdResult<ValueRange>
+    where
+        R: ToString,
+    {
+        self.try_get_values_with(range, &ReadOptions::default())
+            .await
+    }
+
+    /// Same as [`SpreadSheetDriver::try_get_values`] but with explicit render options
+    pub async fn try_get_values_with<R>(
+        &self,
+        range: R,
+        options: &ReadOptions,
+    ) -> SsdResult<ValueRange>
+    where
+        R: ToString,
+    {
+        let range_str = range.to_string();
+        let mut data: ValueRange = self
+            .exchange(
+                "values.get",
+                read_request(json!({ "range": range_str }), options),
+                || async {
+                    let mut call = self
+                        .client_ref()
+                        .spreadsheets()
+                        .values_get(&self.document_id, &range_str)
+                        .value_render_option(options.value_render_option.as_str())
+                        .major_dimension(options.major_dimension.as_str());
+                    if let Some(date_time) = options.date_time_render_option {
+                        call = call.date_time_render_option(date_time.as_str());
+                    }
+                    call.doit()
+                        .await
+                        .map(|(_, response)| response)
+                        .map_err(|e| self.api_error(e))
+                },
+            )
+            .await?;
+        debug!(
+            "Range: {:?} result: {} rows",
+            range_str,
+            data.values.as_ref().map_or(0, Vec::len)
+        );
+        self.transform_value_range(&mut data);
+        Ok(data)
+    }
+
+    /// Reads several ranges (possibly from different sheets) in a single request.
+    /// Results are in the order of `ranges`
+    pub async fn try_get_ranges<R>(&self, ranges: &[R]) -> SsdResult<Vec<MatchedValueRange>>
+    where
+        R: ToString,
+    {
+        self.try_get_ranges_with(ranges, &ReadOptions::default())
+            .await
+    }
+
+    /// Same as [`SpreadSheetDriver::try_get_ranges`] but with explicit render options
+    pub async fn try_get_ranges_with<R>(
+        &self,
+        ranges: &[R],
+        options: &ReadOptions,
+    ) -> SsdResult<Vec<MatchedValueRange>>
+    where
+        R: ToString,
+    {
+        let ranges: Vec<String> = ranges.iter().map(ToString::to_string).collect();
+        let data: BatchGetValuesByDataFilterResponse = self
+            .exchange(
+                "values.batchGetByDataFilter",
+                read_request(json!({ "ranges": ranges }), options),
+                || async {
+                    get_ranges_as_rows(
+                        self.client_ref(),
+                        &self.document_id,
+                        ranges.clone(),
+                        options,
+                    )
+                    .await
+                    .map(|(_, response)| response)
+                    .map_err(|e| self.api_error(e))
+                },
+            )
+            .await?;
+
+        let mut value_ranges = data.value_ranges.unwrap_or_default();
+        if value_ranges.len() != ranges.len() {
+            bail!(SpreadSheetDriverError::RangeNotFound(format!(
+                "Expected {} ranges, got {}",
+                ranges.len(),
+                value_ranges.len()
+            )));
+        }
+        self.transform_matched_ranges(&mut value_ranges);
+        Ok(value_ranges)
+    }
+
+    /// Lowest level read: every range matched by any of the filters (A1 ranges, grid ranges,
+    /// developer metadata lookups). A filter may match several ranges or none,
+    /// so there's no 1:1 correspondence between filters and the result
+    pub async fn try_query(&self, filters: Vec<DataFilter>) -> SsdResult<Vec<MatchedValueRange>> {
+        self.try_query_with(filters, &ReadOptions::default()).await
+    }
+
+    /// Same as [`SpreadSheetDriver::try_query`] but with explicit render options
+    pub async fn try_query_with(
+        &self,
+        filters: Vec<DataFilter>,
+        options: &ReadOptions,
+    ) -> SsdResult<Vec<MatchedValueRange>> {
+        let data: BatchGetValuesByDataFilterResponse = self
+            .exchange(
+                "values.batchGetByDataFilter",
+                read_request(json!({ "dataFilters": filters }), options),
+                || async {
+                    query_data_filters(
+                        self.client_ref(),
+                        &self.document_id,
+                        filters.clone(),
+                        options,
+                    )
+                    .await
+                    .map(|(_, response)| response)
+                    .map_err(|e| self.api_error(e))
+                },
+            )
+            .await?;
+
+        let mut value_ranges = data.value_ranges.unwrap_or_default();
+        self.transform_matched_ranges(&mut value_ranges);
+        Ok(value_ranges)
+    }
+
+    /// Write api
+    #[deprecated(note = "Use `try_write_range`, it doesn't panic on API errors")]
+    pub async fn write_range(&self, range_str: &str, data: Vec<Vec<serde_json::Value>>) {
+        self.try_write_range(range_str, data)
+            .await
+            .unwrap_or_else(|e| panic!("Expected to write to spreadsheet: {:#?}", e))
+    }
+
+    pub async fn try_write_range(&self, range_str: &str, data: Vec<Vec<Value>>) -> SsdResult<()> {
+        self.try_write_range_as(range_str, data, InputMode::UserEntered)
+            .await
+    }
+
+    /// Same as [`SpreadSheetDriver::try_write_range`] but with explicit input mode
+    pub async fn try_write_range_as(
+        &self,
+        range_str: &str,
+        data: Vec<Vec<Value>>,
+        input_mode: InputMode,
+    ) -> SsdResult<()> {
+        let options = WriteOptions {
+            input_mode,
+            ..WriteOptions::default()
+        };
+        self.update_values(range_str, data, &options)
+            .await
+            .map(|_| ())
+    }
+
+    /// Same as [`SpreadSheetDriver::try_write_range`] but with explicit write options.
+    /// The values rendered by the sheet are in `values` of the summary if
+    /// `options.include_values_in_response` asks for them
+    pub async fn try_write_range_with(
+        &self,
+        range_str: &str,
+        data: Vec<Vec<Value>>,
+        options: &WriteOptions,
+    ) -> SsdResult<WriteSummary> {
+        let response = self.update_values(range_str, data, options).await?;
+        WriteSummary::from_response(&response)
+    }
+
+    /// Writes several ranges with a single `values.batchUpdate` call, which costs one request
+    /// of the write quota instead of one per range. Ranges are written in order, so a later one
+    /// wins where they overlap
+    pub async fn try_write_ranges(
+        &self,
+        data: Vec<(SheetA1Range, Vec<Vec<Value>>)>,
+    ) -> SsdResult<BatchUpdateValuesResponse> {
+        self.try_write_ranges_as(data, InputMode::UserEntered).await
+    }
+
+    /// Same as [`SpreadSheetDriver::try_write_ranges`] but with explicit input mode
+    pub async fn try_write_ranges_as(
+        &self,
+        data: Vec<(SheetA1Range, Vec<Vec<Value>>)>,
+        input_mode: InputMode,
+    ) -> SsdResult<BatchUpdateValuesResponse> {
+        let value_ranges: Vec<ValueRange> = data
+            .iter()
+            .map(|(range, values)| ValueRange {
+                major_dimension: None,
+                range: Some(range.to_string()),
+                values: Some(input_rows(values, input_mode)),
+            })
+            .collect();
+        for range in &value_ranges {
+            self.check_grid(range.range.as_deref().unwrap_or_default(), true)
+                .await?;
+        }
+        let req = BatchUpdateValuesRequest {
+            data: Some(value_ranges),
+            value_input_option: Some(input_mode.as_str().to_string()),
+            ..Default::default()
+        };
+        let response: BatchUpdateValuesResponse = self
+            .exchange(
+                "values.batchUpdate",
+                json!({ "data": req.data, "valueInputOption": input_mode.as_str() }),
+                || async {
+                    self.client_ref()
+                        .spreadsheets()
+                        .values_batch_update(req.clone(), self.document_id.as_str())
+                        .doit()
+                        .await
+                        .map(|(_, response)| response)
+                        .map_err(|e| self.api_error(e))
+                },
+            )
+            .await?;
+
+        if self.verify_writes {
+            for (range, values) in &data {
+                self.verify_write(&range.to_string(), values).await?;
+            }
+        }
+        Ok(response)
+    }
+
+    async fn update_values(
+        &self,
+        range_str: &str,
+        data: Vec<Vec<Value>>,
+        options: &WriteOptions,
+    ) -> SsdResult<UpdateValuesResponse> {
+        let WriteOptions {
+            input_mode,
+            include_values_in_response,
+        } = *options;
+        self.check_grid(range_str, true).await?;
+        let values = input_rows(&data, input_mode);
+        let mut request = json!({ "range": range_str, "values": values });
+        // Recorded only when not default, same as append
+        if input_mode != InputMode::UserEntered {
+            request["valueInputOption"] = json!(input_mode.as_str());
+        }
+        let request = response_values_request(request, include_values_in_response);
+        let response: UpdateValuesResponse = self
+            .exchange("values.update", request, || async {
+                let mut call = self
+                    .client_ref()
+                    .spreadsheets()
+                    .values_update(
+                        ValueRange {
+                            major_dimension: None,
+                            range: None,
+                            values: Some(values.clone()),
+                        },
+                        self.document_id.as_str(),
+                        range_str,
+                    )
+                    .value_input_option(input_mode.as_str());
+                if let Some(render) = include_values_in_response {
+                    call = call
+                        .include_values_in_response(true)
+                        .response_value_render_option(render.as_str());
+                }
+                call.doit()
+                    .await
+                    .map(|(_, response)| response)
+                    .map_err(|e| self.api_error(e))
+            })
+            .await?;
+
+        if self.verify_writes {
+            self.verify_write(range_str, &data).await?;
+        }
+        Ok(response)
+    }
+
+    /// Append API
+    pub async fn try_append_row<R>(
+        &self,
+        range: R,
+        row: Vec<Value>,
+    ) -> SsdResult<AppendValuesResponse>
+    where
+        R: Into<String>,
+    {
+        self.try_append_rows(range, vec![row]).await
+    }
+
+    /// Appends all rows with a single call, so the API places them next to each other
+    pub async fn try_append_rows<R>(
+        &self,
+        range: R,
+        rows: Vec<Vec<Value>>,
+    ) -> SsdResult<AppendValuesResponse>
+    where
+        R: Into<String>,
+    {
+        self.try_append_rows_as(range, rows, InputMode::UserEntered)
+            .await
+    }
+
+    /// Same as [`SpreadSheetDriver::try_append_rows`] but with explicit input mode
+    pub async fn try_append_rows_as<R>(
+        &self,
+        range: R,
+        rows: Vec<Vec<Value>>,
+        input_mode: InputMode,
+    ) -> SsdResult<AppendValuesResponse>
+    where
+        R: Into<String>,
+    {
+        let options = AppendOptions {
+            input_mode,
+            ..AppendOptions::default()
+        };
+        self.try_append_rows_with(range, rows, &options).await
+    }
+
+    /// Same as [`SpreadSheetDriver::try_append_rows`] but with explicit append options
+    pub async fn try_append_rows_with<R>(
+        &self,
+        range: R,
+        rows: Vec<Vec<Value>>,
+        options: &AppendOptions,
+    ) -> SsdResult<AppendValuesResponse>
+    where
+        R: Into<String>,
+    {
+        let AppendOptions {
+            input_mode,
+            insert_data_option,
+            include_values_in_response,
+        } = *options;
+        let range = range.into();
+        // Append adds rows on its own, only columns have to fit
+        self.check_grid(&range, false).await?;
+        let req = ValueRange {
+            major_dimension: Some(MajorDimension::Rows.to_string()),
+            range: Some(range.clone()),
+            values: Some(input_rows(&rows, input_mode)),
+        };
+        let mut request = json!({ "range": range, "values": req.values });
+        // Recorded only when not default, same as read options
+        if input_mode != InputMode::UserEntered {
+            request["valueInputOption"] = json!(input_mode.as_str());
+        }
+        if insert_data_option != InsertDataOption::Overwrite {
+            request["insertDataOption"] = json!(insert_data_option.as_str());
+        }
+        let request = response_values_request(request, include_values_in_response);
+        self.exchange("values.append", request, || async {
+            let mut call = self
+                .client_ref()
+                .spreadsheets()
+                .values_append(req.clone(), self.document_id.as_str(), range.as_str())
+                .value_input_option(input_mode.as_str())
+                .insert_data_option(insert_data_option.as_str());
+            if let Some(render) = include_values_in_response {
+                call = call
+                    .include_values_in_response(true)
+                    .response_value_render_option(render.as_str());
+            }
+            call.doit()
+                .await
+                .map_err(|e| self.api_error(e))
+                .map(|t| t.1)
+        })
+        .await
+    }
+
+    /// Appends rows to the table which starts at A1 of the sheet. The width of the table is the
+    /// width of its header row, so callers don't have to know the `A:T`-style range.
+    /// Rows wider than the headers are rejected, as their tail would land outside the table
+    pub async fn try_append_below_headers(
+        &self,
+        sheet: &str,
+        rows: Vec<Vec<Value>>,
+    ) -> SsdResult<AppendValuesResponse> {
+        let first = A1CellId::from_primitives("A", 1);
+        let last_col =
+            Letters::from_column_number(Letters::MAX_COLUMN_NUMBER).expect("Expected valid column");
+        let headers = SheetA1Range::new(
+            sheet,
+            A1Range::new(first.clone(), A1CellId::new(last_col, first.row)),
+        );
+        let mut header = self
+            .try_get_range(&headers)
+            .await?
+            .into_vec()
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+        while header
+            .last()
+            .is_some_and(|cell| cell.is_null() || cell.as_str() == Some(""))
+        {
+            header.pop();
+        }
+        if header.is_empty() {
+            bail!(SpreadSheetDriverError::InvalidArgument(format!(
+                "Sheet '{sheet}' has no header row"
+            )));
+        }
+
+        let width = header.len();
+        if let Some(row) = rows.iter().find(|row| row.len() > width) {
+            bail!(SpreadSheetDriverError::InvalidArgument(format!(
+                "Row of {} cells doesn't fit {width} columns of '{sheet}': {row:?}",
+                row.len()
+            )));
+        }
+        let table = SheetA1Range::new(
+            sheet,
+            A1Range::new(first.clone(), first.delta(width as i32 - 1, 0)),
+        );
+        self.try_append_rows(table.to_string(), rows).await
+    }
+
+    /// Typed API ///
+    #[deprecated(note = "Use `read_rows_with_errors`, it doesn't drop failed rows silently")]
+    pub async fn read_rows_deserialized_ignore_errors<T>(&self, range_str: &str) -> Vec<T>
+    where
+        T: SheetRowSerde,
+    {
+        let result = self.try_get_range(range_str).await;
+        let range = match result {
+            Ok(range) => range,
+            Err(_) => {
+                return vec![];
+            }
+        };
+
+        range
+            .into_vec()
+            .into_iter()
+            .filter_map(|row| {
+                let result = T::deserialize(row);
+                match result {
+                    Ok(v) => Some(v),
+                    Err(err) => {
+                        error!(
+                            "Failed to create {:?} from row.\nError: {}",
+                            type_name::<T>(),
+                            err.to_string_no_bt()
+                        );
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Deserializes every row it can: rows which fail are given back as [`RowError`]s with their
+    /// positions, so the caller decides whether to ignore, log or fail on them.
+    /// Fails only if the range can't be read
+    pub async fn read_rows_with_errors<T>(
+        &self,
+        range_str: &str,
+    ) -> SsdResult<(Vec<(SheetA1CellId, T)>, Vec<RowError>)>
+    where
+        T: SheetRowSerde,
+    {
+        let range = self.try_get_range(range_str).await?;
+        let located = matched_range(&range)?;
+        let start = &located.range.start;
+        let mut parsed = vec![];
+        let mut errors = vec![];
+        for (i, row) in range.into_vec().into_iter().enumerate() {
+            let position = SheetA1CellId::new(&located.sheet, start.delta(0, i as i32));
+            match T::deserialize(row.clone()) {
+                Ok(data) => parsed.push((position, data)),
+                Err(error) => errors.push(RowError {
+                    position,
+                    row,
+                    error,
+                }),
+            }
+        }
+        if !errors.is_empty() {
+            debug!(
+                "{} of {} rows of {} can't be read as {}",
+                errors.len(),
+                errors.len() + parsed.len(),
+                range_str,
+                type_name::<T>()
+            );
+        }
+        Ok((parsed, errors))
+    }
+
+    pub async fn read_rows_deserialized<T>(&self, range_str: &str) -> SsdResult<Vec<T>>
+    where
+        T: SheetRowSerde,
+    {
+        self.read_rows_deserialized_with(range_str, &ReadOptions::default())
+            .await
+    }
+
+    /// Reads raw cell values: numbers and booleans reach the mapper as JSON numbers and booleans
+    /// whatever display format (currency, percent, thousands separators) the sheet applies,
+    /// so numeric fields don't fail on "$1,234.50" or "15%"
+    pub async fn read_rows_unformatted_deserialized<T>(&self, range_str: &str) -> SsdResult<Vec<T>>
+    where
+        T: SheetRowSerde,
+    {
+        let options = ReadOptions {
+            value_render_option: ValueRenderOption::UnformattedValue,
+            ..ReadOptions::default()
+        };
+        self.read_rows_deserialized_with(range_str, &options).await
+    }
+
+    /// Same as [`SpreadSheetDriver::read_rows_deserialized`] with the first cell of every row
+    pub async fn read_rows_positioned<T>(
+        &self,
+        range_str: &str,
+    ) -> SsdResult<Vec<(SheetA1CellId, T)>>
+    where
+        T: SheetRowSerde,
+    {
+        let range = self.try_get_range(range_str).await?;
+        let located = matched_range(&range)?;
+        let start = &located.range.start;
+        range
+            .into_vec()
+            .into_iter()
+            .enumerate()
+            .map(|(i, row)| {
+                let row_dbg = format!("{:?}", row);
+                let data = T::deserialize(row)
+                    .change_context(SpreadSheetDriverError::ParseError(row_dbg))
+                    .attach_printable_lazy(|| format!("Row {i} of {range_str}"))?;
+                let position = SheetA1CellId::new(&located.sheet, start.delta(0, i as i32));
+                Ok((position, data))
+            })
+            .collect()
+    }
+
+    pub async fn read_rows_deserialized_with<T>(
+        &self,
+        range_str: &str,
+        options: &ReadOptions,
+    ) -> SsdResult<Vec<T>>
+    where
+        T: SheetRowSerde,
+    {
+        let range = self.try_get_range_with(range_str, options).await?;
+        let result: SsdResult<Vec<T>> = range
+            .into_vec()
+            .into_iter()
+            .enumerate()
+            .map(|(i, row)| {
+                let row_dbg = format!("{:?}", row);
+                T::deserialize(row)
+                    .change_context(SpreadSheetDriverError::ParseError(row_dbg))
+                    .attach_printable_lazy(|| {
+                        format!("Row {i} of {range_str} on document {}", self.document_id)
+                    })
+            })
+            .collect();
+        result
+    }
+}
+
+/// Range of the first A1 filter. Ranges matched by other filters (e.g. developer metadata
+/// lookups) fall back to the range of the returned values
+pub(crate) fn matched_range(range: &MatchedValueRange) -> SsdResult<SheetA1Range> {
+    let filters = range.data_filters.as_deref().unwrap_or_default();
+    let raw = filters
+        .iter()
+        .find_map(|filter| filter.a1_range.as_ref())
+        .or_else(|| range.value_range.as_ref().and_then(|v| v.range.as_ref()));
+    let Some(raw) = raw else {
+        bail!(SpreadSheetDriverError::RangeNotFound(format!(
+            "MatchedValueRange has neither A1 data filters nor values range ({} filters)",
+            filters.len()
+        )));
+    };
+
+    SheetA1Range::from_raw(raw)
+        .change_context_lazy(|| SpreadSheetDriverError::InvalidArgument(raw.to_string()))
+}
+
+pub async fn get_data_as_rows(
+    client: &Sheets<HttpsConnector<HttpConnector>>,
+    sheet: &str,
+    range_str: String,
+) -> Result<(Response<Body>, BatchGetValuesByDataFilterResponse), Error> {
+    get_ranges_as_rows(client, sheet, vec![range_str], &ReadOptions::default()).await
+}
+
+pub async fn get_ranges_as_rows(
+    client: &Sheets<HttpsConnector<HttpConnector>>,
+    sheet: &str,
+    ranges: Vec<String>,
+    options: &ReadOptions,
+) -> Result<(Response<Body>, BatchGetValuesByDataFilterResponse), Error> {
+    let filters = ranges
+        .into_iter()
+        .map(|range_str| DataFilter {
+            a1_range: Some(range_str),
+            developer_metadata_lookup: None,
+            grid_range: None,
+        })
+        .collect();
+    query_data_filters(client, sheet, filters, options).await
+}
+
+pub async fn query_data_filters(
+    client: &Sheets<HttpsConnector<HttpConnector>>,
+    sheet: &str,
+    filters: Vec<DataFilter>,
+    options: &ReadOptions,
+) -> Result<(Response<Body>, BatchGetValuesByDataFilterResponse), Error> {
+    let req = BatchGetValuesByDataFilterRequest {
+        data_filters: Some(filters),
+        date_time_render_option: options.date_time_render_option.map(|o| o.to_string()),
+        major_dimension: Some(options.major_dimension.to_string()),
+        value_render_option: Some(options.value_render_option.to_string()),
+    };
+
+    let result = client
+        .spreadsheets()
+        .values_batch_get_by_data_filter(req, sheet)
+        .doit()
+        .await;
+
+    let data = match result {
+        Ok(data) => data,
+        Err(err) => return Err(err),
+    };
+    Ok(data)
+}
+
+/// Adds non-default render options to the recorded read request.
+/// Default ones are left out, so cassettes recorded before the options existed still match
+fn read_request(mut request: Value, options: &ReadOptions) -> Value {
+    let defaults = ReadOptions::default();
+    if options.value_render_option != defaults.value_render_option {
+        request["valueRenderOption"] = json!(options.value_render_option.as_str());
+    }
+    if let Some(date_time) = options.date_time_render_option {
+        request["dateTimeRenderOption"] = json!(date_time.as_str());
+    }
+    if options.major_dimension != defaults.major_dimension {
+        request["majorDimension"] = json!(options.major_dimension.as_str());
+    }
+    request
+}
+
+/// Recorded only when asked for, so recordings of plain writes stay the same
+fn response_values_request(mut request: Value, render: Option<ResponseValueRenderOption>) -> Value {
+    if let Some(render) = render {
+        request["includeValuesInResponse"] = json!(true);
+        request["responseValueRenderOption"] = json!(render.as_str());
+    }
+    request
+}
+
+pub trait IntoStrVec {
+    /// Panics on non-string cells
+    #[deprecated(note = "Use `try_into_str_vec`, it doesn't panic on non-string cells")]
+    fn into_str_vec(self) -> Vec<Vec<String>>;
+    /// Fails on non-string cells, e.g. numbers read with `UNFORMATTED_VALUE`
+    fn try_into_str_vec(self) -> SsdResult<Vec<Vec<String>>>;
+    /// Empty if the response has no values
+    fn into_vec(self) -> Vec<Vec<Value>>;
+}
+
+impl IntoStrVec for MatchedValueRange {
+    fn into_str_vec(self) -> Vec<Vec<String>> {
+        self.try_into_str_vec()
+            .unwrap_or_else(|e| panic!("Expected string cells: {e:?}"))
+    }
+
+    fn try_into_str_vec(self) -> SsdResult<Vec<Vec<String>>> {
+        self.into_vec()
+            .into_iter()
+            .enumerate()
+            .map(|(y, row)| {
+                row.into_iter()
+                    .enumerate()
+                    .map(|(x, cell)| match cell {
+                        Value::String(s) => Ok(s),
+                        other => bail!(SpreadSheetDriverError::ParseError(format!(
+                            "Cell {other} at row {y}, column {x} is not a string"
+                        ))),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn into_vec(self) -> Vec<Vec<Value>> {
+        self.value_range
+            .and_then(|range| range.values)
+            .unwrap_or_default()
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod driver_tests {
+    use super::*;
+    use crate::mapper::sheet_row;
+    use crate::mapper::sheet_row::{SheetRow, SheetRowExt};
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use crate::spread_sheet_driver::cassette::{Cassette, Interaction};
+    use crate::spread_sheet_driver::metadata::metadata_filter;
+    use crate::testing::fixtures::MatchedValueRangeBuilder;
+
+    fn bad_request(code: u64, status: &str) -> Error {
+        Error::BadRequest(json!({
+            "error": { "code": code, "message": "Request failed", "status": status }
+        }))
+    }
+
+    #[test]
+    fn api_error__forbidden__permission_denied_with_hint() {
+        let mut driver = SpreadSheetDriver::unauthenticated("document".to_string());
+        driver.principal = Some("bot@project.iam.gserviceaccount.com".to_string());
+
+        let report = driver.api_error(bad_request(403, "PERMISSION_DENIED"));
+
+        match report.current_context() {
+            SpreadSheetDriverError::PermissionDenied {
+                document_id,
+                principal,
+                hint,
+            } => {
+                assert_eq!(document_id, "document");
+                assert_eq!(principal, "bot@project.iam.gserviceaccount.com");
+                assert!(hint.contains("bot@project.iam.gserviceaccount.com"));
+            }
+            other => panic!("Test: Unexpected error {other:?}"),
+        }
+    }
+
+    #[test]
+    fn api_error__not_found_and_other__classified() {
+        let driver = SpreadSheetDriver::unauthenticated("document".to_string());
+
+        let report = driver.api_error(bad_request(404, "NOT_FOUND"));
+        assert!(matches!(
+            report.current_context(),
+            SpreadSheetDriverError::SpreadsheetNotFound { .. }
+        ));
+
+        let report = driver.api_error(bad_request(400, "INVALID_ARGUMENT"));
+        assert!(matches!(
+            report.current_context(),
+            SpreadSheetDriverError::ApiError(_)
+        ));
+    }
+
+    #[test]
+    fn try_into_str_vec__number_cell__error_instead_of_panic() {
+        let range = MatchedValueRange {
+            value_range: Some(ValueRange {
+                values: Some(vec![vec![json!("id"), json!(1)]]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let err = range
+            .try_into_str_vec()
+            .expect_err("Test: Expected number cell to fail");
+        assert!(matches!(
+            err.current_context(),
+            SpreadSheetDriverError::ParseError(_)
+        ));
+        assert!(
+            MatchedValueRange::default().into_vec().is_empty(),
+            "Test: Expected no rows instead of a panic"
+        );
+    }
+
+    #[tokio::test]
+    async fn exchange__failed_call__call_context_attached() {
+        let driver = SpreadSheetDriver::replay(
+            "document".to_string(),
+            Cassette::replay_from("unused.json", vec![]),
+        );
+
+        let report = driver
+            .try_get_ranges(&["'my users'!A1:B2", "'my users'!D1:D2"])
+            .await
+            .expect_err("Test: Expected replay miss");
+
+        let context = report
+            .request_ref::<CallContext>()
+            .next()
+            .expect("Test: Expected call context");
+        assert_eq!(context.sheets, vec!["my users".to_string()]);
+        assert_eq!(
+            context.to_string(),
+            "values.batchGetByDataFilter on document document, sheet my users, \
+             range 'my users'!A1:B2, 'my users'!D1:D2"
+        );
+    }
+
+    #[tokio::test]
+    async fn try_query__filter_matching_several_ranges__all_returned() {
+        let filters = vec![metadata_filter("table", "users")];
+        let response = BatchGetValuesByDataFilterResponse {
+            value_ranges: Some(vec![
+                MatchedValueRangeBuilder::new("users!A1:B1")
+                    .row(["1", "Joe"])
+                    .build(),
+                MatchedValueRangeBuilder::new("archive!A1:B1")
+                    .row(["2", "John"])
+                    .build(),
+            ]),
+            ..Default::default()
+        };
+        let driver = SpreadSheetDriver::replay(
+            "document".to_string(),
+            Cassette::replay_from(
+                "unused.json",
+                vec![Interaction {
+                    operation: "values.batchGetByDataFilter".to_string(),
+                    request: json!({ "dataFilters": filters }),
+                    response: serde_json::to_value(response).expect("Test: Expected to serialize"),
+                }],
+            ),
+        );
+
+        let ranges = driver
+            .try_query(filters)
+            .await
+            .expect("Test: Expected query to succeed");
+
+        assert_eq!(ranges.len(), 2);
+        assert_eq!(
+            ranges[1].clone().into_vec(),
+            vec![vec![json!("2"), json!("John")]]
+        );
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Product {
+        price: f64,
+        quantity: i64,
+        active: bool,
+    }
+
+    impl SheetRowSerde for Product {
+        fn deserialize(row: SheetRow) -> sheet_row::Result<Self> {
+            Ok(Self {
+                price: row.parse_cell(0, "price")?,
+                quantity: row.parse_cell(1, "quantity")?,
+                active: row.parse_cell(2, "active")?,
+            })
+        }
+
+        fn serialize(&self) -> sheet_row::Result<SheetRow> {
+            Ok(vec![
+                json!(self.price),
+                json!(self.quantity),
+                json!(self.active),
+            ])
+        }
+    }
+
+    #[tokio::test]
+    async fn read_rows_unformatted_deserialized__native_values__parsed() {
+        let backend = MemoryBackend::new();
+        backend.workbook().set_sheet(
+            "products",
+            vec![vec![json!(1234.5), json!(3.0), json!(true)]],
+        );
+        let driver = SpreadSheetDriver::with_backend("document".to_string(), backend);
+
+        let products: Vec<Product> = driver
+            .read_rows_unformatted_deserialized("products!A1:C1")
+            .await
+            .expect("Test: Expected native values to parse");
+
+        assert_eq!(
+            products,
+            vec![Product {
+                price: 1234.5,
+                quantity: 3,
+                active: true,
+            }]
+        );
+        let row = products[0]
+            .serialize()
+            .expect("Test: Expected to serialize");
+        assert_eq!(
+            Product::deserialize(row).expect("Test: Expected to parse"),
+            products[0]
+        );
+    }
+
+    #[tokio::test]
+    async fn read_rows_positioned__offset_range__positions_of_rows() {
+        let backend = MemoryBackend::new();
+        backend.workbook().set_sheet(
+            "products",
+            vec![
+                vec![],
+                vec![json!(""), json!(10), json!(1), json!(true)],
+                vec![json!(""), json!(20), json!(2), json!(false)],
+            ],
+        );
+        let driver = SpreadSheetDriver::with_backend("document".to_string(), backend);
+
+        let products: Vec<(SheetA1CellId, Product)> = driver
+            .read_rows_positioned("products!B2:D3")
+            .await
+            .expect("Test: Expected positioned rows");
+
+        let positions: Vec<SheetA1CellId> = products.iter().map(|(p, _)| p.clone()).collect();
+        assert_eq!(
+            positions,
+            vec![
+                SheetA1CellId::from_primitives("products", "B", 2),
+                SheetA1CellId::from_primitives("products", "B", 3),
+            ]
+        );
+        assert_eq!(products[1].1.quantity, 2);
+    }
+
+    #[tokio::test]
+    async fn read_rows_with_errors__invalid_row__kept_with_its_position() {
+        let backend = MemoryBackend::new();
+        backend.workbook().set_sheet(
+            "products",
+            vec![
+                vec![json!(10), json!(1), json!(true)],
+                vec![json!("cheap"), json!(2), json!(false)],
+                vec![json!(30), json!(3), json!(true)],
+            ],
+        );
+        let driver = SpreadSheetDriver::with_backend("document".to_string(), backend);
+
+        let (products, errors) = driver
+            .read_rows_with_errors::<Product>("products!A1:C3")
+            .await
+            .expect("Test: Expected the range to be read");
+
+        assert_eq!(products.len(), 2);
+        assert_eq!(
+            products[1].0,
+            SheetA1CellId::from_primitives("products", "A", 3)
+        );
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].position,
+            SheetA1CellId::from_primitives("products", "A", 2)
+        );
+        assert_eq!(errors[0].row[0], json!("cheap"));
+    }
+
+    #[tokio::test]
+    async fn try_get_values__memory_backend__plain_value_range() {
+        let backend = MemoryBackend::new();
+        backend.workbook().set_sheet(
+            "users",
+            vec![
+                vec![json!("1"), json!("Joe")],
+                vec![json!("2"), json!("Jane")],
+            ],
+        );
+        let driver = SpreadSheetDriver::with_backend("document".to_string(), backend);
+
+        let values = driver
+            .try_get_values("users!A2:B3")
+            .await
+            .expect("Test: Expected values");
+
+        assert_eq!(values.range.as_deref(), Some("users!A2:B3"));
+        assert_eq!(values.values, Some(vec![vec![json!("2"), json!("Jane")]]));
+    }
+
+    #[tokio::test]
+    async fn try_write_ranges__several_ranges__single_call() {
+        let driver = SpreadSheetDriver::with_backend("document".to_string(), MemoryBackend::new());
+
+        let response = driver
+            .try_write_ranges(vec![
+                (
+                    SheetA1Range::from_raw("users!A1:B1").expect("Test: Expected range"),
+                    vec![vec![json!("1"), json!("Joe")]],
+                ),
+                (
+                    SheetA1Range::from_raw("orders!B2:B3").expect("Test: Expected range"),
+                    vec![vec![json!("10")], vec![json!("20")]],
+                ),
+            ])
+            .await
+            .expect("Test: Expected batch write");
+
+        assert_eq!(response.total_updated_cells, Some(4));
+        assert_eq!(response.total_updated_sheets, Some(2));
+        assert_eq!(response.responses.map(|r| r.len()), Some(2));
+        let users = driver
+            .try_get_values("users!A1:B1")
+            .await
+            .expect("Test: Expected read");
+        assert_eq!(users.values, Some(vec![vec![json!("1"), json!("Joe")]]));
+        let orders = driver
+            .try_get_values("orders!B2:B3")
+            .await
+            .expect("Test: Expected read");
+        assert_eq!(
+            orders.values,
+            Some(vec![vec![json!("10")], vec![json!("20")]])
+        );
+    }
+
+    #[tokio::test]
+    async fn try_write_range_with__include_values__server_rendered_values_returned() {
+        let cassette = Cassette::replay_from(
+            "unused.json",
+            vec![Interaction {
+                operation: "values.update".to_string(),
+                request: json!({
+                    "range": "users!A1:B1",
+                    "values": [["1", "2024-01-05"]],
+                    "includeValuesInResponse": true,
+                    "responseValueRenderOption": "FORMATTED_VALUE"
+                }),
+                response: json!({
+                    "updatedRange": "users!A1:B1",
+                    "updatedData": { "range": "users!A1:B1", "values": [["1", "1/5/2024"]] }
+                }),
+            }],
+        );
+        let driver = SpreadSheetDriver::replay("document".to_string(), cassette);
+        let options = WriteOptions {
+            include_values_in_response: Some(ValueRenderOption::FormattedValue),
+            ..WriteOptions::default()
+        };
+
+        let response = driver
+            .try_write_range_with(
+                "users!A1:B1",
+                vec![vec![json!("1"), json!("2024-01-05")]],
+                &options,
+            )
+            .await
+            .expect("Test: Expected write with values in response");
+
+        assert_eq!(
+            response.values,
+            Some(vec![vec![json!("1"), json!("1/5/2024")]])
+        );
+    }
+
+    #[tokio::test]
+    async fn try_get_range__no_value_ranges__range_not_found() {
+        let cassette = Cassette::replay_from(
+            "unused.json",
+            vec![Interaction {
+                operation: "values.batchGetByDataFilter".to_string(),
+                request: json!({ "range": "users!A1:B2" }),
+                response: json!({ "valueRanges": [] }),
+            }],
+        );
+        let driver = SpreadSheetDriver::replay("document".to_string(), cassette);
+
+        let report = driver
+            .try_get_range("users!A1:B2")
+            .await
+            .expect_err("Test: Expected missing range instead of a panic");
+
+        assert!(matches!(
+            report.current_context(),
+            SpreadSheetDriverError::RangeNotFound(range) if range == "users!A1:B2"
+        ));
+    }
+
+    #[tokio::test]
+    async fn try_append_rows_with__insert_rows__recorded_in_request() {
+        let cassette = Cassette::replay_from(
+            "unused.json",
+            vec![Interaction {
+                operation: "values.append".to_string(),
+                request: json!({
+                    "range": "users!A1:B10",
+                    "values": [["2", "John"]],
+                    "valueInputOption": "RAW",
+                    "insertDataOption": "INSERT_ROWS"
+                }),
+                response: serde_json::to_value(AppendValuesResponse::default())
+                    .expect("Test: Expected to serialize"),
+            }],
+        );
+        let driver = SpreadSheetDriver::replay("document".to_string(), cassette);
+        let options = AppendOptions {
+            input_mode: InputMode::Raw,
+            insert_data_option: InsertDataOption::InsertRows,
+            ..AppendOptions::default()
+        };
+
+        driver
+            .try_append_rows_with(
+                "users!A1:B10",
+                vec![vec![json!("2"), json!("John")]],
+                &options,
+            )
+            .await
+            .expect("Test: Expected append with options");
+    }
+
+    #[tokio::test]
+    async fn try_append_below_headers__header_width__appended_into_table() {
+        let backend = MemoryBackend::new();
+        backend.workbook().set_sheet(
+            "users",
+            vec![
+                vec![json!("id"), json!("name"), json!("")],
+                vec![json!("1"), json!("Joe")],
+            ],
+        );
+        let driver = SpreadSheetDriver::with_backend("document".to_string(), backend);
+
+        let response = driver
+            .try_append_below_headers("users", vec![vec![json!("2"), json!("John")]])
+            .await
+            .expect("Test: Expected append");
+        assert_eq!(response.table_range.as_deref(), Some("users!A1:B1"));
+
+        let err = driver
+            .try_append_below_headers("users", vec![vec![json!("3"), json!("Jane"), json!(1)]])
+            .await
+            .expect_err("Test: Expected too wide row to be rejected");
+        assert!(matches!(
+            err.current_context(),
+            SpreadSheetDriverError::InvalidArgument(_)
+        ));
+        let rows = driver
+            .try_get_range("users!A1:C4")
+            .await
+            .expect("Test: Expected table")
+            .into_vec();
+        assert_eq!(rows.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn try_append_below_headers__header_past_zz__whole_header_width() {
+        let backend = MemoryBackend::new();
+        let header = (1..=703).map(|i| json!(format!("h{i}"))).collect();
+        backend.workbook().set_sheet("wide", vec![header]);
+        let driver = SpreadSheetDriver::with_backend("document".to_string(), backend);
+
+        let response = driver
+            .try_append_below_headers("wide", vec![vec![json!("1")]])
+            .await
+            .expect("Test: Expected append");
+
+        assert_eq!(response.table_range.as_deref(), Some("wide!A1:AAA1"));
+    }
+}
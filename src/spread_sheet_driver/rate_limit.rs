@@ -0,0 +1,102 @@
+//////////////////////// Client side rate limit ////////////////////////
+
+use crate::spread_sheet_driver::SpreadSheetDriver;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::trace;
+
+/// Calls sent to the API per period, counted over every call of the driver
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub period: Duration,
+}
+
+impl Default for RateLimit {
+    /// Default per-user quota of the Sheets API
+    fn default() -> Self {
+        Self {
+            requests: 60,
+            period: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RateLimit {
+    fn interval(&self) -> Duration {
+        self.period / self.requests.max(1)
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    interval: Duration,
+    /// Earliest start of the next call, `None` before the first one
+    next: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    fn new(limit: RateLimit) -> Self {
+        Self {
+            interval: limit.interval(),
+            next: Mutex::new(None),
+        }
+    }
+
+    /// Waits for the slot of the call. Slots are spaced evenly, so no burst exceeds the quota
+    pub(crate) async fn acquire(&self) {
+        let now = Instant::now();
+        let slot = {
+            let mut next = self.next.lock().unwrap_or_else(|e| e.into_inner());
+            let slot = next.map_or(now, |next| next.max(now));
+            *next = Some(slot + self.interval);
+            slot
+        };
+        let wait = slot.saturating_duration_since(now);
+        if !wait.is_zero() {
+            trace!("Rate limited, the call waits {:?}", wait);
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+impl SpreadSheetDriver {
+    /// Delays calls so they stay within `limit`, instead of running into the API quota and
+    /// failing with 429. Every call sent by the driver takes a slot, retries included.
+    /// Calls replayed from a cassette or served from the circuit breaker cache don't
+    pub fn with_rate_limit(mut self, limit: RateLimit) -> Self {
+        self.rate_limiter = Some(RateLimiter::new(limit));
+        self
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod rate_limit_tests {
+    use super::*;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use serde_json::Value;
+
+    #[tokio::test]
+    async fn with_rate_limit__several_calls__spaced_by_interval() {
+        let backend = MemoryBackend::new();
+        backend
+            .workbook()
+            .set_sheet("users", vec![vec![Value::from("1"), Value::from("Joe")]]);
+        let driver = SpreadSheetDriver::with_backend("document".to_string(), backend)
+            .with_rate_limit(RateLimit {
+                requests: 20,
+                period: Duration::from_secs(1),
+            });
+        let started = Instant::now();
+
+        for _ in 0..3 {
+            driver
+                .try_get_range("users!A1:B1")
+                .await
+                .expect("Test: Expected read");
+        }
+
+        assert!(started.elapsed() >= Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,195 @@
+//////////////////////// Structured log of API calls ////////////////////////
+
+use crate::spread_sheet_driver::breaker::is_read;
+use crate::spread_sheet_driver::{CallContext, SpreadSheetDriver, SsdResult};
+use google_sheets4::chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::warn;
+
+/// Receives a record per API call (including calls served by a cassette or a local backend).
+/// Called on the task making the call, so slow sinks should hand records off elsewhere
+pub trait RequestSink: Debug + Send + Sync {
+    fn record(&self, record: &RequestRecord);
+}
+
+/// Single API call, serialized as one JSON object
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestRecord {
+    /// RFC 3339 UTC time the call finished
+    pub timestamp: String,
+    pub document_id: String,
+    pub operation: String,
+    /// `false` for reads, so audit pipelines can keep only mutations
+    pub mutation: bool,
+    pub ranges: Vec<String>,
+    pub duration_ms: u64,
+    /// Attempts after the first one
+    pub retries: u32,
+    pub outcome: RequestOutcome,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum RequestOutcome {
+    Ok,
+    Failed { error: String },
+}
+
+impl RequestRecord {
+    pub(crate) fn new<T>(
+        context: CallContext,
+        duration: Duration,
+        retries: u32,
+        result: &SsdResult<T>,
+    ) -> Self {
+        Self {
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            mutation: !is_read(&context.operation),
+            document_id: context.document_id,
+            operation: context.operation,
+            ranges: context.ranges,
+            duration_ms: duration.as_millis() as u64,
+            retries,
+            outcome: match result {
+                Ok(_) => RequestOutcome::Ok,
+                Err(error) => RequestOutcome::Failed {
+                    error: error.current_context().to_string(),
+                },
+            },
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// Writes every record as a line of JSON, e.g. to a file
+#[derive(Debug)]
+pub struct JsonLinesSink<W> {
+    writer: Mutex<W>,
+}
+
+impl<W> JsonLinesSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+        }
+    }
+}
+
+impl<W> RequestSink for JsonLinesSink<W>
+where
+    W: Write + Debug + Send,
+{
+    fn record(&self, record: &RequestRecord) {
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = writeln!(writer, "{}", record.to_json()) {
+            warn!("Can't write request record: {e}");
+        }
+    }
+}
+
+impl SpreadSheetDriver {
+    /// Sends a [`RequestRecord`] of every API call to the sink, independently of tracing
+    pub fn with_request_sink<S>(mut self, sink: S) -> Self
+    where
+        S: RequestSink + 'static,
+    {
+        self.request_sink = Some(Box::new(sink));
+        self
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod request_log_tests {
+    use super::*;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use serde_json::Value;
+    use std::sync::Arc;
+
+    #[derive(Debug, Default)]
+    struct CollectingSink {
+        records: Arc<Mutex<Vec<RequestRecord>>>,
+    }
+
+    impl RequestSink for CollectingSink {
+        fn record(&self, record: &RequestRecord) {
+            self.records.lock().unwrap().push(record.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn with_request_sink__write_read_and_failure__recorded() {
+        let sink = CollectingSink::default();
+        let records = sink.records.clone();
+        let driver = SpreadSheetDriver::with_backend("document".to_string(), MemoryBackend::new())
+            .with_request_sink(sink);
+
+        driver
+            .try_write_range(
+                "users!A1:B1",
+                vec![vec![Value::from("1"), Value::from("Joe")]],
+            )
+            .await
+            .expect("Test: Expected write");
+        driver
+            .try_get_range("users!A1:B1")
+            .await
+            .expect("Test: Expected read");
+        driver
+            .try_batch_update(vec![])
+            .await
+            .expect_err("Test: Expected unsupported operation");
+
+        let records = records.lock().unwrap();
+        let summary: Vec<(&str, bool, Vec<String>)> = records
+            .iter()
+            .map(|r| (r.operation.as_str(), r.mutation, r.ranges.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("values.update", true, vec!["users!A1:B1".to_string()]),
+                (
+                    "values.batchGetByDataFilter",
+                    false,
+                    vec!["users!A1:B1".to_string()]
+                ),
+                ("spreadsheets.batchUpdate", true, vec![]),
+            ]
+        );
+        assert_eq!(records[0].outcome, RequestOutcome::Ok);
+        assert!(matches!(records[2].outcome, RequestOutcome::Failed { .. }));
+    }
+
+    #[test]
+    fn to_json__failed_call__tagged_outcome() {
+        let record = RequestRecord {
+            timestamp: "2024-01-01T00:00:00.000Z".to_string(),
+            document_id: "document".to_string(),
+            operation: "values.append".to_string(),
+            mutation: true,
+            ranges: vec!["users!A1:B10".to_string()],
+            duration_ms: 12,
+            retries: 0,
+            outcome: RequestOutcome::Failed {
+                error: "Spreadsheet API error (Service Unavailable)".to_string(),
+            },
+        };
+
+        let json: Value = serde_json::from_str(&record.to_json()).expect("Test: Expected JSON");
+
+        assert_eq!(json["outcome"]["status"], "failed");
+        assert_eq!(
+            json["outcome"]["error"],
+            "Spreadsheet API error (Service Unavailable)"
+        );
+        assert_eq!(json["duration_ms"], 12);
+    }
+}
@@ -0,0 +1,150 @@
+//////////////////////// Typed write responses ////////////////////////
+
+use crate::mapper::sheet_row::SheetRow;
+use crate::spread_sheet_driver::{SpreadSheetDriverError, SsdResult};
+use crate::types::SheetA1Range;
+use error_stack::{ResultExt, bail};
+use google_sheets4::api::{AppendValuesResponse, UpdateValuesResponse};
+
+/// What a write changed, read from an [`UpdateValuesResponse`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct WriteSummary {
+    pub spreadsheet_id: Option<String>,
+    /// Cells which were written, e.g. "users!A2:C2"
+    pub range: SheetA1Range,
+    pub rows: u32,
+    pub columns: u32,
+    pub cells: u32,
+    /// Written cells as the sheet holds them, if the write asked for them
+    /// (see [`crate::types::WriteOptions::include_values_in_response`])
+    pub values: Option<Vec<SheetRow>>,
+}
+
+impl WriteSummary {
+    /// Fails if the response has no parsable `updated_range`. Missing counts are 0
+    pub fn from_response(response: &UpdateValuesResponse) -> SsdResult<Self> {
+        let Some(raw) = &response.updated_range else {
+            bail!(SpreadSheetDriverError::UnexpectedResponse(
+                "UpdateValuesResponse doesn't have 'updated_range'".to_string()
+            ));
+        };
+        let range = SheetA1Range::from_raw(raw).change_context_lazy(|| {
+            SpreadSheetDriverError::UnexpectedResponse(format!("Updated range {raw} is not A1"))
+        })?;
+
+        Ok(Self {
+            spreadsheet_id: response.spreadsheet_id.clone(),
+            range,
+            rows: count(response.updated_rows),
+            columns: count(response.updated_columns),
+            cells: count(response.updated_cells),
+            values: response
+                .updated_data
+                .as_ref()
+                .map(|data| data.values.clone().unwrap_or_default()),
+        })
+    }
+}
+
+/// What an append changed, read from an [`AppendValuesResponse`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppendSummary {
+    pub spreadsheet_id: Option<String>,
+    /// Table the rows were appended to, `None` if the API found no table (e.g. empty sheet)
+    pub table: Option<SheetA1Range>,
+    pub updates: WriteSummary,
+}
+
+impl AppendSummary {
+    /// Fails if the response has no `updates` or they have no parsable range
+    pub fn from_response(response: &AppendValuesResponse) -> SsdResult<Self> {
+        let Some(updates) = &response.updates else {
+            bail!(SpreadSheetDriverError::UnexpectedResponse(
+                "AppendValuesResponse doesn't have 'updates'".to_string()
+            ));
+        };
+        let table = response
+            .table_range
+            .as_deref()
+            .map(SheetA1Range::from_raw)
+            .transpose()
+            .change_context_lazy(|| {
+                SpreadSheetDriverError::UnexpectedResponse(format!(
+                    "Table range {:?} is not A1",
+                    response.table_range
+                ))
+            })?;
+
+        Ok(Self {
+            spreadsheet_id: response.spreadsheet_id.clone(),
+            table,
+            updates: WriteSummary::from_response(updates)?,
+        })
+    }
+}
+
+fn count(value: Option<i32>) -> u32 {
+    value.unwrap_or_default().max(0) as u32
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod responses_tests {
+    use super::*;
+    use google_sheets4::api::ValueRange;
+    use serde_json::json;
+
+    #[test]
+    fn append_summary__full_response__parsed_ranges_and_counts() {
+        let response = AppendValuesResponse {
+            spreadsheet_id: Some("document".to_string()),
+            table_range: Some("users!A1:B3".to_string()),
+            updates: Some(UpdateValuesResponse {
+                spreadsheet_id: Some("document".to_string()),
+                updated_range: Some("users!A4:B5".to_string()),
+                updated_rows: Some(2),
+                updated_columns: Some(2),
+                updated_cells: Some(4),
+                updated_data: Some(ValueRange {
+                    values: Some(vec![vec![json!("1")], vec![json!("2")]]),
+                    ..Default::default()
+                }),
+            }),
+        };
+
+        let summary = AppendSummary::from_response(&response).expect("Test: Expected summary");
+
+        assert_eq!(summary.spreadsheet_id.as_deref(), Some("document"));
+        assert_eq!(
+            summary.table,
+            Some(SheetA1Range::from_raw("users!A1:B3").unwrap())
+        );
+        assert_eq!(
+            summary.updates.range,
+            SheetA1Range::from_raw("users!A4:B5").unwrap()
+        );
+        assert_eq!(
+            (
+                summary.updates.rows,
+                summary.updates.columns,
+                summary.updates.cells
+            ),
+            (2, 2, 4)
+        );
+        assert_eq!(
+            summary.updates.values,
+            Some(vec![vec![json!("1")], vec![json!("2")]])
+        );
+    }
+
+    #[test]
+    fn append_summary__no_updates__unexpected_response() {
+        let err = AppendSummary::from_response(&AppendValuesResponse::default())
+            .expect_err("Test: Expected missing updates to fail");
+
+        assert!(matches!(
+            err.current_context(),
+            SpreadSheetDriverError::UnexpectedResponse(_)
+        ));
+    }
+}
@@ -0,0 +1,402 @@
+//////////////////////// Retries of failed API calls ////////////////////////
+
+use crate::spread_sheet_driver::breaker::is_read;
+use crate::spread_sheet_driver::{
+    ApiFailure, SpreadSheetDriver, SpreadSheetDriverError, SsdResult,
+};
+use error_stack::Report;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryConfig {
+    /// Retries of a single call, or of all the calls sharing a [`RetryBudget`]
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled before every next one
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Time from the start of the budget after which nothing is retried anymore
+    pub max_elapsed: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+            max_elapsed: Duration::from_secs(20),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct BudgetState {
+    retries_left: u32,
+    deadline: Instant,
+}
+
+/// Retries and time left to the calls of one logical operation. Clones share the budget
+#[derive(Debug, Clone)]
+pub struct RetryBudget {
+    state: Arc<Mutex<BudgetState>>,
+}
+
+impl RetryBudget {
+    pub fn new(config: &RetryConfig) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(BudgetState {
+                retries_left: config.max_retries,
+                deadline: Instant::now() + config.max_elapsed,
+            })),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, BudgetState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn retries_left(&self) -> u32 {
+        self.state().retries_left
+    }
+
+    /// Takes a retry if there's one left and the retry, after `delay`, starts before the deadline
+    fn try_take(&self, delay: Duration) -> bool {
+        let mut state = self.state();
+        if state.retries_left == 0 || Instant::now() + delay >= state.deadline {
+            return false;
+        }
+        state.retries_left -= 1;
+        true
+    }
+
+    /// Runs `operation` within the budget: its calls share the retries, calls of other
+    /// operations running meanwhile don't. Tasks spawned by `operation` don't inherit it
+    pub async fn scope<F>(self, operation: F) -> F::Output
+    where
+        F: Future,
+    {
+        OPERATION_BUDGET.scope(self, operation).await
+    }
+
+    /// Budget of the operation the current task runs in, if any
+    fn current() -> Option<RetryBudget> {
+        OPERATION_BUDGET.try_with(RetryBudget::clone).ok()
+    }
+}
+
+tokio::task_local! {
+    static OPERATION_BUDGET: RetryBudget;
+}
+
+#[derive(Debug)]
+pub(crate) struct Retrier {
+    config: RetryConfig,
+}
+
+impl Retrier {
+    fn new(config: RetryConfig) -> Self {
+        Self { config }
+    }
+
+    /// Budget of the running operation, a fresh one for a call outside of operations
+    fn budget(&self) -> RetryBudget {
+        RetryBudget::current().unwrap_or_else(|| RetryBudget::new(&self.config))
+    }
+
+    fn delay(&self, retry: u32) -> Duration {
+        self.config
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.config.max_delay)
+    }
+}
+
+/// Transient failures of the API (rate limiting, server errors and lost connections), for
+/// calls which are safe to repeat. Rejected requests fail the same way on every retry.
+/// Appends and structural batch updates are not retried: a lost response would make the
+/// retry apply them twice
+pub(crate) fn is_retryable(operation: &str, error: &Report<SpreadSheetDriverError>) -> bool {
+    let idempotent = is_read(operation)
+        || operation == "values.update"
+        || operation == "values.batchUpdate"
+        || operation == "values.batchUpdateByDataFilter"
+        || operation == "developerMetadata.search";
+    let transient = matches!(
+        error.downcast_ref::<ApiFailure>(),
+        Some(ApiFailure::Status(429 | 500..=599) | ApiFailure::Transport)
+    );
+    idempotent
+        && matches!(error.current_context(), SpreadSheetDriverError::ApiError(_))
+        && transient
+}
+
+impl SpreadSheetDriver {
+    /// Retries idempotent calls failed by the API with exponential delays.
+    /// Every call gets `config.max_retries`, unless it runs within a shared budget,
+    /// see [`SpreadSheetDriver::retry_budget`]
+    pub fn with_retries(mut self, config: RetryConfig) -> Self {
+        self.retrier = Some(Retrier::new(config));
+        self
+    }
+
+    /// Budget to share by every call of an operation of several calls (e.g. read, write and
+    /// verify), so it's bounded in time as a whole, see [`RetryBudget::scope`]. `None` without
+    /// retries, or within the scope of another budget: the calls join the running operation
+    pub fn retry_budget(&self) -> Option<RetryBudget> {
+        let retrier = self.retrier.as_ref()?;
+        match RetryBudget::current() {
+            Some(_) => None,
+            None => Some(RetryBudget::new(&retrier.config)),
+        }
+    }
+
+    /// The call and its retries, if any. Also gives back the number of the retries
+    pub(crate) async fn exchange_retried<Resp, F, Fut>(
+        &self,
+        operation: &str,
+        request: Value,
+        call: F,
+    ) -> (SsdResult<Resp>, u32)
+    where
+        Resp: Serialize + DeserializeOwned,
+        F: Fn() -> Fut,
+        Fut: Future<Output = SsdResult<Resp>>,
+    {
+        let Some(retrier) = &self.retrier else {
+            return (self.exchange_guarded(operation, request, call).await, 0);
+        };
+        let budget = retrier.budget();
+        let mut retries = 0;
+        loop {
+            let result = self
+                .exchange_guarded(operation, request.clone(), &call)
+                .await;
+            let Err(error) = &result else {
+                return (result, retries);
+            };
+            let delay = retrier.delay(retries);
+            if !is_retryable(operation, error) || !budget.try_take(delay) {
+                return (result, retries);
+            }
+            retries += 1;
+            warn!(
+                "{} failed ({}), retry {} in {:?}",
+                operation,
+                error.current_context(),
+                retries,
+                delay
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod retry_tests {
+    use super::*;
+    use crate::spread_sheet_driver::backend::SheetsBackend;
+    use crate::spread_sheet_driver::request_log::{RequestRecord, RequestSink};
+    use crate::testing::fixtures::MatchedValueRangeBuilder;
+    use error_stack::report;
+    use google_sheets4::api::BatchGetValuesByDataFilterResponse;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Fails the next `failures` calls with `status` (503 by default), answers reads afterwards
+    #[derive(Debug, Default)]
+    struct FlakyBackend {
+        failures: Arc<AtomicU32>,
+        status: Option<u16>,
+        calls: Arc<AtomicU32>,
+    }
+
+    impl SheetsBackend for FlakyBackend {
+        fn handle(&self, _operation: &str, _request: &Value) -> SsdResult<Value> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let failing = self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok();
+            if failing {
+                return Err(report!(SpreadSheetDriverError::ApiError(
+                    "Service Unavailable".to_string()
+                ))
+                .attach(ApiFailure::Status(self.status.unwrap_or(503))));
+            }
+            let response = BatchGetValuesByDataFilterResponse {
+                value_ranges: Some(vec![
+                    MatchedValueRangeBuilder::new("users!A1:B1")
+                        .row(["1", "Joe"])
+                        .build(),
+                ]),
+                ..Default::default()
+            };
+            Ok(serde_json::to_value(response).expect("Test: Expected to serialize"))
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct Records(Arc<Mutex<Vec<RequestRecord>>>);
+
+    impl RequestSink for Records {
+        fn record(&self, record: &RequestRecord) {
+            self.0
+                .lock()
+                .expect("Test: Expected lock")
+                .push(record.clone());
+        }
+    }
+
+    fn config() -> RetryConfig {
+        RetryConfig {
+            max_retries: 2,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(1),
+            max_elapsed: Duration::from_secs(60),
+        }
+    }
+
+    #[tokio::test]
+    async fn with_retries__transient_failure__retried_and_counted() {
+        let backend = FlakyBackend::default();
+        backend.failures.store(1, Ordering::SeqCst);
+        let records = Records::default();
+        let recorded = records.0.clone();
+        let driver = SpreadSheetDriver::with_backend("document".to_string(), backend)
+            .with_retries(config())
+            .with_request_sink(records);
+
+        driver
+            .try_get_range("users!A1:B1")
+            .await
+            .expect("Test: Expected the retry to succeed");
+
+        let recorded = recorded.lock().expect("Test: Expected lock");
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].retries, 1);
+    }
+
+    #[tokio::test]
+    async fn with_retries__bad_request__not_retried() {
+        let backend = FlakyBackend {
+            status: Some(400),
+            ..FlakyBackend::default()
+        };
+        backend.failures.store(1, Ordering::SeqCst);
+        let calls = backend.calls.clone();
+        let driver =
+            SpreadSheetDriver::with_backend("document".to_string(), backend).with_retries(config());
+
+        driver
+            .try_get_range("users!A1:B1")
+            .await
+            .expect_err("Test: Expected the rejected request to fail");
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn retry_budget__calls_of_operation__share_the_retries() {
+        let backend = FlakyBackend::default();
+        let failures = backend.failures.clone();
+        let driver =
+            SpreadSheetDriver::with_backend("document".to_string(), backend).with_retries(config());
+        let budget = driver
+            .retry_budget()
+            .expect("Test: Expected a budget with retries");
+
+        budget
+            .scope(async {
+                assert!(driver.retry_budget().is_none());
+                failures.store(1, Ordering::SeqCst);
+                driver
+                    .try_get_range("users!A1:B1")
+                    .await
+                    .expect("Test: Expected the first call to be retried");
+                failures.store(1, Ordering::SeqCst);
+                driver
+                    .try_get_range("users!A1:B1")
+                    .await
+                    .expect("Test: Expected the second call to be retried");
+                failures.store(1, Ordering::SeqCst);
+                driver
+                    .try_get_range("users!A1:B1")
+                    .await
+                    .expect_err("Test: Expected the budget to be exhausted");
+            })
+            .await;
+
+        failures.store(1, Ordering::SeqCst);
+        driver
+            .try_get_range("users!A1:B1")
+            .await
+            .expect("Test: Expected a fresh budget outside of the operation");
+    }
+
+    #[tokio::test]
+    async fn retry_budget__other_operation_meanwhile__own_budget() {
+        let backend = FlakyBackend::default();
+        let failures = backend.failures.clone();
+        let driver =
+            SpreadSheetDriver::with_backend("document".to_string(), backend).with_retries(config());
+        let exhausted = driver
+            .retry_budget()
+            .expect("Test: Expected a budget with retries");
+        failures.store(3, Ordering::SeqCst);
+        exhausted
+            .scope(driver.try_get_range("users!A1:B1"))
+            .await
+            .expect_err("Test: Expected the budget to be exhausted");
+        assert_eq!(exhausted.retries_left(), 0);
+
+        // One failure for each operation, the one outside of the scope is retried
+        failures.store(2, Ordering::SeqCst);
+        let (in_scope, outside) = tokio::join!(
+            exhausted.scope(driver.try_get_range("users!A1:B1")),
+            async {
+                tokio::task::yield_now().await;
+                driver.try_get_range("users!A1:B1").await
+            }
+        );
+
+        in_scope.expect_err("Test: Expected no retries left in the exhausted budget");
+        outside.expect("Test: Expected the other operation to retry on its own budget");
+    }
+
+    #[test]
+    fn is_retryable__status_and_operation__only_transient_failures_of_idempotent_calls() {
+        let failed = |failure: ApiFailure| {
+            report!(SpreadSheetDriverError::ApiError("failed".to_string())).attach(failure)
+        };
+
+        assert!(is_retryable(
+            "values.update",
+            &failed(ApiFailure::Status(500))
+        ));
+        assert!(is_retryable(
+            "values.update",
+            &failed(ApiFailure::Status(429))
+        ));
+        assert!(is_retryable(
+            "values.update",
+            &failed(ApiFailure::Transport)
+        ));
+        assert!(!is_retryable(
+            "values.update",
+            &failed(ApiFailure::Status(400))
+        ));
+        assert!(!is_retryable(
+            "values.append",
+            &failed(ApiFailure::Status(500))
+        ));
+        assert!(!is_retryable(
+            "values.update",
+            &report!(SpreadSheetDriverError::InvalidArgument("range".to_string()))
+        ));
+    }
+}
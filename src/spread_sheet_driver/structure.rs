@@ -0,0 +1,786 @@
+//////////////////////// Spreadsheet structure (batchUpdate) API ////////////////////////
+
+use crate::spread_sheet_driver::format::input_rows;
+use crate::spread_sheet_driver::{SpreadSheetDriver, SpreadSheetDriverError, SsdResult};
+use crate::types::{InputMode, MajorDimension, SheetA1CellId, SheetA1Range, SheetGid};
+use error_stack::bail;
+use google_sheets4::api::{
+    AppendDimensionRequest, BatchUpdateSpreadsheetRequest, BatchUpdateSpreadsheetResponse,
+    BatchUpdateValuesByDataFilterRequest, BatchUpdateValuesByDataFilterResponse, BooleanCondition,
+    ConditionValue, CutPasteRequest, DataFilter, DataFilterValueRange, DataValidationRule,
+    DeleteDimensionRequest, DimensionRange, DuplicateSheetRequest,
+    GetSpreadsheetByDataFilterRequest, GridCoordinate, GridRange, InsertDimensionRequest, Request,
+    SetDataValidationRequest, Sheet, SheetProperties, Spreadsheet, UpdateCellsRequest,
+};
+use google_sheets4::common::FieldMask;
+use serde_json::json;
+use std::ops::Range;
+use tracing::{debug, info};
+
+/// What to do when a write targets cells outside the sheet grid
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GridCheck {
+    /// Send the write as is and let the API reject it
+    #[default]
+    Off,
+    /// Fail with `RangeOutOfBounds` before sending the write
+    Fail,
+    /// Append missing rows/columns to the sheet, then write
+    Expand,
+}
+
+impl SpreadSheetDriver {
+    /// Applies structural requests (dimensions, developer metadata, formatting, ...) atomically:
+    /// either all of them are applied or none
+    pub async fn try_batch_update(
+        &self,
+        requests: Vec<Request>,
+    ) -> SsdResult<BatchUpdateSpreadsheetResponse> {
+        let req = BatchUpdateSpreadsheetRequest {
+            requests: Some(requests),
+            ..Default::default()
+        };
+        self.exchange(
+            "spreadsheets.batchUpdate",
+            json!({ "requests": req.requests }),
+            || async {
+                self.client_ref()
+                    .spreadsheets()
+                    .batch_update(req.clone(), self.document_id.as_str())
+                    .doit()
+                    .await
+                    .map(|(_, response)| response)
+                    .map_err(|e| self.api_error(e))
+            },
+        )
+        .await
+    }
+
+    /// Properties (id, title, grid size) of every sheet in the document
+    pub async fn try_get_sheets_properties(&self) -> SsdResult<Vec<SheetProperties>> {
+        let fields = "sheets.properties";
+        let spreadsheet: Spreadsheet = self
+            .exchange("spreadsheets.get", json!({ "fields": fields }), || async {
+                self.client_ref()
+                    .spreadsheets()
+                    .get(self.document_id.as_str())
+                    .param("fields", fields)
+                    .doit()
+                    .await
+                    .map(|(_, response)| response)
+                    .map_err(|e| self.api_error(e))
+            })
+            .await?;
+
+        Ok(spreadsheet
+            .sheets
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|sheet| sheet.properties)
+            .collect())
+    }
+
+    /// Spreadsheet with only the sheets and ranges matched by the filters.
+    /// With `include_grid_data` the matched cells (values and formats) are included as well,
+    /// so a few ranges of a huge document are fetched without its full grid
+    pub async fn try_get_spreadsheet_filtered(
+        &self,
+        filters: Vec<DataFilter>,
+        include_grid_data: bool,
+    ) -> SsdResult<Spreadsheet> {
+        let req = GetSpreadsheetByDataFilterRequest {
+            data_filters: Some(filters),
+            include_grid_data: Some(include_grid_data),
+            ..Default::default()
+        };
+        self.exchange(
+            "spreadsheets.getByDataFilter",
+            json!({ "dataFilters": req.data_filters, "includeGridData": include_grid_data }),
+            || async {
+                self.client_ref()
+                    .spreadsheets()
+                    .get_by_data_filter(req.clone(), self.document_id.as_str())
+                    .doit()
+                    .await
+                    .map(|(_, response)| response)
+                    .map_err(|e| self.api_error(e))
+            },
+        )
+        .await
+    }
+
+    /// Validates ranges of writes and appends against the sheet grid size.
+    /// Grid sizes are fetched once and cached, see [`SpreadSheetDriver::invalidate_sheets_cache`]
+    pub fn with_grid_check(mut self, mode: GridCheck) -> Self {
+        self.grid_check = mode;
+        self
+    }
+
+    /// Same as [`SpreadSheetDriver::try_get_sheets_properties`], but fetched only on the first call
+    pub async fn try_get_sheets_properties_cached(&self) -> SsdResult<Vec<SheetProperties>> {
+        if let Some(cached) = self.sheets_cache().clone() {
+            return Ok(cached);
+        }
+        let properties = self.try_get_sheets_properties().await?;
+        *self.sheets_cache() = Some(properties.clone());
+        Ok(properties)
+    }
+
+    /// Forgets cached sheet properties, e.g. after the sheets were resized outside the driver
+    pub fn invalidate_sheets_cache(&self) {
+        *self.sheets_cache() = None;
+    }
+
+    fn sheets_cache(&self) -> std::sync::MutexGuard<'_, Option<Vec<SheetProperties>>> {
+        self.sheets_cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Makes sure `range_str` fits the grid of its sheet according to the grid check mode.
+    /// Appends grow the sheet by themselves, so they pass `check_rows: false`
+    pub(crate) async fn check_grid(&self, range_str: &str, check_rows: bool) -> SsdResult<()> {
+        if self.grid_check == GridCheck::Off {
+            return Ok(());
+        }
+        let Ok(range) = SheetA1Range::from_raw(range_str) else {
+            debug!(
+                "Range {} is not a bounded A1 range, grid check skipped",
+                range_str
+            );
+            return Ok(());
+        };
+
+        let properties = self.try_get_sheets_properties_cached().await?;
+        let Some(sheet) = properties
+            .iter()
+            .find(|p| p.title.as_deref() == Some(range.sheet.as_str()))
+        else {
+            bail!(SpreadSheetDriverError::RangeNotFound(format!(
+                "Sheet '{}'",
+                range.sheet
+            )));
+        };
+        let grid = sheet.grid_properties.clone().unwrap_or_default();
+        let rows = grid.row_count.unwrap_or_default().max(0) as u32;
+        let columns = grid.column_count.unwrap_or_default().max(0) as u32;
+
+        let needed_rows = match check_rows {
+            true => range.range.end.row().get(),
+            false => 0,
+        };
+        let missing_rows = needed_rows.saturating_sub(rows);
+        let missing_columns = range.range.end.column().get().saturating_sub(columns);
+        if missing_rows == 0 && missing_columns == 0 {
+            return Ok(());
+        }
+
+        if self.grid_check == GridCheck::Fail {
+            bail!(SpreadSheetDriverError::RangeOutOfBounds {
+                range: range_str.to_string(),
+                rows,
+                columns,
+            });
+        }
+
+        let sheet_id = sheet.sheet_id.unwrap_or_default();
+        let requests = [
+            (MajorDimension::Rows, missing_rows),
+            (MajorDimension::Columns, missing_columns),
+        ]
+        .into_iter()
+        .filter(|(_, missing)| *missing > 0)
+        .map(|(dimension, missing)| {
+            append_dimension_request(SheetGid(sheet_id), dimension, missing)
+        })
+        .collect();
+        self.try_batch_update(requests).await?;
+        info!(
+            "Sheet '{}' expanded by {} rows and {} columns",
+            range.sheet, missing_rows, missing_columns
+        );
+
+        if let Some(cached) = self.sheets_cache().as_mut()
+            && let Some(sheet) = cached.iter_mut().find(|p| p.sheet_id == Some(sheet_id))
+        {
+            let grid = sheet.grid_properties.get_or_insert_default();
+            grid.row_count = Some((rows + missing_rows) as i32);
+            grid.column_count = Some((columns + missing_columns) as i32);
+        }
+        Ok(())
+    }
+
+    /// Numeric sheet id (gid) which structural requests use instead of the title
+    pub async fn try_get_sheet_id(&self, title: &str) -> SsdResult<SheetGid> {
+        let properties = self.try_get_sheets_properties().await?;
+        let Some(sheet_id) = properties
+            .iter()
+            .find(|p| p.title.as_deref() == Some(title))
+            .and_then(|p| p.sheet_id)
+        else {
+            bail!(SpreadSheetDriverError::RangeNotFound(format!(
+                "Sheet '{title}'"
+            )));
+        };
+        Ok(SheetGid(sheet_id))
+    }
+
+    /// Link which opens the sheet with the cell selected, see [`SheetA1CellId::to_url`]
+    pub async fn try_cell_url(&self, cell: &SheetA1CellId) -> SsdResult<String> {
+        let gid = self.resolve_sheet_id(&cell.sheet_name).await?;
+        Ok(cell.to_url(&self.document_id, gid))
+    }
+
+    /// Link which opens the sheet with the range selected, see [`SheetA1Range::to_url`]
+    pub async fn try_range_url(&self, range: &SheetA1Range) -> SsdResult<String> {
+        let gid = self.resolve_sheet_id(&range.sheet).await?;
+        Ok(range.to_url(&self.document_id, gid))
+    }
+
+    /// Gids don't change for the life of a sheet, so the cached properties are asked first.
+    /// A sheet added after they were cached is looked up again
+    async fn resolve_sheet_id(&self, title: &str) -> SsdResult<SheetGid> {
+        let cached = self
+            .try_get_sheets_properties_cached()
+            .await?
+            .into_iter()
+            .find(|p| p.title.as_deref() == Some(title))
+            .and_then(|p| p.sheet_id);
+        match cached {
+            Some(sheet_id) => Ok(SheetGid(sheet_id)),
+            None => self.try_get_sheet_id(title).await,
+        }
+    }
+
+    /// Title of the sheet with the gid, e.g. of a row found by developer metadata.
+    /// Asks the cached properties first, like [`Self::resolve_sheet_id`]
+    pub async fn try_get_sheet_title(&self, sheet_id: SheetGid) -> SsdResult<String> {
+        let find = |properties: Vec<SheetProperties>| {
+            properties
+                .into_iter()
+                .find(|p| p.sheet_id == Some(sheet_id.0))
+                .and_then(|p| p.title)
+        };
+        if let Some(title) = find(self.try_get_sheets_properties_cached().await?) {
+            return Ok(title);
+        }
+        let Some(title) = find(self.try_get_sheets_properties().await?) else {
+            bail!(SpreadSheetDriverError::RangeNotFound(format!(
+                "Sheet with id {}",
+                sheet_id.0
+            )));
+        };
+        Ok(title)
+    }
+
+    /// Properties and merged ranges of the sheet, without its grid data
+    pub async fn try_get_sheet_layout(&self, title: &str) -> SsdResult<Sheet> {
+        let fields = "sheets(properties,merges)";
+        let spreadsheet: Spreadsheet = self
+            .exchange("spreadsheets.get", json!({ "fields": fields }), || async {
+                self.client_ref()
+                    .spreadsheets()
+                    .get(self.document_id.as_str())
+                    .param("fields", fields)
+                    .doit()
+                    .await
+                    .map(|(_, response)| response)
+                    .map_err(|e| self.api_error(e))
+            })
+            .await?;
+
+        let sheet = spreadsheet
+            .sheets
+            .unwrap_or_default()
+            .into_iter()
+            .find(|sheet| {
+                sheet
+                    .properties
+                    .as_ref()
+                    .is_some_and(|p| p.title.as_deref() == Some(title))
+            });
+        let Some(sheet) = sheet else {
+            bail!(SpreadSheetDriverError::RangeNotFound(format!(
+                "Sheet '{title}'"
+            )));
+        };
+        Ok(sheet)
+    }
+
+    /// Writes values into locations matched by data filters (e.g. developer metadata lookups)
+    /// instead of explicit A1 ranges
+    pub async fn try_write_by_data_filter(
+        &self,
+        data: Vec<DataFilterValueRange>,
+        input_mode: InputMode,
+    ) -> SsdResult<BatchUpdateValuesByDataFilterResponse> {
+        let data = data
+            .into_iter()
+            .map(|range| DataFilterValueRange {
+                values: range
+                    .values
+                    .as_deref()
+                    .map(|values| input_rows(values, input_mode)),
+                ..range
+            })
+            .collect();
+        let req = BatchUpdateValuesByDataFilterRequest {
+            data: Some(data),
+            value_input_option: Some(input_mode.as_str().to_string()),
+            ..Default::default()
+        };
+        self.exchange(
+            "values.batchUpdateByDataFilter",
+            json!({ "data": req.data, "valueInputOption": input_mode.as_str() }),
+            || async {
+                self.client_ref()
+                    .spreadsheets()
+                    .values_batch_update_by_data_filter(req.clone(), self.document_id.as_str())
+                    .doit()
+                    .await
+                    .map(|(_, response)| response)
+                    .map_err(|e| self.api_error(e))
+            },
+        )
+        .await
+    }
+}
+
+/// 0-based, end-exclusive range of rows on the sheet
+pub fn rows_range(sheet_id: SheetGid, start_index: u32, count: u32) -> DimensionRange {
+    DimensionRange {
+        dimension: Some(MajorDimension::Rows.to_string()),
+        sheet_id: Some(sheet_id.0),
+        start_index: Some(start_index as i32),
+        end_index: Some((start_index + count) as i32),
+    }
+}
+
+/// Inserts `count` blank rows before the 0-based `start_index`, shifting rows below down.
+/// New rows inherit formatting of the row above (if any)
+pub fn insert_rows_request(sheet_id: SheetGid, start_index: u32, count: u32) -> Request {
+    Request {
+        insert_dimension: Some(InsertDimensionRequest {
+            range: Some(rows_range(sheet_id, start_index, count)),
+            inherit_from_before: Some(start_index > 0),
+        }),
+        ..Default::default()
+    }
+}
+
+/// Adds `length` rows or columns to the end of the sheet
+pub fn append_dimension_request(
+    sheet_id: SheetGid,
+    dimension: MajorDimension,
+    length: u32,
+) -> Request {
+    Request {
+        append_dimension: Some(AppendDimensionRequest {
+            dimension: Some(dimension.to_string()),
+            length: Some(length as i32),
+            sheet_id: Some(sheet_id.0),
+        }),
+        ..Default::default()
+    }
+}
+
+/// Removes `count` rows starting from the 0-based `start_index`, shifting rows below up
+pub fn delete_rows_request(sheet_id: SheetGid, start_index: u32, count: u32) -> Request {
+    Request {
+        delete_dimension: Some(DeleteDimensionRequest {
+            range: Some(rows_range(sheet_id, start_index, count)),
+        }),
+        ..Default::default()
+    }
+}
+
+/// Moves the 0-based rectangle (values, formulas and formats) so its top left corner lands on
+/// `to_row`/`to_column`. Formulas referencing the moved cells follow them
+pub fn cut_paste_request(
+    sheet_id: SheetGid,
+    rows: Range<u32>,
+    columns: Range<u32>,
+    to_row: u32,
+    to_column: u32,
+) -> Request {
+    Request {
+        cut_paste: Some(CutPasteRequest {
+            source: Some(GridRange {
+                sheet_id: Some(sheet_id.0),
+                start_row_index: Some(rows.start as i32),
+                end_row_index: Some(rows.end as i32),
+                start_column_index: Some(columns.start as i32),
+                end_column_index: Some(columns.end as i32),
+            }),
+            destination: Some(GridCoordinate {
+                sheet_id: Some(sheet_id.0),
+                row_index: Some(to_row as i32),
+                column_index: Some(to_column as i32),
+            }),
+            paste_type: Some("PASTE_NORMAL".to_string()),
+        }),
+        ..Default::default()
+    }
+}
+
+/// Copies the sheet with its values, formats, validations and protected ranges under a new
+/// title. The id of the copy is in the `duplicateSheet` reply
+pub fn duplicate_sheet_request(sheet_id: SheetGid, new_title: &str) -> Request {
+    Request {
+        duplicate_sheet: Some(DuplicateSheetRequest {
+            source_sheet_id: Some(sheet_id.0),
+            new_sheet_name: Some(new_title.to_string()),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+/// Restricts the cells of the 0-based rectangle to one of `values`, shown as a dropdown.
+/// Other values are rejected when `strict`, otherwise only flagged
+pub fn one_of_list_validation_request(
+    sheet_id: SheetGid,
+    rows: Range<u32>,
+    columns: Range<u32>,
+    values: &[&str],
+    strict: bool,
+) -> Request {
+    let values = values
+        .iter()
+        .map(|&value| ConditionValue {
+            user_entered_value: Some(value.to_string()),
+            ..Default::default()
+        })
+        .collect();
+    Request {
+        set_data_validation: Some(SetDataValidationRequest {
+            range: Some(GridRange {
+                sheet_id: Some(sheet_id.0),
+                start_row_index: Some(rows.start as i32),
+                end_row_index: Some(rows.end as i32),
+                start_column_index: Some(columns.start as i32),
+                end_column_index: Some(columns.end as i32),
+            }),
+            rule: Some(DataValidationRule {
+                condition: Some(BooleanCondition {
+                    type_: Some("ONE_OF_LIST".to_string()),
+                    values: Some(values),
+                }),
+                show_custom_ui: Some(true),
+                strict: Some(strict),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+/// Clears values of the 0-based rectangle, keeping formats and validations
+pub fn clear_values_request(sheet_id: SheetGid, rows: Range<u32>, columns: Range<u32>) -> Request {
+    Request {
+        update_cells: Some(UpdateCellsRequest {
+            fields: Some(FieldMask::new(&["userEnteredValue"])),
+            range: Some(GridRange {
+                sheet_id: Some(sheet_id.0),
+                start_row_index: Some(rows.start as i32),
+                end_row_index: Some(rows.end as i32),
+                start_column_index: Some(columns.start as i32),
+                end_column_index: Some(columns.end as i32),
+            }),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod structure_tests {
+    use super::*;
+    use crate::spread_sheet_driver::cassette::{Cassette, Interaction};
+    use google_sheets4::api::{GridData, GridProperties, Sheet, UpdateValuesResponse};
+
+    #[test]
+    fn insert_rows_request__serialized__ok() {
+        let request = serde_json::to_value(insert_rows_request(SheetGid(7), 10, 3))
+            .expect("Test: Expected to serialize");
+
+        let range = &request["insertDimension"]["range"];
+        assert_eq!(range["dimension"], "ROWS");
+        assert_eq!(range["sheetId"], 7);
+        assert_eq!(range["startIndex"], 10);
+        assert_eq!(range["endIndex"], 13);
+        assert_eq!(request["insertDimension"]["inheritFromBefore"], true);
+    }
+
+    #[tokio::test]
+    async fn try_get_sheet_id__by_title__ok() {
+        let spreadsheet = Spreadsheet {
+            sheets: Some(vec![
+                Sheet {
+                    properties: Some(SheetProperties {
+                        sheet_id: Some(0),
+                        title: Some("users".to_string()),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+                Sheet {
+                    properties: Some(SheetProperties {
+                        sheet_id: Some(42),
+                        title: Some("orders".to_string()),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+            ]),
+            ..Default::default()
+        };
+        let interaction = Interaction {
+            operation: "spreadsheets.get".to_string(),
+            request: json!({ "fields": "sheets.properties" }),
+            response: serde_json::to_value(spreadsheet).expect("Test: Expected to serialize"),
+        };
+        let driver = SpreadSheetDriver::replay(
+            "document".to_string(),
+            Cassette::replay_from("unused.json", vec![interaction.clone(), interaction]),
+        );
+
+        let sheet_id = driver
+            .try_get_sheet_id("orders")
+            .await
+            .expect("Test: Expected sheet id");
+        assert_eq!(sheet_id, SheetGid(42));
+
+        let missing = driver
+            .try_get_sheet_id("products")
+            .await
+            .expect_err("Test: Expected missing sheet");
+        assert!(matches!(
+            missing.current_context(),
+            SpreadSheetDriverError::RangeNotFound(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn try_range_url__cached_properties__gid_resolved_once() {
+        let spreadsheet = Spreadsheet {
+            sheets: Some(vec![Sheet {
+                properties: Some(SheetProperties {
+                    sheet_id: Some(42),
+                    title: Some("orders".to_string()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+        let interaction = Interaction {
+            operation: "spreadsheets.get".to_string(),
+            request: json!({ "fields": "sheets.properties" }),
+            response: serde_json::to_value(spreadsheet).expect("Test: Expected to serialize"),
+        };
+        let driver = SpreadSheetDriver::replay(
+            "document".to_string(),
+            Cassette::replay_from("unused.json", vec![interaction]),
+        );
+
+        let range = driver
+            .try_range_url(&SheetA1Range::from_raw("orders!A1:B2").unwrap())
+            .await
+            .expect("Test: Expected range url");
+        let cell = driver
+            .try_cell_url(&SheetA1CellId::from_primitives("orders", "C", 3))
+            .await
+            .expect("Test: Expected cell url from the cached gid");
+
+        assert_eq!(
+            range,
+            "https://docs.google.com/spreadsheets/d/document/edit#gid=42&range=A1:B2"
+        );
+        assert!(cell.ends_with("#gid=42&range=C3"));
+    }
+
+    #[tokio::test]
+    async fn try_get_spreadsheet_filtered__a1_filter__only_matched_sheet() {
+        let spreadsheet = Spreadsheet {
+            sheets: Some(vec![Sheet {
+                properties: Some(SheetProperties {
+                    sheet_id: Some(42),
+                    title: Some("orders".to_string()),
+                    ..Default::default()
+                }),
+                data: Some(vec![GridData {
+                    start_row: Some(0),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+        let filter = DataFilter {
+            a1_range: Some("orders!A1:C10".to_string()),
+            ..Default::default()
+        };
+        let interaction = Interaction {
+            operation: "spreadsheets.getByDataFilter".to_string(),
+            request: json!({ "dataFilters": [filter.clone()], "includeGridData": true }),
+            response: serde_json::to_value(spreadsheet).expect("Test: Expected to serialize"),
+        };
+        let driver = SpreadSheetDriver::replay(
+            "document".to_string(),
+            Cassette::replay_from("unused.json", vec![interaction]),
+        );
+
+        let spreadsheet = driver
+            .try_get_spreadsheet_filtered(vec![filter], true)
+            .await
+            .expect("Test: Expected filtered spreadsheet");
+
+        let sheets = spreadsheet.sheets.expect("Test: Expected sheets");
+        assert_eq!(sheets.len(), 1);
+        assert_eq!(
+            sheets[0].properties.as_ref().and_then(|p| p.sheet_id),
+            Some(42)
+        );
+        assert_eq!(sheets[0].data.as_ref().map(Vec::len), Some(1));
+    }
+
+    fn users_sheet_interaction(rows: i32, columns: i32) -> Interaction {
+        let spreadsheet = Spreadsheet {
+            sheets: Some(vec![Sheet {
+                properties: Some(SheetProperties {
+                    sheet_id: Some(0),
+                    title: Some("users".to_string()),
+                    grid_properties: Some(GridProperties {
+                        row_count: Some(rows),
+                        column_count: Some(columns),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+        Interaction {
+            operation: "spreadsheets.get".to_string(),
+            request: json!({ "fields": "sheets.properties" }),
+            response: serde_json::to_value(spreadsheet).expect("Test: Expected to serialize"),
+        }
+    }
+
+    fn update_interaction(range: &str, values: serde_json::Value) -> Interaction {
+        Interaction {
+            operation: "values.update".to_string(),
+            request: json!({ "range": range, "values": values }),
+            response: serde_json::to_value(UpdateValuesResponse::default())
+                .expect("Test: Expected to serialize"),
+        }
+    }
+
+    #[tokio::test]
+    async fn try_write_range__outside_grid_with_fail_check__range_out_of_bounds() {
+        // No update is recorded, so sending the write would fail with replay miss
+        let driver = SpreadSheetDriver::replay(
+            "document".to_string(),
+            Cassette::replay_from("unused.json", vec![users_sheet_interaction(10, 2)]),
+        )
+        .with_grid_check(GridCheck::Fail);
+
+        let err = driver
+            .try_write_range(
+                "users!A1:C1",
+                vec![vec![json!("1"), json!("Joe"), json!("x")]],
+            )
+            .await
+            .expect_err("Test: Expected out of bounds write to fail");
+
+        assert!(matches!(
+            err.current_context(),
+            SpreadSheetDriverError::RangeOutOfBounds {
+                rows: 10,
+                columns: 2,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn try_write_range__outside_grid_with_expand_check__appends_columns_once() {
+        let expand = Interaction {
+            operation: "spreadsheets.batchUpdate".to_string(),
+            request: json!({
+                "requests": [append_dimension_request(SheetGid(0), MajorDimension::Columns, 1)]
+            }),
+            response: serde_json::to_value(BatchUpdateSpreadsheetResponse::default())
+                .expect("Test: Expected to serialize"),
+        };
+        // Sheet properties are recorded once: the second write is checked against the cache
+        let driver = SpreadSheetDriver::replay(
+            "document".to_string(),
+            Cassette::replay_from(
+                "unused.json",
+                vec![
+                    users_sheet_interaction(10, 2),
+                    expand,
+                    update_interaction("users!A1:C1", json!([["1", "Joe", "x"]])),
+                    update_interaction("users!A2:C2", json!([["2", "John", "y"]])),
+                ],
+            ),
+        )
+        .with_grid_check(GridCheck::Expand);
+
+        driver
+            .try_write_range(
+                "users!A1:C1",
+                vec![vec![json!("1"), json!("Joe"), json!("x")]],
+            )
+            .await
+            .expect("Test: Expected grid to be expanded");
+        driver
+            .try_write_range(
+                "users!A2:C2",
+                vec![vec![json!("2"), json!("John"), json!("y")]],
+            )
+            .await
+            .expect("Test: Expected write to fit the expanded grid");
+    }
+
+    #[test]
+    fn append_dimension_request__serialized__ok() {
+        let request = serde_json::to_value(append_dimension_request(
+            SheetGid(3),
+            MajorDimension::Rows,
+            20,
+        ))
+        .expect("Test: Expected to serialize");
+
+        assert_eq!(request["appendDimension"]["dimension"], "ROWS");
+        assert_eq!(request["appendDimension"]["sheetId"], 3);
+        assert_eq!(request["appendDimension"]["length"], 20);
+    }
+
+    #[test]
+    fn one_of_list_validation_request__serialized__ok() {
+        let request = serde_json::to_value(one_of_list_validation_request(
+            SheetGid(4),
+            1..11,
+            2..3,
+            &["new", "paid"],
+            true,
+        ))
+        .expect("Test: Expected to serialize");
+
+        let validation = &request["setDataValidation"];
+        assert_eq!(validation["range"]["startColumnIndex"], 2);
+        assert_eq!(validation["range"]["endRowIndex"], 11);
+        assert_eq!(validation["rule"]["condition"]["type"], "ONE_OF_LIST");
+        assert_eq!(
+            validation["rule"]["condition"]["values"][1]["userEnteredValue"],
+            "paid"
+        );
+        assert_eq!(validation["rule"]["strict"], true);
+    }
+}
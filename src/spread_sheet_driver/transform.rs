@@ -0,0 +1,144 @@
+//////////////////////// Post-processing of read cells ////////////////////////
+
+use crate::mapper::sheet_row::SheetRow;
+use crate::spread_sheet_driver::SpreadSheetDriver;
+use google_sheets4::api::{MatchedValueRange, ValueRange};
+use serde_json::Value;
+use std::fmt::Debug;
+
+/// Rewrites every cell read through the driver before it's returned, so sheets maintained by
+/// humans parse without per-type wrappers. See [`SpreadSheetDriver::with_cell_transform`]
+pub trait CellTransform: Debug + Send + Sync {
+    fn transform(&self, cell: Value) -> Value;
+}
+
+/// Removes zero-width characters (e.g. pasted from web pages) and turns non-breaking
+/// spaces into plain ones
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StripInvisible;
+
+impl CellTransform for StripInvisible {
+    fn transform(&self, cell: Value) -> Value {
+        let Value::String(text) = cell else {
+            return cell;
+        };
+        Value::String(
+            text.chars()
+                .filter(|c| {
+                    !matches!(
+                        c,
+                        '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{2060}' | '\u{FEFF}'
+                    )
+                })
+                .map(|c| if c == '\u{00A0}' { ' ' } else { c })
+                .collect(),
+        )
+    }
+}
+
+/// Turns placeholder texts into empty cells, e.g. `EmptyTokens::new(["N/A", "-"])`.
+/// Tokens are matched case-sensitively, surrounding whitespace of the cell is ignored
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EmptyTokens {
+    tokens: Vec<String>,
+}
+
+impl EmptyTokens {
+    pub fn new<I, S>(tokens: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            tokens: tokens.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl CellTransform for EmptyTokens {
+    fn transform(&self, cell: Value) -> Value {
+        match &cell {
+            Value::String(text) if self.tokens.iter().any(|token| token == text.trim()) => {
+                Value::String(String::new())
+            }
+            _ => cell,
+        }
+    }
+}
+
+impl SpreadSheetDriver {
+    /// Applies `transform` to every cell of value reads (`try_get_range`, `try_get_values`,
+    /// `try_get_ranges`, `try_query` and everything built on them), after the transforms added
+    /// before. Cassettes and the circuit breaker keep the raw responses
+    pub fn with_cell_transform<T>(mut self, transform: T) -> Self
+    where
+        T: CellTransform + 'static,
+    {
+        self.cell_transforms.push(Box::new(transform));
+        self
+    }
+
+    pub(crate) fn transform_value_range(&self, range: &mut ValueRange) {
+        if self.cell_transforms.is_empty() {
+            return;
+        }
+        if let Some(rows) = &mut range.values {
+            rows.iter_mut().for_each(|row| self.transform_row(row));
+        }
+    }
+
+    pub(crate) fn transform_matched_ranges(&self, ranges: &mut [MatchedValueRange]) {
+        ranges
+            .iter_mut()
+            .filter_map(|range| range.value_range.as_mut())
+            .for_each(|range| self.transform_value_range(range));
+    }
+
+    fn transform_row(&self, row: &mut SheetRow) {
+        for cell in row.iter_mut() {
+            *cell = self
+                .cell_transforms
+                .iter()
+                .fold(cell.take(), |cell, transform| transform.transform(cell));
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod transform_tests {
+    use super::*;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use serde_json::json;
+
+    #[test]
+    fn strip_invisible__zero_width_and_nbsp__plain_text() {
+        let cell = StripInvisible.transform(json!("\u{FEFF}4\u{200B}2\u{00A0}kg"));
+
+        assert_eq!(cell, json!("42 kg"));
+        assert_eq!(StripInvisible.transform(json!(42)), json!(42));
+    }
+
+    #[tokio::test]
+    async fn try_get_range__transforms__applied_in_order() {
+        let backend = MemoryBackend::new();
+        backend.workbook().set_sheet(
+            "users",
+            vec![vec![json!("1"), json!(" N/A "), json!("Jo\u{200B}e")]],
+        );
+        let driver = SpreadSheetDriver::with_backend("document".to_string(), backend)
+            .with_cell_transform(StripInvisible)
+            .with_cell_transform(EmptyTokens::new(["N/A"]));
+
+        let range = driver
+            .try_get_range("users!A1:C1")
+            .await
+            .expect("Test: Expected range");
+
+        let values = range.value_range.and_then(|r| r.values);
+        assert_eq!(
+            values,
+            Some(vec![vec![json!("1"), json!(""), json!("Joe")]])
+        );
+    }
+}
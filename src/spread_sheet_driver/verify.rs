@@ -0,0 +1,214 @@
+//////////////////////// Read-back verification of writes ////////////////////////
+
+use crate::spread_sheet_driver::{SpreadSheetDriver, SpreadSheetDriverError, SsdResult};
+use crate::types::{ReadOptions, SheetA1CellId, SheetA1Range, ValueRenderOption};
+use error_stack::bail;
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+
+/// Cell which was stored differently from what was sent
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellMismatch {
+    /// A1 position of the cell, e.g. "users!B2"
+    pub cell: String,
+    pub sent: Value,
+    pub stored: Value,
+}
+
+impl Display for CellMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: sent {}, stored {}",
+            self.cell, self.sent, self.stored
+        )
+    }
+}
+
+/// Rendered list of mismatches for the error message
+#[derive(Debug, Clone, PartialEq)]
+pub struct WriteDiff(pub Vec<CellMismatch>);
+
+impl Display for WriteDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let cells: Vec<String> = self.0.iter().map(CellMismatch::to_string).collect();
+        write!(f, "{}", cells.join("; "))
+    }
+}
+
+impl SpreadSheetDriver {
+    /// Reads every written range back and fails with
+    /// [`SpreadSheetDriverError::WriteVerificationFailed`] if the stored values differ from the
+    /// sent ones, e.g. when USER_ENTERED turned "2024-01-01" into a date or "007" into 7.
+    /// Costs an extra read per write, off by default
+    pub fn with_verify_writes(mut self, verify: bool) -> Self {
+        self.verify_writes = verify;
+        self
+    }
+
+    pub fn verifies_writes(&self) -> bool {
+        self.verify_writes
+    }
+
+    /// Compares the range with the sent values. Formulas are compared as entered,
+    /// numbers and strings by their text: "42" sent and 42 stored is not a mismatch
+    pub(crate) async fn verify_write(&self, range_str: &str, sent: &[Vec<Value>]) -> SsdResult<()> {
+        let options = ReadOptions {
+            value_render_option: ValueRenderOption::Formula,
+            ..ReadOptions::default()
+        };
+        let stored = self
+            .try_get_range_with(range_str, &options)
+            .await?
+            .into_vec();
+
+        let diff = diff_values(range_str, sent, &stored);
+        if !diff.is_empty() {
+            bail!(SpreadSheetDriverError::WriteVerificationFailed {
+                range: range_str.to_string(),
+                diff: WriteDiff(diff),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Cells of `sent` which don't match `stored`, missing stored cells count as empty
+pub fn diff_values(
+    range_str: &str,
+    sent: &[Vec<Value>],
+    stored: &[Vec<Value>],
+) -> Vec<CellMismatch> {
+    let range = SheetA1Range::from_raw(range_str).ok();
+    let cell_name = |x: usize, y: usize| match &range {
+        Some(range) => {
+            SheetA1CellId::new(&range.sheet, range.range.start.delta(x as i32, y as i32))
+                .to_string()
+        }
+        None => format!("{range_str} R{}C{}", y + 1, x + 1),
+    };
+
+    let mut diff = vec![];
+    for (y, row) in sent.iter().enumerate() {
+        for (x, sent) in row.iter().enumerate() {
+            let stored = stored
+                .get(y)
+                .and_then(|row| row.get(x))
+                .cloned()
+                .unwrap_or(Value::Null);
+            if cell_text(sent) != cell_text(&stored) {
+                diff.push(CellMismatch {
+                    cell: cell_name(x, y),
+                    sent: sent.clone(),
+                    stored,
+                });
+            }
+        }
+    }
+    diff
+}
+
+fn cell_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Number(n) => match n.as_f64() {
+            Some(f) if n.is_f64() && f.fract() == 0.0 && f.abs() < i64::MAX as f64 => {
+                (f as i64).to_string()
+            }
+            _ => n.to_string(),
+        },
+        _ => value.to_string(),
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod verify_tests {
+    use super::*;
+    use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+    use crate::spread_sheet_driver::cassette::{Cassette, Interaction};
+    use crate::testing::fixtures::MatchedValueRangeBuilder;
+    use crate::types::InputMode;
+    use google_sheets4::api::{BatchGetValuesByDataFilterResponse, UpdateValuesResponse};
+    use serde_json::json;
+
+    #[test]
+    fn diff_values__numbers_as_text_and_trimmed_empties__no_mismatch() {
+        let sent = vec![vec![Value::from("42"), Value::from("")]];
+        let stored = vec![vec![Value::from(42.0)]];
+
+        assert!(diff_values("users!A1:B1", &sent, &stored).is_empty());
+    }
+
+    #[tokio::test]
+    async fn try_write_range__verified_and_stored_as_sent__ok() {
+        let driver = SpreadSheetDriver::with_backend("document".to_string(), MemoryBackend::new())
+            .with_verify_writes(true);
+
+        driver
+            .try_write_range(
+                "users!A1:B1",
+                vec![vec![Value::from("1"), Value::from("Joe")]],
+            )
+            .await
+            .expect("Test: Expected verified write");
+    }
+
+    #[tokio::test]
+    async fn try_write_range__value_transformed__verification_failed_with_diff() {
+        let stored = BatchGetValuesByDataFilterResponse {
+            value_ranges: Some(vec![
+                MatchedValueRangeBuilder::new("users!A2:B2")
+                    .row(["Joe", "45292"])
+                    .build(),
+            ]),
+            ..Default::default()
+        };
+        let cassette = Cassette::replay_from(
+            "unused.json",
+            vec![
+                Interaction {
+                    operation: "values.update".to_string(),
+                    request: json!({
+                        "range": "users!A2:B2",
+                        "values": [["Joe", "2024-01-01"]]
+                    }),
+                    response: serde_json::to_value(UpdateValuesResponse::default())
+                        .expect("Test: Expected to serialize"),
+                },
+                Interaction {
+                    operation: "values.batchGetByDataFilter".to_string(),
+                    request: json!({ "range": "users!A2:B2", "valueRenderOption": "FORMULA" }),
+                    response: serde_json::to_value(stored).expect("Test: Expected to serialize"),
+                },
+            ],
+        );
+        let driver =
+            SpreadSheetDriver::replay("document".to_string(), cassette).with_verify_writes(true);
+
+        let report = driver
+            .try_write_range_as(
+                "users!A2:B2",
+                vec![vec![Value::from("Joe"), Value::from("2024-01-01")]],
+                InputMode::UserEntered,
+            )
+            .await
+            .expect_err("Test: Expected verification to fail");
+
+        match report.current_context() {
+            SpreadSheetDriverError::WriteVerificationFailed { range, diff } => {
+                assert_eq!(range, "users!A2:B2");
+                assert_eq!(
+                    diff.0,
+                    vec![CellMismatch {
+                        cell: "users!B2".to_string(),
+                        sent: Value::from("2024-01-01"),
+                        stored: Value::from("45292"),
+                    }]
+                );
+            }
+            other => panic!("Test: Unexpected error {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,478 @@
+//////////////////////// Example entities and golden fixtures ////////////////////////
+
+use crate::mapper::sheet_cell::{
+    CellParsingError, CellSerdeResult, SheetRawCell, SheetRawCellSerde,
+};
+use crate::mapper::sheet_row;
+use crate::mapper::sheet_row::{SheetRow, SheetRowExt, SheetRowSerde};
+use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+use crate::types::{
+    A1CellId, ColumnKind, ColumnMeta, EntityEssentials, EntityTable, SheetEnum, SpreadSheetDateTime,
+};
+use error_stack::Report;
+use google_sheets4::chrono::NaiveDate;
+use serde_json::{Value, json};
+
+/// Rows as the API returns them (numbers unformatted, trailing empty cells omitted) and the
+/// entities they parse into. `serialize` of the entities parses back into the same entities
+#[derive(Debug, Clone, PartialEq)]
+pub struct Golden<E> {
+    pub rows: Vec<SheetRow>,
+    pub entities: Vec<E>,
+}
+
+impl<E> Golden<E>
+where
+    E: EntityTable,
+{
+    /// Backend with the sheet of the entity: the header row, then the golden rows
+    pub fn backend(&self) -> MemoryBackend {
+        let header: SheetRow = E::column_headers()
+            .iter()
+            .map(|&h| Value::from(h))
+            .collect();
+        let backend = MemoryBackend::new();
+        backend.workbook().set_sheet(
+            E::sheet(),
+            std::iter::once(header).chain(self.rows.clone()).collect(),
+        );
+        backend
+    }
+}
+
+/// Smallest entity: an integer id and a name, the default fixture of the ORM tests
+#[derive(Debug, Clone, PartialEq)]
+pub struct User {
+    pub id: i32,
+    pub name: String,
+}
+
+impl User {
+    pub fn new(id: i32, name: &str) -> Self {
+        Self {
+            id,
+            name: name.to_string(),
+        }
+    }
+}
+
+impl SheetRowSerde for User {
+    fn deserialize(row: SheetRow) -> sheet_row::Result<Self> {
+        Ok(Self {
+            id: row.parse_cell(0, "id")?,
+            name: row.parse_cell(1, "name")?,
+        })
+    }
+
+    fn serialize(&self) -> sheet_row::Result<SheetRow> {
+        Ok(vec![
+            Value::String(self.id.to_string()),
+            Value::String(self.name.clone()),
+        ])
+    }
+}
+
+impl EntityEssentials for User {
+    fn entity_width() -> u32 {
+        2
+    }
+
+    fn column_headers() -> &'static [&'static str] {
+        &["id", "name"]
+    }
+
+    fn columns() -> Vec<ColumnMeta> {
+        vec![
+            ColumnMeta::new("id").kind(ColumnKind::Integer),
+            ColumnMeta::new("name").kind(ColumnKind::Text),
+        ]
+    }
+}
+
+/// Enum field stored as text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CustomerStatus {
+    Active,
+    Suspended,
+    Closed,
+}
+
+impl CustomerStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CustomerStatus::Active => "active",
+            CustomerStatus::Suspended => "suspended",
+            CustomerStatus::Closed => "closed",
+        }
+    }
+}
+
+impl SheetEnum for CustomerStatus {
+    fn variants() -> &'static [&'static str] {
+        &["active", "suspended", "closed"]
+    }
+}
+
+impl SheetRawCellSerde for CustomerStatus {
+    fn serialize(&self) -> SheetRawCell {
+        SheetRawCell::from(self.as_str().to_string())
+    }
+
+    fn deserialize(cell: SheetRawCell) -> CellSerdeResult<Self> {
+        match cell.as_str() {
+            "active" => Ok(CustomerStatus::Active),
+            "suspended" => Ok(CustomerStatus::Suspended),
+            "closed" => Ok(CustomerStatus::Closed),
+            other => Err(Report::new(CellParsingError)
+                .attach_printable(format!("Unknown customer status {other:?}"))),
+        }
+    }
+}
+
+/// Text, optional and enum columns, a date stored as text
+#[derive(Debug, Clone, PartialEq)]
+pub struct Customer {
+    pub id: u32,
+    pub name: String,
+    pub email: Option<String>,
+    pub status: CustomerStatus,
+    pub signed_up: NaiveDate,
+}
+
+impl SheetRowSerde for Customer {
+    fn deserialize(row: SheetRow) -> sheet_row::Result<Self> {
+        Ok(Self {
+            id: row.parse_cell(0, "id")?,
+            name: row.parse_cell(1, "name")?,
+            email: row.parse_optional_cell(2, "email")?,
+            status: row.parse_cell(3, "status")?,
+            signed_up: row.parse_cell(4, "signed_up")?,
+        })
+    }
+
+    fn serialize(&self) -> sheet_row::Result<SheetRow> {
+        Ok(vec![
+            Value::String(self.id.to_string()),
+            Value::String(self.name.clone()),
+            Value::String(self.email.clone().unwrap_or_default()),
+            Value::String(self.status.as_str().to_string()),
+            Value::String(self.signed_up.format("%Y-%m-%d").to_string()),
+        ])
+    }
+}
+
+impl EntityEssentials for Customer {
+    fn entity_width() -> u32 {
+        5
+    }
+
+    fn column_headers() -> &'static [&'static str] {
+        &["id", "name", "email", "status", "signed_up"]
+    }
+
+    fn columns() -> Vec<ColumnMeta> {
+        vec![
+            ColumnMeta::new("id").kind(ColumnKind::Integer),
+            ColumnMeta::new("name").kind(ColumnKind::Text),
+            ColumnMeta::new("email").kind(ColumnKind::Text),
+            ColumnMeta::new("status").kind(ColumnKind::Text),
+            ColumnMeta::new("signed_up")
+                .kind(ColumnKind::Date)
+                .format("yyyy-mm-dd"),
+        ]
+    }
+}
+
+impl EntityTable for Customer {
+    fn sheet() -> &'static str {
+        "customers"
+    }
+
+    fn origin() -> A1CellId {
+        A1CellId::from_primitives("A", 2)
+    }
+}
+
+/// Unformatted numbers: a serial date, a float amount and a boolean
+#[derive(Debug, Clone, PartialEq)]
+pub struct Invoice {
+    pub number: String,
+    pub customer_id: u32,
+    pub issued: SpreadSheetDateTime,
+    pub amount: f64,
+    pub paid: bool,
+}
+
+impl SheetRowSerde for Invoice {
+    fn deserialize(row: SheetRow) -> sheet_row::Result<Self> {
+        Ok(Self {
+            number: row.parse_cell(0, "number")?,
+            customer_id: row.parse_cell(1, "customer_id")?,
+            issued: row.parse_cell(2, "issued")?,
+            amount: row.parse_cell(3, "amount")?,
+            paid: row.parse_cell(4, "paid")?,
+        })
+    }
+
+    fn serialize(&self) -> sheet_row::Result<SheetRow> {
+        Ok(vec![
+            Value::String(self.number.clone()),
+            json!(self.customer_id),
+            json!(self.issued.to_raw()),
+            json!(self.amount),
+            Value::Bool(self.paid),
+        ])
+    }
+}
+
+impl EntityEssentials for Invoice {
+    fn entity_width() -> u32 {
+        5
+    }
+
+    fn column_headers() -> &'static [&'static str] {
+        &["number", "customer_id", "issued", "amount", "paid"]
+    }
+
+    fn columns() -> Vec<ColumnMeta> {
+        vec![
+            ColumnMeta::new("number").kind(ColumnKind::Text),
+            ColumnMeta::new("customer_id").kind(ColumnKind::Integer),
+            ColumnMeta::new("issued")
+                .kind(ColumnKind::Date)
+                .format("yyyy-mm-dd"),
+            ColumnMeta::new("amount")
+                .kind(ColumnKind::Number)
+                .format("0.00"),
+            ColumnMeta::new("paid").kind(ColumnKind::Boolean),
+        ]
+    }
+}
+
+impl EntityTable for Invoice {
+    fn sheet() -> &'static str {
+        "invoices"
+    }
+
+    fn origin() -> A1CellId {
+        A1CellId::from_primitives("A", 2)
+    }
+}
+
+/// Wide row: a sensor with a reading per hour, hours without a reading are empty
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailyReadings {
+    pub sensor: String,
+    pub hours: Vec<Option<f64>>,
+}
+
+impl DailyReadings {
+    pub const HOURS: usize = 24;
+}
+
+impl SheetRowSerde for DailyReadings {
+    fn deserialize(row: SheetRow) -> sheet_row::Result<Self> {
+        Ok(Self {
+            sensor: row.parse_cell(0, "sensor")?,
+            hours: (0..Self::HOURS)
+                .map(|hour| row.parse_optional_cell(hour + 1, "hour"))
+                .collect::<sheet_row::Result<_>>()?,
+        })
+    }
+
+    fn serialize(&self) -> sheet_row::Result<SheetRow> {
+        let hours = self.hours.iter().map(|reading| match reading {
+            Some(reading) => json!(reading),
+            None => Value::String(String::new()),
+        });
+        Ok(std::iter::once(Value::String(self.sensor.clone()))
+            .chain(hours)
+            .collect())
+    }
+}
+
+impl EntityEssentials for DailyReadings {
+    fn entity_width() -> u32 {
+        1 + Self::HOURS as u32
+    }
+
+    fn column_headers() -> &'static [&'static str] {
+        &[
+            "sensor", "h00", "h01", "h02", "h03", "h04", "h05", "h06", "h07", "h08", "h09", "h10",
+            "h11", "h12", "h13", "h14", "h15", "h16", "h17", "h18", "h19", "h20", "h21", "h22",
+            "h23",
+        ]
+    }
+}
+
+impl EntityTable for DailyReadings {
+    fn sheet() -> &'static str {
+        "readings"
+    }
+
+    fn origin() -> A1CellId {
+        A1CellId::from_primitives("A", 2)
+    }
+}
+
+fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).expect("Expected valid golden date")
+}
+
+/// Ids come back as integral floats, missing email is an empty cell in the middle of the row
+pub fn golden_customers() -> Golden<Customer> {
+    Golden {
+        rows: vec![
+            vec![
+                json!(1.0),
+                json!("Joe"),
+                json!("joe@mail.com"),
+                json!("active"),
+                json!("2024-01-15"),
+            ],
+            vec![
+                json!(2),
+                json!("Jane"),
+                json!(""),
+                json!("suspended"),
+                json!("2023-12-31"),
+            ],
+        ],
+        entities: vec![
+            Customer {
+                id: 1,
+                name: "Joe".to_string(),
+                email: Some("joe@mail.com".to_string()),
+                status: CustomerStatus::Active,
+                signed_up: date(2024, 1, 15),
+            },
+            Customer {
+                id: 2,
+                name: "Jane".to_string(),
+                email: None,
+                status: CustomerStatus::Suspended,
+                signed_up: date(2023, 12, 31),
+            },
+        ],
+    }
+}
+
+/// Dates are serial numbers of days since 1899-12-30, the way unformatted reads return them
+pub fn golden_invoices() -> Golden<Invoice> {
+    let issued = |raw| SpreadSheetDateTime::from_raw(raw).expect("Expected valid golden date");
+    Golden {
+        rows: vec![
+            vec![
+                json!("INV-1"),
+                json!(1),
+                json!(45292),
+                json!(120.5),
+                json!(true),
+            ],
+            vec![
+                json!("INV-2"),
+                json!(2),
+                json!(45323),
+                json!(99),
+                json!(false),
+            ],
+        ],
+        entities: vec![
+            Invoice {
+                number: "INV-1".to_string(),
+                customer_id: 1,
+                issued: issued(45292.0),
+                amount: 120.5,
+                paid: true,
+            },
+            Invoice {
+                number: "INV-2".to_string(),
+                customer_id: 2,
+                issued: issued(45323.0),
+                amount: 99.0,
+                paid: false,
+            },
+        ],
+    }
+}
+
+/// The second sensor stopped reporting at noon, so its row ends early
+pub fn golden_readings() -> Golden<DailyReadings> {
+    let full: Vec<f64> = (0..DailyReadings::HOURS)
+        .map(|hour| hour as f64 / 2.0)
+        .collect();
+    let row = |sensor: &str, hours: &[f64]| -> SheetRow {
+        std::iter::once(json!(sensor))
+            .chain(hours.iter().map(|reading| json!(reading)))
+            .collect()
+    };
+    let hours = |known: &[f64]| -> Vec<Option<f64>> {
+        (0..DailyReadings::HOURS)
+            .map(|hour| known.get(hour).copied())
+            .collect()
+    };
+    Golden {
+        rows: vec![row("north", &full), row("south", &full[..12])],
+        entities: vec![
+            DailyReadings {
+                sensor: "north".to_string(),
+                hours: hours(&full),
+            },
+            DailyReadings {
+                sensor: "south".to_string(),
+                hours: hours(&full[..12]),
+            },
+        ],
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod examples_support_tests {
+    use super::*;
+    use crate::testing::fixtures::backend_repository;
+
+    fn assert_round_trip<E>(golden: Golden<E>)
+    where
+        E: EntityEssentials,
+    {
+        let parsed: Vec<E> = golden
+            .rows
+            .into_iter()
+            .map(|row| E::deserialize(row).expect("Test: Expected golden row to parse"))
+            .collect();
+        assert_eq!(parsed, golden.entities);
+
+        for entity in &golden.entities {
+            let row = entity.serialize().expect("Test: Expected to serialize");
+            assert_eq!(row.len(), E::entity_width() as usize);
+            assert_eq!(
+                &E::deserialize(row).expect("Test: Expected to parse back"),
+                entity
+            );
+        }
+    }
+
+    #[test]
+    fn golden__every_example__parsed_and_serialized_back() {
+        assert_round_trip(golden_customers());
+        assert_round_trip(golden_invoices());
+        assert_round_trip(golden_readings());
+    }
+
+    #[tokio::test]
+    async fn golden_backend__find_all__entities_at_rows_below_header() {
+        let golden = golden_customers();
+        let repository = backend_repository(golden.backend());
+
+        let found = repository
+            .of::<Customer>()
+            .find_all()
+            .await
+            .expect("Test: Expected customers");
+
+        let data: Vec<Customer> = found.iter().map(|e| e.data.clone()).collect();
+        assert_eq!(data, golden.entities);
+        assert_eq!(found[1].position.to_string(), "customers!A3".to_string());
+    }
+}
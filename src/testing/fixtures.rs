@@ -0,0 +1,276 @@
+//////////////////////// Builders of google_sheets4 API fixtures ////////////////////////
+
+use crate::mapper::sheet_row::SheetRow;
+use crate::orm::Repository;
+use crate::spread_sheet_driver::backend::SheetsBackend;
+use crate::spread_sheet_driver::backend::memory::MemoryBackend;
+use crate::spread_sheet_driver::cassette::{Cassette, Interaction};
+use crate::spread_sheet_driver::{SharedSpreadSheetDriver, SpreadSheetDriver};
+use crate::types::MajorDimension;
+use google_sheets4::api::{
+    AppendValuesResponse, DataFilter, MatchedValueRange, UpdateValuesResponse, ValueRange,
+};
+use serde_json::Value;
+use std::fmt::Display;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Builds `ValueRange` from rows of anything convertible into JSON values
+/// Example: ValueRangeBuilder::new().range("users!A1:B1").row(["1", "Joe"]).build()
+#[derive(Debug, Clone, Default)]
+pub struct ValueRangeBuilder {
+    range: Option<String>,
+    major_dimension: Option<MajorDimension>,
+    rows: Vec<SheetRow>,
+}
+
+impl ValueRangeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn range<R>(mut self, range: R) -> Self
+    where
+        R: Display,
+    {
+        self.range = Some(range.to_string());
+        self
+    }
+
+    pub fn major_dimension(mut self, major_dimension: MajorDimension) -> Self {
+        self.major_dimension = Some(major_dimension);
+        self
+    }
+
+    pub fn row<I, V>(mut self, row: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<Value>,
+    {
+        self.rows.push(row.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn rows<I, R, V>(self, rows: I) -> Self
+    where
+        I: IntoIterator<Item = R>,
+        R: IntoIterator<Item = V>,
+        V: Into<Value>,
+    {
+        rows.into_iter().fold(self, |builder, row| builder.row(row))
+    }
+
+    pub fn build(self) -> ValueRange {
+        ValueRange {
+            major_dimension: self.major_dimension.map(|d| d.to_string()),
+            range: self.range,
+            values: Some(self.rows),
+        }
+    }
+}
+
+/// Builds `MatchedValueRange` as returned by the batchGetByDataFilter for a single A1 filter,
+/// which is the shape expected by `PositionalParsing`
+#[derive(Debug, Clone)]
+pub struct MatchedValueRangeBuilder {
+    a1_range: String,
+    values: ValueRangeBuilder,
+}
+
+impl MatchedValueRangeBuilder {
+    /// `a1_range` - the range of the data filter, e.g. "users!A1:B3"
+    pub fn new<R>(a1_range: R) -> Self
+    where
+        R: Display,
+    {
+        let a1_range = a1_range.to_string();
+        Self {
+            values: ValueRangeBuilder::new()
+                .range(&a1_range)
+                .major_dimension(MajorDimension::Rows),
+            a1_range,
+        }
+    }
+
+    pub fn row<I, V>(mut self, row: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<Value>,
+    {
+        self.values = self.values.row(row);
+        self
+    }
+
+    pub fn rows<I, R, V>(mut self, rows: I) -> Self
+    where
+        I: IntoIterator<Item = R>,
+        R: IntoIterator<Item = V>,
+        V: Into<Value>,
+    {
+        self.values = self.values.rows(rows);
+        self
+    }
+
+    pub fn build(self) -> MatchedValueRange {
+        MatchedValueRange {
+            data_filters: Some(vec![DataFilter {
+                a1_range: Some(self.a1_range),
+                ..Default::default()
+            }]),
+            value_range: Some(self.values.build()),
+        }
+    }
+}
+
+/// Builds `AppendValuesResponse` with `updates` filled in the way the API does it.
+/// Updated rows/columns/cells are derived from the appended rows
+#[derive(Debug, Clone)]
+pub struct AppendValuesResponseBuilder {
+    spreadsheet_id: String,
+    table_range: Option<String>,
+    updated_range: String,
+    rows: Vec<SheetRow>,
+}
+
+impl AppendValuesResponseBuilder {
+    /// `updated_range` - the range the rows were appended to, e.g. "users!A4:B4"
+    pub fn new<R>(updated_range: R) -> Self
+    where
+        R: Display,
+    {
+        Self {
+            spreadsheet_id: "spreadsheet".to_string(),
+            table_range: None,
+            updated_range: updated_range.to_string(),
+            rows: vec![],
+        }
+    }
+
+    pub fn spreadsheet_id<S>(mut self, spreadsheet_id: S) -> Self
+    where
+        S: Display,
+    {
+        self.spreadsheet_id = spreadsheet_id.to_string();
+        self
+    }
+
+    pub fn table_range<R>(mut self, table_range: R) -> Self
+    where
+        R: Display,
+    {
+        self.table_range = Some(table_range.to_string());
+        self
+    }
+
+    pub fn row<I, V>(mut self, row: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<Value>,
+    {
+        self.rows.push(row.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn build(self) -> AppendValuesResponse {
+        let updated_rows = self.rows.len() as i32;
+        let updated_columns = self.rows.iter().map(Vec::len).max().unwrap_or_default() as i32;
+        let updated_cells = self.rows.iter().map(Vec::len).sum::<usize>() as i32;
+
+        AppendValuesResponse {
+            spreadsheet_id: Some(self.spreadsheet_id.clone()),
+            table_range: self.table_range,
+            updates: Some(UpdateValuesResponse {
+                spreadsheet_id: Some(self.spreadsheet_id),
+                updated_range: Some(self.updated_range),
+                updated_rows: Some(updated_rows),
+                updated_columns: Some(updated_columns),
+                updated_cells: Some(updated_cells),
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+/// Driver serving every call from `backend`, shared the way a repository holds it
+pub fn shared_driver<B>(backend: B) -> SharedSpreadSheetDriver
+where
+    B: SheetsBackend + 'static,
+{
+    Arc::new(Mutex::new(SpreadSheetDriver::with_backend(
+        "document".to_string(),
+        backend,
+    )))
+}
+
+/// Repository over `backend`, e.g. a `MemoryBackend` with its sheets set up
+pub fn backend_repository<B>(backend: B) -> Repository
+where
+    B: SheetsBackend + 'static,
+{
+    Repository::new(shared_driver(backend))
+}
+
+/// Repository over an empty in-memory workbook
+pub fn memory_repository() -> Repository {
+    backend_repository(MemoryBackend::new())
+}
+
+/// Repository replaying `interactions` instead of calling the API
+pub fn replay_repository(interactions: Vec<Interaction>) -> Repository {
+    let cassette = Cassette::replay_from("unused.json", interactions);
+    let driver = SpreadSheetDriver::replay("document".to_string(), cassette);
+    Repository::new(Arc::new(Mutex::new(driver)))
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod fixtures_tests {
+    use super::*;
+
+    #[test]
+    fn value_range__with_mixed_rows__ok() {
+        let range = ValueRangeBuilder::new()
+            .range("users!A1:B2")
+            .row(["1", "Joe"])
+            .row([Value::from(2), Value::from(true)])
+            .build();
+
+        assert_eq!(range.range.as_deref(), Some("users!A1:B2"));
+        assert_eq!(
+            range.values,
+            Some(vec![
+                vec![Value::from("1"), Value::from("Joe")],
+                vec![Value::from(2), Value::from(true)],
+            ])
+        );
+    }
+
+    #[test]
+    fn matched_value_range__has_single_a1_filter__ok() {
+        let mvr = MatchedValueRangeBuilder::new("users!A1:B1")
+            .row(["1", "Joe"])
+            .build();
+
+        let filters = mvr.data_filters.expect("Test: Expected data filters");
+        assert_eq!(filters.len(), 1);
+        assert_eq!(filters[0].a1_range.as_deref(), Some("users!A1:B1"));
+        assert_eq!(
+            mvr.value_range.and_then(|v| v.major_dimension),
+            Some("ROWS".to_string())
+        );
+    }
+
+    #[test]
+    fn append_values_response__counts_derived_from_rows__ok() {
+        let avr = AppendValuesResponseBuilder::new("users!A4:B5")
+            .row(["4", "Jill"])
+            .row(["5", "Jack"])
+            .build();
+
+        let updates = avr.updates.expect("Test: Expected updates");
+        assert_eq!(updates.updated_range.as_deref(), Some("users!A4:B5"));
+        assert_eq!(updates.updated_rows, Some(2));
+        assert_eq!(updates.updated_columns, Some(2));
+        assert_eq!(updates.updated_cells, Some(4));
+    }
+}
@@ -0,0 +1,2 @@
+pub mod examples_support;
+pub mod fixtures;
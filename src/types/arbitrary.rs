@@ -0,0 +1,154 @@
+//////////////////////// proptest strategies for coordinate types ////////////////////////
+
+use crate::types::cell::conversions::dec_to_string_as_base26;
+use crate::types::{A1CellId, A1Range, Letters, NumCellId, NumRange, SheetA1CellId, SheetA1Range};
+use proptest::prelude::*;
+use std::num::NonZero;
+
+/// Column ZZZ, the widest one the letters are generated up to
+const MAX_COLUMN: u32 = Letters::MAX_COLUMN_NUMBER;
+/// Spreadsheets are limited to 10M cells, so no sheet has more rows
+const MAX_ROW: u32 = 10_000_000;
+
+impl Arbitrary for Letters {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (1..=MAX_COLUMN)
+            .prop_map(|column| Letters::from_valid(dec_to_string_as_base26(column)))
+            .boxed()
+    }
+}
+
+impl Arbitrary for A1CellId {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (any::<Letters>(), 1..=MAX_ROW)
+            .prop_map(|(col, row)| A1CellId::new(col, NonZero::new(row).expect("Non-zero row")))
+            .boxed()
+    }
+}
+
+impl Arbitrary for SheetA1CellId {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (sheet_name(), any::<A1CellId>())
+            .prop_map(|(sheet, cell)| SheetA1CellId::new(sheet, cell))
+            .boxed()
+    }
+}
+
+/// Normalized: start is at the top left of end
+impl Arbitrary for A1Range {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (any::<A1CellId>(), any::<A1CellId>())
+            .prop_map(|(from, to)| A1Range::new(from, to).normalized())
+            .boxed()
+    }
+}
+
+impl Arbitrary for SheetA1Range {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (sheet_name(), any::<A1Range>())
+            .prop_map(|(sheet, range)| SheetA1Range::new(sheet, range))
+            .boxed()
+    }
+}
+
+impl Arbitrary for NumCellId {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (0..MAX_COLUMN, 0..MAX_ROW)
+            .prop_map(|(col, row)| NumCellId::from_primitives(col, row))
+            .boxed()
+    }
+}
+
+impl Arbitrary for NumRange {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        any::<A1Range>().prop_map(NumRange::from).boxed()
+    }
+}
+
+/// Plain names, quoting is not covered
+fn sheet_name() -> impl Strategy<Value = String> {
+    "[A-Za-z][A-Za-z0-9_]{0,11}"
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod arbitrary_tests {
+    use super::*;
+    use crate::types::cell::conversions::string_to_dec_as_base26;
+
+    proptest! {
+        #[test]
+        fn base26__round_trip__same_number(column in 1..=MAX_COLUMN) {
+            prop_assert_eq!(string_to_dec_as_base26(&dec_to_string_as_base26(column)), column);
+        }
+
+        #[test]
+        fn letters__add_then_sub__same_letters(
+            letters in any::<Letters>(),
+            delta in 0..MAX_COLUMN,
+        ) {
+            prop_assume!(letters.column_number() + delta <= MAX_COLUMN);
+            let moved = letters.clone() + delta;
+            prop_assert_eq!(&moved - &letters, delta as i32);
+            prop_assert_eq!(&letters - &moved, -(delta as i32));
+            prop_assert_eq!(moved - delta, letters);
+        }
+
+        #[test]
+        fn letters__order__matches_column_numbers(a in any::<Letters>(), b in any::<Letters>()) {
+            prop_assert_eq!(
+                a.partial_cmp(&b),
+                string_to_dec_as_base26(&a).partial_cmp(&string_to_dec_as_base26(&b))
+            );
+        }
+
+        #[test]
+        fn a1_cell_id__parse_to_string__same_cell(cell in any::<A1CellId>()) {
+            let parsed = A1CellId::from_raw(cell.to_string()).expect("Test: Expected valid cell");
+            prop_assert_eq!(parsed, cell);
+        }
+
+        #[test]
+        fn num_cell_id__to_a1_and_back__same_cell(cell in any::<NumCellId>()) {
+            prop_assert_eq!(NumCellId::from(A1CellId::from(cell)), cell);
+        }
+
+        #[test]
+        fn sheet_a1_range__parse_to_string__same_range(range in any::<SheetA1Range>()) {
+            let parsed =
+                SheetA1Range::from_raw(range.to_string()).expect("Test: Expected valid range");
+            prop_assert_eq!(parsed, range);
+        }
+
+        #[test]
+        fn a1_range__normalized__idempotent_and_ordered(
+            from in any::<A1CellId>(),
+            to in any::<A1CellId>(),
+        ) {
+            let range = A1Range::new(from, to).normalized();
+            prop_assert!(range.start.col <= range.end.col && range.start.row <= range.end.row);
+            prop_assert_eq!(range.normalized(), range);
+        }
+    }
+}
@@ -1,35 +1,47 @@
 use crate::types::cell::conversions::string_to_dec_as_base26;
 use crate::types::cell::num_cell_id::NumCellId;
-use crate::types::letters::Letters;
-use crate::types::{A1Range, SheetA1Range};
-use error_stack::{ResultExt, bail};
-use huh::IntoReport;
+use crate::types::letters::{Letters, LettersError};
+use crate::types::{A1Range, SheetA1Range, SheetGid};
+use error_stack::{Report, bail};
+use std::borrow::Cow;
 use std::cmp::Ordering;
-use std::fmt::Display;
+use std::fmt::{Display, Formatter};
 use std::num::{NonZero, NonZeroU32};
 use std::ops::{Add, Deref};
 
 pub type Result<T> = error_stack::Result<T, A1CellIdError>;
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+/// Ordered by sheet name, then row-major within the sheet
+#[derive(Debug, Clone, Eq, PartialEq, Hash, PartialOrd, Ord)]
 pub struct SheetA1CellId {
     pub sheet_name: String,
     pub cell: A1CellId,
 }
 
 impl SheetA1CellId {
+    /// Parses the A1 notation [`Display`] produces: `users!A1`, `'My ''best'' sheet'!A1`.
+    /// The older `users:A1` form is accepted too
     pub fn from_raw<S>(str: S) -> Result<SheetA1CellId>
     where
         S: Display,
     {
         let string = str.to_string();
-        let parts: Vec<&str> = string.split(':').collect();
-        if parts.len() != 2 {
-            bail!(A1CellIdError::InvalidCellFormat(str.to_string()))
+        // The cell has no `!`, while a quoted sheet name may
+        let (sheet_name, cell) = match string.rsplit_once('!') {
+            Some((sheet, cell)) => (unquote_sheet_name(sheet), cell),
+            None => {
+                let parts: Vec<&str> = string.split(':').collect();
+                if parts.len() != 2 {
+                    bail!(A1CellIdError::InvalidCellFormat(string))
+                };
+                (parts[0].to_owned(), parts[1])
+            }
         };
+        if sheet_name.is_empty() {
+            bail!(A1CellIdError::InvalidCellFormat(string.clone()))
+        }
 
-        let sheet_name = parts[0].to_owned();
-        let cell = A1CellId::from_raw(parts[1])?;
+        let cell = A1CellId::from_raw(cell)?;
         Ok(SheetA1CellId { sheet_name, cell })
     }
 }
@@ -56,6 +68,51 @@ impl SheetA1CellId {
         }
     }
 
+    /// 0-based offset of the cell from `origin`, e.g. "users!C5" relative to "users!B2" is
+    /// `(col: 1, row: 3)`. `None` for cells of other sheets, above or left of `origin`
+    pub fn relative_to(&self, origin: &SheetA1CellId) -> Option<NumCellId> {
+        if self.sheet_name != origin.sheet_name {
+            return None;
+        }
+        let (cell, origin) = (self.cell.as_indices(), origin.cell.as_indices());
+        Some(NumCellId::from_primitives(
+            cell.col.checked_sub(origin.col)?,
+            cell.row.checked_sub(origin.row)?,
+        ))
+    }
+
+    /// Rows between the cell and `origin`, e.g. "users!C5" is 3 rows after "users!A2".
+    /// `None` for cells of other sheets or above `origin`
+    pub fn row_offset_from(&self, origin: &SheetA1CellId) -> Option<u32> {
+        if self.sheet_name != origin.sheet_name {
+            return None;
+        }
+        self.cell.row.get().checked_sub(origin.cell.row.get())
+    }
+
+    /// Same column `rows` rows below, or above for negative `rows`.
+    /// `None` if that is above the first row
+    pub fn shift_rows(&self, rows: i32) -> Option<SheetA1CellId> {
+        let row = self.cell.row.get().checked_add_signed(rows)?;
+        Some(SheetA1CellId::new(
+            &self.sheet_name,
+            A1CellId::new(self.cell.col.clone(), NonZero::new(row)?),
+        ))
+    }
+
+    /// Whether the cell is on the sheet of `range` and inside its bounds
+    pub fn is_within(&self, range: &SheetA1Range) -> bool {
+        let range = range.normalized();
+        let (cell, start, end) = (
+            self.cell.as_indices(),
+            range.range.start.as_indices(),
+            range.range.end.as_indices(),
+        );
+        self.sheet_name == range.sheet
+            && (start.col..=end.col).contains(&cell.col)
+            && (start.row..=end.row).contains(&cell.row)
+    }
+
     pub fn into_range<C>(self, end_col: C, end_row: u32) -> SheetA1Range
     where
         C: Display,
@@ -65,12 +122,62 @@ impl SheetA1CellId {
             A1Range::new(self.cell, A1CellId::from_primitives(end_col, end_row)),
         )
     }
+
+    /// Link which opens the sheet with the cell selected, e.g. for error messages.
+    /// Links address sheets by gid, see `SpreadSheetDriver::try_cell_url` to resolve it
+    pub fn to_url(&self, spreadsheet_id: &str, gid: SheetGid) -> String {
+        spreadsheet_url(spreadsheet_id, gid, &self.cell)
+    }
+}
+
+/// `https://docs.google.com/spreadsheets/d/<id>/edit#gid=<gid>&range=B2`
+pub(crate) fn spreadsheet_url<R>(spreadsheet_id: &str, gid: SheetGid, range: R) -> String
+where
+    R: Display,
+{
+    format!("https://docs.google.com/spreadsheets/d/{spreadsheet_id}/edit#gid={gid}&range={range}")
+}
+
+impl Display for SheetA1CellId {
+    /// `users!A1`, `'My sheet'!A1`
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}!{}", quote_sheet_name(&self.sheet_name), self.cell)
+    }
+}
+
+/// Sheet name the way A1 notation expects it: plain names as is ("users"), others in single
+/// quotes with the quotes inside doubled ("'My ''best'' sheet'"). Names which look like a cell
+/// ("Q1") are quoted too
+pub fn quote_sheet_name(name: &str) -> Cow<'_, str> {
+    let plain = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && A1CellId::from_raw(name).is_err();
+    match plain {
+        true => Cow::Borrowed(name),
+        false => Cow::Owned(format!("'{}'", name.replace('\'', "''"))),
+    }
+}
+
+/// Reverse of [`quote_sheet_name`]
+pub fn unquote_sheet_name(raw: &str) -> String {
+    match raw
+        .strip_prefix('\'')
+        .and_then(|name| name.strip_suffix('\''))
+    {
+        Some(name) => name.replace("''", "'"),
+        None => raw.to_string(),
+    }
 }
 
 #[derive(Debug, Clone, thiserror::Error, PartialEq)]
 pub enum A1CellIdError {
     #[error("Invalid cell format: {0}")]
     InvalidCellFormat(String),
+    #[error("Column {0} is past the last column of a sheet (ZZZ)")]
+    ColumnOutOfRange(String),
 }
 
 /// Defines a cell id in A1 notation.
@@ -86,31 +193,7 @@ impl A1CellId {
     where
         S: Display,
     {
-        let string = value.to_string();
-        let mut col = String::new();
-        let mut row = String::new();
-
-        for c in string.chars() {
-            if c.is_alphabetic() {
-                col.push(c);
-            } else if c.is_numeric() {
-                row.push(c);
-            } else {
-                bail!(A1CellIdError::InvalidCellFormat(string));
-            }
-        }
-
-        if col.is_empty() || row.is_empty() {
-            bail!(A1CellIdError::InvalidCellFormat(string));
-        }
-
-        let col = Letters::new(col);
-        let row = row
-            .parse::<u32>()
-            .into_report()
-            .change_context(A1CellIdError::InvalidCellFormat(string))?;
-
-        Ok(A1CellId::from_primitives(col, row))
+        A1CellId::try_from(value.to_string().as_str()).map_err(Report::new)
     }
 }
 
@@ -161,7 +244,7 @@ impl A1CellId {
         C: Display,
     {
         Self {
-            col: Letters::new(col.to_string()),
+            col: Letters::from_valid(col.to_string()),
             row: NonZero::new(row).expect("Expected a non-zero cell row number"),
         }
     }
@@ -174,17 +257,12 @@ impl A1CellId {
         }
     }
 
-    pub fn to_string(&self) -> String {
-        format!("{}{}", self.col.deref(), self.row)
-    }
-
     pub(crate) fn delta(&self, columns: i32, rows: i32) -> A1CellId {
         let number = self.row.get() as i32 + rows;
-        let letter = if columns < 0 {
-            self.col.clone() - columns.unsigned_abs()
-        } else {
-            self.col.clone() + columns as u32
-        };
+        let letter = self
+            .col
+            .checked_add_signed(i64::from(columns))
+            .expect("Expected column at or after A");
 
         A1CellId::new(
             letter,
@@ -193,44 +271,62 @@ impl A1CellId {
     }
 }
 
+impl Display for A1CellId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.col.deref(), self.row)
+    }
+}
+
 impl TryFrom<&str> for A1CellId {
     type Error = A1CellIdError;
 
+    /// Lowercase columns are accepted ("b2" is B2), columns past ZZZ are rejected with
+    /// [`A1CellIdError::ColumnOutOfRange`]
     fn try_from(value: &str) -> std::result::Result<A1CellId, A1CellIdError> {
-        let mut letter = String::new();
-        let mut number = String::new();
-
-        for c in value.chars() {
-            if c.is_alphabetic() {
-                letter.push(c);
-            } else if c.is_numeric() {
-                number.push(c);
-            } else {
-                return Err(A1CellIdError::InvalidCellFormat(value.to_string()));
-            }
+        let invalid = || A1CellIdError::InvalidCellFormat(value.to_string());
+        let split = value
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(value.len());
+        let (letters, number) = value.split_at(split);
+        if letters.is_empty() || number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
         }
 
-        if letter.is_empty() || number.is_empty() {
-            return Err(A1CellIdError::InvalidCellFormat(value.to_string()));
-        }
+        let col = Letters::try_from(letters.to_string()).map_err(|report| {
+            match report.current_context() {
+                LettersError::PastLastColumn(_) => {
+                    A1CellIdError::ColumnOutOfRange(letters.to_uppercase())
+                }
+                _ => invalid(),
+            }
+        })?;
+        let row = number.parse().map_err(|_| invalid())?;
+        Ok(Self { col, row })
+    }
+}
 
-        Ok(Self {
-            col: Letters::new(letter),
-            row: number.parse().unwrap(),
-        })
+/// Row-major, the order cells are read in: A1 < B1 < A2.
+/// See [`A1CellId::cmp_column_major`] for the other one
+impl Ord for A1CellId {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.row
+            .cmp(&other.row)
+            .then_with(|| self.col.cmp(&other.col))
     }
 }
 
 impl PartialOrd for A1CellId {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        let Some(x_ord) = self.col.partial_cmp(&other.col) else {
-            return None;
-        };
-        let Some(y_ord) = self.row.partial_cmp(&other.row) else {
-            return None;
-        };
+        Some(self.cmp(other))
+    }
+}
 
-        Some(y_ord.then(x_ord))
+impl A1CellId {
+    /// Column-major order: A1 < A2 < B1, e.g. `cells.sort_by(A1CellId::cmp_column_major)`
+    pub fn cmp_column_major(&self, other: &Self) -> Ordering {
+        self.col
+            .cmp(&other.col)
+            .then_with(|| self.row.cmp(&other.row))
     }
 }
 
@@ -262,6 +358,13 @@ mod a1_cell_id_tests {
             A1CellId::from_primitives("1", 1);
         }
 
+        #[test]
+        fn cell_id__from_raw_zero_row__error_instead_of_panic() {
+            assert!(A1CellId::from_raw("A0").is_err());
+            assert!(A1CellId::try_from("A0").is_err());
+            assert!(A1CellId::try_from("A99999999999").is_err());
+        }
+
         #[test]
         fn cell_id__to_string__ok() {
             let cell_id = A1CellId::from_primitives("A", 1);
@@ -300,6 +403,35 @@ mod a1_cell_id_tests {
                 let result = A1CellId::from_raw("Z");
                 assert!(result.is_err());
             }
+
+            #[test]
+            fn cell_id__from_raw_up_to_zzz__ok_and_typed_error_past_it() {
+                let last = A1CellId::from_raw("ZZZ7").expect("Test: Expected the last column");
+                assert_eq!(last.col.column_number(), Letters::MAX_COLUMN_NUMBER);
+                assert_eq!(
+                    A1CellId::try_from("ab3").map(|cell| cell.to_string()),
+                    Ok("AB3".to_string())
+                );
+
+                let past = A1CellId::from_raw("AAAA1").expect_err("Test: Expected past ZZZ");
+                assert_eq!(
+                    past.current_context(),
+                    &A1CellIdError::ColumnOutOfRange("AAAA".to_string())
+                );
+                assert_eq!(
+                    A1CellId::try_from("1A"),
+                    Err(A1CellIdError::InvalidCellFormat("1A".to_string()))
+                );
+            }
+
+            #[test]
+            fn cell_id__try_from_non_ascii_letters__invalid_format() {
+                assert_eq!(
+                    A1CellId::try_from("ÉÉÉ1"),
+                    Err(A1CellIdError::InvalidCellFormat("ÉÉÉ1".to_string()))
+                );
+                assert!(A1CellId::from_raw("Ä1").is_err());
+            }
         }
     }
     #[cfg(test)]
@@ -366,4 +498,179 @@ mod a1_cell_id_tests {
             assert_eq!(cell_id.partial_cmp(&other), Some(Ordering::Greater));
         }
     }
+
+    mod ord_tests {
+        use super::*;
+
+        fn cells(raw: &[&str]) -> Vec<A1CellId> {
+            raw.iter()
+                .map(|cell| A1CellId::from_raw(cell).expect("Test: Expected cell"))
+                .collect()
+        }
+
+        #[test]
+        fn sort__row_major_and_column_major__ok() {
+            let mut row_major = cells(&["B2", "AA1", "A2", "Z1", "A1"]);
+            let mut column_major = row_major.clone();
+
+            row_major.sort();
+            column_major.sort_by(A1CellId::cmp_column_major);
+
+            assert_eq!(row_major, cells(&["A1", "Z1", "AA1", "A2", "B2"]));
+            assert_eq!(column_major, cells(&["A1", "A2", "B2", "Z1", "AA1"]));
+        }
+
+        #[test]
+        fn sheet_cells__sort__by_sheet_then_row_major() {
+            let mut sheet_cells = vec![
+                SheetA1CellId::from_primitives("users", "A", 2),
+                SheetA1CellId::from_primitives("orders", "B", 5),
+                SheetA1CellId::from_primitives("users", "B", 1),
+            ];
+
+            sheet_cells.sort();
+
+            assert_eq!(
+                sheet_cells,
+                vec![
+                    SheetA1CellId::from_primitives("orders", "B", 5),
+                    SheetA1CellId::from_primitives("users", "B", 1),
+                    SheetA1CellId::from_primitives("users", "A", 2),
+                ]
+            );
+        }
+    }
+
+    mod display_tests {
+        use super::*;
+
+        #[test]
+        fn sheet_cell__display__quoted_when_needed() {
+            let cell = |sheet: &str| SheetA1CellId::from_primitives(sheet, "B", 2).to_string();
+
+            assert_eq!(cell("users"), "users!B2");
+            assert_eq!(cell("My sheet"), "'My sheet'!B2");
+            assert_eq!(cell("Joe's"), "'Joe''s'!B2");
+            assert_eq!(cell("Q1"), "'Q1'!B2");
+            assert_eq!(cell("2024"), "'2024'!B2");
+        }
+
+        #[test]
+        fn sheet_cell__to_url__gid_and_cell_without_sheet() {
+            let cell = SheetA1CellId::from_primitives("My sheet", "B", 2);
+
+            assert_eq!(
+                cell.to_url("doc-id", SheetGid(42)),
+                "https://docs.google.com/spreadsheets/d/doc-id/edit#gid=42&range=B2"
+            );
+        }
+
+        #[test]
+        fn sheet_cell__display_then_from_raw__same_cell() {
+            for sheet in ["users", "My sheet", "Joe's", "Q1", "a!b", "it''s"] {
+                let cell = SheetA1CellId::from_primitives(sheet, "AB", 12);
+
+                let parsed = SheetA1CellId::from_raw(&cell).expect("Test: Expected cell");
+
+                assert_eq!(parsed, cell);
+            }
+            assert_eq!(
+                SheetA1CellId::from_raw("users:B2").expect("Test: Expected cell"),
+                SheetA1CellId::from_primitives("users", "B", 2)
+            );
+            assert!(SheetA1CellId::from_raw("!B2").is_err());
+            assert!(SheetA1CellId::from_raw("users!B2:C3").is_err());
+        }
+
+        #[test]
+        fn unquote_sheet_name__quoted__original_name() {
+            for name in ["users", "My sheet", "Joe's", "Q1"] {
+                assert_eq!(unquote_sheet_name(&quote_sheet_name(name)), name);
+            }
+        }
+    }
+
+    mod relative_to_tests {
+        use super::*;
+
+        #[test]
+        fn relative_to__cell_below_right__offset() {
+            let origin = SheetA1CellId::from_primitives("users", "B", 2);
+            let cell = SheetA1CellId::from_primitives("users", "C", 5);
+
+            assert_eq!(
+                cell.relative_to(&origin),
+                Some(NumCellId::from_primitives(1, 3))
+            );
+            assert_eq!(
+                origin.relative_to(&origin),
+                Some(NumCellId::from_primitives(0, 0))
+            );
+        }
+
+        #[test]
+        fn relative_to__other_sheet_or_before_origin__none() {
+            let origin = SheetA1CellId::from_primitives("users", "B", 2);
+
+            assert_eq!(
+                SheetA1CellId::from_primitives("orders", "C", 5).relative_to(&origin),
+                None
+            );
+            assert_eq!(
+                SheetA1CellId::from_primitives("users", "A", 5).relative_to(&origin),
+                None
+            );
+            assert_eq!(
+                SheetA1CellId::from_primitives("users", "C", 1).relative_to(&origin),
+                None
+            );
+        }
+    }
+    #[cfg(test)]
+    mod position_arithmetic_tests {
+        use super::*;
+
+        #[test]
+        fn row_offset_from__row_below_origin__offset() {
+            let origin = SheetA1CellId::from_primitives("users", "A", 2);
+
+            assert_eq!(
+                SheetA1CellId::from_primitives("users", "C", 5).row_offset_from(&origin),
+                Some(3)
+            );
+            assert_eq!(
+                SheetA1CellId::from_primitives("users", "A", 1).row_offset_from(&origin),
+                None
+            );
+            assert_eq!(
+                SheetA1CellId::from_primitives("orders", "A", 5).row_offset_from(&origin),
+                None
+            );
+        }
+
+        #[test]
+        fn shift_rows__up_and_down__same_column() {
+            let cell = SheetA1CellId::from_primitives("users", "B", 3);
+
+            assert_eq!(
+                cell.shift_rows(2),
+                Some(SheetA1CellId::from_primitives("users", "B", 5))
+            );
+            assert_eq!(
+                cell.shift_rows(-2),
+                Some(SheetA1CellId::from_primitives("users", "B", 1))
+            );
+            assert_eq!(cell.shift_rows(-3), None);
+        }
+
+        #[test]
+        fn is_within__inside_outside_and_other_sheet() {
+            let range = SheetA1Range::from_raw("users!B2:C4").expect("Test: Expected range");
+
+            assert!(SheetA1CellId::from_primitives("users", "C", 4).is_within(&range));
+            assert!(!SheetA1CellId::from_primitives("users", "A", 3).is_within(&range));
+            assert!(!SheetA1CellId::from_primitives("users", "B", 5).is_within(&range));
+            assert!(!SheetA1CellId::from_primitives("orders", "B", 2).is_within(&range));
+        }
+    }
 }
@@ -90,14 +90,14 @@ pub fn string_to_dec_as_base26(string: &str) -> u32 {
 
 /// Convert a decimal number to a string of letters in 1-indexed base-26.
 pub fn dec_to_string_as_base26(mut dec_number: u32) -> String {
-    let mut result = String::new();
+    let mut letters = Vec::with_capacity(7);
     while dec_number > 0 {
         dec_number -= 1; // Adjust for 1-based indexing
-        let letter = (dec_number % 26) as u8 + b'A';
-        result.push(letter as char);
+        letters.push((dec_number % 26) as u8 + b'A');
         dec_number /= 26;
     }
-    result.chars().rev().collect()
+    letters.reverse();
+    String::from_utf8(letters).expect("Expected ASCII letters")
 }
 
 #[allow(non_snake_case)]
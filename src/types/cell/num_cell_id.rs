@@ -1,3 +1,5 @@
+use std::cmp::Ordering;
+
 /// Defines a cell id as 0-indexed 2D coordinates
 #[derive(Debug, PartialEq, Clone, Copy, Eq)]
 pub struct NumCellId {
@@ -5,6 +7,21 @@ pub struct NumCellId {
     pub row: u32,
 }
 
+/// Row-major, same as [`crate::types::A1CellId`]
+impl Ord for NumCellId {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.row
+            .cmp(&other.row)
+            .then_with(|| self.col.cmp(&other.col))
+    }
+}
+
+impl PartialOrd for NumCellId {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl NumCellId {
     pub fn from_primitives(col: u32, row: u32) -> Self {
         Self { col, row }
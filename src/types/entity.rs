@@ -1,5 +1,6 @@
 use crate::mapper::sheet_row::SheetRowSerde;
-use crate::types::SheetA1CellId;
+use crate::types::{A1CellId, SheetA1CellId, SheetA1Range};
+use serde_json::Value;
 use std::fmt::Debug;
 use std::ops::{Deref, DerefMut};
 
@@ -11,12 +12,45 @@ where
 {
     pub(crate) position: SheetA1CellId,
     pub(crate) data: E,
+    /// Stable row id kept in developer metadata, see `RowIdentity::Metadata`
+    pub(crate) row_tag: Option<String>,
 }
 
 impl<E> Entity<E>
 where
     E: EntityEssentials,
 {
+    /// Entity without a row id, e.g. for tests or custom flows on top of the driver
+    pub fn new(position: SheetA1CellId, data: E) -> Self {
+        Self {
+            position,
+            data,
+            row_tag: None,
+        }
+    }
+
+    pub fn into_parts(self) -> (SheetA1CellId, E) {
+        (self.position, self.data)
+    }
+
+    /// Same position and row id with converted data
+    pub fn map_data<T, F>(self, f: F) -> Entity<T>
+    where
+        T: EntityEssentials,
+        F: FnOnce(E) -> T,
+    {
+        Entity {
+            position: self.position,
+            data: f(self.data),
+            row_tag: self.row_tag,
+        }
+    }
+
+    /// For rows moved by the caller, the repository addresses the entity by this position
+    pub fn set_position(&mut self, position: SheetA1CellId) {
+        self.position = position;
+    }
+
     pub fn data(&self) -> &E {
         &self.data
     }
@@ -26,6 +60,32 @@ where
     pub fn position(&self) -> &SheetA1CellId {
         &self.position
     }
+    /// Row id from developer metadata, named apart from the fields reached through `Deref`
+    pub fn row_tag(&self) -> Option<&str> {
+        self.row_tag.as_deref()
+    }
+
+    /// See [`SheetA1CellId::row_offset_from`]
+    pub fn row_offset_from(&self, origin: &SheetA1CellId) -> Option<u32> {
+        self.position.row_offset_from(origin)
+    }
+
+    /// Moves the entity `rows` rows down (up for negative ones), e.g. after rows were
+    /// inserted above it. Returns `false` and keeps the position if it'd be above the first row
+    pub fn shift_rows(&mut self, rows: i32) -> bool {
+        match self.position.shift_rows(rows) {
+            Some(position) => {
+                self.position = position;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// See [`SheetA1CellId::is_within`]
+    pub fn is_within(&self, range: &SheetA1Range) -> bool {
+        self.position.is_within(range)
+    }
 }
 
 /// Syntactic sugar to ease work with the wrapped data
@@ -46,4 +106,183 @@ impl<E: EntityEssentials> DerefMut for Entity<E> {
 pub trait EntityEssentials: Sized + Debug + SheetRowSerde + Clone + PartialEq {
     /// Returns width in columns of the entity
     fn entity_width() -> u32;
+
+    /// Header row of the table, in column order. Empty if the entity doesn't declare it
+    fn column_headers() -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Metadata of the columns, in column order. By default untyped columns named after
+    /// [`EntityEssentials::column_headers`]
+    fn columns() -> Vec<ColumnMeta> {
+        Self::column_headers()
+            .iter()
+            .map(|&header| ColumnMeta::new(header))
+            .collect()
+    }
+
+    /// Offsets of the columns computed by the sheet, e.g. by formulas. Updates write around
+    /// them, so the stale values read before aren't written over the formulas
+    fn read_only_columns() -> &'static [u32] {
+        &[]
+    }
+}
+
+/// Entity with trailing columns known only at runtime, e.g. one per month.
+/// [`EntityEssentials::entity_width`] is the width of the leading fixed columns, the actual
+/// width is read from the header row and carried by the table, see `Repository::dynamic_table`
+pub trait DynamicWidthEntity: EntityEssentials {
+    /// Width of the table with the `header` row, up to the last non-empty header by default
+    fn width_from_header(header: &[Value]) -> u32 {
+        let populated = header
+            .iter()
+            .rposition(|cell| !matches!(cell, Value::Null) && cell.as_str() != Some(""))
+            .map_or(0, |last| last as u32 + 1);
+        populated.max(Self::entity_width())
+    }
+}
+
+/// Kind of the column values, a hint for validation and formatting
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColumnKind {
+    #[default]
+    Any,
+    Text,
+    Integer,
+    Number,
+    Boolean,
+    Date,
+}
+
+/// Field type stored as one of a fixed set of texts, e.g. the status of an order.
+/// Lets the sheet offer the same choice as the Rust type, see `Table::apply_enum_validation`
+pub trait SheetEnum {
+    /// Texts of the variants as they are stored in the cells
+    fn variants() -> &'static [&'static str];
+}
+
+/// Declared column of an entity, e.g.
+/// `ColumnMeta::new("price").kind(ColumnKind::Number).format("0.00")`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColumnMeta {
+    pub header: &'static str,
+    pub kind: ColumnKind,
+    /// Number format pattern of the cells, e.g. "0.00" or "yyyy-mm-dd"
+    pub format: Option<&'static str>,
+}
+
+impl ColumnMeta {
+    pub const fn new(header: &'static str) -> Self {
+        Self {
+            header,
+            kind: ColumnKind::Any,
+            format: None,
+        }
+    }
+
+    pub const fn kind(mut self, kind: ColumnKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    pub const fn format(mut self, format: &'static str) -> Self {
+        self.format = Some(format);
+        self
+    }
+}
+
+/// Entity which always lives in the same table, so the repository can find it
+/// without coordinates: `repo.of::<User>().find_all()`
+pub trait EntityTable: EntityEssentials {
+    fn sheet() -> &'static str;
+    /// Top left cell of the table
+    fn origin() -> A1CellId;
+    /// Capacity of the table in rows
+    fn rows() -> u32 {
+        1000
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod entity_tests {
+    use super::*;
+    use crate::mapper::sheet_row;
+    use crate::mapper::sheet_row::SheetRow;
+    use crate::testing::examples_support::User;
+
+    #[test]
+    fn columns__default__untyped_headers() {
+        #[derive(Debug, Clone, PartialEq)]
+        struct Named(User);
+
+        impl SheetRowSerde for Named {
+            fn deserialize(row: SheetRow) -> sheet_row::Result<Self> {
+                User::deserialize(row).map(Named)
+            }
+
+            fn serialize(&self) -> sheet_row::Result<SheetRow> {
+                self.0.serialize()
+            }
+        }
+
+        impl EntityEssentials for Named {
+            fn entity_width() -> u32 {
+                2
+            }
+
+            fn column_headers() -> &'static [&'static str] {
+                &["id", "name"]
+            }
+        }
+
+        assert_eq!(
+            Named::columns(),
+            vec![ColumnMeta::new("id"), ColumnMeta::new("name")]
+        );
+    }
+
+    #[test]
+    fn map_data_and_into_parts__ok() {
+        let position = SheetA1CellId::from_primitives("users", "A", 2);
+        let entity = Entity::new(
+            position.clone(),
+            User {
+                id: 1,
+                name: "Joe".to_string(),
+            },
+        );
+
+        let renamed = entity.map_data(|user| User {
+            name: "Joseph".to_string(),
+            ..user
+        });
+
+        assert_eq!(renamed.row_tag(), None);
+        let (renamed_position, user) = renamed.into_parts();
+        assert_eq!(renamed_position, position);
+        assert_eq!(user.name, "Joseph");
+    }
+
+    #[test]
+    fn shift_rows__above_first_row__position_kept() {
+        let mut entity = Entity::new(
+            SheetA1CellId::from_primitives("users", "A", 2),
+            User {
+                id: 1,
+                name: "Joe".to_string(),
+            },
+        );
+
+        assert!(entity.shift_rows(3));
+        assert_eq!(
+            entity.position(),
+            &SheetA1CellId::from_primitives("users", "A", 5)
+        );
+        assert!(!entity.shift_rows(-5));
+        assert_eq!(
+            entity.row_offset_from(&SheetA1CellId::from_primitives("users", "A", 2)),
+            Some(3)
+        );
+    }
 }
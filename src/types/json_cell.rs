@@ -0,0 +1,86 @@
+//////////////////////// JSON payload in a single cell ////////////////////////
+
+use derive_more::{Deref, From};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// Value stored as a JSON string in one cell, for semi-structured payloads which don't
+/// justify columns of their own. Empty cells are read as `None` by `parse_optional_cell`
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deref, From)]
+pub struct Json<T>(pub T);
+
+impl<T> Json<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Json<T>
+where
+    T: Serialize,
+{
+    /// Compact JSON written to the cell
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(&self.0)
+    }
+}
+
+impl<T> Json<T>
+where
+    T: DeserializeOwned,
+{
+    /// Payload parsed from the cell content
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json).map(Json)
+    }
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod json_cell_tests {
+    use super::*;
+    use crate::mapper::sheet_cell::{SheetRawCell, SheetRawCellSerde};
+    use crate::mapper::sheet_row::SheetRowExt;
+    use serde::Deserialize;
+    use serde_json::Value;
+    use std::collections::HashMap;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Settings {
+        theme: String,
+        tags: Vec<String>,
+    }
+
+    #[test]
+    fn serialize__struct__parsed_back() {
+        let settings = Json(Settings {
+            theme: "dark".to_string(),
+            tags: vec!["beta".to_string()],
+        });
+
+        let cell = settings.serialize();
+        assert_eq!(cell.as_str(), r#"{"theme":"dark","tags":["beta"]}"#);
+        let parsed = Json::<Settings>::deserialize(cell).expect("Test: Expected JSON");
+        assert_eq!(parsed, settings);
+    }
+
+    #[test]
+    fn try_serialize__map_with_non_string_keys__err() {
+        let payload = Json(HashMap::from([((1, 2), "point".to_string())]));
+
+        assert!(payload.try_serialize().is_err());
+    }
+
+    #[test]
+    fn parse_optional_cell__empty_or_invalid__none_or_err() {
+        let row = vec![Value::from(""), Value::from("{\"theme\":")];
+
+        assert_eq!(
+            row.parse_optional_cell::<Json<Settings>>(0, "settings")
+                .expect("Test: Expected empty cell"),
+            None
+        );
+        assert!(row.parse_cell::<Json<Settings>>(1, "settings").is_err());
+        assert!(Json::<Settings>::deserialize(SheetRawCell::from("7".to_string())).is_err());
+    }
+}
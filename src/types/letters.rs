@@ -12,6 +12,8 @@ use thiserror::Error;
 pub enum LettersError {
     NonAlphanumeric(String),
     EmptyString,
+    #[display("Column {_0} is past the last column of a sheet (ZZZ)")]
+    PastLastColumn(String),
 }
 
 /// Encapsulates the letters of the alphabet to use it for the cell id
@@ -20,10 +22,16 @@ pub struct Letters(String);
 
 impl Letters {
     /// Panics
+    #[deprecated(note = "Use `Letters::try_from`, it doesn't panic on invalid letters")]
     pub fn new(value: String) -> Self {
+        Self::from_valid(value)
+    }
+
+    /// For letters known to be valid (constants, results of arithmetic). Panics otherwise
+    pub(crate) fn from_valid(value: String) -> Self {
         assert!(!value.is_empty(), "Expected non-empty letters");
         assert!(
-            value.chars().all(char::is_alphabetic),
+            value.chars().all(|c| c.is_ascii_alphabetic()),
             "Invalid cell column letters: {:?}",
             value
         );
@@ -44,47 +52,75 @@ impl TryFrom<String> for Letters {
             bail!(LettersError::EmptyString)
         }
 
-        if !value.chars().all(char::is_alphabetic) {
-            let text = format!(
-                "String value: {} must contain only alphabetic characters",
-                value
-            );
+        if !value.chars().all(|c| c.is_ascii_alphabetic()) {
+            let text = format!("String value: {} must contain only ASCII letters", value);
             return Err(Report::new(LettersError::NonAlphanumeric(value)).attach_printable(text));
         }
-        Ok(Self::new(value.to_uppercase()))
+        let value = value.to_ascii_uppercase();
+        if value.len() > 3 {
+            bail!(LettersError::PastLastColumn(value))
+        }
+        Ok(Self::from_valid(value))
+    }
+}
+
+impl Letters {
+    /// Number of the last column a sheet can have, ZZZ
+    pub const MAX_COLUMN_NUMBER: u32 = 18_278;
+
+    /// 1-based column number: A -> 1, Z -> 26, AA -> 27
+    pub fn column_number(&self) -> u32 {
+        string_to_dec_as_base26(self)
+    }
+
+    /// `None` for 0 (the column numbers are 1-based) and past [`Letters::MAX_COLUMN_NUMBER`]
+    pub fn from_column_number(number: u32) -> Option<Self> {
+        (1..=Self::MAX_COLUMN_NUMBER)
+            .contains(&number)
+            .then(|| Self(dec_to_string_as_base26(number)))
+    }
+
+    /// Letters `delta` columns to the right (left if negative), `None` before the column A
+    /// or past the column ZZZ
+    pub fn checked_add_signed(&self, delta: i64) -> Option<Self> {
+        let number = i64::from(self.column_number()).checked_add(delta)?;
+        Self::from_column_number(u32::try_from(number).ok()?)
     }
 }
 
 impl Add<u32> for Letters {
     type Output = Letters;
 
+    /// Panics past the column ZZZ, see [`Letters::checked_add_signed`]
     fn add(self, delta: u32) -> Self::Output {
-        let dec_number = string_to_dec_as_base26(&self);
-        let result = dec_number + delta;
-        let value = dec_to_string_as_base26(result);
-        Letters::new(value)
+        self.checked_add_signed(i64::from(delta))
+            .expect("Expected column at or before ZZZ")
+    }
+}
+
+/// By column number: Z < AA
+impl Ord for Letters {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.column_number().cmp(&other.column_number())
     }
 }
 
 impl PartialOrd for Letters {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        let self_number = string_to_dec_as_base26(self);
-        let other_number = string_to_dec_as_base26(other);
-
-        self_number.partial_cmp(&other_number)
+        Some(self.cmp(other))
     }
 }
 
 impl Sub<u32> for Letters {
     type Output = Letters;
 
+    /// Panics before the column A, see [`Letters::checked_add_signed`]
     fn sub(self, delta: u32) -> Self::Output {
-        let dec_number = string_to_dec_as_base26(&self);
-        let result = dec_number - delta;
-        let value = dec_to_string_as_base26(result);
-        Letters::new(value)
+        self.checked_add_signed(-i64::from(delta))
+            .expect("Expected column at or after A")
     }
 }
+
 impl Sub<&Letters> for Letters {
     type Output = i32;
 
@@ -96,32 +132,13 @@ impl Sub<&Letters> for Letters {
 impl Sub<&Letters> for &Letters {
     type Output = i32;
 
+    /// Distance between the columns, negative if `other` is to the right
     fn sub(self, other: &Letters) -> Self::Output {
-        let mut letters = self.chars().rev();
-        let mut other_letters = other.chars().rev();
-        let mut result = 0;
-        let mut carry = 0;
-
-        while let (Some(letter), Some(other_letter)) = (letters.next(), other_letters.next()) {
-            let letter = letter as i32 - 'A' as i32;
-            let other_letter = other_letter as i32 - 'A' as i32;
-
-            let value = letter - other_letter - carry;
-
-            if value < 0 {
-                carry = 1;
-                result += 26 + value;
-            } else {
-                carry = 0;
-                result += value;
-            }
-        }
-
-        result
+        self.column_number() as i32 - other.column_number() as i32
     }
 }
 
-#[allow(non_snake_case)]
+#[allow(non_snake_case, deprecated)]
 #[cfg(test)]
 mod letters_tests {
     use super::*;
@@ -182,7 +199,85 @@ mod letters_tests {
     }
 
     #[test]
-    #[should_panic(expected = "Expected non-empty letters")]
+    fn letters__sub_letters__distance_across_lengths() {
+        let ba = Letters::new("BA".to_string());
+        let aa = Letters::new("AA".to_string());
+        let z = Letters::new("Z".to_string());
+
+        assert_eq!(&ba - &aa, 26);
+        assert_eq!(&ba - &z, 27);
+        assert_eq!(&z - &ba, -27);
+    }
+
+    #[test]
+    fn letters__checked_add_signed__bounds() {
+        let a = Letters::new("A".to_string());
+
+        assert_eq!(a.checked_add_signed(-1), None);
+        assert_eq!(a.checked_add_signed(i64::MAX), None);
+        assert_eq!(
+            a.checked_add_signed(26).map(|l| l.to_string()),
+            Some("AA".to_string())
+        );
+        assert_eq!(
+            Letters::new("AAA".to_string()).checked_add_signed(-677),
+            Some(Letters::new("Z".to_string()))
+        );
+    }
+
+    #[test]
+    fn letters__column_numbers_up_to_zzz__consistent_arithmetic() {
+        let mut previous: Option<Letters> = None;
+        for number in 1..=18_278 {
+            let letters = Letters::from_column_number(number).expect("Test: Expected letters");
+            assert_eq!(letters.column_number(), number);
+            if let Some(previous) = previous {
+                assert_eq!(previous.clone() + 1, letters);
+                assert_eq!(letters.clone() - 1, previous);
+                assert_eq!(&letters - &previous, 1);
+                assert!(previous < letters);
+            }
+            previous = Some(letters);
+        }
+        assert_eq!(Letters::from_column_number(0), None);
+        assert_eq!(
+            Letters::from_column_number(Letters::MAX_COLUMN_NUMBER + 1),
+            None
+        );
+    }
+
+    #[test]
+    fn letters__try_from_past_zzz__past_last_column() {
+        let last = Letters::try_from("zzz".to_string()).expect("Test: Expected the last column");
+        assert_eq!(last.column_number(), Letters::MAX_COLUMN_NUMBER);
+        assert_eq!(last.checked_add_signed(1), None);
+
+        let report = Letters::try_from("AAAA".to_string()).expect_err("Test: Expected past ZZZ");
+        assert!(matches!(
+            report.current_context(),
+            LettersError::PastLastColumn(letters) if letters == "AAAA"
+        ));
+    }
+
+    #[test]
+    fn letters__try_from_non_ascii_letters__non_alphanumeric() {
+        for value in ["ÉÉÉ", "ß", "Я"] {
+            let report = Letters::try_from(value.to_string()).expect_err("Test: Expected error");
+            assert!(matches!(
+                report.current_context(),
+                LettersError::NonAlphanumeric(_)
+            ));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected column at or before ZZZ")]
+    fn letters__add__past_zzz__panics() {
+        let _ = Letters::new("ZZZ".to_string()) + 1;
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected column at or after A")]
     fn letters__sub__with_underflow__panics() {
         let letters = Letters::new("A".to_string());
         let _ = letters - 1;
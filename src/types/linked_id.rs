@@ -0,0 +1,183 @@
+//////////////////////// IDs linking to an external system ////////////////////////
+
+use std::fmt::{Debug, Display, Formatter};
+use std::marker::PhantomData;
+
+/// URL of the entity page in the external system, shared by every cell of the column
+pub trait LinkTemplate {
+    /// URL with `{id}` placeholders, e.g. "https://tracker.example.com/issues/{id}"
+    const URL_TEMPLATE: &'static str;
+}
+
+/// ID cell which displays the ID but links to the entity in another system.
+/// Written as a `=HYPERLINK(url, id)` formula (needs `InputMode::UserEntered`), read back
+/// from the displayed ID or from the formula (`ValueRenderOption::Formula`)
+pub struct LinkedId<T, L> {
+    id: T,
+    link: PhantomData<L>,
+}
+
+impl<T, L> LinkedId<T, L>
+where
+    T: Display,
+    L: LinkTemplate,
+{
+    pub fn new(id: T) -> Self {
+        Self {
+            id,
+            link: PhantomData,
+        }
+    }
+
+    pub fn id(&self) -> &T {
+        &self.id
+    }
+
+    pub fn into_id(self) -> T {
+        self.id
+    }
+
+    /// Template with the ID inserted as is
+    pub fn url(&self) -> String {
+        L::URL_TEMPLATE.replace("{id}", &self.id.to_string())
+    }
+
+    /// Cell content to write
+    pub fn to_formula(&self) -> String {
+        format!(
+            "=HYPERLINK({}, {})",
+            quote(&self.url()),
+            quote(&self.id.to_string())
+        )
+    }
+}
+
+/// Displayed text of the cell: the label of a HYPERLINK formula or the cell as is
+pub(crate) fn hyperlink_label(cell: &str) -> String {
+    let trimmed = cell.trim();
+    let Some(arguments) = trimmed
+        .get(..11)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("=HYPERLINK("))
+        .and_then(|_| trimmed[11..].strip_suffix(')'))
+    else {
+        return cell.to_string();
+    };
+
+    let mut arguments = split_arguments(arguments);
+    let label = match arguments.len() {
+        // Without a label the sheet displays the URL
+        1 => arguments.remove(0),
+        _ => arguments.remove(1),
+    };
+    unquote(label.trim())
+}
+
+/// Formula arguments, commas inside string literals don't separate them
+fn split_arguments(arguments: &str) -> Vec<&str> {
+    let mut split = vec![];
+    let mut in_string = false;
+    let mut start = 0;
+    for (i, c) in arguments.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            ',' if !in_string => {
+                split.push(&arguments[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    split.push(&arguments[start..]);
+    split
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\"\""))
+}
+
+fn unquote(literal: &str) -> String {
+    match literal
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+    {
+        Some(inner) => inner.replace("\"\"", "\""),
+        None => literal.to_string(),
+    }
+}
+
+impl<T, L> Debug for LinkedId<T, L>
+where
+    T: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("LinkedId").field(&self.id).finish()
+    }
+}
+
+impl<T, L> Display for LinkedId<T, L>
+where
+    T: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.id, f)
+    }
+}
+
+impl<T, L> Clone for LinkedId<T, L>
+where
+    T: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            link: PhantomData,
+        }
+    }
+}
+
+impl<T, L> PartialEq for LinkedId<T, L>
+where
+    T: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T, L> Eq for LinkedId<T, L> where T: Eq {}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod linked_id_tests {
+    use super::*;
+
+    struct Tracker;
+
+    impl LinkTemplate for Tracker {
+        const URL_TEMPLATE: &'static str = "https://tracker.example.com/issues/{id}";
+    }
+
+    #[test]
+    fn to_formula__id__hyperlink_with_id_label() {
+        let id = LinkedId::<u32, Tracker>::new(42);
+
+        assert_eq!(id.url(), "https://tracker.example.com/issues/42");
+        assert_eq!(
+            id.to_formula(),
+            r#"=HYPERLINK("https://tracker.example.com/issues/42", "42")"#
+        );
+    }
+
+    #[test]
+    fn hyperlink_label__formula_or_displayed_value__label() {
+        let formula = LinkedId::<String, Tracker>::new(r#"A-1, "B""#.to_string()).to_formula();
+
+        assert_eq!(hyperlink_label(&formula), r#"A-1, "B""#);
+        assert_eq!(hyperlink_label(r#"=hyperlink("https://x.io", 7)"#), "7");
+        assert_eq!(
+            hyperlink_label(r#"=HYPERLINK("https://x.io")"#),
+            "https://x.io"
+        );
+        assert_eq!(hyperlink_label("42"), "42");
+    }
+}
@@ -1,16 +1,24 @@
+#[cfg(any(test, feature = "proptest"))]
+mod arbitrary;
 mod cell;
 mod entity;
+mod json_cell;
 mod letters;
+mod linked_id;
 mod range;
 mod sheet_date;
 mod typed_options;
 
-pub use cell::a1_cell_id::{A1CellId, Result, SheetA1CellId};
+pub use cell::a1_cell_id::{A1CellId, Result, SheetA1CellId, quote_sheet_name, unquote_sheet_name};
 pub use cell::num_cell_id::*;
 pub use entity::Entity;
 pub use entity::*;
+pub use json_cell::Json;
 pub use letters::Letters;
+pub(crate) use linked_id::hyperlink_label;
+pub use linked_id::{LinkTemplate, LinkedId};
 pub use range::a1_range::*;
 pub use range::num_range::*;
+pub use range::user_range::{UserRange, UserRangeBounds, UserRangeError, parse_user_range};
 pub use sheet_date::*;
 pub use typed_options::*;
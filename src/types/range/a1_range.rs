@@ -1,5 +1,6 @@
+use crate::types::cell::a1_cell_id::spreadsheet_url;
 use crate::types::letters::Letters;
-use crate::types::{A1CellId, SheetA1CellId};
+use crate::types::{A1CellId, SheetA1CellId, SheetGid, quote_sheet_name, unquote_sheet_name};
 use error_stack::{ResultExt, bail};
 use std::fmt::Display;
 use std::num::NonZero;
@@ -112,7 +113,7 @@ impl A1Range {
     /// Offset the range to the A1 as `from`
     pub fn into_zero_base_range(self) -> A1Range {
         let delta_numbers = 1 - self.start.row.get() as i32;
-        let minus_letters = -(&self.start.col - &Letters::new("A".to_string()));
+        let minus_letters = -(&self.start.col - &Letters::from_valid("A".to_string()));
 
         A1Range {
             start: A1CellId::from_primitives("A", 1),
@@ -140,9 +141,46 @@ impl A1Range {
 
         Ok(Self::new(start, end))
     }
+}
 
-    pub fn to_string(&self) -> String {
-        format!("{}:{}", self.start.to_string(), self.end.to_string())
+impl Display for A1Range {
+    /// `A1:C3`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.start, self.end)
+    }
+}
+
+impl A1Range {
+    /// Same range with `start` at the top left and `end` at the bottom right,
+    /// e.g. "C3:A1" and "A3:C1" become "A1:C3"
+    pub fn normalized(&self) -> A1Range {
+        let (start, end) = (&self.start, &self.end);
+        let (first_col, last_col) = match start.col <= end.col {
+            true => (&start.col, &end.col),
+            false => (&end.col, &start.col),
+        };
+        A1Range {
+            start: A1CellId {
+                col: first_col.clone(),
+                row: start.row.min(end.row),
+            },
+            end: A1CellId {
+                col: last_col.clone(),
+                row: start.row.max(end.row),
+            },
+        }
+    }
+
+    pub fn is_single_cell(&self) -> bool {
+        self.start == self.end
+    }
+
+    pub fn is_single_row(&self) -> bool {
+        self.start.row == self.end.row
+    }
+
+    pub fn is_single_column(&self) -> bool {
+        self.start.col == self.end.col
     }
 }
 
@@ -194,6 +232,64 @@ mod range_tests {
         assert_eq!(range.to_string(), "A1:C3");
     }
 
+    #[test]
+    fn normalized__reversed_corners__top_left_to_bottom_right() {
+        for (from, to) in [("C3", "A1"), ("A3", "C1"), ("C1", "A3"), ("A1", "C3")] {
+            let range = A1Range::from_str(from, to).unwrap().normalized();
+            assert_eq!(range.to_string(), "A1:C3", "Test: Range {from}:{to}");
+        }
+    }
+
+    #[test]
+    fn is_single__cell_row_column__ok() {
+        let cell = A1Range::from_str("B2", "B2").unwrap();
+        let row = A1Range::from_str("C2", "A2").unwrap();
+        let column = A1Range::from_str("B1", "B9").unwrap();
+
+        assert!(cell.is_single_cell() && cell.is_single_row() && cell.is_single_column());
+        assert!(!row.is_single_cell() && row.is_single_row() && !row.is_single_column());
+        assert!(!column.is_single_cell() && !column.is_single_row() && column.is_single_column());
+    }
+
+    #[test]
+    fn sheet_range__cells__with_sheet_name() {
+        let range = SheetA1Range::from_str("users", "B2:A1").unwrap();
+
+        let cells: Vec<SheetA1CellId> = range.cells().collect();
+
+        assert_eq!(
+            cells,
+            vec![
+                SheetA1CellId::from_primitives("users", "A", 1),
+                SheetA1CellId::from_primitives("users", "B", 1),
+                SheetA1CellId::from_primitives("users", "A", 2),
+                SheetA1CellId::from_primitives("users", "B", 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn sheet_range__frozen__same_a1_as_display() {
+        let range = SheetA1Range::from_str("My sheet", "A1:C3").unwrap();
+
+        let frozen = range.clone().frozen();
+
+        assert_eq!(frozen.as_a1(), "'My sheet'!A1:C3");
+        assert_eq!(frozen.as_a1(), range.to_string());
+        assert_eq!(format!("{frozen}"), frozen.as_a1());
+        assert_eq!(frozen.range.end, range.range.end);
+    }
+
+    #[test]
+    fn sheet_range__to_url__gid_and_range() {
+        let range = SheetA1Range::from_str("users", "A1:B2").unwrap();
+
+        assert_eq!(
+            range.to_url("doc-id", SheetGid(0)),
+            "https://docs.google.com/spreadsheets/d/doc-id/edit#gid=0&range=A1:B2"
+        );
+    }
+
     #[test]
     fn range__into_zero_base_range__already_zero_base__ok() {
         let range = A1Range::from_str("A1", "C3").unwrap();
@@ -211,7 +307,7 @@ mod range_tests {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct SheetA1Range {
     pub sheet: String,
     pub range: A1Range,
@@ -221,6 +317,20 @@ impl SheetA1Range {
     pub(crate) fn start(&self) -> SheetA1CellId {
         SheetA1CellId::new(self.sheet.clone(), self.range.start.clone())
     }
+
+    /// See [`A1Range::normalized`]
+    pub fn normalized(&self) -> SheetA1Range {
+        SheetA1Range::new(&self.sheet, self.range.normalized())
+    }
+
+    /// Cells of the range by rows, from the top left to the bottom right
+    pub fn cells(&self) -> impl Iterator<Item = SheetA1CellId> + use<> {
+        let sheet = self.sheet.clone();
+        self.range
+            .normalized()
+            .iter()
+            .map(move |cell| SheetA1CellId::new(&sheet, cell))
+    }
 }
 
 impl SheetA1Range {
@@ -235,11 +345,10 @@ impl SheetA1Range {
             bail!(A1RangeError::InvalidRangeFormat(value.to_string()));
         }
 
-        // Remove leading and traling ' from the page
-        let page = parts[0].trim_matches('\'');
+        let page = unquote_sheet_name(parts[0]);
         let range = A1Range::from_raw(parts[1])?;
 
-        Ok(Self::new(page.to_string(), range))
+        Ok(Self::new(page, range))
     }
 }
 
@@ -261,10 +370,63 @@ impl SheetA1Range {
 
 impl Display for SheetA1Range {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            format!("{}!{}", self.sheet, self.range.to_string())
-        )
+        write!(f, "{}!{}", quote_sheet_name(&self.sheet), self.range)
+    }
+}
+
+impl SheetA1Range {
+    /// Link which opens the sheet with the range selected, see [`SheetA1CellId::to_url`]
+    pub fn to_url(&self, spreadsheet_id: &str, gid: SheetGid) -> String {
+        spreadsheet_url(spreadsheet_id, gid, &self.range)
+    }
+}
+
+impl SheetA1Range {
+    /// Range which is formatted once, for the ranges passed to the API over and over
+    pub fn frozen(self) -> FrozenSheetA1Range {
+        FrozenSheetA1Range::from(self)
+    }
+}
+
+/// Immutable [`SheetA1Range`] along with its A1 notation
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FrozenSheetA1Range {
+    range: SheetA1Range,
+    a1: String,
+}
+
+impl FrozenSheetA1Range {
+    /// `users!A1:C3`, same as the `to_string()` of the range
+    pub fn as_a1(&self) -> &str {
+        &self.a1
+    }
+
+    pub fn range(&self) -> &SheetA1Range {
+        &self.range
+    }
+
+    pub fn into_range(self) -> SheetA1Range {
+        self.range
+    }
+}
+
+impl From<SheetA1Range> for FrozenSheetA1Range {
+    fn from(range: SheetA1Range) -> Self {
+        let a1 = range.to_string();
+        Self { range, a1 }
+    }
+}
+
+impl std::ops::Deref for FrozenSheetA1Range {
+    type Target = SheetA1Range;
+
+    fn deref(&self) -> &Self::Target {
+        &self.range
+    }
+}
+
+impl Display for FrozenSheetA1Range {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.a1)
     }
 }
@@ -10,7 +10,9 @@ impl From<NumRange> for A1Range {
 }
 
 impl From<A1Range> for NumRange {
+    /// Reversed corners are reordered, see [`A1Range::normalized`]
     fn from(value: A1Range) -> Self {
+        let value = value.normalized();
         let start = value.start.into();
         let end = value.end.into();
         Self::new(start, end)
@@ -40,4 +42,12 @@ mod range_tests {
         assert_eq!(range.start, NumCellId::from_primitives(0, 0));
         assert_eq!(range.end, NumCellId::from_primitives(1, 1));
     }
+
+    #[test]
+    fn from_a1_range__on_reversed_range__normalized() {
+        let a1_range = A1Range::from_str("B2", "A1").unwrap();
+        let range = NumRange::from(a1_range);
+        assert_eq!(range.start, NumCellId::from_primitives(0, 0));
+        assert_eq!(range.end, NumCellId::from_primitives(1, 1));
+    }
 }
@@ -1,3 +1,4 @@
 pub mod a1_range;
 mod conversion;
 pub mod num_range;
+pub mod user_range;
@@ -1,5 +1,5 @@
 /// Re-exporting conversion functions
-use crate::types::NumCellId;
+use crate::types::{MajorDimension, NumCellId};
 
 /// Defines a 0-indexed range in 2D coordinates
 /// Both start and end are inclusive
@@ -25,6 +25,77 @@ impl NumRange {
     }
 }
 
+impl NumRange {
+    pub fn width(&self) -> u32 {
+        self.end.col - self.start.col + 1
+    }
+
+    pub fn height(&self) -> u32 {
+        self.end.row - self.start.row + 1
+    }
+
+    /// Cells from the top left to the bottom right by rows, same order as [`crate::types::A1Range::iter`]
+    pub fn iter(&self) -> NumRangeIterator {
+        self.iter_by(MajorDimension::Rows)
+    }
+
+    /// Cells from the top left to the bottom right by columns
+    pub fn iter_columns(&self) -> NumRangeIterator {
+        self.iter_by(MajorDimension::Columns)
+    }
+
+    pub fn iter_by(&self, major_dimension: MajorDimension) -> NumRangeIterator {
+        NumRangeIterator {
+            range: self.clone(),
+            major_dimension,
+            current: Some(self.start),
+        }
+    }
+
+    /// Row-major cells with their offset from the range start, e.g. indices into the values
+    /// of the range: `((0, 0), start)`, `((1, 0), next cell to the right)`, ...
+    pub fn enumerate_cells(&self) -> impl Iterator<Item = (NumCellId, NumCellId)> + use<> {
+        let start = self.start;
+        self.iter().map(move |cell| {
+            let offset = NumCellId::from_primitives(cell.col - start.col, cell.row - start.row);
+            (offset, cell)
+        })
+    }
+}
+
+pub struct NumRangeIterator {
+    range: NumRange,
+    major_dimension: MajorDimension,
+    /// `None` once the range is exhausted
+    current: Option<NumCellId>,
+}
+
+impl Iterator for NumRangeIterator {
+    type Item = NumCellId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.current?;
+        let (start, end) = (self.range.start, self.range.end);
+
+        self.current = match self.major_dimension {
+            MajorDimension::Rows if current.col < end.col => {
+                Some(NumCellId::from_primitives(current.col + 1, current.row))
+            }
+            MajorDimension::Rows if current.row < end.row => {
+                Some(NumCellId::from_primitives(start.col, current.row + 1))
+            }
+            MajorDimension::Columns if current.row < end.row => {
+                Some(NumCellId::from_primitives(current.col, current.row + 1))
+            }
+            MajorDimension::Columns if current.col < end.col => {
+                Some(NumCellId::from_primitives(current.col + 1, start.row))
+            }
+            _ => None,
+        };
+        Some(current)
+    }
+}
+
 #[allow(non_snake_case)]
 #[cfg(test)]
 mod range_tests {
@@ -39,6 +110,55 @@ mod range_tests {
         assert_eq!(range.end, end);
     }
 
+    fn cells(cells: &[(u32, u32)]) -> Vec<NumCellId> {
+        cells
+            .iter()
+            .map(|&(col, row)| NumCellId::from_primitives(col, row))
+            .collect()
+    }
+
+    #[test]
+    fn iter__rows_and_columns__ok() {
+        let range = NumRange::new(
+            NumCellId::from_primitives(1, 0),
+            NumCellId::from_primitives(2, 1),
+        );
+
+        assert_eq!(
+            range.iter().collect::<Vec<_>>(),
+            cells(&[(1, 0), (2, 0), (1, 1), (2, 1)])
+        );
+        assert_eq!(
+            range.iter_columns().collect::<Vec<_>>(),
+            cells(&[(1, 0), (1, 1), (2, 0), (2, 1)])
+        );
+        assert_eq!(range.iter().count() as u32, range.width() * range.height());
+    }
+
+    #[test]
+    fn enumerate_cells__offset_range__offsets_from_start() {
+        let range = NumRange::new(
+            NumCellId::from_primitives(3, 5),
+            NumCellId::from_primitives(4, 5),
+        );
+
+        let enumerated: Vec<_> = range.enumerate_cells().collect();
+
+        assert_eq!(
+            enumerated,
+            vec![
+                (
+                    NumCellId::from_primitives(0, 0),
+                    NumCellId::from_primitives(3, 5)
+                ),
+                (
+                    NumCellId::from_primitives(1, 0),
+                    NumCellId::from_primitives(4, 5)
+                ),
+            ]
+        );
+    }
+
     #[test]
     #[should_panic(expected = "Start column must be less or equal to end column")]
     fn new__on_invalid_range__panic() {
@@ -0,0 +1,327 @@
+//////////////////////// Ranges typed by end users ////////////////////////
+
+use crate::types::letters::Letters;
+use crate::types::{A1CellId, A1Range, SheetA1Range, quote_sheet_name, unquote_sheet_name};
+use error_stack::bail;
+use std::fmt::{Display, Formatter};
+use std::num::NonZeroU32;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum UserRangeError {
+    #[error("Range is empty")]
+    Empty,
+    #[error("Sheet name is empty in '{0}'")]
+    EmptySheetName(String),
+    #[error("'{reference}' is not a valid reference: {hint}")]
+    InvalidReference {
+        reference: String,
+        hint: &'static str,
+    },
+}
+
+pub type Result<T> = error_stack::Result<T, UserRangeError>;
+
+/// Cells a [`UserRange`] covers on its sheet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UserRangeBounds {
+    /// `Sheet1`
+    WholeSheet,
+    /// `A1`, `B2:C3`, `R2C3`, normalized to top left and bottom right corners
+    Cells(A1Range),
+    /// `A:B` (from the first row) or `A2:B`, open to the bottom of the sheet
+    Columns {
+        first: Letters,
+        last: Letters,
+        from_row: NonZeroU32,
+    },
+}
+
+/// Range parsed by [`parse_user_range`]. Its `Display` is the canonical A1 notation,
+/// which the API accepts anywhere a range is expected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserRange {
+    /// `None` when the input has no sheet, the API then uses the first sheet
+    pub sheet: Option<String>,
+    pub bounds: UserRangeBounds,
+}
+
+impl UserRange {
+    /// Sheet of the range or `default_sheet` if the input has none
+    pub fn with_default_sheet(mut self, default_sheet: &str) -> Self {
+        self.sheet.get_or_insert_with(|| default_sheet.to_string());
+        self
+    }
+
+    /// Bounded range with a sheet, `None` for open ranges and ranges without a sheet
+    pub fn to_sheet_range(&self) -> Option<SheetA1Range> {
+        match (&self.sheet, &self.bounds) {
+            (Some(sheet), UserRangeBounds::Cells(range)) => {
+                Some(SheetA1Range::new(sheet, range.clone()))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Display for UserRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(sheet) = &self.sheet {
+            f.write_str(&quote_sheet_name(sheet))?;
+            if self.bounds == UserRangeBounds::WholeSheet {
+                return Ok(());
+            }
+            f.write_str("!")?;
+        }
+        match &self.bounds {
+            UserRangeBounds::WholeSheet => Ok(()),
+            UserRangeBounds::Cells(range) if range.is_single_cell() => {
+                write!(f, "{}", range.start)
+            }
+            UserRangeBounds::Cells(range) => write!(f, "{range}"),
+            UserRangeBounds::Columns {
+                first,
+                last,
+                from_row,
+            } => match from_row.get() {
+                1 => write!(f, "{first}:{last}"),
+                row => write!(f, "{first}{row}:{last}"),
+            },
+        }
+    }
+}
+
+/// Parses a range the way a user would type it: `Sheet1!A1`, `A1:B`, `Sheet1`, `A:A`,
+/// `'My sheet'!B2:C3`, `Sheet1!R2C3`. Letters may be lowercase, `$` markers are ignored and
+/// reversed corners are normalized. Input without `!` which is not a reference is a sheet name
+pub fn parse_user_range(input: &str) -> Result<UserRange> {
+    let input = input.trim();
+    if input.is_empty() {
+        bail!(UserRangeError::Empty);
+    }
+
+    let Some((sheet, reference)) = input.rsplit_once('!') else {
+        return match parse_bounds(input) {
+            Ok(bounds) => Ok(UserRange {
+                sheet: None,
+                bounds,
+            }),
+            Err(error) if input.contains(':') => Err(error),
+            Err(_) => Ok(UserRange {
+                sheet: Some(unquote_sheet_name(input)),
+                bounds: UserRangeBounds::WholeSheet,
+            }),
+        };
+    };
+
+    let sheet = unquote_sheet_name(sheet.trim());
+    if sheet.is_empty() {
+        bail!(UserRangeError::EmptySheetName(input.to_string()));
+    }
+    let bounds = match reference.trim() {
+        "" => UserRangeBounds::WholeSheet,
+        reference => parse_bounds(reference)?,
+    };
+    Ok(UserRange {
+        sheet: Some(sheet),
+        bounds,
+    })
+}
+
+/// Column and row of one side of a reference, either may be missing (`A`, `A1`, `R1C1`)
+struct Endpoint {
+    column: Option<Letters>,
+    row: Option<NonZeroU32>,
+}
+
+fn parse_bounds(reference: &str) -> Result<UserRangeBounds> {
+    let normalized = reference.replace('$', "").to_uppercase();
+    let endpoints = normalized
+        .split(':')
+        .map(|part| parse_endpoint(part.trim(), reference))
+        .collect::<Result<Vec<_>>>()?;
+    let invalid = |hint| UserRangeError::InvalidReference {
+        reference: reference.to_string(),
+        hint,
+    };
+
+    match endpoints.as_slice() {
+        [
+            Endpoint {
+                column: Some(column),
+                row: Some(row),
+            },
+        ] => {
+            let cell = A1CellId::new(column.clone(), *row);
+            Ok(UserRangeBounds::Cells(A1Range::new(cell.clone(), cell)))
+        }
+        [_] => bail!(invalid("a single reference must be a cell, e.g. A1")),
+        [
+            Endpoint {
+                column: Some(first),
+                row: first_row,
+            },
+            Endpoint {
+                column: Some(last),
+                row: last_row,
+            },
+        ] => match (first_row, last_row) {
+            (Some(first_row), Some(last_row)) => Ok(UserRangeBounds::Cells(
+                A1Range::new(
+                    A1CellId::new(first.clone(), *first_row),
+                    A1CellId::new(last.clone(), *last_row),
+                )
+                .normalized(),
+            )),
+            (first_row, None) => {
+                let (first, last) = match first <= last {
+                    true => (first.clone(), last.clone()),
+                    false => (last.clone(), first.clone()),
+                };
+                Ok(UserRangeBounds::Columns {
+                    first,
+                    last,
+                    from_row: first_row.unwrap_or(NonZeroU32::MIN),
+                })
+            }
+            (None, Some(_)) => bail!(invalid(
+                "a range ending with a row must start with a cell, e.g. A1:B5"
+            )),
+        },
+        [_, _] => bail!(invalid(
+            "both sides of a range need columns, e.g. A1:B5 or A:B; row ranges are not supported"
+        )),
+        _ => bail!(invalid("a range has at most one ':'")),
+    }
+}
+
+fn parse_endpoint(part: &str, reference: &str) -> Result<Endpoint> {
+    let invalid = |hint| UserRangeError::InvalidReference {
+        reference: reference.to_string(),
+        hint,
+    };
+    if let Some(endpoint) = parse_r1c1(part) {
+        return endpoint.ok_or_else(|| {
+            invalid("R1C1 rows and columns start at 1 and end at column 18278 (ZZZ)").into()
+        });
+    }
+
+    let split = part
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(part.len());
+    let (letters, digits) = part.split_at(split);
+    let column = match letters {
+        "" => None,
+        letters => Some(
+            Letters::try_from(letters.to_string())
+                .map_err(|_| invalid("columns go from A to ZZZ"))?,
+        ),
+    };
+    let row = match digits {
+        "" => None,
+        digits if digits.chars().all(|c| c.is_ascii_digit()) => Some(
+            digits
+                .parse::<NonZeroU32>()
+                .map_err(|_| invalid("rows start at 1"))?,
+        ),
+        _ => bail!(invalid("expected a column and a row, e.g. B2")),
+    };
+    if column.is_none() && row.is_none() {
+        bail!(invalid("a side of the range is empty"));
+    }
+    Ok(Endpoint { column, row })
+}
+
+/// `None` if the part is not in R1C1 notation, `Some(None)` if its numbers are out of range
+fn parse_r1c1(part: &str) -> Option<Option<Endpoint>> {
+    let (row, column) = part.strip_prefix('R')?.split_once('C')?;
+    let is_number = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+    if !is_number(row) || !is_number(column) {
+        return None;
+    }
+    let endpoint = || {
+        Some(Endpoint {
+            column: Some(Letters::from_column_number(column.parse().ok()?)?),
+            row: Some(row.parse().ok()?),
+        })
+    };
+    Some(endpoint())
+}
+
+#[allow(non_snake_case)]
+#[cfg(test)]
+mod user_range_tests {
+    use super::*;
+
+    fn parsed(input: &str) -> String {
+        parse_user_range(input)
+            .unwrap_or_else(|e| panic!("Test: Expected '{input}' to parse: {e:?}"))
+            .to_string()
+    }
+
+    #[test]
+    fn parse_user_range__supported_forms__canonical_a1() {
+        let cases = [
+            ("Sheet1!A1", "Sheet1!A1"),
+            ("A1:B", "A1:B"),
+            ("Sheet1", "Sheet1"),
+            ("a:a", "A:A"),
+            ("Sheet1!R2C3", "Sheet1!C2"),
+            ("R1C1:R3C2", "A1:B3"),
+            (" 'My sheet'!$c$3:a1 ", "'My sheet'!A1:C3"),
+            ("users!B2:A", "users!A2:B"),
+            ("users!", "users"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parsed(input), expected, "Test: Input '{input}'");
+        }
+    }
+
+    #[test]
+    fn parse_user_range__bounded_with_default_sheet__sheet_range() {
+        let range = parse_user_range("B2:C3")
+            .expect("Test: Expected range")
+            .with_default_sheet("users");
+
+        assert_eq!(
+            range.to_sheet_range(),
+            Some(SheetA1Range::from_str("users", "B2:C3").expect("Test: Expected range"))
+        );
+        let open = parse_user_range("users!A:B").expect("Test: Expected range");
+        assert_eq!(open.to_sheet_range(), None);
+    }
+
+    #[test]
+    fn parse_user_range__invalid__descriptive_error() {
+        let cases = [
+            ("", UserRangeError::Empty),
+            ("!A1", UserRangeError::EmptySheetName("!A1".to_string())),
+            (
+                "users!2:5",
+                UserRangeError::InvalidReference {
+                    reference: "2:5".to_string(),
+                    hint: "both sides of a range need columns, e.g. A1:B5 or A:B; row ranges are \
+                           not supported",
+                },
+            ),
+            (
+                "users!A0",
+                UserRangeError::InvalidReference {
+                    reference: "A0".to_string(),
+                    hint: "rows start at 1",
+                },
+            ),
+            (
+                "A1:B2:C3",
+                UserRangeError::InvalidReference {
+                    reference: "A1:B2:C3".to_string(),
+                    hint: "a range has at most one ':'",
+                },
+            ),
+        ];
+        for (input, expected) in cases {
+            let error = parse_user_range(input).expect_err("Test: Expected error");
+            assert_eq!(error.current_context(), &expected, "Test: Input '{input}'");
+        }
+    }
+}
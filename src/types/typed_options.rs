@@ -1,9 +1,9 @@
 //////////////////////// Typed options ////////////////////////
 // TODO: Use derive_more to reduce boilerplate
 
-use derive_more::{Display, FromStr};
+use derive_more::{Deref, Display, From, FromStr};
 
-#[derive(Debug, Display, Clone, FromStr)]
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, FromStr)]
 pub enum MajorDimension {
     /// Resulting Vec<Vec<_>> vector will represent rows
     #[display("ROWS")]
@@ -22,7 +22,7 @@ impl MajorDimension {
     }
 }
 
-#[derive(Debug, Display, Clone, FromStr)]
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, FromStr)]
 pub enum InputMode {
     /// Will add ' before the numeric operations to avoid Google Sheets to interpret them as formulas
     #[display("RAW")]
@@ -41,7 +41,7 @@ impl InputMode {
     }
 }
 
-#[derive(Debug, Display, Clone, FromStr)]
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, FromStr)]
 pub enum ValueRenderOption {
     /// The values will be calculated
     #[display("FORMATTED_VALUE")]
@@ -64,4 +64,119 @@ impl ValueRenderOption {
     }
 }
 
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, FromStr)]
+pub enum DateTimeRenderOption {
+    /// Dates and times are returned as serial numbers (days since 1899-12-30)
+    #[display("SERIAL_NUMBER")]
+    SerialNumber,
+    /// Dates and times are returned as strings formatted by the cell's number format
+    #[display("FORMATTED_STRING")]
+    FormattedString,
+}
+
+impl DateTimeRenderOption {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DateTimeRenderOption::SerialNumber => "SERIAL_NUMBER",
+            DateTimeRenderOption::FormattedString => "FORMATTED_STRING",
+        }
+    }
+}
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, FromStr)]
+pub enum InsertDataOption {
+    /// Appended rows overwrite the empty cells after the table
+    #[display("OVERWRITE")]
+    Overwrite,
+    /// Rows are inserted after the table, shifting whatever is below it down
+    #[display("INSERT_ROWS")]
+    InsertRows,
+}
+
+impl InsertDataOption {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InsertDataOption::Overwrite => "OVERWRITE",
+            InsertDataOption::InsertRows => "INSERT_ROWS",
+        }
+    }
+}
+
+/// Render option of the values returned by writes, takes the same values as reads do
+pub type ResponseValueRenderOption = ValueRenderOption;
+
+/// How values are rendered by reads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadOptions {
+    pub value_render_option: ValueRenderOption,
+    /// Ignored with `ValueRenderOption::FormattedValue`. `None` leaves it to the API (serial numbers)
+    pub date_time_render_option: Option<DateTimeRenderOption>,
+    /// `Columns` returns one inner `Vec` per column
+    pub major_dimension: MajorDimension,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        Self {
+            value_render_option: ValueRenderOption::UnformattedValue,
+            date_time_render_option: None,
+            major_dimension: MajorDimension::Rows,
+        }
+    }
+}
+
+/// How rows are appended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AppendOptions {
+    pub input_mode: InputMode,
+    pub insert_data_option: InsertDataOption,
+    /// See [`WriteOptions::include_values_in_response`], the values are in `updates`
+    pub include_values_in_response: Option<ResponseValueRenderOption>,
+}
+
+impl Default for AppendOptions {
+    fn default() -> Self {
+        Self {
+            input_mode: InputMode::UserEntered,
+            insert_data_option: InsertDataOption::Overwrite,
+            include_values_in_response: None,
+        }
+    }
+}
+
+/// How values are written by updates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriteOptions {
+    pub input_mode: InputMode,
+    /// `Some` has the response carry the written cells as the sheet holds them afterward
+    /// (`updated_data`), rendered as given. Shows what `UserEntered` parsing made of the
+    /// input, e.g. a string which became a date, without reading the range again
+    pub include_values_in_response: Option<ResponseValueRenderOption>,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        Self {
+            input_mode: InputMode::UserEntered,
+            include_values_in_response: None,
+        }
+    }
+}
+
+#[deprecated(note = "Use `SheetTitle` for titles and `SheetGid` for numeric sheet ids")]
 pub type SheetId = String;
+
+/// Title of a sheet as shown on its tab, which A1 ranges refer to
+#[derive(Debug, Display, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Deref, From)]
+pub struct SheetTitle(pub String);
+
+impl From<&str> for SheetTitle {
+    fn from(title: &str) -> Self {
+        Self(title.to_string())
+    }
+}
+
+/// Numeric sheet id (the `gid` of sheet URLs) which structural and metadata requests use.
+/// Unlike the title it survives renames
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, From)]
+pub struct SheetGid(pub i32);